@@ -13,9 +13,9 @@ use cranelift_module::FuncId;
 use csv::StringRecord;
 use dbsp::{
     trace::{BatchReader, Cursor},
-    DBSPHandle, Error, Runtime,
+    DBSPHandle, Error, Runtime, RuntimeError,
 };
-use std::{collections::BTreeMap, mem::transmute, ops::Not, path::Path, thread, time::Instant};
+use std::{collections::BTreeMap, mem::transmute, ops::Not, path::Path, time::Instant};
 
 // TODO: A lot of this still needs fleshing out, mainly the little tweaks that
 // users may want to add to parsing and how to do that ergonomically.
@@ -117,7 +117,7 @@ impl DbspCircuit {
         result
     }
 
-    pub fn kill(self) -> thread::Result<()> {
+    pub fn kill(self) -> Result<(), RuntimeError> {
         tracing::trace!("killing circuit");
         let result = self.runtime.kill();
 
@@ -17,6 +17,9 @@ const SEED: [u8; 32] = [
     0x11, 0x7d, 0xc, 0xe4, 0x64, 0xbf, 0x72, 0x17, 0x46, 0x28, 0x46, 0x42, 0xb2, 0x4b, 0x72, 0x18,
 ];
 
+/// The number of partitions used by the parallel consolidation benchmark
+const PARALLEL_PARTITIONS: usize = 4;
+
 fn data<T>(length: usize) -> Vec<T>
 where
     Standard: Distribution<T>,
@@ -96,6 +99,20 @@ macro_rules! consolidation_benches {
             )*
             group.finish();
 
+            let mut group = c.benchmark_group("consolidate-parallel");
+            $(
+                group.bench_function($name, |b| {
+                    let unsorted = data::<((usize, usize), isize)>($size);
+
+                    b.iter_batched(
+                        || unsorted.clone(),
+                        |mut unsorted| consolidation::consolidate_partitioned(black_box(&mut unsorted), PARALLEL_PARTITIONS),
+                        BatchSize::PerIteration,
+                    );
+                });
+            )*
+            group.finish();
+
             let mut group = c.benchmark_group("consolidate-stable");
             $(
                 group.bench_function($name, |b| {
@@ -293,15 +293,9 @@ fn main() -> Result<()> {
         }
     });
 
-    hruntime.join().map_err(|error| {
-        if let Some(message) = error.downcast_ref::<&'static str>() {
-            anyhow::anyhow!("failed to join runtime with main thread: {message}")
-        } else if let Some(message) = error.downcast_ref::<String>() {
-            anyhow::anyhow!("failed to join runtime with main thread: {message}")
-        } else {
-            anyhow::anyhow!("failed to join runtime with main thread")
-        }
-    })
+    hruntime
+        .join()
+        .map_err(|error| anyhow::anyhow!("failed to join runtime with main thread: {error}"))
 }
 
 #[cfg(all(windows, miri))]
@@ -0,0 +1,240 @@
+use crate::algebra::{HasOne, HasZero, F64};
+use size_of::SizeOf;
+use std::{
+    fmt::{self, Debug, Display},
+    ops::{Add, AddAssign, Mul, Neg},
+};
+
+#[cfg(feature = "with-serde")]
+use serde::{Deserialize, Serialize};
+
+/// A floating point weight that treats values close to zero as zero.
+///
+/// [`F64`] can be used as a weight, but it compares with zero exactly, so
+/// rounding errors can keep a record alive forever: e.g., inserting a record
+/// with weights `0.1` and `0.2` and retracting it with weight `-0.3` leaves it
+/// with weight `5.5e-17` instead of removing it.  `F64Weight` considers all
+/// values whose magnitude is below [`Self::TOLERANCE`] to be zero and rounds
+/// the results of addition that fall within the tolerance to exact zero.
+///
+/// Floating point arithmetic is not exact, so `F64Weight` is only an
+/// approximation of a ring: addition is not associative, and the result of
+/// adding up the same weights can differ depending on the order of addition,
+/// which in turn depends on how updates are batched.  Weights whose
+/// magnitude is below the tolerance are dropped even if they were not
+/// meant to cancel out.  Use it for approximate aggregates, such as
+/// averages and variances, where these errors are acceptable.
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, SizeOf)]
+#[cfg_attr(feature = "with-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "with-serde", serde(transparent))]
+#[repr(transparent)]
+pub struct F64Weight(F64);
+
+impl F64Weight {
+    /// Values whose magnitude is below the tolerance are considered zero.
+    pub const TOLERANCE: f64 = 1e-9;
+
+    #[inline]
+    pub const fn new(value: f64) -> Self {
+        Self(F64::new(value))
+    }
+
+    #[inline]
+    pub const fn into_inner(self) -> f64 {
+        self.0.into_inner()
+    }
+
+    /// Round values within the tolerance to zero.
+    #[inline]
+    fn normalize(value: F64) -> Self {
+        if value.into_inner().abs() < Self::TOLERANCE {
+            Self::zero()
+        } else {
+            Self(value)
+        }
+    }
+}
+
+impl From<f64> for F64Weight {
+    #[inline]
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<F64> for F64Weight {
+    #[inline]
+    fn from(value: F64) -> Self {
+        Self(value)
+    }
+}
+
+impl Add for F64Weight {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::normalize(self.0 + rhs.0)
+    }
+}
+
+impl<'a> Add<&'a F64Weight> for &'a F64Weight {
+    type Output = F64Weight;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        *self + *rhs
+    }
+}
+
+impl AddAssign for F64Weight {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl AddAssign<&'_ F64Weight> for F64Weight {
+    #[inline]
+    fn add_assign(&mut self, rhs: &Self) {
+        *self = *self + *rhs;
+    }
+}
+
+impl Mul for F64Weight {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::normalize(self.0 * rhs.0)
+    }
+}
+
+impl<'a> Mul<&'a F64Weight> for &'a F64Weight {
+    type Output = F64Weight;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl Neg for F64Weight {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+impl Neg for &F64Weight {
+    type Output = F64Weight;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        F64Weight(-self.0)
+    }
+}
+
+impl HasZero for F64Weight {
+    #[inline]
+    fn zero() -> Self {
+        Self::new(0.0)
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.into_inner().abs() < Self::TOLERANCE
+    }
+}
+
+impl HasOne for F64Weight {
+    #[inline]
+    fn one() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl Debug for F64Weight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for F64Weight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl bincode::Encode for F64Weight {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> core::result::Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.0, encoder)
+    }
+}
+
+impl bincode::Decode for F64Weight {
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self(bincode::Decode::decode(decoder)?))
+    }
+}
+
+impl<'de> bincode::BorrowDecode<'de> for F64Weight {
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self(bincode::BorrowDecode::borrow_decode(decoder)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::F64Weight;
+    use crate::{
+        algebra::{AddByRef, HasOne, HasZero, MulByRef, NegByRef, ZRingValue},
+        zset, OrdZSet,
+    };
+
+    #[test]
+    fn ring_ops() {
+        let two = F64Weight::one().add_by_ref(&F64Weight::one());
+        assert_eq!(two, F64Weight::new(2.0));
+        assert_eq!(two.neg_by_ref(), F64Weight::new(-2.0));
+        assert_eq!(two.mul_by_ref(&two), F64Weight::new(4.0));
+        assert!(two.ge0());
+        assert!(two.neg_by_ref().le0());
+        assert!(F64Weight::zero().is_zero());
+    }
+
+    #[test]
+    fn cancel_within_tolerance() {
+        let x = F64Weight::new(0.1) + F64Weight::new(0.2);
+        assert_ne!(x.into_inner() - 0.3, 0.0);
+
+        let sum = x + F64Weight::new(-0.3);
+        assert!(sum.is_zero());
+        assert_eq!(sum, F64Weight::zero());
+        assert!(F64Weight::new(F64Weight::TOLERANCE / 2.0).is_zero());
+        assert!(!F64Weight::new(F64Weight::TOLERANCE * 2.0).is_zero());
+    }
+
+    #[test]
+    fn zero_weight_keys_are_dropped() {
+        let inserts: OrdZSet<u64, F64Weight> = zset! {
+            1 => F64Weight::new(0.1),
+            2 => F64Weight::new(1.5),
+        };
+        let more: OrdZSet<u64, F64Weight> = zset! { 1 => F64Weight::new(0.2) };
+        let retractions: OrdZSet<u64, F64Weight> = zset! { 1 => F64Weight::new(-0.3) };
+
+        let result = inserts.add_by_ref(&more).add_by_ref(&retractions);
+        assert_eq!(result, zset! { 2 => F64Weight::new(1.5) });
+        assert_eq!(result.len(), 1);
+    }
+}
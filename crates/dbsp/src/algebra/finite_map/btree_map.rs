@@ -0,0 +1,367 @@
+use crate::{
+    algebra::{AddAssignByRef, FiniteMap, GroupValue, HasZero},
+    NumEntries,
+};
+use size_of::SizeOf;
+use std::{
+    collections::{
+        btree_map::{self, Entry},
+        BTreeMap,
+    },
+    fmt::{self, Debug},
+    ops::{Add, AddAssign, Neg},
+};
+
+/// A [`FiniteMap`] backed by a B-tree.
+///
+/// Unlike [`FiniteHashMap`](`crate::algebra::FiniteHashMap`), a
+/// `FiniteBTreeMap` keeps its keys sorted: both [`FiniteMap::support`] and
+/// [`Self::iter`] visit keys in ascending order, so algorithms that need
+/// ordered keys don't have to sort them separately.
+#[derive(Clone, SizeOf)]
+pub struct FiniteBTreeMap<K, R> {
+    value: BTreeMap<K, R>,
+}
+
+impl<K, R> FiniteBTreeMap<K, R>
+where
+    K: Ord,
+    R: GroupValue,
+{
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self {
+            value: BTreeMap::new(),
+        }
+    }
+
+    /// Iterate over the keys with non-zero weights and their weights in
+    /// ascending order of keys.
+    pub fn iter(&self) -> btree_map::Iter<'_, K, R> {
+        self.value.iter()
+    }
+}
+
+impl<K, R> FiniteMap<K, R> for FiniteBTreeMap<K, R>
+where
+    K: Ord,
+    R: GroupValue,
+{
+    type Support<'a> = btree_map::Keys<'a, K, R>
+    where
+        Self: 'a,
+        K: 'a;
+
+    fn singleton(key: K, value: R) -> Self {
+        let mut result = Self::new();
+        result.increment_owned(key, value);
+        result
+    }
+
+    fn lookup(&self, key: &K) -> R {
+        self.value.get(key).cloned().unwrap_or_else(R::zero)
+    }
+
+    fn get_in_support(&self, key: &K) -> Option<&R> {
+        self.value.get(key)
+    }
+
+    fn increment(&mut self, key: &K, value: R)
+    where
+        K: Clone,
+    {
+        if value.is_zero() {
+            return;
+        }
+
+        match self.value.get_mut(key) {
+            Some(weight) => {
+                *weight += value;
+                if weight.is_zero() {
+                    self.value.remove(key);
+                }
+            }
+            None => {
+                self.value.insert(key.clone(), value);
+            }
+        }
+    }
+
+    fn increment_owned(&mut self, key: K, value: R) {
+        if value.is_zero() {
+            return;
+        }
+
+        match self.value.entry(key) {
+            Entry::Occupied(mut entry) => {
+                *entry.get_mut() += value;
+                if entry.get().is_zero() {
+                    entry.remove();
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        }
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &R) -> bool,
+    {
+        self.value.retain(|key, value| f(key, value));
+    }
+
+    fn support_size(&self) -> usize {
+        self.value.len()
+    }
+
+    fn support(&self) -> Self::Support<'_> {
+        self.value.keys()
+    }
+}
+
+impl<K, R> Default for FiniteBTreeMap<K, R>
+where
+    K: Ord,
+    R: GroupValue,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, R> Debug for FiniteBTreeMap<K, R>
+where
+    K: Debug,
+    R: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.value.iter()).finish()
+    }
+}
+
+impl<K, R> PartialEq for FiniteBTreeMap<K, R>
+where
+    K: PartialEq,
+    R: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<K, R> Eq for FiniteBTreeMap<K, R>
+where
+    K: Eq,
+    R: Eq,
+{
+}
+
+impl<K, R> IntoIterator for FiniteBTreeMap<K, R> {
+    type Item = (K, R);
+    type IntoIter = btree_map::IntoIter<K, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.value.into_iter()
+    }
+}
+
+impl<'a, K, R> IntoIterator for &'a FiniteBTreeMap<K, R> {
+    type Item = (&'a K, &'a R);
+    type IntoIter = btree_map::Iter<'a, K, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.value.iter()
+    }
+}
+
+impl<K, R> FromIterator<(K, R)> for FiniteBTreeMap<K, R>
+where
+    K: Ord,
+    R: GroupValue,
+{
+    /// Build a map from `(key, weight)` pairs, adding up the weights of
+    /// duplicate keys.
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, R)>,
+    {
+        let mut result = Self::new();
+        for (key, value) in iter {
+            result.increment_owned(key, value);
+        }
+        result
+    }
+}
+
+impl<K, R> HasZero for FiniteBTreeMap<K, R>
+where
+    K: Ord,
+    R: GroupValue,
+{
+    fn is_zero(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    fn zero() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, R> NumEntries for FiniteBTreeMap<K, R> {
+    const CONST_NUM_ENTRIES: Option<usize> = None;
+
+    fn num_entries_shallow(&self) -> usize {
+        self.value.len()
+    }
+
+    fn num_entries_deep(&self) -> usize {
+        self.value.len()
+    }
+}
+
+impl<K, R> Add for FiniteBTreeMap<K, R>
+where
+    K: Ord + Clone,
+    R: GroupValue,
+{
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<K, R> Add<&FiniteBTreeMap<K, R>> for &FiniteBTreeMap<K, R>
+where
+    K: Ord + Clone,
+    R: GroupValue,
+{
+    type Output = FiniteBTreeMap<K, R>;
+
+    fn add(self, rhs: &FiniteBTreeMap<K, R>) -> Self::Output {
+        // Iterate over the smaller map.
+        let (mut result, other) = if self.support_size() >= rhs.support_size() {
+            (self.clone(), rhs)
+        } else {
+            (rhs.clone(), self)
+        };
+        result.add_assign_by_ref(other);
+        result
+    }
+}
+
+impl<K, R> AddAssign for FiniteBTreeMap<K, R>
+where
+    K: Ord + Clone,
+    R: GroupValue,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        for (key, value) in rhs.value {
+            self.increment_owned(key, value);
+        }
+    }
+}
+
+impl<K, R> AddAssign<&FiniteBTreeMap<K, R>> for FiniteBTreeMap<K, R>
+where
+    K: Ord + Clone,
+    R: GroupValue,
+{
+    fn add_assign(&mut self, rhs: &FiniteBTreeMap<K, R>) {
+        for (key, value) in rhs.value.iter() {
+            self.increment(key, value.clone());
+        }
+    }
+}
+
+impl<K, R> Neg for FiniteBTreeMap<K, R>
+where
+    K: Ord,
+    R: GroupValue,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            value: self
+                .value
+                .into_iter()
+                .map(|(key, value)| (key, value.neg()))
+                .collect(),
+        }
+    }
+}
+
+impl<K, R> Neg for &FiniteBTreeMap<K, R>
+where
+    K: Ord + Clone,
+    R: GroupValue,
+{
+    type Output = FiniteBTreeMap<K, R>;
+
+    fn neg(self) -> Self::Output {
+        FiniteBTreeMap {
+            value: self
+                .value
+                .iter()
+                .map(|(key, value)| (key.clone(), value.neg_by_ref()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FiniteBTreeMap;
+    use crate::algebra::{FiniteMap, HasZero, NegByRef};
+
+    #[test]
+    fn increment() {
+        let mut map = FiniteBTreeMap::<&str, i64>::new();
+        map.increment(&"a", 1);
+        map.increment_owned("b", 2);
+        map.increment(&"c", 0);
+        assert_eq!(map.support_size(), 2);
+        assert_eq!(map.lookup(&"a"), 1);
+        assert_eq!(map.lookup(&"c"), 0);
+        assert_eq!(map.get_in_support(&"c"), None);
+
+        // Keys whose weight drops to zero leave the support of the map.
+        map.increment(&"a", -1);
+        assert_eq!(map.get_in_support(&"a"), None);
+        assert_eq!(map, FiniteBTreeMap::singleton("b", 2));
+    }
+
+    #[test]
+    fn group() {
+        let map1: FiniteBTreeMap<u64, i64> = [(1, 1), (2, 2), (1, 3)].into_iter().collect();
+        let map2: FiniteBTreeMap<u64, i64> = [(2, -2), (3, 1)].into_iter().collect();
+
+        assert_eq!(map1, [(1, 4), (2, 2)].into_iter().collect());
+        assert_eq!(&map1 + &map2, [(1, 4), (3, 1)].into_iter().collect());
+        assert_eq!(map1.clone() + map2.clone(), &map2 + &map1);
+        assert!((&map1 + &map1.neg_by_ref()).is_zero());
+        assert_eq!(-map2, [(2, 2), (3, -1)].into_iter().collect());
+    }
+
+    #[test]
+    fn sorted_support() {
+        let mut map: FiniteBTreeMap<i64, i64> = [(5, 1), (-3, 2), (8, 1), (0, 4), (2, 1)]
+            .into_iter()
+            .collect();
+        map.increment(&8, -1);
+        map.increment_owned(-10, 1);
+
+        assert_eq!(
+            map.support().copied().collect::<Vec<_>>(),
+            [-10, -3, 0, 2, 5]
+        );
+        assert_eq!(
+            map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+            [(-10, 1), (-3, 2), (0, 4), (2, 1), (5, 1)]
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::{
+    algebra::{AddAssignByRef, FiniteMap, GroupValue, HasZero},
+    NumEntries,
+};
+use hashbrown::{
+    hash_map::{self, Entry},
+    HashMap,
+};
+use size_of::SizeOf;
+use std::{
+    fmt::{self, Debug},
+    hash::Hash,
+    ops::{Add, AddAssign, Neg},
+};
+
+#[cfg(feature = "with-serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A [`FiniteMap`] backed by a hash table.
+///
+/// Unlike [`OrdZSet`](`crate::OrdZSet`) and
+/// [`FiniteBTreeMap`](`crate::algebra::FiniteBTreeMap`), a `FiniteHashMap`
+/// does not keep its keys sorted, which makes it cheaper to build when the
+/// consumer does not care about the order of keys.
+#[derive(Clone, SizeOf)]
+pub struct FiniteHashMap<K, R> {
+    value: HashMap<K, R>,
+}
+
+impl<K, R> FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: GroupValue,
+{
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self {
+            value: HashMap::new(),
+        }
+    }
+
+    /// Create an empty map with space for at least `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            value: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Add all `(key, weight)` pairs produced by `iter` to the map.
+    ///
+    /// Reserves space for the lower bound of the iterator's size hint
+    /// upfront, so that the map is resized at most once for iterators that
+    /// know their exact length instead of growing incrementally as keys are
+    /// inserted.  The upper bound is ignored, since it may vastly exceed the
+    /// number of pairs actually produced, e.g., by a filtering iterator.
+    pub fn extend_from<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, R)>,
+    {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.value.reserve(lower);
+
+        for (key, value) in iter {
+            self.increment_owned(key, value);
+        }
+    }
+
+    /// The number of keys the map can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.value.capacity()
+    }
+
+    /// Iterate over the keys with non-zero weights and their weights in
+    /// arbitrary order.
+    pub fn iter(&self) -> hash_map::Iter<'_, K, R> {
+        self.value.iter()
+    }
+}
+
+impl<K, R> FiniteMap<K, R> for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: GroupValue,
+{
+    type Support<'a>
+        = hash_map::Keys<'a, K, R>
+    where
+        Self: 'a,
+        K: 'a;
+
+    fn singleton(key: K, value: R) -> Self {
+        let mut result = Self::with_capacity(1);
+        result.increment_owned(key, value);
+        result
+    }
+
+    fn lookup(&self, key: &K) -> R {
+        self.value.get(key).cloned().unwrap_or_else(R::zero)
+    }
+
+    fn get_in_support(&self, key: &K) -> Option<&R> {
+        self.value.get(key)
+    }
+
+    fn increment(&mut self, key: &K, value: R)
+    where
+        K: Clone,
+    {
+        if value.is_zero() {
+            return;
+        }
+
+        match self.value.get_mut(key) {
+            Some(weight) => {
+                *weight += value;
+                if weight.is_zero() {
+                    self.value.remove(key);
+                }
+            }
+            None => {
+                self.value.insert(key.clone(), value);
+            }
+        }
+    }
+
+    fn increment_owned(&mut self, key: K, value: R) {
+        if value.is_zero() {
+            return;
+        }
+
+        match self.value.entry(key) {
+            Entry::Occupied(mut entry) => {
+                *entry.get_mut() += value;
+                if entry.get().is_zero() {
+                    entry.remove();
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        }
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &R) -> bool,
+    {
+        self.value.retain(|key, value| f(key, value));
+    }
+
+    fn support_size(&self) -> usize {
+        self.value.len()
+    }
+
+    fn support(&self) -> Self::Support<'_> {
+        self.value.keys()
+    }
+}
+
+impl<K, R> Default for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: GroupValue,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, R> Debug for FiniteHashMap<K, R>
+where
+    K: Hash + Eq + Debug,
+    R: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.value.iter()).finish()
+    }
+}
+
+impl<K, R> PartialEq for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: Eq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<K, R> Eq for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: Eq,
+{
+}
+
+impl<K, R> IntoIterator for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+{
+    type Item = (K, R);
+    type IntoIter = hash_map::IntoIter<K, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.value.into_iter()
+    }
+}
+
+impl<'a, K, R> IntoIterator for &'a FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+{
+    type Item = (&'a K, &'a R);
+    type IntoIter = hash_map::Iter<'a, K, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.value.iter()
+    }
+}
+
+impl<K, R> FromIterator<(K, R)> for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: GroupValue,
+{
+    /// Build a map from `(key, weight)` pairs, adding up the weights of
+    /// duplicate keys.
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, R)>,
+    {
+        let mut result = Self::new();
+        result.extend_from(iter);
+        result
+    }
+}
+
+impl<K, R> HasZero for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: GroupValue,
+{
+    fn is_zero(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    fn zero() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, R> NumEntries for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+{
+    const CONST_NUM_ENTRIES: Option<usize> = None;
+
+    fn num_entries_shallow(&self) -> usize {
+        self.value.len()
+    }
+
+    fn num_entries_deep(&self) -> usize {
+        self.value.len()
+    }
+}
+
+impl<K, R> Add for FiniteHashMap<K, R>
+where
+    K: Hash + Eq + Clone,
+    R: GroupValue,
+{
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<K, R> Add<&FiniteHashMap<K, R>> for &FiniteHashMap<K, R>
+where
+    K: Hash + Eq + Clone,
+    R: GroupValue,
+{
+    type Output = FiniteHashMap<K, R>;
+
+    fn add(self, rhs: &FiniteHashMap<K, R>) -> Self::Output {
+        // Iterate over the smaller map.
+        let (mut result, other) = if self.support_size() >= rhs.support_size() {
+            (self.clone(), rhs)
+        } else {
+            (rhs.clone(), self)
+        };
+        result.add_assign_by_ref(other);
+        result
+    }
+}
+
+impl<K, R> AddAssign for FiniteHashMap<K, R>
+where
+    K: Hash + Eq + Clone,
+    R: GroupValue,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        for (key, value) in rhs.value {
+            self.increment_owned(key, value);
+        }
+    }
+}
+
+impl<K, R> AddAssign<&FiniteHashMap<K, R>> for FiniteHashMap<K, R>
+where
+    K: Hash + Eq + Clone,
+    R: GroupValue,
+{
+    fn add_assign(&mut self, rhs: &FiniteHashMap<K, R>) {
+        for (key, value) in rhs.value.iter() {
+            self.increment(key, value.clone());
+        }
+    }
+}
+
+impl<K, R> Neg for FiniteHashMap<K, R>
+where
+    K: Hash + Eq,
+    R: GroupValue,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            value: self
+                .value
+                .into_iter()
+                .map(|(key, value)| (key, value.neg()))
+                .collect(),
+        }
+    }
+}
+
+impl<K, R> Neg for &FiniteHashMap<K, R>
+where
+    K: Hash + Eq + Clone,
+    R: GroupValue,
+{
+    type Output = FiniteHashMap<K, R>;
+
+    fn neg(self) -> Self::Output {
+        FiniteHashMap {
+            value: self
+                .value
+                .iter()
+                .map(|(key, value)| (key.clone(), value.neg_by_ref()))
+                .collect(),
+        }
+    }
+}
+
+/// Serializes the map as a sequence of `(key, weight)` pairs.
+#[cfg(feature = "with-serde")]
+impl<K, R> Serialize for FiniteHashMap<K, R>
+where
+    K: Serialize,
+    R: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.value.iter())
+    }
+}
+
+/// Deserializes a sequence of `(key, weight)` pairs, adding up the weights
+/// of duplicate keys and dropping keys whose weight is zero, so that the
+/// deserialized map doesn't store zero weights even if its serialized form
+/// does.
+#[cfg(feature = "with-serde")]
+impl<'de, K, R> Deserialize<'de> for FiniteHashMap<K, R>
+where
+    K: Deserialize<'de> + Hash + Eq,
+    R: Deserialize<'de> + GroupValue,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, R)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FiniteHashMap;
+    use crate::algebra::{FiniteMap, HasZero, NegByRef};
+
+    #[test]
+    fn increment() {
+        let mut map = FiniteHashMap::<&str, i64>::new();
+        map.increment(&"a", 1);
+        map.increment_owned("b", 2);
+        map.increment(&"c", 0);
+        assert_eq!(map.support_size(), 2);
+        assert_eq!(map.lookup(&"a"), 1);
+        assert_eq!(map.lookup(&"c"), 0);
+        assert_eq!(map.get_in_support(&"c"), None);
+
+        // Keys whose weight drops to zero leave the support of the map.
+        map.increment(&"a", -1);
+        assert_eq!(map.get_in_support(&"a"), None);
+        assert_eq!(map, FiniteHashMap::singleton("b", 2));
+    }
+
+    #[test]
+    fn extend_from() {
+        const NUM_ITEMS: u64 = 100_000;
+
+        let items = || (0..NUM_ITEMS).map(|i| (i % (NUM_ITEMS / 2), 1i64));
+
+        // Count how many times the map grows when inserting items one by one.
+        let mut incremental = FiniteHashMap::new();
+        let mut incremental_resizes = 0;
+        for (key, value) in items() {
+            let capacity = incremental.capacity();
+            incremental.increment_owned(key, value);
+            if incremental.capacity() != capacity {
+                incremental_resizes += 1;
+            }
+        }
+
+        let mut bulk = FiniteHashMap::new();
+        bulk.extend_from(items());
+        let bulk_capacity = bulk.capacity();
+
+        // The bulk path reserves space for all items upfront instead of
+        // growing the map repeatedly.
+        assert!(incremental_resizes > 1);
+        assert!(bulk_capacity >= NUM_ITEMS as usize);
+        assert_eq!(bulk, incremental);
+        assert_eq!(bulk.support_size(), (NUM_ITEMS / 2) as usize);
+        assert_eq!(bulk, items().collect());
+
+        // Iterators that may produce fewer items than their upper bound don't
+        // reserve space for it.
+        let filtered_items = (0..NUM_ITEMS)
+            .filter(|i| i % 10_000 == 0)
+            .map(|i| (i, 1i64));
+        assert_eq!(filtered_items.size_hint(), (0, Some(NUM_ITEMS as usize)));
+        let mut filtered = FiniteHashMap::new();
+        filtered.extend_from(filtered_items);
+        assert_eq!(filtered.support_size(), 10);
+        assert!(filtered.capacity() < 100);
+    }
+
+    #[test]
+    fn group() {
+        let map1: FiniteHashMap<u64, i64> = [(1, 1), (2, 2), (1, 3)].into_iter().collect();
+        let map2: FiniteHashMap<u64, i64> = [(2, -2), (3, 1)].into_iter().collect();
+
+        assert_eq!(map1, [(1, 4), (2, 2)].into_iter().collect());
+        assert_eq!(&map1 + &map2, [(1, 4), (3, 1)].into_iter().collect());
+        assert_eq!(map1.clone() + map2.clone(), &map2 + &map1);
+        assert!((&map1 + &map1.neg_by_ref()).is_zero());
+        assert_eq!(-map2, [(2, 2), (3, -1)].into_iter().collect());
+    }
+
+    #[test]
+    fn retain() {
+        let mut map: FiniteHashMap<u64, i64> = [(1, 1), (2, -2), (3, 3), (4, -4), (5, 5)]
+            .into_iter()
+            .collect();
+
+        map.retain(|_, value| *value > 0);
+        assert_eq!(map.support_size(), 3);
+        assert_eq!(map, [(1, 1), (3, 3), (5, 5)].into_iter().collect());
+
+        // Drop all keys below a watermark.
+        map.retain(|key, _| *key >= 3);
+        assert_eq!(map.support_size(), 2);
+        assert_eq!(map.get_in_support(&1), None);
+        assert_eq!(map, [(3, 3), (5, 5)].into_iter().collect());
+    }
+
+    #[test]
+    #[cfg(feature = "with-serde")]
+    fn serde_roundtrip() {
+        let map: FiniteHashMap<(String, u64), i64> = [
+            (("a".to_string(), 1), 1),
+            (("b".to_string(), 2), -3),
+            (("c".to_string(), 3), 7),
+        ]
+        .into_iter()
+        .collect();
+
+        let json = serde_json::to_string(&map).unwrap();
+        let deserialized: FiniteHashMap<(String, u64), i64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, map);
+    }
+
+    #[test]
+    #[cfg(feature = "with-serde")]
+    fn serde_drops_zeros() {
+        let json = r#"[[["a", 1], 0], [["b", 2], 5], [["c", 3], 2], [["c", 3], -2]]"#;
+        let map: FiniteHashMap<(String, u64), i64> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(map.support_size(), 1);
+        assert_eq!(map.get_in_support(&("a".to_string(), 1)), None);
+        assert_eq!(map.get_in_support(&("c".to_string(), 3)), None);
+        assert_eq!(map, FiniteHashMap::singleton(("b".to_string(), 2), 5));
+    }
+}
@@ -0,0 +1,57 @@
+//! Finite maps from keys to weights.
+
+mod btree_map;
+mod hash_map;
+
+pub use btree_map::FiniteBTreeMap;
+pub use hash_map::FiniteHashMap;
+
+use crate::algebra::HasZero;
+
+/// A finite map from keys to weights.
+///
+/// The map only stores keys with non-zero weights (the _support_ of the map);
+/// all other keys are implicitly mapped to zero.  Implementations form a
+/// group under point-wise addition, so they can be used as stream values in
+/// the same way as Z-sets.
+pub trait FiniteMap<K, R>: HasZero + FromIterator<(K, R)> {
+    /// Iterator over the keys in the support of the map.
+    type Support<'a>: Iterator<Item = &'a K>
+    where
+        Self: 'a,
+        K: 'a;
+
+    /// Create a map containing a single `key` with weight `value`.
+    fn singleton(key: K, value: R) -> Self;
+
+    /// Returns the weight of `key`, which is zero for keys outside of the
+    /// support of the map.
+    fn lookup(&self, key: &K) -> R;
+
+    /// Returns the weight of `key` if it belongs to the support of the map.
+    fn get_in_support(&self, key: &K) -> Option<&R>;
+
+    /// Add `value` to the weight of `key`, removing the key from the map
+    /// if its weight becomes zero.
+    fn increment(&mut self, key: &K, value: R)
+    where
+        K: Clone;
+
+    /// Like [`Self::increment`], but takes ownership of `key`.
+    fn increment_owned(&mut self, key: K, value: R);
+
+    /// Retain only the keys for which `f` returns `true`, removing all other
+    /// keys from the support of the map in a single pass.
+    ///
+    /// This can be used, e.g., to drop all keys below a watermark:
+    /// `map.retain(|key, _| *key >= watermark)`.
+    fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &R) -> bool;
+
+    /// The number of keys with non-zero weights.
+    fn support_size(&self) -> usize;
+
+    /// Iterate over the keys with non-zero weights.
+    fn support(&self) -> Self::Support<'_>;
+}
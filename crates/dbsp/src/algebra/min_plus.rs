@@ -0,0 +1,283 @@
+use crate::algebra::{HasOne, HasZero};
+use size_of::SizeOf;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Display},
+    ops::{Add, AddAssign, Mul},
+};
+
+/// The tropical (min-plus) semiring over `T`.
+///
+/// Addition in this semiring is `min` and multiplication is `+`.  The zero
+/// element, i.e., the identity for `min`, is positive infinity, represented by
+/// [`Self::INFINITY`], and the one element, i.e., the identity for `+`, is
+/// `T::zero()`.
+///
+/// In this semiring, multiplying the lengths of two path segments yields the
+/// length of the combined path, and adding the lengths of alternative paths
+/// yields the length of the shortest one, which is the basic step of
+/// shortest path computations.
+///
+/// `MinPlus` is a semiring rather than a ring: `min` has no inverse, so it
+/// cannot be used as the weight type of streams, which circuit operators
+/// require to form a group.  Use it as a value type instead, e.g., as the
+/// accumulator of a [`Fold`](`crate::operator::Fold`) aggregate.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, SizeOf)]
+pub struct MinPlus<T> {
+    // `None` represents positive infinity.
+    value: Option<T>,
+}
+
+impl<T> MinPlus<T> {
+    /// Positive infinity, the zero element of the semiring.
+    pub const INFINITY: Self = Self { value: None };
+
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self { value: Some(value) }
+    }
+
+    /// Returns the finite value, or `None` if `self` is infinite.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.value
+    }
+
+    #[inline]
+    pub const fn is_infinite(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+impl<T> PartialOrd for MinPlus<T>
+where
+    T: Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for MinPlus<T>
+where
+    T: Ord,
+{
+    /// Orders finite values by `T` and infinity above all of them.
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.value, &other.value) {
+            (Some(x), Some(y)) => x.cmp(y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+impl<T> Add for MinPlus<T>
+where
+    T: Ord,
+{
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        self.min(rhs)
+    }
+}
+
+impl<'a, T> Add<&'a MinPlus<T>> for &'a MinPlus<T>
+where
+    T: Ord + Clone,
+{
+    type Output = MinPlus<T>;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        self.min(rhs).clone()
+    }
+}
+
+impl<T> AddAssign for MinPlus<T>
+where
+    T: Ord,
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        if rhs < *self {
+            *self = rhs;
+        }
+    }
+}
+
+impl<T> AddAssign<&'_ MinPlus<T>> for MinPlus<T>
+where
+    T: Ord + Clone,
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: &Self) {
+        if *rhs < *self {
+            *self = rhs.clone();
+        }
+    }
+}
+
+impl<T> Mul for MinPlus<T>
+where
+    T: Add<Output = T>,
+{
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            value: self.value.zip(rhs.value).map(|(x, y)| x + y),
+        }
+    }
+}
+
+impl<'a, T> Mul<&'a MinPlus<T>> for &'a MinPlus<T>
+where
+    T: Add<Output = T> + Clone,
+{
+    type Output = MinPlus<T>;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.clone() * rhs.clone()
+    }
+}
+
+impl<T> HasZero for MinPlus<T> {
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.is_infinite()
+    }
+
+    #[inline]
+    fn zero() -> Self {
+        Self::INFINITY
+    }
+}
+
+impl<T> HasOne for MinPlus<T>
+where
+    T: HasZero,
+{
+    #[inline]
+    fn one() -> Self {
+        Self::new(T::zero())
+    }
+}
+
+impl<T> Default for MinPlus<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::INFINITY
+    }
+}
+
+impl<T> From<T> for MinPlus<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Debug for MinPlus<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => value.fmt(f),
+            None => f.write_str("inf"),
+        }
+    }
+}
+
+impl<T> Display for MinPlus<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => value.fmt(f),
+            None => f.write_str("inf"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinPlus;
+    use crate::algebra::{AddAssignByRef, AddByRef, HasOne, HasZero, MulByRef};
+
+    type Distance = MinPlus<u64>;
+
+    #[test]
+    fn semiring_identities() {
+        let values = [
+            Distance::INFINITY,
+            Distance::new(0),
+            Distance::new(3),
+            Distance::new(7),
+        ];
+
+        for a in values {
+            // Zero is the identity for addition and annihilates multiplication.
+            assert_eq!(a.add_by_ref(&Distance::zero()), a);
+            assert!(a.mul_by_ref(&Distance::zero()).is_zero());
+
+            // One is the identity for multiplication.
+            assert_eq!(a.mul_by_ref(&Distance::one()), a);
+            assert_eq!(Distance::one().mul_by_ref(&a), a);
+
+            // Addition is idempotent.
+            assert_eq!(a.add_by_ref(&a), a);
+
+            for b in values {
+                assert_eq!(a.add_by_ref(&b), b.add_by_ref(&a));
+                assert_eq!(a.mul_by_ref(&b), b.mul_by_ref(&a));
+
+                for c in values {
+                    // Multiplication distributes over addition.
+                    assert_eq!(
+                        a.mul_by_ref(&b.add_by_ref(&c)),
+                        a.mul_by_ref(&b).add_by_ref(&a.mul_by_ref(&c))
+                    );
+                }
+            }
+        }
+
+        assert_eq!(
+            Distance::new(3).add_by_ref(&Distance::new(7)),
+            Distance::new(3)
+        );
+        assert_eq!(
+            Distance::new(3).mul_by_ref(&Distance::new(7)),
+            Distance::new(10)
+        );
+        assert!(Distance::new(0) < Distance::INFINITY);
+    }
+
+    #[test]
+    fn relaxation() {
+        // Edges `a -> b` and `b -> c` with a total length of 7 and a direct
+        // edge `a -> c` of length 10.
+        let a_b = Distance::new(3);
+        let b_c = Distance::new(4);
+        let a_c = Distance::new(10);
+
+        let dist_a = Distance::one();
+        let mut dist_c = Distance::zero();
+
+        dist_c.add_assign_by_ref(&dist_a.mul_by_ref(&a_c));
+        assert_eq!(dist_c, Distance::new(10));
+
+        let dist_b = dist_a.mul_by_ref(&a_b);
+        dist_c.add_assign_by_ref(&dist_b.mul_by_ref(&b_c));
+        assert_eq!(dist_c.into_inner(), Some(7));
+    }
+}
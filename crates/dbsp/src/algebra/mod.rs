@@ -3,18 +3,28 @@
 
 #[macro_use]
 mod checked_int;
+mod f64_weight;
+mod finite_map;
 mod floats;
 mod lattice;
+mod min_plus;
 mod order;
 mod present;
+mod saturating_int;
+mod tdigest;
 
 pub mod zset;
 
 pub use checked_int::CheckedInt;
+pub use f64_weight::F64Weight;
+pub use finite_map::{FiniteBTreeMap, FiniteHashMap, FiniteMap};
 pub use floats::{F32, F64};
 pub use lattice::Lattice;
+pub use min_plus::MinPlus;
 pub use order::{PartialOrder, TotalOrder};
 pub use present::Present;
+pub use saturating_int::SaturatingInt;
+pub use tdigest::TDigest;
 pub use zset::{IndexedZSet, ZSet};
 
 use size_of::SizeOf;
@@ -0,0 +1,249 @@
+use crate::algebra::{AddAssignByRef, AddByRef, HasOne, HasZero, MulByRef, NegByRef};
+use num::traits::{SaturatingAdd, SaturatingMul, SaturatingSub};
+use size_of::SizeOf;
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display, Error, Formatter},
+    ops::{Add, AddAssign, Neg},
+};
+
+/// Numeric values that saturate at the bounds of their type on overflow
+///
+/// Computes exactly like any signed numeric value until the result of an
+/// operation exceeds `T::MAX` or `T::MIN`, in which case the result is clamped
+/// to the bound instead of panicking like [`CheckedInt`](`super::CheckedInt`)
+/// does.
+///
+/// Saturated values break the ring laws: once a value is clamped, addition
+/// is no longer associative and `x + (-x)` is not guaranteed to be zero, e.g.,
+/// `(MAX + 1) + (-1) = MAX - 1` while `MAX + (1 + (-1)) = MAX`.  In
+/// particular, an update and its retraction may not cancel out, leaving
+/// records with non-zero weights in the output.  It is therefore only safe
+/// to use `SaturatingInt` when values are expected to stay within the bounds
+/// of `T`, and saturation serves as a graceful way to handle outliers, e.g.,
+/// for approximate counts where a clamped result is preferable to a panic.
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    SizeOf,
+    bincode::Decode,
+    bincode::Encode,
+)]
+#[repr(transparent)]
+pub struct SaturatingInt<T> {
+    value: T,
+}
+
+impl<T> SaturatingInt<T> {
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Add for SaturatingInt<T>
+where
+    T: SaturatingAdd,
+{
+    type Output = Self;
+
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        Self {
+            value: self.value.saturating_add(&other.value),
+        }
+    }
+}
+
+impl<T> AddByRef for SaturatingInt<T>
+where
+    T: SaturatingAdd,
+{
+    #[inline]
+    fn add_by_ref(&self, other: &Self) -> Self {
+        Self {
+            value: self.value.saturating_add(&other.value),
+        }
+    }
+}
+
+impl<T> AddAssign for SaturatingInt<T>
+where
+    T: SaturatingAdd,
+{
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        self.value = self.value.saturating_add(&other.value);
+    }
+}
+
+impl<T> AddAssignByRef for SaturatingInt<T>
+where
+    T: SaturatingAdd,
+{
+    #[inline]
+    fn add_assign_by_ref(&mut self, other: &Self) {
+        self.value = self.value.saturating_add(&other.value);
+    }
+}
+
+impl<T> MulByRef for SaturatingInt<T>
+where
+    T: SaturatingMul,
+{
+    type Output = Self;
+
+    #[inline]
+    fn mul_by_ref(&self, rhs: &Self) -> Self::Output {
+        Self {
+            value: self.value.saturating_mul(&rhs.value),
+        }
+    }
+}
+
+impl<T> NegByRef for SaturatingInt<T>
+where
+    T: SaturatingSub + HasZero,
+{
+    #[inline]
+    fn neg_by_ref(&self) -> Self {
+        // `-T::MIN` saturates to `T::MAX`.
+        Self {
+            value: T::zero().saturating_sub(&self.value),
+        }
+    }
+}
+
+impl<T> Neg for SaturatingInt<T>
+where
+    T: SaturatingSub + HasZero,
+{
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        Self {
+            value: T::zero().saturating_sub(&self.value),
+        }
+    }
+}
+
+impl<T> HasZero for SaturatingInt<T>
+where
+    T: HasZero,
+{
+    #[inline]
+    fn is_zero(&self) -> bool {
+        T::is_zero(&self.value)
+    }
+
+    #[inline]
+    fn zero() -> Self {
+        Self::new(T::zero())
+    }
+}
+
+impl<T> HasOne for SaturatingInt<T>
+where
+    T: HasOne,
+{
+    #[inline]
+    fn one() -> Self {
+        Self::new(T::one())
+    }
+}
+
+impl<T> PartialEq<T> for SaturatingInt<T>
+where
+    T: PartialEq,
+{
+    #[inline]
+    fn eq(&self, other: &T) -> bool {
+        &self.value == other
+    }
+}
+
+impl<T> PartialOrd<T> for SaturatingInt<T>
+where
+    T: PartialOrd,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        self.value.partial_cmp(other)
+    }
+}
+
+impl<T> From<T> for SaturatingInt<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self { value }
+    }
+}
+
+impl<T> Debug for SaturatingInt<T>
+where
+    T: Debug,
+{
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.value.fmt(f)
+    }
+}
+
+impl<T> Display for SaturatingInt<T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.value.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod saturating_integer_ring_tests {
+    use super::{AddAssignByRef, AddByRef, HasOne, HasZero, MulByRef, NegByRef, SaturatingInt};
+
+    type SaturatingI64 = SaturatingInt<i64>;
+
+    #[test]
+    fn fixed_integer_tests() {
+        assert_eq!(0i64, SaturatingI64::zero().into_inner());
+        assert_eq!(1i64, SaturatingI64::one().into_inner());
+
+        let two = SaturatingI64::one().add_by_ref(&SaturatingI64::one());
+        assert_eq!(2i64, two.into_inner());
+        assert_eq!(-2i64, two.neg_by_ref().into_inner());
+        assert_eq!(-4i64, two.mul_by_ref(&two.neg_by_ref()).into_inner());
+
+        let mut three = two;
+        three.add_assign_by_ref(&SaturatingI64::from(1i64));
+        assert_eq!(3i64, three.into_inner());
+        assert!(!three.is_zero());
+    }
+
+    #[test]
+    fn overflow_test() {
+        let max = SaturatingI64::from(i64::MAX);
+        assert_eq!(i64::MAX, max.add_by_ref(&SaturatingI64::one()).into_inner());
+        assert_eq!(i64::MAX, max.mul_by_ref(&max).into_inner());
+
+        let min = SaturatingI64::from(i64::MIN);
+        let mut sum = min;
+        sum.add_assign_by_ref(&SaturatingI64::one().neg_by_ref());
+        assert_eq!(i64::MIN, sum.into_inner());
+        assert_eq!(i64::MIN, min.mul_by_ref(&max).into_inner());
+        assert_eq!(i64::MAX, min.neg_by_ref().into_inner());
+        assert_eq!(i64::MAX, (-min).into_inner());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::algebra::{HasZero, F64};
+use size_of::SizeOf;
+use std::{
+    mem::take,
+    ops::{Add, AddAssign},
+};
+
+/// Compression parameter of the digest.  Larger values produce more accurate
+/// estimates at the cost of keeping more centroids.
+const COMPRESSION: f64 = 100.0;
+
+/// Maximal number of centroids the digest accumulates before compressing
+/// them.
+const MAX_CENTROIDS: usize = 10 * COMPRESSION as usize;
+
+/// A cluster of nearby values represented by their mean and count.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, SizeOf, bincode::Decode, bincode::Encode,
+)]
+struct Centroid {
+    mean: F64,
+    weight: u64,
+}
+
+impl Centroid {
+    fn new(mean: f64, weight: u64) -> Self {
+        Self {
+            mean: F64::new(mean),
+            weight,
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        self.mean.into_inner()
+    }
+
+    /// Absorb `other` into `self`.
+    fn merge(&mut self, other: &Self) {
+        let weight = self.weight + other.weight;
+        let mean =
+            self.mean() + (other.mean() - self.mean()) * (other.weight as f64 / weight as f64);
+
+        self.mean = F64::new(mean);
+        self.weight = weight;
+    }
+}
+
+/// A t-digest sketch that summarizes a multiset of numbers and estimates its
+/// quantiles.
+///
+/// The digest represents its input as a list of centroids, i.e., clusters of
+/// adjacent values.  Centroids close to the tails of the distribution are
+/// kept small, which makes estimates of extreme quantiles particularly
+/// accurate, while the total number of centroids stays bounded regardless of
+/// the size of the input.
+///
+/// Digests form a commutative monoid with [`TDigest::merge`] as the `+`
+/// operation and the empty digest as zero, so they can be used as aggregate
+/// values.  The `+` operation is associative and commutative up to the
+/// approximation error of the sketch.  Unlike the weight types used in
+/// Z-sets, digests do not form a group: values cannot be removed from a
+/// digest once added, so digests can only summarize insert-only data.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    SizeOf,
+    bincode::Decode,
+    bincode::Encode,
+)]
+pub struct TDigest {
+    /// Centroids ordered by mean.
+    centroids: Vec<Centroid>,
+    /// Total weight of all centroids.
+    count: u64,
+    min: F64,
+    max: F64,
+}
+
+impl TDigest {
+    /// Create an empty digest.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total number of values summarized by the digest.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Add `weight` occurrences of `value` to the digest.
+    pub fn insert(&mut self, value: f64, weight: u64) {
+        if weight == 0 {
+            return;
+        }
+
+        let value_f64 = F64::new(value);
+        if self.count == 0 {
+            self.min = value_f64;
+            self.max = value_f64;
+        } else {
+            self.min = self.min.min(value_f64);
+            self.max = self.max.max(value_f64);
+        }
+        self.count += weight;
+
+        let position = self
+            .centroids
+            .partition_point(|centroid| centroid.mean <= value_f64);
+        self.centroids
+            .insert(position, Centroid::new(value, weight));
+
+        if self.centroids.len() > MAX_CENTROIDS {
+            self.compress();
+        }
+    }
+
+    /// Merge two digests into one that summarizes the union of their
+    /// inputs.
+    pub fn merge(&self, other: &Self) -> Self {
+        if other.is_zero() {
+            return self.clone();
+        }
+        if self.is_zero() {
+            return other.clone();
+        }
+
+        let mut centroids = Vec::with_capacity(self.centroids.len() + other.centroids.len());
+        centroids.extend_from_slice(&self.centroids);
+        centroids.extend_from_slice(&other.centroids);
+        centroids.sort_by_key(|c| c.mean);
+
+        let mut result = Self {
+            centroids,
+            count: self.count + other.count,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        };
+        result.compress();
+        result
+    }
+
+    /// Estimate the `q`-th quantile of the summarized values, where `q` is
+    /// between 0 and 1.
+    ///
+    /// Returns `None` if the digest is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let first = self.centroids.first()?;
+        let last = self.centroids.last().unwrap();
+
+        let total = self.count as f64;
+        let target = q.clamp(0.0, 1.0) * total;
+        let (min, max) = (self.min.into_inner(), self.max.into_inner());
+        if target <= 0.0 {
+            return Some(min);
+        } else if target >= total {
+            return Some(max);
+        }
+
+        // Each centroid is assumed to be centered at the middle of its rank
+        // range; interpolate linearly between the centers of adjacent
+        // centroids, and between the outermost centers and the min/max
+        // values.
+        let first_center = first.weight as f64 / 2.0;
+        if target <= first_center {
+            return Some(min + (first.mean() - min) * (target / first_center));
+        }
+
+        let mut cumulative = 0.0;
+        for pair in self.centroids.windows(2) {
+            let (left, right) = (&pair[0], &pair[1]);
+
+            let left_center = cumulative + left.weight as f64 / 2.0;
+            let right_center = cumulative + left.weight as f64 + right.weight as f64 / 2.0;
+            if target < right_center {
+                let fraction = (target - left_center) / (right_center - left_center);
+                return Some(left.mean() + (right.mean() - left.mean()) * fraction);
+            }
+
+            cumulative += left.weight as f64;
+        }
+
+        let last_center = total - last.weight as f64 / 2.0;
+        let fraction = ((target - last_center) / (total - last_center)).min(1.0);
+        Some(last.mean() + (max - last.mean()) * fraction)
+    }
+
+    /// Merge adjacent centroids while keeping the size of each centroid
+    /// within the bound determined by its position in the distribution.
+    fn compress(&mut self) {
+        if self.centroids.len() <= 1 {
+            return;
+        }
+
+        let total = self.count as f64;
+        let mut centroids = take(&mut self.centroids).into_iter();
+        let mut compressed = Vec::new();
+
+        let mut current = centroids.next().unwrap();
+        let mut weight_so_far = 0.0;
+
+        for centroid in centroids {
+            let proposed_weight = (current.weight + centroid.weight) as f64;
+            let q0 = weight_so_far / total;
+            let q2 = (weight_so_far + proposed_weight) / total;
+            let limit = 4.0 * total * f64::min(q0 * (1.0 - q0), q2 * (1.0 - q2)) / COMPRESSION;
+
+            if proposed_weight <= limit {
+                current.merge(&centroid);
+            } else {
+                weight_so_far += current.weight as f64;
+                compressed.push(current);
+                current = centroid;
+            }
+        }
+        compressed.push(current);
+
+        self.centroids = compressed;
+    }
+}
+
+impl HasZero for TDigest {
+    fn is_zero(&self) -> bool {
+        self.count == 0
+    }
+
+    fn zero() -> Self {
+        Self::new()
+    }
+}
+
+impl Add for TDigest {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.merge(&rhs)
+    }
+}
+
+impl Add<&TDigest> for &TDigest {
+    type Output = TDigest;
+
+    fn add(self, rhs: &TDigest) -> Self::Output {
+        self.merge(rhs)
+    }
+}
+
+impl AddAssign for TDigest {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.merge(&rhs);
+    }
+}
+
+impl AddAssign<&TDigest> for TDigest {
+    fn add_assign(&mut self, rhs: &TDigest) {
+        *self = self.merge(rhs);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TDigest;
+    use crate::algebra::HasZero;
+
+    const NUM_VALUES: u64 = 10_000;
+
+    // Maximal tolerated error of the median estimate, as a fraction of the
+    // range of values.
+    const MAX_ERROR: f64 = 0.01;
+
+    // A permutation of `0..NUM_VALUES`.
+    fn values() -> impl Iterator<Item = f64> {
+        (0..NUM_VALUES).map(|i| ((i * 7919) % NUM_VALUES) as f64)
+    }
+
+    fn exact_quantile(q: f64) -> f64 {
+        q * (NUM_VALUES - 1) as f64
+    }
+
+    fn assert_close(estimate: f64, expected: f64) {
+        assert!(
+            (estimate - expected).abs() <= MAX_ERROR * NUM_VALUES as f64,
+            "estimate: {estimate}, expected: {expected}"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let digest = TDigest::new();
+        assert!(digest.is_zero());
+        assert_eq!(digest.quantile(0.5), None);
+    }
+
+    #[test]
+    fn median() {
+        let mut digest = TDigest::new();
+        for value in values() {
+            digest.insert(value, 1);
+        }
+
+        assert_eq!(digest.count(), NUM_VALUES);
+        assert_close(digest.quantile(0.5).unwrap(), exact_quantile(0.5));
+        assert_close(digest.quantile(0.99).unwrap(), exact_quantile(0.99));
+        assert_eq!(digest.quantile(0.0), Some(0.0));
+        assert_eq!(digest.quantile(1.0), Some((NUM_VALUES - 1) as f64));
+    }
+
+    #[test]
+    fn merge() {
+        let mut left = TDigest::new();
+        let mut right = TDigest::new();
+        for (i, value) in values().enumerate() {
+            if i % 3 == 0 {
+                left.insert(value, 1);
+            } else {
+                right.insert(value, 1);
+            }
+        }
+
+        let merged = &left + &right;
+        assert_eq!(merged.count(), NUM_VALUES);
+        assert_close(merged.quantile(0.5).unwrap(), exact_quantile(0.5));
+        assert_eq!(&merged + &TDigest::zero(), merged);
+    }
+}
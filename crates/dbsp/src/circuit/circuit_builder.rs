@@ -41,19 +41,19 @@ use crate::{
         trace::{CircuitEvent, SchedulerEvent},
     },
     circuit_cache_key,
-    operator::communication::Exchange,
+    operator::{communication::Exchange, panic_message, take_operator_error, OperatorError},
     time::{Timestamp, UnitTimestamp},
     Runtime,
 };
 use std::{
     borrow::Cow,
     cell::{Ref, RefCell, RefMut, UnsafeCell},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
     fmt::{Debug, Display, Write},
     iter::repeat,
     marker::PhantomData,
-    panic::Location,
+    panic::{catch_unwind, AssertUnwindSafe, Location},
     rc::Rc,
     thread::panicking,
 };
@@ -727,6 +727,13 @@ pub trait Circuit: WithClock + Clone + 'static {
     /// This method should only be used by schedulers.
     fn eval_node(&self, id: NodeId) -> Result<(), SchedulerError>;
 
+    /// Catch panics during the evaluation of node `id` and report them as
+    /// [`SchedulerError::OperatorPanic`] instead of unwinding the worker
+    /// thread.
+    ///
+    /// See [`Stream::with_error_recovery`].
+    fn enable_error_recovery(&self, id: NodeId);
+
     /// Evaluate closure `f` inside a new circuit region.
     ///
     /// A region is a logical grouping of circuit nodes.  Regions are used
@@ -1239,6 +1246,9 @@ where
     circuit_event_handlers: CircuitEventHandlers,
     scheduler_event_handlers: SchedulerEventHandlers,
     store: CircuitCache,
+    // Nodes whose panics are converted into errors (see
+    // `Stream::with_error_recovery`).
+    error_recovery: HashSet<NodeId>,
 }
 
 impl<P> CircuitInner<P>
@@ -1263,6 +1273,7 @@ where
             circuit_event_handlers,
             scheduler_event_handlers,
             store: TypedMap::new(),
+            error_recovery: HashSet::new(),
         }
     }
 
@@ -1281,6 +1292,7 @@ where
         self.nodes.clear();
         self.edges.clear();
         self.store.clear();
+        self.error_recovery.clear();
     }
 
     fn register_circuit_event_handler<F>(&mut self, name: &str, handler: F)
@@ -1811,13 +1823,55 @@ where
         // reference to a node and pass it to an operator,
         // but this module doesn't expose nodes, only
         // streams.
-        unsafe { circuit.nodes[id.0].eval()? };
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+
+        if circuit.error_recovery.contains(&id) {
+            let node = &mut circuit.nodes[id.0];
+            // The node is left in an unspecified state after a panic.  This is
+            // fine, since the circuit cannot be stepped after an error.
+            match catch_unwind(AssertUnwindSafe(|| unsafe { node.eval() })) {
+                Ok(result) => result?,
+                Err(payload) => {
+                    return Err(SchedulerError::OperatorPanic {
+                        node_id: node.global_id().clone(),
+                        operator: node.name().into_owned(),
+                        message: panic_message(payload.as_ref()),
+                    })
+                }
+            }
+        } else {
+            unsafe { circuit.nodes[id.0].eval()? };
+        }
+
+        #[cfg(feature = "metrics")]
+        Runtime::record_operator_eval(&circuit.nodes[id.0].name(), start.elapsed());
+
+        // Report errors reported by the operator.
+        if let Some(error) = take_operator_error() {
+            let node = circuit.nodes[id.0].as_ref();
+            return Err(match error {
+                OperatorError::Panic(message) => SchedulerError::OperatorPanic {
+                    node_id: node.global_id().clone(),
+                    operator: node.name().into_owned(),
+                    message,
+                },
+                OperatorError::Input(error) => SchedulerError::Input {
+                    operator: node.name().into_owned(),
+                    error,
+                },
+            });
+        }
 
         circuit.log_scheduler_event(&SchedulerEvent::eval_end(circuit.nodes[id.0].as_ref()));
 
         Ok(())
     }
 
+    fn enable_error_recovery(&self, id: NodeId) {
+        self.inner_mut().error_recovery.insert(id);
+    }
+
     #[track_caller]
     fn region<F, T>(&self, name: &str, f: F) -> T
     where
@@ -3655,10 +3709,18 @@ impl CircuitHandle {
     /// Every call to `step()` corresponds to one tick of the global logical
     /// clock and causes each operator in the circuit to get evaluated once,
     /// consuming one value from each of its input streams.
+    ///
+    /// In a multithreaded runtime, blocks while the runtime is paused (see
+    /// [`Runtime::pause`]).
     pub fn step(&self) -> Result<(), SchedulerError> {
         // TODO: Add a runtime check to prevent re-entering this method from an
         // operator.
 
+        // Block at the clock cycle boundary while the runtime is paused.
+        if !Runtime::wait_while_paused() {
+            return Err(SchedulerError::Killed);
+        }
+
         self.executor.run(&self.circuit)
     }
 
@@ -1,13 +1,15 @@
 use crate::{
-    circuit::runtime::RuntimeHandle, profile::Profiler, Error as DBSPError, RootCircuit, Runtime,
-    RuntimeError, SchedulerError,
+    circuit::runtime::RuntimeHandle,
+    operator::{CollectionHandle, OutputHandle},
+    profile::{LatencyHistogram, Profiler},
+    trace::Batch,
+    DBData, Error as DBSPError, RootCircuit, Runtime, RuntimeError, SchedulerError,
 };
 use crossbeam::channel::{bounded, Receiver, Sender, TryRecvError};
 use std::{
     fs,
     fs::create_dir_all,
     path::{Path, PathBuf},
-    thread::Result as ThreadResult,
     time::Instant,
 };
 
@@ -134,9 +136,10 @@ impl Runtime {
                     init_status.push(Err(DBSPError::Scheduler(scheduler_error)))
                 }
                 Ok(Ok(ret)) => init_status.push(Ok(ret)),
-                Err(_) => {
-                    init_status.push(Err(DBSPError::Runtime(RuntimeError::WorkerPanic(worker))))
-                }
+                Err(_) => init_status.push(Err(DBSPError::Runtime(RuntimeError::WorkerPanic {
+                    worker,
+                    message: String::new(),
+                }))),
             }
         }
 
@@ -146,8 +149,15 @@ impl Runtime {
                 .into_iter()
                 .find_map(|status| status.err())
                 .unwrap();
-            let _ = runtime.kill();
-            return Err(error);
+            let kill_result = runtime.kill();
+
+            // Report the panic message collected when joining the workers.
+            return Err(match (error, kill_result) {
+                (DBSPError::Runtime(RuntimeError::WorkerPanic { .. }), Err(panic)) => {
+                    DBSPError::Runtime(panic)
+                }
+                (error, _) => error,
+            });
         }
 
         let dbsp = DBSPHandle::new(runtime, command_senders, status_receivers);
@@ -156,6 +166,51 @@ impl Runtime {
         // worker 0 output.
         Ok((dbsp, init_status[0].as_ref().unwrap().clone()))
     }
+
+    /// Check that a circuit produces identical outputs when executed twice
+    /// on the same inputs.
+    ///
+    /// Instantiates the circuit returned by `constructor` twice, each time in
+    /// a new runtime with `nworkers` worker threads.  Feeds both instances
+    /// the same sequence of `inputs`, one element of the sequence per clock
+    /// cycle, and compares the consolidated outputs of the two instances
+    /// after each step.  Returns `true` iff the outputs are identical at
+    /// every step.
+    ///
+    /// This is a testing utility that helps catch nondeterminism, e.g., due
+    /// to hash map iteration order or thread scheduling.
+    pub fn run_deterministic<F, K, V, O>(
+        nworkers: usize,
+        constructor: F,
+        inputs: Vec<Vec<(K, V)>>,
+    ) -> Result<bool, DBSPError>
+    where
+        F: FnOnce(&mut RootCircuit) -> (CollectionHandle<K, V>, OutputHandle<O>)
+            + Clone
+            + Send
+            + 'static,
+        K: DBData,
+        V: DBData,
+        O: Batch<Time = ()> + PartialEq + Send,
+    {
+        let mut outputs = Vec::with_capacity(2);
+
+        for _ in 0..2 {
+            let (mut dbsp, (mut input_handle, output_handle)) =
+                Self::init_circuit(nworkers, constructor.clone())?;
+
+            let mut run_outputs = Vec::with_capacity(inputs.len());
+            for step_inputs in inputs.iter() {
+                input_handle.append(&mut step_inputs.clone());
+                dbsp.step()?;
+                run_outputs.push(output_handle.consolidate());
+            }
+
+            outputs.push(run_outputs);
+        }
+
+        Ok(outputs[0] == outputs[1])
+    }
 }
 
 #[derive(Clone)]
@@ -181,6 +236,8 @@ pub struct DBSPHandle {
     // Channels used to receive command completion status from
     // workers.
     status_receivers: Vec<Receiver<Result<Response, SchedulerError>>>,
+    // Wall-clock durations of successful steps.
+    step_latency: LatencyHistogram,
 }
 
 impl DBSPHandle {
@@ -194,15 +251,30 @@ impl DBSPHandle {
             runtime: Some(runtime),
             command_senders,
             status_receivers,
+            step_latency: LatencyHistogram::new(),
         }
     }
 
-    fn kill_inner(&mut self) -> ThreadResult<()> {
+    fn kill_inner(&mut self) -> Result<(), RuntimeError> {
         self.command_senders.clear();
         self.status_receivers.clear();
         self.runtime.take().unwrap().kill()
     }
 
+    /// Kill the runtime after detecting that `worker` panicked.
+    ///
+    /// Returns the panic reported when joining the worker threads.
+    fn kill_after_panic(&mut self, worker: usize) -> DBSPError {
+        let panic = self
+            .kill_inner()
+            .err()
+            .unwrap_or_else(|| RuntimeError::WorkerPanic {
+                worker,
+                message: String::new(),
+            });
+        DBSPError::Runtime(panic)
+    }
+
     fn broadcast_command<F>(&mut self, command: Command, mut handler: F) -> Result<(), DBSPError>
     where
         F: FnMut(Response),
@@ -214,8 +286,7 @@ impl DBSPHandle {
         // Send command.
         for (worker, sender) in self.command_senders.iter().enumerate() {
             if matches!(sender.send(command.clone()), Err(_)) {
-                let _ = self.kill_inner();
-                return Err(DBSPError::Runtime(RuntimeError::WorkerPanic(worker)));
+                return Err(self.kill_after_panic(worker));
             }
             self.runtime.as_ref().unwrap().unpark_worker(worker);
         }
@@ -224,8 +295,7 @@ impl DBSPHandle {
         for (worker, receiver) in self.status_receivers.iter().enumerate() {
             match receiver.recv() {
                 Err(_) => {
-                    let _ = self.kill_inner();
-                    return Err(DBSPError::Runtime(RuntimeError::WorkerPanic(worker)));
+                    return Err(self.kill_after_panic(worker));
                 }
                 Ok(Err(e)) => {
                     let _ = self.kill_inner();
@@ -242,9 +312,40 @@ impl DBSPHandle {
         self.status_receivers.len()
     }
 
+    /// Returns the runtime that evaluates the circuit, or `None` if the
+    /// runtime has been terminated after an error.
+    ///
+    /// The runtime can be cloned and used to pause and resume the circuit
+    /// from another thread (see [`Runtime::pause`]).
+    pub fn runtime(&self) -> Option<&Runtime> {
+        self.runtime.as_ref().map(RuntimeHandle::runtime)
+    }
+
     /// Evaluate the circuit for one clock cycle.
+    ///
+    /// Blocks while the runtime is paused (see [`Runtime::pause`]).
     pub fn step(&mut self) -> Result<(), DBSPError> {
-        self.broadcast_command(Command::Step, |_| {})
+        let start = Instant::now();
+        self.broadcast_command(Command::Step, |_| {})?;
+        self.step_latency.record(start.elapsed());
+
+        Ok(())
+    }
+
+    /// Histogram of the wall-clock durations of all successful
+    /// [`step`](`Self::step`) invocations, measured from the moment the
+    /// step command is sent to the workers until all workers complete the
+    /// step.
+    ///
+    /// Use [`LatencyHistogram::percentile`] to monitor tail latency of the
+    /// circuit.
+    pub fn step_latency(&self) -> &LatencyHistogram {
+        &self.step_latency
+    }
+
+    /// Clear the histogram of step durations (see [`Self::step_latency`]).
+    pub fn reset_step_latency(&mut self) {
+        self.step_latency.reset();
     }
 
     /// Enable CPU profiler.
@@ -287,13 +388,14 @@ impl DBSPHandle {
 
     /// Terminate the execution of the circuit, exiting all worker threads.
     ///
-    /// If one or more of the worker threads panics, returns the argument the
-    /// `panic!` macro was called with (see `std::thread::Result`).
+    /// If one or more of the worker threads panics, returns
+    /// [`RuntimeError::WorkerPanic`] with the message the `panic!` macro was
+    /// called with (see [`RuntimeHandle::join`]).
     ///
     /// This is the preferred way of killing a circuit.  Simply dropping the
     /// handle will have the same effect, but without reporting the error
     /// status.
-    pub fn kill(mut self) -> ThreadResult<()> {
+    pub fn kill(mut self) -> Result<(), RuntimeError> {
         if self.runtime.is_none() {
             return Ok(());
         }
@@ -312,7 +414,19 @@ impl Drop for DBSPHandle {
 
 #[cfg(test)]
 mod tests {
-    use crate::{operator::Generator, Circuit, Error as DBSPError, Runtime, RuntimeError};
+    use crate::{
+        operator::{FilterMap, Generator},
+        Circuit, Error as DBSPError, Runtime, RuntimeError,
+    };
+    use crossbeam::channel::bounded;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
 
     // Panic during initialization in worker thread.
     #[test]
@@ -335,7 +449,7 @@ mod tests {
         });
 
         if let DBSPError::Runtime(err) = res.unwrap_err() {
-            assert_eq!(err, RuntimeError::WorkerPanic(0));
+            assert!(matches!(err, RuntimeError::WorkerPanic { worker: 0, .. }));
         } else {
             panic!();
         }
@@ -369,7 +483,7 @@ mod tests {
         .unwrap();
 
         if let DBSPError::Runtime(err) = handle.step().unwrap_err() {
-            assert_eq!(err, RuntimeError::WorkerPanic(0));
+            assert!(matches!(err, RuntimeError::WorkerPanic { worker: 0, .. }));
         } else {
             panic!();
         }
@@ -419,4 +533,132 @@ mod tests {
 
         handle.step().unwrap();
     }
+
+    // Pause and resume the runtime.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_pause_resume() {
+        let steps = Arc::new(AtomicUsize::new(0));
+
+        let (mut handle, _) = Runtime::init_circuit(4, {
+            let steps = steps.clone();
+            move |circuit| {
+                circuit.add_source(Generator::new(move || steps.fetch_add(1, Ordering::SeqCst)));
+            }
+        })
+        .unwrap();
+
+        handle.step().unwrap();
+        assert_eq!(steps.load(Ordering::SeqCst), 4);
+
+        let runtime = handle.runtime().unwrap().clone();
+        runtime.pause();
+        assert!(runtime.is_paused());
+
+        // Workers block at the start of the next clock cycle.
+        let (started_sender, started_receiver) = bounded(0);
+        let stepper = thread::spawn(move || {
+            started_sender.send(()).unwrap();
+            handle.step().unwrap();
+            handle
+        });
+        started_receiver.recv().unwrap();
+
+        // No worker evaluates the circuit while the runtime is paused.
+        assert_eq!(steps.load(Ordering::SeqCst), 4);
+        assert!(!stepper.is_finished());
+
+        // All workers evaluate exactly one clock cycle after resuming.
+        runtime.resume();
+        assert!(!runtime.is_paused());
+        let handle = stepper.join().unwrap();
+        assert_eq!(steps.load(Ordering::SeqCst), 8);
+
+        handle.kill().unwrap();
+    }
+
+    // Step durations are recorded in the latency histogram.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_step_latency() {
+        let (mut handle, mut input_handle) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<usize, isize>();
+            input.distinct().output();
+            input_handle
+        })
+        .unwrap();
+
+        assert_eq!(handle.step_latency().count(), 0);
+        assert_eq!(handle.step_latency().percentile(50.0), None);
+
+        for step in 0..20 {
+            input_handle.append(&mut (0..1000).map(|i| (i * step, 1)).collect());
+            handle.step().unwrap();
+        }
+
+        let latency = handle.step_latency();
+        assert_eq!(latency.count(), 20);
+
+        // Don't make assumptions about actual durations, other than that
+        // they are non-zero and consistent with each other.
+        let min = latency.min().unwrap();
+        let max = latency.max().unwrap();
+        assert!(min > Duration::ZERO);
+        assert!(min <= latency.percentile(50.0).unwrap());
+        assert!(latency.percentile(50.0).unwrap() <= latency.percentile(99.0).unwrap());
+        assert!(latency.percentile(99.0).unwrap() <= max);
+
+        handle.reset_step_latency();
+        assert_eq!(handle.step_latency().count(), 0);
+
+        handle.kill().unwrap();
+    }
+
+    fn deterministic_inputs() -> Vec<Vec<(usize, (usize, isize))>> {
+        vec![
+            vec![(1, (1, 1)), (1, (2, 1)), (2, (5, 1))],
+            vec![(1, (3, 1)), (2, (5, -1)), (3, (7, 2))],
+            vec![(3, (7, -1)), (4, (1, 1))],
+        ]
+    }
+
+    #[test]
+    fn test_run_deterministic() {
+        let deterministic = Runtime::run_deterministic(
+            4,
+            |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+                let output = input.average(|_k, v| *v as isize).output();
+
+                (input_handle, output)
+            },
+            deterministic_inputs(),
+        )
+        .unwrap();
+
+        assert!(deterministic);
+    }
+
+    #[test]
+    fn test_run_nondeterministic() {
+        // Values produced by this circuit depend on how many times it was
+        // instantiated.
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let deterministic = Runtime::run_deterministic(
+            1,
+            |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+                let output = input
+                    .map(|(k, v)| (*k, *v + COUNTER.fetch_add(1, Ordering::Relaxed)))
+                    .output();
+
+                (input_handle, output)
+            },
+            deterministic_inputs(),
+        )
+        .unwrap();
+
+        assert!(!deterministic);
+    }
 }
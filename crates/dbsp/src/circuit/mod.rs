@@ -7,6 +7,7 @@
 
 mod activations;
 mod dbsp_handle;
+mod ready_signal;
 
 pub(crate) mod runtime;
 
@@ -24,6 +25,11 @@ pub use circuit_builder::{
     NodeId, OwnershipPreference, RootCircuit, Scope, Stream, WithClock,
 };
 pub use dbsp_handle::DBSPHandle;
-pub use runtime::{Error as RuntimeError, LocalStore, LocalStoreMarker, Runtime, RuntimeHandle};
+pub use ready_signal::ReadySignal;
+pub use runtime::{
+    CoreId, Error as RuntimeError, LocalStore, LocalStoreMarker, Runtime, RuntimeHandle,
+};
+#[cfg(feature = "metrics")]
+pub use runtime::OperatorMetrics;
 
 pub use schedule::Error as SchedulerError;
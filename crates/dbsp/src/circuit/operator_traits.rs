@@ -106,6 +106,11 @@ pub trait Operator: 'static {
     /// schedule an async operator until it has all external inputs
     /// available.  The scheduler checks that the operator is ready to
     /// execute using the [`ready`](`Self::ready`) method.
+    ///
+    /// The same mechanism allows an operator to apply backpressure, e.g., a
+    /// sink that refuses new inputs while an external consumer catches up.
+    /// See [`ReadySignal`](`crate::circuit::ReadySignal`) for a ready-made
+    /// implementation of the readiness protocol.
     fn is_async(&self) -> bool {
         false
     }
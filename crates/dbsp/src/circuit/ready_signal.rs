@@ -0,0 +1,211 @@
+//! Readiness flag for asynchronous operators.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+type Callback = Box<dyn Fn() + Send + Sync>;
+
+struct Inner {
+    ready: AtomicBool,
+    callback: Mutex<Option<Callback>>,
+}
+
+/// A thread-safe readiness flag that implements the scheduler's side of the
+/// async operator protocol.
+///
+/// An operator that can temporarily refuse to accept new inputs, e.g., a
+/// sink that writes to a slow external consumer, can use this type to apply
+/// backpressure to the circuit.  Such an operator declares itself
+/// [async](`crate::circuit::operator_traits::Operator::is_async`) and
+/// delegates its
+/// [`ready`](`crate::circuit::operator_traits::Operator::ready`) and
+/// [`register_ready_callback`](`crate::circuit::operator_traits::Operator::register_ready_callback`)
+/// methods to a `ReadySignal`.  While the signal is not ready, the scheduler
+/// defers evaluating the operator and all operators that depend on it and
+/// evaluates other operators instead.  Once there is no other work to do, the
+/// scheduler blocks until the signal is raised via [`Self::set_ready`],
+/// possibly by another thread, without polling the operator.
+#[derive(Clone)]
+pub struct ReadySignal {
+    inner: Arc<Inner>,
+}
+
+impl ReadySignal {
+    /// Create a new signal in the specified initial state.
+    pub fn new(ready: bool) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                ready: AtomicBool::new(ready),
+                callback: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Returns `true` if the signal is in the ready state.
+    pub fn is_ready(&self) -> bool {
+        self.inner.ready.load(Ordering::Acquire)
+    }
+
+    /// Switch the signal to the ready state and notify the scheduler.
+    pub fn set_ready(&self) {
+        self.inner.ready.store(true, Ordering::Release);
+        if let Some(callback) = self.inner.callback.lock().unwrap().as_ref() {
+            callback();
+        }
+    }
+
+    /// Switch the signal to the not-ready state.
+    ///
+    /// The scheduler expects an async operator that became ready to remain
+    /// ready until it is evaluated, so this method should only be invoked by
+    /// the operator itself, typically from its `eval` method.
+    pub fn set_not_ready(&self) {
+        self.inner.ready.store(false, Ordering::Release);
+    }
+
+    /// Register a callback to invoke when the signal becomes ready.
+    ///
+    /// Replaces any previously registered callback.
+    pub fn register_callback<F>(&self, callback: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        *self.inner.callback.lock().unwrap() = Some(Box::new(callback));
+    }
+}
+
+impl Default for ReadySignal {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReadySignal;
+    use crate::{
+        circuit::{
+            operator_traits::{Operator, UnaryOperator},
+            Scope,
+        },
+        operator::Generator,
+        Circuit, RootCircuit,
+    };
+    use crossbeam::channel::unbounded;
+    use std::{
+        borrow::Cow,
+        cell::RefCell,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread::spawn,
+    };
+
+    /// Pass-through operator that is only ready once the test raises its
+    /// signal, and counts how many times the scheduler checks its readiness.
+    struct Throttle {
+        signal: ReadySignal,
+        polls: Arc<AtomicUsize>,
+        log: Rc<RefCell<Vec<&'static str>>>,
+    }
+
+    impl Operator for Throttle {
+        fn name(&self) -> Cow<'static, str> {
+            Cow::from("Throttle")
+        }
+
+        fn is_async(&self) -> bool {
+            true
+        }
+
+        fn ready(&self) -> bool {
+            self.polls.fetch_add(1, Ordering::AcqRel);
+            self.signal.is_ready()
+        }
+
+        fn register_ready_callback<F>(&mut self, cb: F)
+        where
+            F: Fn() + Send + Sync + 'static,
+        {
+            self.signal.register_callback(cb);
+        }
+
+        fn fixedpoint(&self, _scope: Scope) -> bool {
+            true
+        }
+    }
+
+    impl UnaryOperator<usize, usize> for Throttle {
+        fn eval(&mut self, input: &usize) -> usize {
+            self.log.borrow_mut().push("throttle");
+            self.signal.set_not_ready();
+            *input
+        }
+    }
+
+    #[test]
+    fn backpressure() {
+        const STEPS: usize = 5;
+
+        let signal = ReadySignal::new(false);
+        let polls = Arc::new(AtomicUsize::new(0));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let (started_sender, started) = unbounded();
+
+        let circuit = {
+            let signal = signal.clone();
+            let polls = polls.clone();
+            let log = log.clone();
+
+            RootCircuit::build(move |circuit| {
+                let mut n = 0;
+                let source = circuit.add_source(Generator::new(move || {
+                    n += 1;
+                    started_sender.send(()).unwrap();
+                    n
+                }));
+
+                let log_clone = log.clone();
+                let mut expected = 0;
+                circuit
+                    .add_unary_operator(Throttle { signal, polls, log }, &source)
+                    .inspect(move |n| {
+                        expected += 1;
+                        assert_eq!(*n, expected);
+                        log_clone.borrow_mut().push("inspect");
+                    });
+            })
+            .unwrap()
+            .0
+        };
+
+        for _ in 0..STEPS {
+            // Raise the signal from another thread once the step has started
+            // evaluating the circuit.  The step must block until then.
+            let signal = signal.clone();
+            let started = started.clone();
+            let handle = spawn(move || {
+                started.recv().unwrap();
+                signal.set_ready();
+            });
+
+            circuit.step().unwrap();
+            handle.join().unwrap();
+        }
+
+        // Operators downstream of `Throttle` were deferred until it was
+        // evaluated.
+        assert_eq!(log.borrow().len(), 2 * STEPS);
+        for pair in log.borrow().chunks(2) {
+            assert_eq!(pair, ["throttle", "inspect"]);
+        }
+
+        // The scheduler only checks readiness when notified instead of
+        // spinning on the operator.
+        assert!(polls.load(Ordering::Acquire) <= 2 * STEPS + 1);
+    }
+}
@@ -1,31 +1,40 @@
 //! A multithreaded runtime for evaluating DBSP circuits in a data-parallel
 //! fashion.
 
+use crate::operator::panic_message;
+pub use core_affinity::CoreId;
 use crossbeam::channel::bounded;
 use crossbeam_utils::sync::{Parker, Unparker};
 use std::{
     cell::{Cell, RefCell},
     fmt,
     fmt::{Debug, Display, Error as FmtError, Formatter},
+    panic,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Condvar, Mutex, Once,
     },
     thread::{Builder, JoinHandle, LocalKey, Result as ThreadResult},
 };
+#[cfg(feature = "metrics")]
+use std::{collections::BTreeMap, time::Duration};
 use typedmap::{TypedDashMap, TypedMapKey};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Error {
-    WorkerPanic(usize),
+    /// Worker thread `worker` panicked with `message`.
+    WorkerPanic {
+        worker: usize,
+        message: String,
+    },
     Killed,
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
         match self {
-            Self::WorkerPanic(worker) => {
-                write!(f, "worker thread '{worker}' panicked")
+            Self::WorkerPanic { worker, message } => {
+                write!(f, "worker thread '{worker}' panicked: {message}")
             }
             Self::Killed => f.write_str("circuit killed by the user"),
         }
@@ -64,25 +73,103 @@ pub type LocalStore = TypedDashMap<LocalStoreMarker>;
 struct RuntimeInner {
     nworkers: usize,
     store: LocalStore,
+    // `true` if panics in worker threads are recorded by the panic hook
+    // (see `Runtime::run_with_panic_hook`).
+    panic_hook: bool,
+    // Panics recorded by the panic hook, indexed by worker.
+    panics: Vec<Mutex<Option<String>>>,
+    // Set by `Runtime::pause` and cleared by `Runtime::resume`.  Workers
+    // check this barrier at the start of each clock cycle.
+    pause: PauseBarrier,
+}
+
+/// A barrier that blocks workers at a clock cycle boundary while the runtime
+/// is paused.
+struct PauseBarrier {
+    state: Mutex<PauseState>,
+    // Signaled when the runtime is resumed or killed.
+    resumed: Condvar,
+}
+
+struct PauseState {
+    // Number of clock cycles started by each worker.
+    started: Vec<u64>,
+    // Set while the runtime is paused: workers block before starting more
+    // than this many clock cycles.
+    pause_at: Option<u64>,
+}
+
+impl PauseBarrier {
+    fn new(nworkers: usize) -> Self {
+        Self {
+            state: Mutex::new(PauseState {
+                started: vec![0; nworkers],
+                pause_at: None,
+            }),
+            resumed: Condvar::new(),
+        }
+    }
 }
 
 impl Debug for RuntimeInner {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("RuntimeInner")
             .field("nworkers", &self.nworkers)
+            .field("panic_hook", &self.panic_hook)
             .finish()
     }
 }
 
 impl RuntimeInner {
-    fn new(nworkers: usize) -> Self {
+    fn new(nworkers: usize, panic_hook: bool) -> Self {
         Self {
             nworkers,
             store: TypedDashMap::new(),
+            panic_hook,
+            panics: (0..nworkers).map(|_| Mutex::new(None)).collect(),
+            pause: PauseBarrier::new(nworkers),
         }
     }
 }
 
+/// Install a process-wide panic hook that records panics in worker threads
+/// of runtimes created by [`Runtime::run_with_panic_hook`], tagged with the
+/// location of the panic, before invoking the previously installed hook.
+fn install_panic_hook() {
+    static PANIC_HOOK: Once = Once::new();
+
+    PANIC_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            // Thread-locals may already be destroyed if the thread panics
+            // during shutdown.
+            let runtime = RUNTIME
+                .try_with(|rt| rt.try_borrow().ok().and_then(|rt| rt.clone()))
+                .ok()
+                .flatten();
+
+            if let Some(runtime) = runtime.filter(|runtime| runtime.inner().panic_hook) {
+                if let Ok(worker) = WORKER_INDEX.try_with(Cell::get) {
+                    let mut message = panic_message(info.payload());
+                    if let Some(location) = info.location() {
+                        message = format!("{message} at {location}");
+                    }
+
+                    // Panics caught inside the worker, e.g., by
+                    // `Stream::with_error_recovery`, are overwritten by
+                    // the panic that terminates the thread, if any.
+                    if let Ok(mut panic) = runtime.inner().panics[worker].lock() {
+                        *panic = Some(message);
+                    }
+                }
+            }
+
+            default_hook(info)
+        }));
+    });
+}
+
 /// A multithreaded runtime that hosts `N` circuits running in parallel worker
 /// threads. Typically, all `N` circuits are identical, but this is not required
 /// or enforced.
@@ -138,17 +225,80 @@ impl Runtime {
     where
         F: FnOnce() + Clone + Send + 'static,
     {
-        let runtime = Self(Arc::new(RuntimeInner::new(workers)));
+        Self::run_inner(workers, false, Vec::new(), circuit)
+    }
+
+    /// Like [`Self::run`], but pins worker thread `i` to CPU core
+    /// `core_ids[i]`.
+    ///
+    /// Creates one worker thread per element of `core_ids`.  Pinning workers
+    /// to distinct cores improves cache locality, particularly on NUMA
+    /// machines.  Pinning is best-effort: if a worker fails to pin itself to
+    /// its core, e.g., because the core is not available to the process, the
+    /// worker runs unpinned.  Workers can find out which core they are
+    /// pinned to using [`Self::worker_core`].
+    ///
+    /// Use [`core_affinity::get_core_ids`] to list the cores available to
+    /// the current process.
+    pub fn run_pinned<F>(core_ids: &[CoreId], circuit: F) -> RuntimeHandle
+    where
+        F: FnOnce() + Clone + Send + 'static,
+    {
+        Self::run_inner(core_ids.len(), false, core_ids.to_vec(), circuit)
+    }
+
+    /// Like [`Self::run`], but additionally installs a panic hook that tags
+    /// panics in worker threads with the index of the worker and the
+    /// location of the panic.
+    ///
+    /// When a worker panics, the error returned by
+    /// [`RuntimeHandle::join`] or [`RuntimeHandle::kill`] includes the
+    /// source location of the panic in addition to the panic message.
+    ///
+    /// The hook is installed once per process and invokes the previously
+    /// installed panic hook, so panics are still printed as usual.  It does
+    /// not affect runtimes created with [`Self::run`].
+    pub fn run_with_panic_hook<F>(workers: usize, circuit: F) -> RuntimeHandle
+    where
+        F: FnOnce() + Clone + Send + 'static,
+    {
+        install_panic_hook();
+        Self::run_inner(workers, true, Vec::new(), circuit)
+    }
+
+    /// Spawn `workers` worker threads, pinning worker `i` to `core_ids[i]`
+    /// if `core_ids` is not empty.
+    fn run_inner<F>(
+        workers: usize,
+        panic_hook: bool,
+        core_ids: Vec<CoreId>,
+        circuit: F,
+    ) -> RuntimeHandle
+    where
+        F: FnOnce() + Clone + Send + 'static,
+    {
+        let runtime = Self(Arc::new(RuntimeInner::new(workers, panic_hook)));
 
         let mut handles = Vec::with_capacity(workers);
         handles.extend((0..workers).map(|worker_index| {
             let runtime = runtime.clone();
             let build_circuit = circuit.clone();
+            let core_id = core_ids.get(worker_index).copied();
 
             let (init_sender, init_receiver) = bounded(1);
             let join_handle = Builder::new()
                 .name(format!("dbsp-worker-{worker_index}"))
                 .spawn(move || {
+                    // Pin the worker to its core, falling back to running
+                    // unpinned on failure.
+                    if let Some(core_id) = core_id {
+                        if core_affinity::set_for_current(core_id) {
+                            runtime
+                                .local_store()
+                                .insert(WorkerCore(worker_index), core_id);
+                        }
+                    }
+
                     // Set the worker's runtime handle and index
                     RUNTIME.with(|rt| *rt.borrow_mut() = Some(runtime));
                     WORKER_INDEX.with(|idx| idx.set(worker_index));
@@ -206,6 +356,18 @@ impl Runtime {
         &self.0
     }
 
+    /// Returns the CPU core that the current worker thread is pinned to, or
+    /// `None` if the worker is not pinned or the current thread is not
+    /// running in a multithreaded runtime (see [`Self::run_pinned`]).
+    pub fn worker_core() -> Option<CoreId> {
+        let runtime = Self::runtime()?;
+        let core_id = runtime
+            .local_store()
+            .get(&WorkerCore(Self::worker_index()))
+            .map(|core_id| *core_id.value());
+        core_id
+    }
+
     /// Returns the number of workers in this runtime.
     pub fn num_workers(&self) -> usize {
         self.inner().nworkers
@@ -240,6 +402,31 @@ impl Runtime {
         result
     }
 
+    /// Returns the value that the next call to
+    /// [`sequence_next`](`Self::sequence_next`) with the same worker index
+    /// will return, without incrementing the counter.
+    pub fn sequence_peek(&self, worker_index: usize) -> usize {
+        debug_assert!(worker_index < self.inner().nworkers);
+        self.local_store()
+            .get(&WorkerId(worker_index))
+            .map(|entry| *entry.value())
+            .unwrap_or(0)
+    }
+
+    /// Reset the per-worker sequential counter, so that the next call to
+    /// [`sequence_next`](`Self::sequence_next`) with the same worker index
+    /// returns 0.
+    ///
+    /// Identifiers generated by `sequence_next` are only consistent across
+    /// workers if all workers reset their counters at the same point, e.g.,
+    /// before building a new circuit in the same runtime.  Each worker must
+    /// only reset its own counter; the store locks the entry, so this is
+    /// safe while other workers access their counters.
+    pub fn sequence_reset(&self, worker_index: usize) {
+        debug_assert!(worker_index < self.inner().nworkers);
+        self.local_store().insert(WorkerId(worker_index), 0);
+    }
+
     /// Returns current worker's parker to be used by schedulers.
     ///
     /// Whenever a circuit scheduler needs to block waiting for
@@ -257,6 +444,128 @@ impl Runtime {
     pub fn kill_in_progress() -> bool {
         KILL_SIGNAL.with(|signal| signal.load(Ordering::SeqCst))
     }
+
+    /// Returns the metrics recorded for each operator evaluated by the
+    /// workers of this runtime, indexed by operator name.
+    ///
+    /// Metrics of operators with the same name, including instances of the
+    /// same operator in different workers, are added up.  Only operators
+    /// evaluated in worker threads of a runtime are recorded.
+    #[cfg(feature = "metrics")]
+    pub fn operator_metrics(&self) -> BTreeMap<String, OperatorMetrics> {
+        let mut result = BTreeMap::new();
+
+        for worker_index in 0..self.inner().nworkers {
+            if let Some(metrics) = self.local_store().get(&OperatorMetricsId(worker_index)) {
+                for (name, metrics) in metrics.value().iter() {
+                    result
+                        .entry(name.clone())
+                        .or_insert_with(OperatorMetrics::default)
+                        .add(metrics);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Record an evaluation of operator `name` that took `elapsed` time in
+    /// the runtime of the current worker thread, if any.
+    ///
+    /// Each worker records metrics in its own entry of the local store, so
+    /// workers don't contend for a shared map.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn record_operator_eval(name: &str, elapsed: Duration) {
+        let runtime = match Self::runtime() {
+            Some(runtime) => runtime,
+            None => return,
+        };
+
+        let mut metrics = runtime
+            .local_store()
+            .entry(OperatorMetricsId(Self::worker_index()))
+            .or_default();
+        match metrics.value_mut().get_mut(name) {
+            Some(metrics) => metrics.record(elapsed),
+            None => {
+                let mut operator_metrics = OperatorMetrics::default();
+                operator_metrics.record(elapsed);
+                metrics
+                    .value_mut()
+                    .insert(name.to_string(), operator_metrics);
+            }
+        }
+    }
+
+    /// Pause all workers at the next clock cycle boundary.
+    ///
+    /// Workers finish the clock cycle that any of them has already started,
+    /// after which each call to
+    /// [`CircuitHandle::step`](`crate::CircuitHandle::step`) blocks until
+    /// the runtime is resumed using [`Self::resume`].  Hence all workers stop
+    /// after evaluating the same number of clock cycles.  This method does
+    /// not wait for the workers to reach the boundary.
+    ///
+    /// Killing a paused runtime terminates the blocked `step` calls with
+    /// [`SchedulerError::Killed`](`crate::SchedulerError::Killed`).
+    pub fn pause(&self) {
+        let mut state = self.inner().pause.state.lock().unwrap();
+        if state.pause_at.is_none() {
+            state.pause_at = state.started.iter().max().copied();
+        }
+    }
+
+    /// Resume workers paused by [`Self::pause`].
+    pub fn resume(&self) {
+        self.inner().pause.state.lock().unwrap().pause_at = None;
+        self.inner().pause.resumed.notify_all();
+    }
+
+    /// Returns `true` if the runtime has been paused using [`Self::pause`]
+    /// and not yet resumed.
+    pub fn is_paused(&self) -> bool {
+        self.inner().pause.state.lock().unwrap().pause_at.is_some()
+    }
+
+    /// Block the current worker thread at the start of a clock cycle while
+    /// its runtime is paused.
+    ///
+    /// Returns `false` if the worker received a kill signal while paused.
+    /// Returns `true` immediately if the current thread is not running in a
+    /// multithreaded runtime.
+    pub(crate) fn wait_while_paused() -> bool {
+        if let Some(runtime) = Self::runtime() {
+            let worker = Self::worker_index();
+            let pause = &runtime.inner().pause;
+            let mut state = pause.state.lock().unwrap();
+
+            while matches!(state.pause_at, Some(pause_at) if state.started[worker] >= pause_at) {
+                if Self::kill_in_progress() {
+                    return false;
+                }
+                state = pause.resumed.wait(state).unwrap();
+            }
+
+            state.started[worker] += 1;
+        }
+
+        true
+    }
+
+    /// Wake up workers blocked in [`Self::wait_while_paused`], so they can
+    /// observe a kill signal.
+    fn wake_paused_workers(&self) {
+        let _state = self.inner().pause.state.lock().unwrap();
+        self.inner().pause.resumed.notify_all();
+    }
+
+    /// Returns the panic recorded by the panic hook for `worker`, if any.
+    fn take_panic(&self, worker: usize) -> Option<String> {
+        self.inner().panics[worker]
+            .lock()
+            .ok()
+            .and_then(|mut panic| panic.take())
+    }
 }
 
 /// Per-worker controls.
@@ -307,6 +616,23 @@ impl RuntimeHandle {
         &self.runtime
     }
 
+    /// Pause the runtime at the next clock cycle boundary (see
+    /// [`Runtime::pause`]).
+    pub fn pause(&self) {
+        self.runtime.pause();
+    }
+
+    /// Resume a runtime paused by [`Self::pause`] (see [`Runtime::resume`]).
+    pub fn resume(&self) {
+        self.runtime.resume();
+    }
+
+    /// Returns `true` if the runtime has been paused using [`Self::pause`]
+    /// and not yet resumed.
+    pub fn is_paused(&self) -> bool {
+        self.runtime.is_paused()
+    }
+
     /// Terminate the runtime and all worker threads without waiting for any
     /// in-progress computation to complete.
     ///
@@ -314,11 +640,15 @@ impl RuntimeHandle {
     /// evaluated to completion, after which the worker thread terminates
     /// even if the circuit has not been fully evaluated for the current
     /// clock cycle.
-    pub fn kill(self) -> ThreadResult<()> {
+    ///
+    /// Returns an error if any of the worker threads panicked (see
+    /// [`Self::join`]).
+    pub fn kill(self) -> Result<(), Error> {
         for worker in self.workers.iter() {
             worker.kill_signal.store(true, Ordering::SeqCst);
             worker.unpark();
         }
+        self.runtime.wake_paused_workers();
 
         self.join()
     }
@@ -326,7 +656,11 @@ impl RuntimeHandle {
     /// Wait for all workers in the runtime to terminate.
     ///
     /// The calling thread blocks until all worker threads have terminated.
-    pub fn join(self) -> ThreadResult<()> {
+    ///
+    /// If one or more of the worker threads panicked, returns
+    /// [`Error::WorkerPanic`] for the panicking worker with the smallest
+    /// index, with the message the `panic!` macro was called with.
+    pub fn join(self) -> Result<(), Error> {
         // Insist on joining all threads even if some of them fail.
         #[allow(clippy::needless_collect)]
         let results: Vec<ThreadResult<()>> = self
@@ -334,7 +668,19 @@ impl RuntimeHandle {
             .into_iter()
             .map(|h| h.join_handle.join())
             .collect();
-        results.into_iter().collect::<ThreadResult<()>>()
+
+        results
+            .into_iter()
+            .enumerate()
+            .try_for_each(|(worker, result)| {
+                result.map_err(|payload| Error::WorkerPanic {
+                    worker,
+                    message: self
+                        .runtime
+                        .take_panic(worker)
+                        .unwrap_or_else(|| panic_message(&*payload)),
+                })
+            })
     }
 }
 
@@ -345,15 +691,63 @@ impl TypedMapKey<LocalStoreMarker> for WorkerId {
     type Value = usize;
 }
 
+/// Evaluation metrics of an operator (see [`Runtime::operator_metrics`]).
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperatorMetrics {
+    /// Number of times the operator was evaluated.
+    pub invocations: u64,
+    /// Cumulative time spent evaluating the operator.
+    pub eval_time: Duration,
+}
+
+#[cfg(feature = "metrics")]
+impl OperatorMetrics {
+    fn record(&mut self, elapsed: Duration) {
+        self.invocations += 1;
+        self.eval_time += elapsed;
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.invocations += other.invocations;
+        self.eval_time += other.eval_time;
+    }
+}
+
+/// Metrics of all operators evaluated by a worker, indexed by worker.
+#[cfg(feature = "metrics")]
+#[derive(Hash, PartialEq, Eq)]
+struct OperatorMetricsId(usize);
+
+#[cfg(feature = "metrics")]
+impl TypedMapKey<LocalStoreMarker> for OperatorMetricsId {
+    type Value = BTreeMap<String, OperatorMetrics>;
+}
+
+/// The CPU core a worker is pinned to.
+#[derive(Hash, PartialEq, Eq)]
+struct WorkerCore(usize);
+
+impl TypedMapKey<LocalStoreMarker> for WorkerCore {
+    type Value = CoreId;
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Runtime;
+    use super::{Error, Runtime};
     use crate::{
         circuit::schedule::{DynamicScheduler, Scheduler, StaticScheduler},
         operator::Generator,
-        Circuit, RootCircuit,
+        zset, Circuit, RootCircuit,
+    };
+    use crossbeam::channel::{unbounded, RecvTimeoutError};
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        sync::{Arc, Mutex},
+        thread::sleep,
+        time::Duration,
     };
-    use std::{cell::RefCell, rc::Rc, thread::sleep, time::Duration};
 
     #[test]
     #[cfg_attr(miri, ignore)]
@@ -396,6 +790,29 @@ mod tests {
         hruntime.join().unwrap();
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_sequence_reset() {
+        let hruntime = Runtime::run(4, || {
+            let runtime = Runtime::runtime().unwrap();
+            let worker_index = Runtime::worker_index();
+
+            assert_eq!(runtime.sequence_peek(worker_index), 0);
+            for i in 0..10 {
+                assert_eq!(runtime.sequence_next(worker_index), i);
+            }
+            assert_eq!(runtime.sequence_peek(worker_index), 10);
+            assert_eq!(runtime.sequence_peek(worker_index), 10);
+
+            runtime.sequence_reset(worker_index);
+            assert_eq!(runtime.sequence_peek(worker_index), 0);
+            assert_eq!(runtime.sequence_next(worker_index), 0);
+            assert_eq!(runtime.sequence_next(worker_index), 1);
+        });
+
+        hruntime.join().unwrap();
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn test_kill_static() {
@@ -442,4 +859,177 @@ mod tests {
         sleep(Duration::from_millis(100));
         hruntime.kill().unwrap();
     }
+
+    // Panics in worker threads are reported by `RuntimeHandle::join`.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_worker_panic() {
+        let hruntime = Runtime::run(4, || {
+            if Runtime::worker_index() == 2 {
+                panic!("worker {} failed", Runtime::worker_index());
+            }
+        });
+
+        assert_eq!(
+            hruntime.join().unwrap_err(),
+            Error::WorkerPanic {
+                worker: 2,
+                message: "worker 2 failed".to_string()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_worker_panic_hook() {
+        let hruntime = Runtime::run_with_panic_hook(4, || {
+            if Runtime::worker_index() == 1 {
+                panic!("known message");
+            }
+        });
+
+        match hruntime.join().unwrap_err() {
+            Error::WorkerPanic { worker, message } => {
+                assert_eq!(worker, 1);
+                assert!(message.starts_with("known message at "));
+                assert!(message.contains("runtime.rs"));
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+
+    // Each worker is pinned to its own core.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_run_pinned() {
+        let core_ids = core_affinity::get_core_ids().unwrap_or_default();
+        if core_ids.len() < 2 {
+            // Not enough cores to check that workers are pinned to distinct
+            // cores.
+            return;
+        }
+        let core_ids = &core_ids[..core_ids.len().min(4)];
+
+        let cores = Arc::new(Mutex::new(Vec::new()));
+        let cores_clone = cores.clone();
+        Runtime::run_pinned(core_ids, move || {
+            cores_clone
+                .lock()
+                .unwrap()
+                .push((Runtime::worker_index(), Runtime::worker_core()));
+        })
+        .join()
+        .unwrap();
+
+        let mut cores = cores.lock().unwrap().clone();
+        cores.sort_by_key(|(worker, _)| *worker);
+        assert_eq!(
+            cores,
+            core_ids
+                .iter()
+                .enumerate()
+                .map(|(worker, core_id)| (worker, Some(*core_id)))
+                .collect::<Vec<_>>()
+        );
+
+        // Threads outside of a runtime are not pinned.
+        assert_eq!(Runtime::worker_core(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(miri, ignore)]
+    fn test_operator_metrics() {
+        let hruntime = Runtime::run(2, || {
+            let root = RootCircuit::build(|circuit| {
+                circuit
+                    .add_source(Generator::new(|| 1usize))
+                    .apply_named("Double", |n: &usize| n * 2)
+                    .inspect(|_: &usize| {});
+            })
+            .unwrap()
+            .0;
+
+            for _ in 0..10 {
+                root.step().unwrap();
+            }
+        });
+
+        let runtime = hruntime.runtime().clone();
+        hruntime.join().unwrap();
+
+        let metrics = runtime.operator_metrics();
+        for name in ["Generator", "Double", "Inspect"] {
+            // 10 steps in each of the 2 workers.
+            assert_eq!(metrics[name].invocations, 20, "operator {name}");
+        }
+    }
+
+    // Pause and resume workers driven by `Runtime::run`.
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_pause_resume() {
+        const WORKERS: usize = 4;
+        const STEPS: usize = 6;
+        const PAUSE_STEP: usize = 3;
+
+        let (step_sender, step_receiver) = unbounded();
+
+        let hruntime = Runtime::run(WORKERS, move || {
+            let worker = Runtime::worker_index();
+
+            let root = RootCircuit::build(move |circuit| {
+                let mut step = 0;
+                circuit
+                    .add_source(Generator::new(move || {
+                        step += 1;
+                        // The exchange below keeps workers in lockstep, so no
+                        // worker has started clock cycle `PAUSE_STEP + 1` yet.
+                        if worker == 0 && step == PAUSE_STEP {
+                            Runtime::runtime().unwrap().pause();
+                        }
+                        zset! { worker => 1 }
+                    }))
+                    .shard();
+            })
+            .unwrap()
+            .0;
+
+            for step in 1..=STEPS {
+                if root.step().is_err() {
+                    return;
+                }
+                step_sender.send((worker, step)).unwrap();
+            }
+
+            // Keep stepping until killed.
+            while root.step().is_ok() {}
+        });
+
+        // All workers complete exactly `PAUSE_STEP` clock cycles.
+        let mut completed = vec![0; WORKERS];
+        for _ in 0..WORKERS * PAUSE_STEP {
+            let (worker, step) = step_receiver.recv().unwrap();
+            completed[worker] = step;
+        }
+        assert_eq!(completed, vec![PAUSE_STEP; WORKERS]);
+        assert!(hruntime.is_paused());
+        assert_eq!(
+            step_receiver.recv_timeout(Duration::from_millis(100)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        // The circuit advances after resuming.
+        hruntime.resume();
+        assert!(!hruntime.is_paused());
+        for _ in 0..WORKERS * (STEPS - PAUSE_STEP) {
+            let (worker, step) = step_receiver.recv().unwrap();
+            completed[worker] = step;
+        }
+        assert_eq!(completed, vec![STEPS; WORKERS]);
+
+        // Killing a paused runtime unblocks its workers.
+        hruntime.pause();
+        hruntime.kill().unwrap();
+    }
 }
@@ -28,6 +28,18 @@ pub enum Error {
     /// Execution of the circuit interrupted by the user (via
     /// [`RuntimeHandle::kill`](`crate::circuit::RuntimeHandle::kill`)).
     Killed,
+    /// User closure of operator `operator` panicked with `message`.  Only
+    /// reported for operators built inside
+    /// [`Stream::with_error_recovery`](`crate::Stream::with_error_recovery`).
+    OperatorPanic {
+        node_id: GlobalNodeId,
+        operator: String,
+        message: String,
+    },
+    /// Input or output operator `operator` failed to read, parse, or write
+    /// external data (see
+    /// [`report_input_error`](`crate::operator::report_input_error`)).
+    Input { operator: String, error: String },
 }
 
 impl Display for Error {
@@ -41,6 +53,12 @@ impl Display for Error {
                 write!(f, "unschedulable circuit due to a cyclic topology: cycle through node '{node_id}'")
             }
             Self::Killed => f.write_str("circuit has been killed by the user"),
+            Self::OperatorPanic { node_id, operator, message } => {
+                write!(f, "operator '{operator}' (node '{node_id}') panicked: {message}")
+            }
+            Self::Input { operator, error } => {
+                write!(f, "input error in operator '{operator}': {error}")
+            }
         }
     }
 }
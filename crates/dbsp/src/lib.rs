@@ -28,6 +28,8 @@ pub use circuit::{
     ChildCircuit, Circuit, CircuitHandle, DBSPHandle, RootCircuit, Runtime, RuntimeError,
     SchedulerError, Stream,
 };
-pub use operator::{CollectionHandle, InputHandle, OutputHandle, UpsertHandle};
+pub use operator::{
+    CollectionHandle, DynCollectionHandle, InputHandle, OutputHandle, UpsertHandle,
+};
 pub use trace::ord::{OrdIndexedZSet, OrdZSet};
 pub use trace::{DBData, DBTimestamp, DBWeight};
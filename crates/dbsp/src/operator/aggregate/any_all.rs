@@ -0,0 +1,249 @@
+use crate::{
+    algebra::{IndexedZSet, MonoidValue, Semigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: Clone + 'static,
+{
+    /// Incrementally check whether any value associated with each key
+    /// satisfies `predicate`.
+    ///
+    /// Outputs `true` for a key as soon as a value with non-zero weight that
+    /// satisfies `predicate` appears and switches back to `false` when the
+    /// last such value is retracted.  Keys without values are removed from
+    /// the output.
+    ///
+    /// The operator reevaluates the group of each modified key, but stops
+    /// scanning the group at the first matching value.
+    pub fn aggregate_any<F>(&self, predicate: F) -> Stream<C, OrdIndexedZSet<Z::Key, bool, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        F: Fn(&Z::Val) -> bool + Clone + 'static,
+    {
+        self.aggregate(Any::new(predicate))
+    }
+
+    /// Incrementally check whether all values associated with each key
+    /// satisfy `predicate`.
+    ///
+    /// Outputs `false` for a key as soon as a value with non-zero weight that
+    /// doesn't satisfy `predicate` appears and switches back to `true` when
+    /// the last such value is retracted.  Keys without values are removed
+    /// from the output.
+    ///
+    /// The operator reevaluates the group of each modified key, but stops
+    /// scanning the group at the first value that doesn't match.
+    pub fn aggregate_all<F>(&self, predicate: F) -> Stream<C, OrdIndexedZSet<Z::Key, bool, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        F: Fn(&Z::Val) -> bool + Clone + 'static,
+    {
+        self.aggregate(All::new(predicate))
+    }
+}
+
+/// Scans the values with non-zero weights in `cursor` until it finds one for
+/// which `predicate` returns `stop_at`.
+///
+/// Returns `Some(stop_at)` if such a value exists, `Some(!stop_at)` if there
+/// is no such value, and `None` if there are no values with non-zero
+/// weights.
+fn short_circuit<V, T, R, F, C>(cursor: &mut C, predicate: &F, stop_at: bool) -> Option<bool>
+where
+    T: Timestamp,
+    R: MonoidValue,
+    F: Fn(&V) -> bool,
+    C: Cursor<V, (), T, R>,
+{
+    let mut result = None;
+
+    while cursor.key_valid() {
+        let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+            acc.add_assign_by_ref(weight);
+            acc
+        });
+
+        if !weight.is_zero() {
+            if predicate(cursor.key()) == stop_at {
+                return Some(stop_at);
+            }
+            result = Some(!stop_at);
+        }
+
+        cursor.step_key();
+    }
+
+    result
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns `true` if
+/// any value with non-zero weight satisfies a predicate (see
+/// [`Stream::aggregate_any`]).
+#[derive(Clone)]
+pub struct Any<F> {
+    predicate: F,
+}
+
+impl<F> Any<F> {
+    /// Create an `Any` aggregator with predicate `predicate`.
+    pub fn new(predicate: F) -> Self {
+        Self { predicate }
+    }
+}
+
+/// Semigroup structure over the accumulator of the [`Any`] aggregator:
+/// logical OR.
+#[derive(Clone)]
+pub struct AnySemigroup;
+
+impl Semigroup<bool> for AnySemigroup {
+    fn combine(left: &bool, right: &bool) -> bool {
+        *left || *right
+    }
+}
+
+impl<V, T, R, F> Aggregator<V, T, R> for Any<F>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    F: Fn(&V) -> bool + Clone + 'static,
+{
+    type Accumulator = bool;
+    type Output = bool;
+    type Semigroup = AnySemigroup;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        short_circuit(cursor, &self.predicate, true)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns `true` if
+/// all values with non-zero weights satisfy a predicate (see
+/// [`Stream::aggregate_all`]).
+#[derive(Clone)]
+pub struct All<F> {
+    predicate: F,
+}
+
+impl<F> All<F> {
+    /// Create an `All` aggregator with predicate `predicate`.
+    pub fn new(predicate: F) -> Self {
+        Self { predicate }
+    }
+}
+
+/// Semigroup structure over the accumulator of the [`All`] aggregator:
+/// logical AND.
+#[derive(Clone)]
+pub struct AllSemigroup;
+
+impl Semigroup<bool> for AllSemigroup {
+    fn combine(left: &bool, right: &bool) -> bool {
+        *left && *right
+    }
+}
+
+impl<V, T, R, F> Aggregator<V, T, R> for All<F>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    F: Fn(&V) -> bool + Clone + 'static,
+{
+    type Accumulator = bool;
+    type Output = bool;
+    type Semigroup = AllSemigroup;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        short_circuit(cursor, &self.predicate, false)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn any_all_transitions() {
+        let (mut circuit, (mut input, any, all)) = Runtime::init_circuit(4, |circuit| {
+            // auction -> bid; the reserve price is 100.
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let any = input.aggregate_any(|bid| *bid > 100).integrate().output();
+            let all = input.aggregate_all(|bid| *bid > 100).integrate().output();
+
+            (input_handle, any, all)
+        })
+        .unwrap();
+
+        input.append(&mut vec![(1, (50, 1)), (1, (150, 1)), (2, (200, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            any.consolidate(),
+            indexed_zset! { 1 => { true => 1 }, 2 => { true => 1 } }
+        );
+        assert_eq!(
+            all.consolidate(),
+            indexed_zset! { 1 => { false => 1 }, 2 => { true => 1 } }
+        );
+
+        // Retracting the last matching value of key 1 flips `any` to `false`.
+        input.append(&mut vec![(1, (150, -1)), (1, (60, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            any.consolidate(),
+            indexed_zset! { 1 => { false => 1 }, 2 => { true => 1 } }
+        );
+        // Retracting the last non-matching value of key 1 flips `all` to
+        // `true`, and a new matching value flips `any` back to `true`.
+        input.append(&mut vec![(1, (50, -1)), (1, (60, -1)), (1, (120, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            any.consolidate(),
+            indexed_zset! { 1 => { true => 1 }, 2 => { true => 1 } }
+        );
+        assert_eq!(
+            all.consolidate(),
+            indexed_zset! { 1 => { true => 1 }, 2 => { true => 1 } }
+        );
+
+        // A non-matching value flips `all` to `false`.
+        input.append(&mut vec![(2, (10, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            all.consolidate(),
+            indexed_zset! { 1 => { true => 1 }, 2 => { false => 1 } }
+        );
+
+        // Keys without values disappear from the output.
+        input.append(&mut vec![(2, (10, -1)), (2, (200, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(any.consolidate(), indexed_zset! { 1 => { true => 1 } });
+        assert_eq!(all.consolidate(), indexed_zset! { 1 => { true => 1 } });
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{
+    algebra::{IndexedZSet, MonoidValue, Semigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use std::{cmp::Ordering, marker::PhantomData};
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: Clone + 'static,
+{
+    /// Incrementally compute the value with the largest ordering key in each
+    /// group and return its projection.
+    ///
+    /// For each key in the input indexed Z-set, finds the value `v` with
+    /// non-zero weight that maximizes `key(v)` and outputs `project(v)`.
+    /// Among values with equal ordering keys, the smallest value wins, so
+    /// the output is deterministic.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // The bidder who made the highest bid per auction.
+    /// let winners = bids.aggregate_argmax(|bid: &Bid| bid.price, |bid: &Bid| bid.bidder);
+    /// ```
+    pub fn aggregate_argmax<K, P, KF, PF>(
+        &self,
+        key: KF,
+        project: PF,
+    ) -> Stream<C, OrdIndexedZSet<Z::Key, P, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        K: DBData,
+        P: DBData,
+        KF: Fn(&Z::Val) -> K + Clone + 'static,
+        PF: Fn(&Z::Val) -> P + Clone + 'static,
+    {
+        self.aggregate(ArgMax::new(key, project))
+    }
+
+    /// Incrementally compute the value with the smallest ordering key in
+    /// each group and return its projection.
+    ///
+    /// Like [`Self::aggregate_argmax`], but finds the value that minimizes
+    /// `key(v)`.  Among values with equal ordering keys, the smallest value
+    /// wins.
+    pub fn aggregate_argmin<K, P, KF, PF>(
+        &self,
+        key: KF,
+        project: PF,
+    ) -> Stream<C, OrdIndexedZSet<Z::Key, P, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        K: DBData,
+        P: DBData,
+        KF: Fn(&Z::Val) -> K + Clone + 'static,
+        PF: Fn(&Z::Val) -> P + Clone + 'static,
+    {
+        self.aggregate(ArgMin::new(key, project))
+    }
+}
+
+/// Returns the `(key, value)` pair that wins according to `order` applied to
+/// the keys, breaking ties in favor of the smaller value.
+fn select<K, V>(left: &(K, V), right: &(K, V), order: Ordering) -> (K, V)
+where
+    K: Ord + Clone,
+    V: Ord + Clone,
+{
+    match left.0.cmp(&right.0) {
+        Ordering::Equal if left.1 <= right.1 => left.clone(),
+        Ordering::Equal => right.clone(),
+        ordering if ordering == order => left.clone(),
+        _ => right.clone(),
+    }
+}
+
+/// Scans the values with non-zero weights in `cursor` and returns the
+/// `(key(v), v)` pair that wins according to `order`.
+fn scan<V, T, R, K, KF, C>(cursor: &mut C, key: &KF, order: Ordering) -> Option<(K, V)>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    K: Ord + Clone,
+    KF: Fn(&V) -> K,
+    C: Cursor<V, (), T, R>,
+{
+    let mut result: Option<(K, V)> = None;
+
+    while cursor.key_valid() {
+        let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+            acc.add_assign_by_ref(weight);
+            acc
+        });
+
+        if !weight.is_zero() {
+            let candidate = (key(cursor.key()), cursor.key().clone());
+            result = Some(match result {
+                None => candidate,
+                Some(current) => select(&current, &candidate, order),
+            });
+        }
+
+        cursor.step_key();
+    }
+
+    result
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns the
+/// projection of the value with the largest ordering key (see
+/// [`Stream::aggregate_argmax`]).
+#[derive(Clone)]
+pub struct ArgMax<KF, PF> {
+    key: KF,
+    project: PF,
+}
+
+impl<KF, PF> ArgMax<KF, PF> {
+    /// Create an `ArgMax` aggregator with ordering key function `key` and
+    /// projection `project`.
+    pub fn new(key: KF, project: PF) -> Self {
+        Self { key, project }
+    }
+}
+
+/// Semigroup structure over the accumulator of the [`ArgMax`] aggregator.
+#[derive(Clone)]
+pub struct ArgMaxSemigroup<K, V>(PhantomData<(K, V)>);
+
+impl<K, V> Semigroup<(K, V)> for ArgMaxSemigroup<K, V>
+where
+    K: Ord + Clone,
+    V: Ord + Clone,
+{
+    fn combine(left: &(K, V), right: &(K, V)) -> (K, V) {
+        select(left, right, Ordering::Greater)
+    }
+}
+
+impl<V, T, R, K, P, KF, PF> Aggregator<V, T, R> for ArgMax<KF, PF>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    K: DBData,
+    P: DBData,
+    KF: Fn(&V) -> K + Clone + 'static,
+    PF: Fn(&V) -> P + Clone + 'static,
+{
+    type Accumulator = (K, V);
+    type Output = P;
+    type Semigroup = ArgMaxSemigroup<K, V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        scan(cursor, &self.key, Ordering::Greater)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        (self.project)(&accumulator.1)
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns the
+/// projection of the value with the smallest ordering key (see
+/// [`Stream::aggregate_argmin`]).
+#[derive(Clone)]
+pub struct ArgMin<KF, PF> {
+    key: KF,
+    project: PF,
+}
+
+impl<KF, PF> ArgMin<KF, PF> {
+    /// Create an `ArgMin` aggregator with ordering key function `key` and
+    /// projection `project`.
+    pub fn new(key: KF, project: PF) -> Self {
+        Self { key, project }
+    }
+}
+
+/// Semigroup structure over the accumulator of the [`ArgMin`] aggregator.
+#[derive(Clone)]
+pub struct ArgMinSemigroup<K, V>(PhantomData<(K, V)>);
+
+impl<K, V> Semigroup<(K, V)> for ArgMinSemigroup<K, V>
+where
+    K: Ord + Clone,
+    V: Ord + Clone,
+{
+    fn combine(left: &(K, V), right: &(K, V)) -> (K, V) {
+        select(left, right, Ordering::Less)
+    }
+}
+
+impl<V, T, R, K, P, KF, PF> Aggregator<V, T, R> for ArgMin<KF, PF>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    K: DBData,
+    P: DBData,
+    KF: Fn(&V) -> K + Clone + 'static,
+    PF: Fn(&V) -> P + Clone + 'static,
+{
+    type Accumulator = (K, V);
+    type Output = P;
+    type Semigroup = ArgMinSemigroup<K, V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        scan(cursor, &self.key, Ordering::Less)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        (self.project)(&accumulator.1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn argmax_argmin_ties() {
+        let (mut circuit, (mut input, argmax, argmin)) = Runtime::init_circuit(4, |circuit| {
+            // auction -> (price, bidder)
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, (u64, u64), isize>();
+            let argmax = input
+                .aggregate_argmax(|(price, _)| *price, |(_, bidder)| *bidder)
+                .integrate()
+                .output();
+            let argmin = input
+                .aggregate_argmin(|(price, _)| *price, |(_, bidder)| *bidder)
+                .integrate()
+                .output();
+
+            (input_handle, argmax, argmin)
+        })
+        .unwrap();
+
+        // Bidders 7 and 5 tie for the highest bid in auction 1; bidders 9 and 8
+        // tie for the lowest bid.  The smaller value `(price, bidder)`, i.e.,
+        // the smaller bidder, wins.
+        input.append(&mut vec![
+            (1, ((100, 7), 1)),
+            (1, ((100, 5), 1)),
+            (1, ((10, 9), 1)),
+            (1, ((10, 8), 1)),
+            (1, ((50, 1), 1)),
+            (2, ((20, 3), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            argmax.consolidate(),
+            indexed_zset! { 1 => { 5 => 1 }, 2 => { 3 => 1 } }
+        );
+        assert_eq!(
+            argmin.consolidate(),
+            indexed_zset! { 1 => { 8 => 1 }, 2 => { 3 => 1 } }
+        );
+
+        // Retracting the winners promotes the other tied values.
+        input.append(&mut vec![(1, ((100, 5), -1)), (1, ((10, 8), -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            argmax.consolidate(),
+            indexed_zset! { 1 => { 7 => 1 }, 2 => { 3 => 1 } }
+        );
+        assert_eq!(
+            argmin.consolidate(),
+            indexed_zset! { 1 => { 9 => 1 }, 2 => { 3 => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
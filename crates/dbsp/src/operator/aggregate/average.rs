@@ -1,13 +1,18 @@
 use crate::{
     algebra::{
         AddAssignByRef, AddByRef, GroupValue, HasOne, HasZero, IndexedZSet, MulByRef, NegByRef,
-        ZRingValue,
+        ZRingValue, F64,
     },
     circuit::WithClock,
-    trace::layers::{column_layer::ColumnLayer, ordered::OrderedLayer},
+    operator::FilterMap,
+    trace::{
+        layers::{column_layer::ColumnLayer, ordered::OrderedLayer},
+        Batch,
+    },
     utils::VecExt,
-    Circuit, DBData, DBTimestamp, DBWeight, OrdIndexedZSet, Stream,
+    Circuit, DBData, DBTimestamp, DBWeight, OrdIndexedZSet, OrdZSet, Stream,
 };
+use num::traits::AsPrimitive;
 use size_of::SizeOf;
 use std::{
     hash::Hash,
@@ -244,6 +249,35 @@ where
 
         average
     }
+
+    /// Incremental average aggregate with floating point output.
+    ///
+    /// Like [`Stream::average`], but outputs a Z-set of `(key, average)`
+    /// pairs, where the average is computed by dividing the exact sum and
+    /// count of the values of each key in `f64` arithmetic.
+    ///
+    /// Keys whose count drops to zero, e.g., because all of their values
+    /// have been retracted, are removed from the output.
+    #[allow(clippy::type_complexity)]
+    #[track_caller]
+    pub fn aggregate_average<A, F>(&self, f: F) -> Stream<C, OrdZSet<(Z::Key, F64), Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue + AsPrimitive<f64>,
+        Avg<A, Z::R>: MulByRef<Z::R, Output = Avg<A, Z::R>>,
+        A: DBData + From<Z::R> + GroupValue + AsPrimitive<f64>,
+        F: Fn(&Z::Key, &Z::Val) -> A + Clone + 'static,
+    {
+        self.aggregate_linear(move |key, val| Avg::new(f(key, val), Z::R::one()))
+            .flat_map(|(key, avg)| {
+                if avg.count.is_zero() {
+                    None
+                } else {
+                    let average = avg.sum.as_() / avg.count.as_();
+                    Some((key.clone(), F64::new(average)))
+                }
+            })
+    }
 }
 
 /// The gist of what we're doing here is this:
@@ -265,7 +299,7 @@ where
 /// containing an extra `isize` field
 fn apply_average<K, A, R, W>(aggregate: OrdIndexedZSet<K, Avg<A, R>, W>) -> OrdIndexedZSet<K, A, W>
 where
-    K: Ord,
+    K: DBData,
     A: DBData + From<R> + Div<Output = A>,
     R: DBData + ZRingValue,
     W: DBWeight,
@@ -294,6 +328,27 @@ where
         unsafe { averages.push_unchecked(avg.compute_avg().unwrap()) };
     }
 
+    // Distinct `(sum, count)` pairs of the same key can have equal averages or
+    // averages that are ordered differently from the pairs themselves, e.g.,
+    // when an update changes both the sum and the count of a key but not their
+    // ratio.  In this rare case we fall back to building a consolidated batch.
+    let ordered = offs.windows(2).all(|range| {
+        averages[range[0]..range[1]]
+            .windows(2)
+            .all(|pair| pair[0] < pair[1])
+    });
+    if !ordered {
+        let mut tuples = Vec::with_capacity(averages.len());
+        for (key, range) in keys.into_iter().zip(offs.windows(2)).skip(lower_bound) {
+            let (averages, diffs) = (&averages[range[0]..range[1]], &diffs[range[0]..range[1]]);
+            for (average, diff) in averages.iter().zip(diffs) {
+                tuples.push(((key.clone(), average.clone()), diff.clone()));
+            }
+        }
+
+        return OrdIndexedZSet::from_tuples((), tuples);
+    }
+
     // Safety: `averages.len() == diffs.len()`
     let averages = unsafe { ColumnLayer::from_parts(averages, diffs, lower_bound_avg) };
 
@@ -309,9 +364,12 @@ where
 #[cfg(test)]
 mod tests {
     use crate::{
+        algebra::F64,
         indexed_zset,
         operator::aggregate::average::{apply_average, Avg},
+        IndexedZSet, Runtime,
     };
+    use std::collections::BTreeMap;
 
     #[test]
     fn apply_average_smoke() {
@@ -350,4 +408,112 @@ mod tests {
             assert_eq!(decoded, input);
         }
     }
+
+    #[test]
+    fn average_retractions() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, isize, isize>();
+            let output = input.average(|_k, v| *v).integrate().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        let steps: Vec<Vec<(u64, (isize, isize))>> = vec![
+            vec![(1, (10, 1)), (1, (20, 1)), (2, (5, 3)), (3, (-7, 1))],
+            vec![(1, (30, 2)), (2, (5, -1)), (3, (-7, -1))],
+            // All values of key 2 are retracted; the key must disappear
+            // rather than produce a division by zero.
+            vec![(2, (5, -2)), (3, (1, 1))],
+        ];
+
+        // Non-incremental reference: recompute averages from the full
+        // contents of the input after each step.
+        let mut contents: BTreeMap<(u64, isize), isize> = BTreeMap::new();
+
+        for mut step in steps {
+            for (key, (val, weight)) in step.iter() {
+                *contents.entry((*key, *val)).or_default() += weight;
+            }
+            contents.retain(|_, weight| *weight != 0);
+
+            let mut sums: BTreeMap<u64, (isize, isize)> = BTreeMap::new();
+            for ((key, val), weight) in contents.iter() {
+                let (sum, count) = sums.entry(*key).or_default();
+                *sum += val * weight;
+                *count += weight;
+            }
+            let expected = sums
+                .into_iter()
+                .map(|(key, (sum, count))| (key, (sum / count, 1)))
+                .collect::<Vec<_>>();
+
+            input.append(&mut step);
+            circuit.step().unwrap();
+
+            let actual = output
+                .consolidate()
+                .iter()
+                .map(|(key, avg, weight)| (key, (avg, weight)))
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn aggregate_average_retractions() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, isize, isize>();
+            let output = input.aggregate_average(|_k, v| *v).integrate().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        let steps: Vec<Vec<(u64, (isize, isize))>> = vec![
+            vec![(1, (10, 1)), (1, (25, 1)), (2, (5, 3)), (3, (-7, 1))],
+            vec![(1, (30, 2)), (2, (5, -1)), (3, (-7, -1)), (4, (1, 1))],
+            // All values of key 2 are retracted, and the count of key 4 drops
+            // to zero while its sum does not.  Both keys must disappear
+            // rather than produce a division by zero.
+            vec![(2, (5, -2)), (3, (1, 1)), (4, (3, -1))],
+        ];
+
+        // Non-incremental reference: recompute averages from the full
+        // contents of the input after each step.
+        let mut contents: BTreeMap<(u64, isize), isize> = BTreeMap::new();
+
+        for mut step in steps {
+            for (key, (val, weight)) in step.iter() {
+                *contents.entry((*key, *val)).or_default() += weight;
+            }
+            contents.retain(|_, weight| *weight != 0);
+
+            let mut sums: BTreeMap<u64, (isize, isize)> = BTreeMap::new();
+            for ((key, val), weight) in contents.iter() {
+                let (sum, count) = sums.entry(*key).or_default();
+                *sum += val * weight;
+                *count += weight;
+            }
+            let expected = sums
+                .into_iter()
+                .filter(|(_, (_, count))| *count != 0)
+                .map(|(key, (sum, count))| ((key, F64::new(sum as f64 / count as f64)), 1))
+                .collect::<Vec<_>>();
+
+            input.append(&mut step);
+            circuit.step().unwrap();
+
+            let actual = output
+                .consolidate()
+                .iter()
+                .map(|(key, (), weight)| (key, weight))
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+
+        circuit.kill().unwrap();
+    }
 }
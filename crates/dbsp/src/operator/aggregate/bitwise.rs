@@ -0,0 +1,459 @@
+use crate::{
+    algebra::{
+        AddAssignByRef, AddByRef, HasOne, HasZero, IndexedZSet, MonoidValue, MulByRef, NegByRef,
+        Semigroup, ZRingValue,
+    },
+    circuit::WithClock,
+    operator::{aggregate::Aggregator, FilterMap},
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use size_of::SizeOf;
+use std::{
+    marker::PhantomData,
+    ops::{Add, AddAssign, BitAnd, BitOr, BitXor, Neg, Rem},
+};
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: Clone + 'static,
+{
+    /// Incrementally compute the bitwise OR of the values associated with
+    /// each key.
+    ///
+    /// Values with non-zero weights contribute to the aggregate once,
+    /// regardless of their weight.  Keys without values are removed from
+    /// the output.
+    ///
+    /// Bitwise OR is not invertible, so the aggregate of each modified key
+    /// is recomputed from the complete contents of its group.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_bit_or(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::Val, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        Z::Val: BitOr<Output = Z::Val>,
+    {
+        self.aggregate(BitwiseOr)
+    }
+
+    /// Incrementally compute the bitwise AND of the values associated with
+    /// each key.
+    ///
+    /// Values with non-zero weights contribute to the aggregate once,
+    /// regardless of their weight.  Keys without values are removed from
+    /// the output.
+    ///
+    /// Bitwise AND is not invertible, so the aggregate of each modified key
+    /// is recomputed from the complete contents of its group.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_bit_and(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::Val, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        Z::Val: BitAnd<Output = Z::Val>,
+    {
+        self.aggregate(BitwiseAnd)
+    }
+
+    /// Incrementally compute the bitwise XOR of the values associated with
+    /// each key.
+    ///
+    /// A value with weight `w` contributes to the aggregate `w` times, i.e.,
+    /// it cancels out if `w` is even.  Keys without values are removed from
+    /// the output; keys whose values cancel out have aggregate `0`.
+    ///
+    /// # Design
+    ///
+    /// Unlike bitwise AND and OR, XOR forms a group where each element is
+    /// its own inverse.  Hence the aggregate is linear: it is computed with
+    /// [`Stream::aggregate_linear`] over [`XorSum`], which pairs the XOR of
+    /// the values with their total weight, and is updated from input deltas
+    /// without rescanning the group.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_bit_xor(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::Val, Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue + From<i8> + Rem<Output = Z::R>,
+        Z::Val: BitXor<Output = Z::Val> + HasZero,
+    {
+        let aggregate =
+            self.aggregate_linear(move |_key, val| XorSum::new(val.clone(), Z::R::one()));
+        let xor = aggregate.map_index(|(key, sum)| (key.clone(), sum.value()));
+        xor.mark_sharded_if(&aggregate);
+
+        xor
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns the bitwise
+/// OR of the values with non-zero weights (see [`Stream::aggregate_bit_or`]).
+#[derive(Clone)]
+pub struct BitwiseOr;
+
+/// Semigroup structure over the accumulator of the [`BitwiseOr`] aggregator.
+#[derive(Clone)]
+pub struct BitwiseOrSemigroup<V>(PhantomData<V>);
+
+impl<V> Semigroup<V> for BitwiseOrSemigroup<V>
+where
+    V: BitOr<Output = V> + Clone,
+{
+    fn combine(left: &V, right: &V) -> V {
+        left.clone() | right.clone()
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for BitwiseOr
+where
+    V: DBData + BitOr<Output = V>,
+    T: Timestamp,
+    R: MonoidValue,
+{
+    type Accumulator = V;
+    type Output = V;
+    type Semigroup = BitwiseOrSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        fold_present(cursor, |acc, val| acc | val)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns the bitwise
+/// AND of the values with non-zero weights (see
+/// [`Stream::aggregate_bit_and`]).
+#[derive(Clone)]
+pub struct BitwiseAnd;
+
+/// Semigroup structure over the accumulator of the [`BitwiseAnd`]
+/// aggregator.
+#[derive(Clone)]
+pub struct BitwiseAndSemigroup<V>(PhantomData<V>);
+
+impl<V> Semigroup<V> for BitwiseAndSemigroup<V>
+where
+    V: BitAnd<Output = V> + Clone,
+{
+    fn combine(left: &V, right: &V) -> V {
+        left.clone() & right.clone()
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for BitwiseAnd
+where
+    V: DBData + BitAnd<Output = V>,
+    T: Timestamp,
+    R: MonoidValue,
+{
+    type Accumulator = V;
+    type Output = V;
+    type Semigroup = BitwiseAndSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        fold_present(cursor, |acc, val| acc & val)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// Folds the values with non-zero weights in `cursor` with `op`.  Returns
+/// `None` if there are no such values.
+fn fold_present<V, T, R, C, F>(cursor: &mut C, op: F) -> Option<V>
+where
+    V: Clone,
+    T: Timestamp,
+    R: MonoidValue,
+    C: Cursor<V, (), T, R>,
+    F: Fn(V, V) -> V,
+{
+    let mut result: Option<V> = None;
+
+    while cursor.key_valid() {
+        let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+            acc.add_assign_by_ref(weight);
+            acc
+        });
+
+        if !weight.is_zero() {
+            let val = cursor.key().clone();
+            result = Some(match result {
+                None => val,
+                Some(acc) => op(acc, val),
+            });
+        }
+
+        cursor.step_key();
+    }
+
+    result
+}
+
+/// Representation of a partially computed bitwise XOR aggregate as a `(value,
+/// count)` tuple (see [`Stream::aggregate_bit_xor`]).
+///
+/// `value` is the XOR of all values in the group and `count` is their total
+/// weight, which distinguishes a group whose values cancel out from an empty
+/// group.  `XorSum` forms a commutative group with point-wise plus operation
+/// `(value1, count1) + (value2, count2) = (value1 ^ value2, count1 + count2)`.
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, SizeOf)]
+pub struct XorSum<T, R> {
+    value: T,
+    count: R,
+}
+
+impl<T, R> XorSum<T, R> {
+    /// Create a new `XorSum` object with the given `value` and `count`.
+    pub const fn new(value: T, count: R) -> Self {
+        Self { value, count }
+    }
+
+    /// Returns the `value` component of the `(value, count)` tuple.
+    pub fn value(&self) -> T
+    where
+        T: Clone,
+    {
+        self.value.clone()
+    }
+
+    /// Returns the `count` component of the `(value, count)` tuple.
+    pub fn count(&self) -> R
+    where
+        R: Clone,
+    {
+        self.count.clone()
+    }
+}
+
+impl<T, R> bincode::Encode for XorSum<T, R>
+where
+    T: bincode::Encode + bincode::Decode,
+    R: bincode::Encode + bincode::Decode,
+{
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> core::result::Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.value, encoder)?;
+        bincode::Encode::encode(&self.count, encoder)?;
+        Ok(())
+    }
+}
+
+impl<T, R> bincode::Decode for XorSum<T, R>
+where
+    T: bincode::Encode + bincode::Decode,
+    R: bincode::Encode + bincode::Decode,
+{
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let value: T = bincode::Decode::decode(decoder)?;
+        let count: R = bincode::Decode::decode(decoder)?;
+        Ok(Self::new(value, count))
+    }
+}
+
+impl<T, R> HasZero for XorSum<T, R>
+where
+    T: HasZero,
+    R: HasZero,
+{
+    fn is_zero(&self) -> bool {
+        self.value.is_zero() && self.count.is_zero()
+    }
+
+    fn zero() -> Self {
+        Self::new(T::zero(), R::zero())
+    }
+}
+
+impl<T, R> Add for XorSum<T, R>
+where
+    T: BitXor<Output = T>,
+    R: Add<Output = R>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.value ^ rhs.value, self.count + rhs.count)
+    }
+}
+
+impl<T, R> AddByRef for XorSum<T, R>
+where
+    T: BitXor<Output = T> + Clone,
+    R: AddByRef,
+{
+    fn add_by_ref(&self, other: &Self) -> Self {
+        Self::new(
+            self.value.clone() ^ other.value.clone(),
+            self.count.add_by_ref(&other.count),
+        )
+    }
+}
+
+impl<T, R> AddAssign for XorSum<T, R>
+where
+    T: BitXor<Output = T> + Clone,
+    R: AddAssign,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        self.value = self.value.clone() ^ rhs.value;
+        self.count += rhs.count;
+    }
+}
+
+impl<T, R> AddAssignByRef for XorSum<T, R>
+where
+    T: BitXor<Output = T> + Clone,
+    R: AddAssignByRef,
+{
+    fn add_assign_by_ref(&mut self, rhs: &Self) {
+        self.value = self.value.clone() ^ rhs.value.clone();
+        self.count.add_assign_by_ref(&rhs.count);
+    }
+}
+
+// Each value is its own inverse with respect to XOR.
+impl<T, R> Neg for XorSum<T, R>
+where
+    R: Neg<Output = R>,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(self.value, self.count.neg())
+    }
+}
+
+impl<T, R> NegByRef for XorSum<T, R>
+where
+    T: Clone,
+    R: NegByRef,
+{
+    fn neg_by_ref(&self) -> Self {
+        Self::new(self.value.clone(), self.count.neg_by_ref())
+    }
+}
+
+impl<T, R> MulByRef<R> for XorSum<T, R>
+where
+    T: HasZero + Clone,
+    R: MulByRef<Output = R> + Rem<Output = R> + HasZero,
+    // This bound is only here to prevent conflict with `MulByRef<Present>`.
+    R: From<i8> + Clone,
+{
+    type Output = XorSum<T, R>;
+
+    fn mul_by_ref(&self, rhs: &R) -> XorSum<T, R> {
+        // Adding a value to itself an even number of times cancels it out.
+        let value = if (rhs.clone() % R::from(2)).is_zero() {
+            T::zero()
+        } else {
+            self.value.clone()
+        };
+
+        Self::new(value, self.count.mul_by_ref(rhs))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn bit_or_and() {
+        let (mut circuit, (mut input, or, and)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u8, isize>();
+            let or = input.aggregate_bit_or().integrate().output();
+            let and = input.aggregate_bit_and().integrate().output();
+
+            (input_handle, or, and)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (0b0011, 1)),
+            (1, (0b0110, 2)),
+            (2, (0b1000, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            or.consolidate(),
+            indexed_zset! { 1 => { 0b0111 => 1 }, 2 => { 0b1000 => 1 } }
+        );
+        assert_eq!(
+            and.consolidate(),
+            indexed_zset! { 1 => { 0b0010 => 1 }, 2 => { 0b1000 => 1 } }
+        );
+
+        // Retractions force the group to be recomputed.  A value only stops
+        // contributing when its weight drops to zero.
+        input.append(&mut vec![(1, (0b0110, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            or.consolidate(),
+            indexed_zset! { 1 => { 0b0111 => 1 }, 2 => { 0b1000 => 1 } }
+        );
+
+        input.append(&mut vec![(1, (0b0110, -1)), (2, (0b1000, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(or.consolidate(), indexed_zset! { 1 => { 0b0011 => 1 } });
+        assert_eq!(and.consolidate(), indexed_zset! { 1 => { 0b0011 => 1 } });
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn bit_xor() {
+        let (mut circuit, (mut input, xor)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u8, isize>();
+            let xor = input.aggregate_bit_xor().integrate().output();
+
+            (input_handle, xor)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (0b0011, 1)),
+            (1, (0b0110, 1)),
+            (2, (0b1000, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            xor.consolidate(),
+            indexed_zset! { 1 => { 0b0101 => 1 }, 2 => { 0b1000 => 1 } }
+        );
+
+        // Adding a value twice cancels it out, but the key remains in the
+        // output.
+        input.append(&mut vec![(1, (0b0001, 2)), (2, (0b1000, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            xor.consolidate(),
+            indexed_zset! { 1 => { 0b0101 => 1 }, 2 => { 0 => 1 } }
+        );
+
+        // Retracting a value removes its contribution; retracting all values
+        // removes the key.
+        input.append(&mut vec![(1, (0b0110, -1)), (2, (0b1000, -2))]);
+        circuit.step().unwrap();
+        assert_eq!(xor.consolidate(), indexed_zset! { 1 => { 0b0011 => 1 } });
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    algebra::{HasOne, HasZero, IndexedZSet, ZRingValue},
+    operator::Aggregator,
+    trace::{Batch, BatchReader, Builder, Cursor},
+    OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+};
+use num::FromPrimitive;
+use std::{cmp::min, collections::BTreeMap, mem::take, ops::Neg};
+
+/// Records of a count window that hasn't been completed yet.
+struct OpenBlock<V, R> {
+    /// Sequence number of the block within its key.
+    index: u64,
+    /// Total weight of records in the block.
+    count: R,
+    /// Records in the block with their weights.
+    records: Vec<(V, R)>,
+}
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue + FromPrimitive,
+{
+    /// Aggregate consecutive blocks of `n` records of each key.
+    ///
+    /// Splits the records of each key into tumbling windows by record count
+    /// rather than by time: the first `n` records that arrive for a key form
+    /// block `0`, the next `n` records form block `1`, and so on.  Once a
+    /// block is complete, the operator applies `aggregator` to its records
+    /// and outputs `(key, (block_index, aggregate))` with weight `+1`.
+    /// Completed blocks never change, so the output stream only contains
+    /// insertions, and each completed block is output exactly once.
+    ///
+    /// A record with weight `w > 0` counts as `w` records and can span
+    /// multiple blocks.  Records that arrive for a key during the same
+    /// clock cycle are assigned to blocks in the order of their values.
+    ///
+    /// The operator stores the records of the open block of each key, and
+    /// releases them once the block is complete.  The sequence number of the
+    /// next block is retained for every key.
+    ///
+    /// # Retractions
+    ///
+    /// Retractions are only supported within the current open block of
+    /// each key, i.e., before the block is complete: a retracted record is
+    /// removed from the open block, making room for another record.
+    /// Retractions of records that belong to completed blocks are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero or cannot be represented as a weight.
+    #[allow(clippy::type_complexity)]
+    pub fn tumbling_count_window<A>(
+        &self,
+        n: usize,
+        aggregator: A,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (u64, A::Output), Z::R>>
+    where
+        A: Aggregator<Z::Val, (), Z::R>,
+    {
+        assert!(n > 0, "count window must contain at least one record");
+        let n = Z::R::from_usize(n).expect("count window size overflows the weight type");
+
+        let mut blocks: BTreeMap<Z::Key, OpenBlock<Z::Val, Z::R>> = BTreeMap::new();
+
+        self.shard()
+            .apply_named("TumblingCountWindow", move |batch: &Z| {
+                let mut builder =
+                    <OrdIndexedZSet<Z::Key, (u64, A::Output), Z::R> as Batch>::Builder::with_capacity(
+                        (),
+                        0,
+                    );
+
+                let mut cursor = batch.cursor();
+                while cursor.key_valid() {
+                    let block = blocks
+                        .entry(cursor.key().clone())
+                        .or_insert_with(|| OpenBlock {
+                            index: 0,
+                            count: Z::R::zero(),
+                            records: Vec::new(),
+                        });
+
+                    while cursor.val_valid() {
+                        let mut weight = cursor.weight();
+
+                        if weight.ge0() {
+                            while !weight.is_zero() {
+                                // Add as much of the record as fits in the
+                                // open block.
+                                let available = n.clone() + block.count.clone().neg();
+                                let added = min(weight.clone(), available);
+                                block.records.push((cursor.val().clone(), added.clone()));
+                                block.count += added.clone();
+                                weight += added.neg();
+
+                                if block.count == n {
+                                    let records =
+                                        OrdZSet::from_keys((), take(&mut block.records));
+                                    if let Some(agg) =
+                                        aggregator.aggregate_and_finalize(&mut records.cursor())
+                                    {
+                                        builder.push((
+                                            (cursor.key().clone(), (block.index, agg)),
+                                            Z::R::one(),
+                                        ));
+                                    }
+                                    block.index += 1;
+                                    block.count = Z::R::zero();
+                                }
+                            }
+                        } else {
+                            // Retract up to `-weight` copies of the record
+                            // from the open block.
+                            let mut retracted = weight.neg();
+                            while !retracted.is_zero() {
+                                match block
+                                    .records
+                                    .iter()
+                                    .position(|(val, _)| val == cursor.val())
+                                {
+                                    Some(pos) => {
+                                        let record_weight = &mut block.records[pos].1;
+                                        let removed = min(retracted.clone(), record_weight.clone());
+                                        *record_weight += removed.clone().neg();
+                                        if record_weight.is_zero() {
+                                            block.records.swap_remove(pos);
+                                        }
+                                        block.count += removed.clone().neg();
+                                        retracted += removed.neg();
+                                    }
+                                    None => break,
+                                }
+                            }
+                        }
+
+                        cursor.step_val();
+                    }
+
+                    cursor.step_key();
+                }
+
+                builder.done()
+            })
+            .mark_sharded()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{algebra::DefaultSemigroup, indexed_zset, operator::Fold, Runtime};
+
+    #[test]
+    fn tumbling_count_window_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let sum =
+                <Fold<_, DefaultSemigroup<_>, _, _>>::new(0, |sum: &mut i64, val: &i64, w| {
+                    *sum += val * w as i64
+                });
+            let output = input.tumbling_count_window(3, sum).output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        // Incomplete blocks produce no output.
+        input.append(&mut vec![(1, (1, 1)), (1, (2, 1)), (2, (10, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+
+        // Completing a block outputs its aggregate once.
+        input.append(&mut vec![(1, (3, 1)), (2, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! { 1 => { (0, 6) => 1 } });
+
+        // Two blocks completed in the same step; a record with weight 2
+        // counts as two records.
+        input.append(&mut vec![
+            (1, (4, 1)),
+            (1, (5, 2)),
+            (1, (6, 1)),
+            (1, (7, 1)),
+            (1, (8, 1)),
+            (2, (30, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (1, 14) => 1, (2, 21) => 1 }, 2 => { (0, 60) => 1 } }
+        );
+
+        // A retraction within the open block makes room for another record.
+        input.append(&mut vec![(2, (40, 1)), (2, (50, 1))]);
+        circuit.step().unwrap();
+        input.append(&mut vec![(2, (40, -1)), (2, (45, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+        input.append(&mut vec![(2, (60, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 2 => { (1, 155) => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn tumbling_count_window_weights() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let sum =
+                <Fold<_, DefaultSemigroup<_>, _, _>>::new(0, |sum: &mut i64, val: &i64, w| {
+                    *sum += val * w as i64
+                });
+            let output = input.tumbling_count_window(3, sum).output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        // A record with weight 7 fills two blocks and one slot of the third.
+        input.append(&mut vec![(1, (5, 7))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (0, 15) => 1, (1, 15) => 1 } }
+        );
+
+        // Retractions with weight > 1 remove multiple copies from the open
+        // block, but not more than it contains.
+        input.append(&mut vec![(1, (6, 1))]);
+        circuit.step().unwrap();
+        input.append(&mut vec![(1, (5, -4))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+        input.append(&mut vec![(1, (1, 2))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! { 1 => { (2, 8) => 1 } });
+
+        circuit.kill().unwrap();
+    }
+}
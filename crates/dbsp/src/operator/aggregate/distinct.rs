@@ -0,0 +1,108 @@
+use crate::{
+    algebra::{DefaultSemigroup, HasOne, IndexedZSet, ZRingValue},
+    operator::Aggregator,
+    trace::Cursor,
+    DBWeight, OrdIndexedZSet, RootCircuit, Stream, Timestamp,
+};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incrementally count the distinct values of each key.
+    ///
+    /// For each key, outputs the number of values whose net weight is
+    /// non-zero.  Unlike `self.distinct().count()`, this operator doesn't
+    /// materialize the deduplicated collection: it counts values directly
+    /// in the trace of `self`, which already groups values by key.  Keys
+    /// without such values are removed from the output.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_count_distinct(
+        &self,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::R, Z::R>> {
+        self.aggregate(CountDistinct)
+    }
+}
+
+/// Aggregator that counts values with non-zero weights.
+#[derive(Clone)]
+struct CountDistinct;
+
+impl<V, T, R> Aggregator<V, T, R> for CountDistinct
+where
+    T: Timestamp,
+    R: DBWeight + HasOne,
+{
+    type Accumulator = R;
+    type Output = R;
+    type Semigroup = DefaultSemigroup<R>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut count = R::zero();
+
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+            if !weight.is_zero() {
+                count.add_assign_by_ref(&R::one());
+            }
+
+            cursor.step_key();
+        }
+
+        (!count.is_zero()).then_some(count)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn aggregate_count_distinct() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let output = input.aggregate_count_distinct().integrate().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (10, 1)),
+            (1, (11, 2)),
+            (1, (12, 1)),
+            (2, (20, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 3 => 1 }, 2 => { 1 => 1 } }
+        );
+
+        // Partially retracting a value doesn't change the count.
+        input.append(&mut vec![(1, (11, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 3 => 1 }, 2 => { 1 => 1 } }
+        );
+
+        // Driving the weight of a value to zero decrements the count.
+        input.append(&mut vec![(1, (11, -1)), (2, (20, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! { 1 => { 2 => 1 } });
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    algebra::{
+        AddAssignByRef, FiniteHashMap, FiniteMap, GroupValue, HasOne, IndexedZSet, MulByRef,
+        ZRingValue,
+    },
+    trace::Cursor,
+    Circuit, DBData, RootCircuit, Stream,
+};
+use std::hash::Hash;
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::Key: Hash,
+    Z::R: ZRingValue,
+{
+    /// A version of [`Self::aggregate_linear`] that does not order its
+    /// output.
+    ///
+    /// Computes the same linear aggregate as `aggregate_linear`, i.e.,
+    /// `f` must satisfy `f(a+b) = f(a) + f(b)`, but keeps the aggregate of
+    /// each key in a hash table instead of an ordered trace and outputs
+    /// changes to the aggregate as a [`FiniteHashMap`] of `(key, aggregate)`
+    /// pairs.  This avoids sorting keys, which makes this operator faster
+    /// for high-cardinality groupings whose consumers don't need ordered
+    /// keys.
+    ///
+    /// As with `aggregate_linear`, keys whose aggregate is zero are absent
+    /// from the output.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_hashed<F, A>(
+        &self,
+        f: F,
+    ) -> Stream<RootCircuit, FiniteHashMap<(Z::Key, A), Z::R>>
+    where
+        F: Fn(&Z::Key, &Z::Val) -> A + 'static,
+        A: DBData + Hash + MulByRef<Z::R, Output = A> + GroupValue,
+    {
+        self.circuit().region("aggregate_hashed", || {
+            // Changes to the aggregate of each key.
+            let delta = self
+                .shard()
+                .apply_named("AggregateHashedDelta", move |batch: &Z| {
+                    let mut delta = FiniteHashMap::with_capacity(batch.key_count());
+                    let mut cursor = batch.cursor();
+                    while cursor.key_valid() {
+                        let mut agg = A::zero();
+                        while cursor.val_valid() {
+                            agg += f(cursor.key(), cursor.val()).mul_by_ref(&cursor.weight());
+                            cursor.step_val();
+                        }
+                        delta.increment_owned(cursor.key().clone(), agg);
+                        cursor.step_key();
+                    }
+                    delta
+                });
+
+            // Aggregates at the end of the previous clock cycle.
+            let aggregates = delta
+                .stream_fold(FiniteHashMap::new(), |mut aggregates, delta| {
+                    aggregates.add_assign_by_ref(delta);
+                    aggregates
+                })
+                .delay();
+
+            aggregates.apply2(&delta, |aggregates, delta| {
+                let mut output = FiniteHashMap::with_capacity(2 * delta.support_size());
+                for (key, change) in delta.iter() {
+                    let old = aggregates.lookup(key);
+                    let new = old.add_by_ref(change);
+                    if !old.is_zero() {
+                        output.increment_owned((key.clone(), old), -Z::R::one());
+                    }
+                    if !new.is_zero() {
+                        output.increment_owned((key.clone(), new), Z::R::one());
+                    }
+                }
+                output
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::FiniteHashMap,
+        operator::Generator,
+        trace::{Batch, BatchReader, Cursor},
+        Circuit, OrdIndexedZSet, RootCircuit,
+    };
+
+    const NUM_KEYS: u64 = 100;
+    const NUM_STEPS: usize = 10;
+
+    fn sorted_hashed(map: &FiniteHashMap<(u64, i64), isize>) -> Vec<((u64, i64), isize)> {
+        let mut result: Vec<_> = map.iter().map(|(k, w)| (*k, *w)).collect();
+        result.sort();
+        result
+    }
+
+    fn sorted_ordered(batch: &OrdIndexedZSet<u64, i64, isize>) -> Vec<((u64, i64), isize)> {
+        let mut result = Vec::new();
+        let mut cursor = batch.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                result.push(((*cursor.key(), *cursor.val()), cursor.weight()));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+        result
+    }
+
+    #[test]
+    fn aggregate_hashed_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            // Pseudo-random insertions and deletions of `(key, value)` pairs.
+            let mut step = 0;
+            let input = circuit.add_source(Generator::new(move || {
+                step += 1;
+                let tuples = (0..200u64)
+                    .map(|i| {
+                        let x = (i * 7919 + step * 104729) % 1000;
+                        let weight = if x % 3 == 0 { -1 } else { 1 };
+                        ((x % NUM_KEYS, (x % 17) as i64), weight)
+                    })
+                    .collect::<Vec<_>>();
+                OrdIndexedZSet::<u64, i64, isize>::from_tuples((), tuples)
+            }));
+
+            let hashed = input.aggregate_hashed(|_key, val| *val).integrate();
+            let ordered = input.aggregate_linear(|_key, val| *val).integrate();
+
+            hashed.apply2(&ordered, |hashed, ordered| {
+                assert_eq!(sorted_hashed(hashed), sorted_ordered(ordered))
+            });
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..NUM_STEPS {
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -17,6 +17,7 @@ use crate::{
         operator_traits::{BinaryOperator, Operator, UnaryOperator},
         Circuit, Scope, Stream, WithClock,
     },
+    operator::trace::share_retention,
     time::Timestamp,
     trace::{
         cursor::{Cursor, CursorGroup},
@@ -26,15 +27,33 @@ use crate::{
 };
 
 // Some standard aggregators.
+mod any_all;
+mod argmax;
 mod average;
+mod bitwise;
+mod count_window;
+mod distinct;
 mod fold;
+mod hashed;
 mod max;
 mod min;
-
+mod pivot;
+mod running;
+mod slice;
+mod string_agg;
+mod tdigest;
+mod variance;
+
+pub use any_all::{All, AllSemigroup, Any, AnySemigroup};
+pub use argmax::{ArgMax, ArgMaxSemigroup, ArgMin, ArgMinSemigroup};
 pub use average::Avg;
+pub use bitwise::{BitwiseAnd, BitwiseAndSemigroup, BitwiseOr, BitwiseOrSemigroup, XorSum};
 pub use fold::Fold;
 pub use max::{Max, MaxSemigroup};
 pub use min::{Min, MinSemigroup};
+pub use pivot::{Pivot, PivotSemigroup};
+pub use string_agg::{StringAgg, StringAggSemigroup};
+pub use variance::{Variance, VarianceSum};
 
 /// A trait for aggregator objects.  An aggregator summarizes the contents
 /// of a Z-set into a single value.
@@ -233,14 +252,17 @@ where
         //                └─────┘                  └────────────────────┘      └──────┘
         // ```
 
-        circuit
-            .add_binary_operator(
-                AggregateIncremental::new(aggregator, circuit.clone()),
-                &stream,
-                &stream.trace::<Spine<<<C as WithClock>::Time as Timestamp>::OrdValBatch<Z::Key, Z::Val, Z::R>>>(),
-            )
-            .upsert::<O>()
-            .mark_sharded()
+        let updates = circuit.add_binary_operator(
+            AggregateIncremental::new(aggregator, circuit.clone()),
+            &stream,
+            &stream.trace::<Spine<<<C as WithClock>::Time as Timestamp>::OrdValBatch<Z::Key, Z::Val, Z::R>>>(),
+        );
+
+        // The output trace maintained by `upsert` is indexed by the same keys
+        // as the input.
+        share_retention::<C, Z::Key>(circuit, stream.origin_node_id(), updates.origin_node_id());
+
+        updates.upsert::<O>().mark_sharded()
     }
 
     /// A version of [`Self::aggregate`] optimized for linear
@@ -272,6 +294,23 @@ where
         self.weigh(f).aggregate_generic(WeightedCount)
     }
 
+    /// Incrementally count the values associated with each key.
+    ///
+    /// Computes the total weight of all values associated with each key in
+    /// the input indexed Z-set.  Keys whose total weight is zero are removed
+    /// from the output.
+    ///
+    /// This is a shorthand for `aggregate_linear(|_, _| 1)`, so it only
+    /// processes keys modified by each input delta.
+    #[allow(clippy::type_complexity)]
+    pub fn count(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::R, Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue,
+    {
+        self.aggregate_linear(|_key, _val| Z::R::one())
+    }
+
     /// Convert indexed Z-set `Z` into a Z-set where the weight of each key
     /// is computed as:
     ///
@@ -997,6 +1036,49 @@ mod test {
         dbsp.kill().unwrap();
     }
 
+    #[test]
+    fn count_shorthand_test() {
+        let (mut dbsp, (mut input_handle, count, expected)) = Runtime::init_circuit(4, |circuit| {
+            let (input_stream, input_handle) =
+                circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let count = input_stream.count().output();
+            let expected = input_stream
+                .aggregate(<Fold<_, DefaultSemigroup<_>, _, _>>::new(
+                    0,
+                    |sum: &mut isize, _v: &usize, w: isize| *sum += w,
+                ))
+                .output();
+
+            (input_handle, count, expected)
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, (1, 1)), (1, (2, 2)), (2, (1, 1))]);
+        dbsp.step().unwrap();
+        let output = count.consolidate();
+        assert_eq!(output, indexed_zset! {1 => {3 => 1}, 2 => {1 => 1}});
+        assert_eq!(output, expected.consolidate());
+
+        input_handle.append(&mut vec![(1, (2, -1)), (2, (3, 1))]);
+        dbsp.step().unwrap();
+        let output = count.consolidate();
+        assert_eq!(
+            output,
+            indexed_zset! {1 => {3 => -1, 2 => 1}, 2 => {1 => -1, 2 => 1}}
+        );
+        assert_eq!(output, expected.consolidate());
+
+        // Retract all values of key 2.
+        input_handle.append(&mut vec![(2, (1, -1)), (2, (3, -1))]);
+        dbsp.step().unwrap();
+        let output = count.consolidate();
+        assert_eq!(output, indexed_zset! {2 => {2 => -1}});
+        assert_eq!(output, expected.consolidate());
+
+        dbsp.kill().unwrap();
+    }
+
     #[test]
     fn count_test1() {
         count_test(1);
@@ -0,0 +1,218 @@
+use crate::{
+    algebra::{IndexedZSet, MonoidValue, Semigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use std::marker::PhantomData;
+
+/// An [aggregator](`crate::operator::Aggregator`) that converts a set of
+/// `(attribute, value)` pairs into a fixed-width row.
+///
+/// The aggregator is parameterized by a list of attributes.  For each input
+/// `(attribute, value)` pair with non-zero weight it stores `value` in the
+/// slot of the output vector corresponding to `attribute`.  Slots of
+/// attributes that don't occur in the input are filled with `V::default()`.
+/// Pairs whose attribute is not in the list are ignored, so a key without
+/// any listed attributes produces no output row.
+///
+/// If the input contains multiple values for the same attribute, the largest
+/// one wins.
+#[derive(Clone)]
+pub struct Pivot<A> {
+    attributes: Vec<A>,
+}
+
+impl<A> Pivot<A> {
+    /// Create a `Pivot` aggregator over the given list of attributes.
+    pub fn new(attributes: Vec<A>) -> Self {
+        Self { attributes }
+    }
+
+    /// Attributes in the order in which they appear in output rows.
+    pub fn attributes(&self) -> &[A] {
+        &self.attributes
+    }
+}
+
+/// Semigroup over partially assembled rows that combines them slot-wise,
+/// preferring the right-hand value when both are present.
+#[derive(Clone)]
+pub struct PivotSemigroup<V>(PhantomData<V>);
+
+impl<V> Semigroup<Vec<Option<V>>> for PivotSemigroup<V>
+where
+    V: Clone,
+{
+    fn combine(left: &Vec<Option<V>>, right: &Vec<Option<V>>) -> Vec<Option<V>> {
+        left.iter()
+            .zip(right.iter())
+            .map(|(l, r)| r.as_ref().or(l.as_ref()).cloned())
+            .collect()
+    }
+}
+
+impl<A, V, T, R> Aggregator<(A, V), T, R> for Pivot<A>
+where
+    A: DBData,
+    V: DBData + Default,
+    T: Timestamp,
+    R: MonoidValue,
+{
+    type Accumulator = Vec<Option<V>>;
+    type Output = Vec<V>;
+    type Semigroup = PivotSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<(A, V), (), T, R>,
+    {
+        let mut row = vec![None; self.attributes.len()];
+        let mut non_empty = false;
+
+        while cursor.key_valid() {
+            let mut weight = R::zero();
+
+            cursor.map_times(|_t, w| weight.add_assign_by_ref(w));
+            if !weight.is_zero() {
+                let (attribute, value) = cursor.key();
+                if let Some(slot) = self.attributes.iter().position(|a| a == attribute) {
+                    row[slot] = Some(value.clone());
+                    non_empty = true;
+                }
+            }
+
+            cursor.step_key();
+        }
+
+        non_empty.then_some(row)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect()
+    }
+}
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: Clone + 'static,
+{
+    /// Pivot a long-format indexed Z-set into wide rows.
+    ///
+    /// Takes an indexed Z-set that maps each key to a set of
+    /// `(attribute, value)` pairs and, for each key, assembles a vector
+    /// with one slot per element of `attributes`, in the same order.  Slots
+    /// of attributes that have no value for the key are set to
+    /// `V::default()`.  See [`Pivot`] for details.
+    ///
+    /// This is an incremental operator: when the value of an attribute
+    /// changes, the operator retracts the old row of the affected key
+    /// and inserts an updated row.
+    #[allow(clippy::type_complexity)]
+    pub fn pivot<A, V>(&self, attributes: Vec<A>) -> Stream<C, OrdIndexedZSet<Z::Key, Vec<V>, Z::R>>
+    where
+        Z: IndexedZSet<Val = (A, V)> + Send,
+        Z::R: ZRingValue,
+        A: DBData,
+        V: DBData + Default,
+    {
+        self.aggregate(Pivot::new(attributes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn pivot_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) =
+                circuit.add_input_indexed_zset::<usize, (String, i64), isize>();
+            let output = input
+                .pivot(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+                .output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (("a".to_string(), 10), 1)),
+            (1, (("b".to_string(), 20), 1)),
+            (1, (("c".to_string(), 30), 1)),
+            (2, (("a".to_string(), 1), 1)),
+            (2, (("d".to_string(), 4), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { vec![10, 20, 30] => 1 }, 2 => { vec![1, 0, 0] => 1 } }
+        );
+
+        // Update a single attribute of key 1.
+        input.append(&mut vec![
+            (1, (("b".to_string(), 20), -1)),
+            (1, (("b".to_string(), 25), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { vec![10, 20, 30] => -1, vec![10, 25, 30] => 1 } }
+        );
+
+        // Add a missing attribute to key 2.
+        input.append(&mut vec![(2, (("c".to_string(), 3), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 2 => { vec![1, 0, 0] => -1, vec![1, 0, 3] => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn pivot_unlisted_attributes_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) =
+                circuit.add_input_indexed_zset::<usize, (String, i64), isize>();
+            let output = input.pivot(vec!["a".to_string(), "b".to_string()]).output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        // Key 1 only has attributes that are not in the pivot list.
+        input.append(&mut vec![
+            (1, (("c".to_string(), 3), 1)),
+            (1, (("d".to_string(), 4), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+
+        // Adding a listed attribute produces a row.
+        input.append(&mut vec![(1, (("b".to_string(), 2), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { vec![0, 2] => 1 } }
+        );
+
+        // Removing it retracts the row, even though unlisted attributes remain.
+        input.append(&mut vec![(1, (("b".to_string(), 2), -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { vec![0, 2] => -1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
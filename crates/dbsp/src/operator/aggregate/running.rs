@@ -0,0 +1,314 @@
+use crate::{
+    algebra::{HasOne, HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    trace::{Batch, BatchReader, Builder, Cursor},
+    Circuit, OrdIndexedZSet, RootCircuit, Stream,
+};
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeMap, marker::PhantomData};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incrementally compute the largest value of each key.
+    ///
+    /// Outputs changes to an indexed Z-set that maps each key in `self` to
+    /// the largest value associated with this key with weight `+1`, like
+    /// `self.aggregate(Max)`.  Unlike [`aggregate`](`Self::aggregate`),
+    /// this operator keeps the current maximum of each key in memory and is
+    /// optimized for append-only inputs, e.g., for tracking a watermark per
+    /// key: inserting new values only compares them with the current
+    /// maximum and replaces it if necessary, without scanning the values
+    /// accumulated for the key so far.
+    ///
+    /// Retracting a value other than the current maximum doesn't affect
+    /// the output.  Retracting the current maximum falls back to computing
+    /// the maximum of the key from scratch, which requires scanning the
+    /// accumulated values of the key backward until a value with non-zero
+    /// weight is found.
+    ///
+    /// The fast path assumes that all values in the accumulated input have
+    /// positive weights, as is the case for relations.
+    #[allow(clippy::type_complexity)]
+    pub fn running_max(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        self.circuit()
+            .region("running_max", || self.running_extreme(Ordering::Greater))
+    }
+
+    /// Incrementally compute the smallest value of each key.
+    ///
+    /// This is the dual of [`Self::running_max`]: inserting new values
+    /// compares them with the current minimum of the key, while retracting
+    /// the current minimum recomputes the minimum of the key from scratch.
+    #[allow(clippy::type_complexity)]
+    pub fn running_min(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        self.circuit()
+            .region("running_min", || self.running_extreme(Ordering::Less))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn running_extreme(
+        &self,
+        direction: Ordering,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        let stream = self.shard();
+
+        self.circuit()
+            .add_binary_operator(
+                RunningExtreme::new(direction),
+                &stream,
+                &stream.integrate_trace(),
+            )
+            .mark_sharded()
+    }
+}
+
+/// Operator that maintains the largest or smallest value of each key.
+///
+/// Takes a stream of changes and the integral of this stream, including
+/// the current change, and outputs changes to the extreme value of each
+/// key.  `direction` is `Ordering::Greater` for the maximum and
+/// `Ordering::Less` for the minimum.
+struct RunningExtreme<Z, I>
+where
+    Z: IndexedZSet,
+{
+    direction: Ordering,
+    // Current extreme value of each key with non-empty support.
+    extremes: BTreeMap<Z::Key, Z::Val>,
+    _type: PhantomData<I>,
+}
+
+impl<Z, I> RunningExtreme<Z, I>
+where
+    Z: IndexedZSet,
+{
+    fn new(direction: Ordering) -> Self {
+        Self {
+            direction,
+            extremes: BTreeMap::new(),
+            _type: PhantomData,
+        }
+    }
+
+    /// Returns `true` if `new` should replace `old` as the extreme value.
+    fn better(&self, new: &Z::Val, old: &Z::Val) -> bool {
+        new.cmp(old) == self.direction
+    }
+
+    /// Compute the extreme value of the current key of `cursor` from
+    /// scratch.
+    fn recompute<C>(&self, cursor: &mut C) -> Option<Z::Val>
+    where
+        C: Cursor<Z::Key, Z::Val, (), Z::R>,
+    {
+        if self.direction == Ordering::Greater {
+            cursor.fast_forward_vals();
+        }
+
+        while cursor.val_valid() {
+            if !cursor.weight().is_zero() {
+                return Some(cursor.val().clone());
+            }
+
+            if self.direction == Ordering::Greater {
+                cursor.step_val_reverse();
+            } else {
+                cursor.step_val();
+            }
+        }
+
+        None
+    }
+}
+
+impl<Z, I> Operator for RunningExtreme<Z, I>
+where
+    Z: IndexedZSet,
+    I: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("RunningExtreme")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, I> BinaryOperator<Z, I, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> for RunningExtreme<Z, I>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    I: BatchReader<Key = Z::Key, Val = Z::Val, Time = (), R = Z::R>,
+{
+    fn eval(&mut self, delta: &Z, integral: &I) -> OrdIndexedZSet<Z::Key, Z::Val, Z::R> {
+        let mut builder = <OrdIndexedZSet<Z::Key, Z::Val, Z::R> as Batch>::Builder::with_capacity(
+            (),
+            2 * delta.key_count(),
+        );
+
+        let mut delta_cursor = delta.cursor();
+        let mut integral_cursor = integral.cursor();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key().clone();
+            let old = self.extremes.get(&key).cloned();
+
+            // Fast path: compare inserted values with the current extreme.
+            // Fall back to recomputing the extreme if the current extreme
+            // is retracted.
+            let mut retracted = false;
+            let mut new = old.clone();
+            while delta_cursor.val_valid() {
+                let weight = delta_cursor.weight();
+                let val = delta_cursor.val();
+
+                if weight.ge0() {
+                    let replace = match &new {
+                        Some(extreme) => self.better(val, extreme),
+                        None => true,
+                    };
+                    if replace {
+                        new = Some(val.clone());
+                    }
+                } else if old.as_ref() == Some(val) {
+                    retracted = true;
+                }
+                delta_cursor.step_val();
+            }
+
+            if retracted {
+                integral_cursor.seek_key(&key);
+                new = if integral_cursor.key_valid() && integral_cursor.key() == &key {
+                    self.recompute(&mut integral_cursor)
+                } else {
+                    None
+                };
+            }
+
+            if old != new {
+                // Values of the key must be pushed to the builder in order.
+                let mut updates = Vec::with_capacity(2);
+                if let Some(old) = old {
+                    updates.push((old, -Z::R::one()));
+                }
+                if let Some(new) = &new {
+                    updates.push((new.clone(), Z::R::one()));
+                }
+                updates.sort_by(|(val1, _), (val2, _)| val1.cmp(val2));
+                for (val, weight) in updates {
+                    builder.push(((key.clone(), val), weight));
+                }
+
+                match new {
+                    Some(new) => {
+                        self.extremes.insert(key, new);
+                    }
+                    None => {
+                        self.extremes.remove(&key);
+                    }
+                }
+            }
+
+            delta_cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, operator::Max, Runtime};
+
+    #[test]
+    fn running_max_insert_only() {
+        let (mut circuit, (mut input, output, expected)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let output = input.running_max().integrate().output();
+            let expected = input.aggregate(Max).integrate().output();
+
+            (input_handle, output, expected)
+        })
+        .unwrap();
+
+        // Insert a few values per key at every step, some of which exceed the
+        // current maximum.
+        for step in 0..10u64 {
+            let mut tuples = (0..20u64)
+                .map(|i| (i % 5, ((i * 7 + step * 13) % 50, 1)))
+                .collect();
+            input.append(&mut tuples);
+            circuit.step().unwrap();
+            assert_eq!(output.consolidate(), expected.consolidate());
+        }
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn running_extreme_retraction() {
+        let (mut circuit, (mut input, max, min)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let max = input.running_max().integrate().output();
+            let min = input.running_min().integrate().output();
+
+            (input_handle, max, min)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (5, 1)),
+            (1, (3, 1)),
+            (1, (8, 1)),
+            (1, (1, 1)),
+            (2, (10, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            max.consolidate(),
+            indexed_zset! { 1 => { 8 => 1 }, 2 => { 10 => 1 } }
+        );
+        assert_eq!(
+            min.consolidate(),
+            indexed_zset! { 1 => { 1 => 1 }, 2 => { 10 => 1 } }
+        );
+
+        // Retracting values other than the extremes doesn't change the output.
+        input.append(&mut vec![(1, (3, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            max.consolidate(),
+            indexed_zset! { 1 => { 8 => 1 }, 2 => { 10 => 1 } }
+        );
+        assert_eq!(
+            min.consolidate(),
+            indexed_zset! { 1 => { 1 => 1 }, 2 => { 10 => 1 } }
+        );
+
+        // Retracting the extremes demotes them to the next value.
+        input.append(&mut vec![(1, (8, -1)), (1, (1, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            max.consolidate(),
+            indexed_zset! { 1 => { 5 => 1 }, 2 => { 10 => 1 } }
+        );
+        assert_eq!(
+            min.consolidate(),
+            indexed_zset! { 1 => { 5 => 1 }, 2 => { 10 => 1 } }
+        );
+
+        // Retracting the extreme while inserting a new value in the same step.
+        input.append(&mut vec![(1, (5, -1)), (1, (4, 1)), (2, (10, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(max.consolidate(), indexed_zset! { 1 => { 4 => 1 } });
+        assert_eq!(min.consolidate(), indexed_zset! { 1 => { 4 => 1 } });
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! Aggregation over slices of values.
+
+use crate::{
+    algebra::{HasOne, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    operator::group::{read_group, subtract_delta},
+    trace::{Batch, BatchReader, Cursor, Spine},
+    Circuit, DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use std::{borrow::Cow, marker::PhantomData};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incremental aggregation operator that presents the values associated
+    /// with each key as a slice.
+    ///
+    /// For each key `k` in the input indexed Z-set, `aggregate_func` is
+    /// invoked with `k` and a slice of all `(value, weight)` pairs
+    /// associated with `k`, sorted by value and with zero weights removed.
+    /// The result is added to the output indexed Z-set with weight `+1`.
+    /// Keys without values are not passed to `aggregate_func` and don't
+    /// appear in the output.
+    ///
+    /// Compared to [`Stream::aggregate`], which scans a cursor over the
+    /// values of a key, a slice gives the aggregation function random
+    /// access to the whole group, e.g., the number of distinct values of a
+    /// key is simply the length of the slice.
+    ///
+    /// The operator is incremental: when the values of a key change, it
+    /// evaluates `aggregate_func` over the old and the new contents of the
+    /// group, and outputs the difference between the two results.  Hence
+    /// `aggregate_func` must be deterministic.  Values of a key can be
+    /// spread across several batches of the integral of the input, so the
+    /// slice is assembled by merging them in a buffer that is reused across
+    /// keys and steps.
+    pub fn aggregate_slice<F, A>(
+        &self,
+        aggregate_func: F,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, A, Z::R>>
+    where
+        F: Fn(&Z::Key, &[(Z::Val, Z::R)]) -> A + 'static,
+        A: DBData,
+    {
+        self.circuit().region("aggregate_slice", || {
+            let stream = self.shard();
+
+            self.circuit()
+                .add_binary_operator(
+                    AggregateSlice::new(aggregate_func),
+                    &stream,
+                    &stream.integrate_trace(),
+                )
+                .mark_sharded()
+        })
+    }
+}
+
+/// Operator that applies a function to slices of values of keys modified
+/// by each input delta.
+///
+/// Takes the stream of changes to the input and its integral, including
+/// the current changes.
+struct AggregateSlice<Z, A, F>
+where
+    Z: IndexedZSet,
+{
+    aggregate_func: F,
+    // Buffers that hold the old and the new contents of a group.
+    old_values: Vec<(Z::Val, Z::R)>,
+    new_values: Vec<(Z::Val, Z::R)>,
+    _type: PhantomData<A>,
+}
+
+impl<Z, A, F> AggregateSlice<Z, A, F>
+where
+    Z: IndexedZSet,
+{
+    fn new(aggregate_func: F) -> Self {
+        Self {
+            aggregate_func,
+            old_values: Vec::new(),
+            new_values: Vec::new(),
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<Z, A, F> Operator for AggregateSlice<Z, A, F>
+where
+    Z: IndexedZSet,
+    A: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("AggregateSlice")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, A, F> BinaryOperator<Z, Spine<Z>, OrdIndexedZSet<Z::Key, A, Z::R>>
+    for AggregateSlice<Z, A, F>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    A: DBData,
+    F: Fn(&Z::Key, &[(Z::Val, Z::R)]) -> A + 'static,
+{
+    fn eval(&mut self, delta: &Z, trace: &Spine<Z>) -> OrdIndexedZSet<Z::Key, A, Z::R> {
+        let mut tuples = Vec::new();
+
+        let mut delta_cursor = delta.cursor();
+        let mut trace_cursor = trace.cursor();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key().clone();
+
+            // The trace includes the current delta, so it contains the new
+            // contents of the group.
+            read_group(&mut trace_cursor, &key, &mut self.new_values);
+            subtract_delta(&self.new_values, &mut delta_cursor, &mut self.old_values);
+
+            let old = (!self.old_values.is_empty())
+                .then(|| (self.aggregate_func)(&key, &self.old_values));
+            let new = (!self.new_values.is_empty())
+                .then(|| (self.aggregate_func)(&key, &self.new_values));
+
+            if old != new {
+                if let Some(old) = old {
+                    tuples.push(((key.clone(), old), -Z::R::one()));
+                }
+                if let Some(new) = new {
+                    tuples.push(((key.clone(), new), Z::R::one()));
+                }
+            }
+
+            delta_cursor.step_key();
+        }
+
+        OrdIndexedZSet::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{operator::Generator, trace::Batch, Circuit, OrdIndexedZSet, RootCircuit, Stream};
+
+    #[test]
+    fn distinct_bidders_per_auction() {
+        let circuit = RootCircuit::build(move |circuit| {
+            // Pseudo-random bids, including duplicate bids by the same
+            // bidder, and retractions of earlier bids.
+            let mut step = 0;
+            let mut history = Vec::new();
+            let bids: Stream<_, OrdIndexedZSet<u64, u64, isize>> =
+                circuit.add_source(Generator::new(move || {
+                    step += 1;
+                    let mut tuples = Vec::new();
+                    for i in 0..30u64 {
+                        let x = (i * 7919 + step * 104729) % 997;
+                        history.push((x % 10, x % 13));
+                        tuples.push(((x % 10, x % 13), 1));
+                    }
+                    for i in 0..20u64 {
+                        let bid = history.swap_remove(((i * 31 + step) as usize) % history.len());
+                        tuples.push((bid, -1));
+                    }
+                    OrdIndexedZSet::from_tuples((), tuples)
+                }));
+
+            let bidders = bids
+                .aggregate_slice(|_auction, bidders| bidders.len() as isize)
+                .integrate();
+            let expected = bids.distinct().count().integrate();
+
+            bidders.apply2(&expected, |bidders, expected| assert_eq!(bidders, expected));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..20 {
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    algebra::{Semigroup, ZRingValue},
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    DBData, DBWeight, Timestamp,
+};
+use std::{cmp::Ordering, marker::PhantomData};
+
+/// An [aggregator](`crate::operator::Aggregator`) that concatenates the
+/// values in a group, separated by a configurable separator, similar to
+/// SQL's `STRING_AGG`.
+///
+/// Values are concatenated in ascending order, so the output is
+/// deterministic.  A value with weight `w > 0` occurs `w` times in the
+/// output.  The accumulator stores each distinct value once along with its
+/// weight, and values are only repeated when building the output string.
+/// Groups without values with positive weights don't produce an output.
+///
+/// Concatenation has no inverse, so this aggregate cannot be updated
+/// incrementally.  When used with [`Stream::aggregate`](`crate::Stream::aggregate`),
+/// it is recomputed from the complete contents of each modified group.
+///
+/// # Panics
+///
+/// The aggregate of a group that contains a value with a negative weight is
+/// undefined, so the aggregator panics on such a group.
+#[derive(Clone)]
+pub struct StringAgg {
+    separator: String,
+}
+
+impl StringAgg {
+    /// Create a `StringAgg` aggregator that separates values with
+    /// `separator`.
+    pub fn new<S>(separator: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            separator: separator.into(),
+        }
+    }
+}
+
+/// Semigroup structure over the accumulator of the [`StringAgg`]
+/// aggregator: merges two vectors of values with their weights, sorted by
+/// value.
+#[derive(Clone)]
+pub struct StringAggSemigroup<V, R>(PhantomData<(V, R)>);
+
+impl<V, R> Semigroup<Vec<(V, R)>> for StringAggSemigroup<V, R>
+where
+    V: Ord + Clone,
+    R: ZRingValue,
+{
+    fn combine(left: &Vec<(V, R)>, right: &Vec<(V, R)>) -> Vec<(V, R)> {
+        let mut result = Vec::with_capacity(left.len() + right.len());
+
+        let mut left = left.iter().peekable();
+        let mut right = right.iter().peekable();
+        loop {
+            match (left.peek(), right.peek()) {
+                (Some((lval, lweight)), Some((rval, rweight))) => match lval.cmp(rval) {
+                    Ordering::Less => result.push(left.next().unwrap().clone()),
+                    Ordering::Greater => result.push(right.next().unwrap().clone()),
+                    Ordering::Equal => {
+                        result.push((lval.clone(), lweight.add_by_ref(rweight)));
+                        left.next();
+                        right.next();
+                    }
+                },
+                (Some(_), None) => result.extend(left.by_ref().cloned()),
+                (None, Some(_)) => result.extend(right.by_ref().cloned()),
+                (None, None) => break,
+            }
+        }
+
+        result
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for StringAgg
+where
+    V: DBData + AsRef<str>,
+    T: Timestamp,
+    R: DBWeight + ZRingValue,
+{
+    type Accumulator = Vec<(V, R)>;
+    type Output = String;
+    type Semigroup = StringAggSemigroup<V, R>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut values = Vec::new();
+
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+
+            assert!(
+                weight.ge0(),
+                "StringAgg: value {:?} has negative weight {:?} in a group",
+                cursor.key(),
+                weight,
+            );
+            if !weight.is_zero() {
+                values.push((cursor.key().clone(), weight));
+            }
+
+            cursor.step_key();
+        }
+
+        (!values.is_empty()).then_some(values)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        let mut result = String::new();
+        let mut first = true;
+        let minus_one = -R::one();
+
+        for (value, weight) in accumulator.iter() {
+            let mut weight = weight.clone();
+            while !weight.is_zero() {
+                if !first {
+                    result.push_str(&self.separator);
+                }
+                result.push_str(value.as_ref());
+                weight.add_assign_by_ref(&minus_one);
+                first = false;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, operator::StringAgg, RootCircuit, Runtime};
+
+    #[test]
+    fn string_agg_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, String, isize>();
+            let output = input.aggregate(StringAgg::new(",")).output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, ("b".to_string(), 1)),
+            (1, ("a".to_string(), 1)),
+            (2, ("x".to_string(), 2)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => { "a,b".to_string() => 1 },
+                2 => { "x,x".to_string() => 1 },
+            }
+        );
+
+        // Inserting a value retracts the old aggregate and emits the new one.
+        input.append(&mut vec![(1, ("c".to_string(), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { "a,b".to_string() => -1, "a,b,c".to_string() => 1 } }
+        );
+
+        // Removing all values of a group removes the key from the output.
+        input.append(&mut vec![(2, ("x".to_string(), -2))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 2 => { "x,x".to_string() => -1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn string_agg_weights_test() {
+        let (circuit, (mut input, output)) = RootCircuit::build(|circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, String, isize>();
+            let output = input.aggregate(StringAgg::new("|")).output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        // Empty strings are separated like any other value.
+        input.append(&mut vec![
+            (1, ("".to_string(), 2)),
+            (1, ("a".to_string(), 1)),
+            (1, ("b".to_string(), 3)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { "||a|b|b|b".to_string() => 1 } }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "negative weight")]
+    fn string_agg_negative_weight() {
+        let (circuit, mut input) = RootCircuit::build(|circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, String, isize>();
+            input.aggregate(StringAgg::new(","));
+
+            input_handle
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, ("a".to_string(), 1)),
+            (1, ("b".to_string(), -1)),
+        ]);
+        circuit.step().unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    algebra::{DefaultSemigroup, IndexedZSet, TDigest, ZRingValue, F64},
+    operator::{FilterMap, Fold},
+    trace::{BatchReader, Cursor},
+    DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use std::collections::BTreeMap;
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: Clone + 'static,
+{
+    /// Summarize the values associated with each key with a [`TDigest`]
+    /// sketch.
+    ///
+    /// Applies `f` to each value in the input stream and adds the resulting
+    /// number to the digest of the corresponding key, counting each value as
+    /// many times as its weight.  Use [`TDigest::quantile`] on the output to
+    /// estimate percentiles of the per-key distribution, or call
+    /// [`Self::tdigest_quantile`] directly.
+    ///
+    /// The operator is incremental: it maintains the current digest of each
+    /// key and, at each clock cycle, merges it with a digest of the values
+    /// added to the key during the cycle.  The cost of a clock cycle is
+    /// therefore proportional to the size of the input change and the number
+    /// of keys it affects, and doesn't depend on the number of values
+    /// accumulated for each key.
+    ///
+    /// The current digests are kept in memory across clock cycles, so the
+    /// operator is only available in the root circuit.
+    ///
+    /// # Panics
+    ///
+    /// Digests cannot forget values once added, so this operator only
+    /// supports insert-only streams and panics if the input stream contains
+    /// a negative weight.
+    #[allow(clippy::type_complexity)]
+    pub fn tdigest_aggregate<F>(
+        &self,
+        f: F,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, TDigest, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        u64: TryFrom<Z::R>,
+        F: Fn(&Z::Val) -> f64 + Clone + 'static,
+    {
+        self.inspect(|batch: &Z| {
+            let mut cursor = batch.cursor();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    assert!(
+                        cursor.weight().ge0(),
+                        "tdigest_aggregate: input stream must be insert-only"
+                    );
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+        })
+        // Digest of the values added to each key during the current clock
+        // cycle.
+        .stream_aggregate(<Fold<_, DefaultSemigroup<_>, _, _>>::new(
+            TDigest::new(),
+            move |digest: &mut TDigest, val: &Z::Val, weight: Z::R| {
+                let weight = u64::try_from(weight)
+                    .ok()
+                    .expect("tdigest_aggregate: weight does not fit in u64");
+                digest.insert(f(val), weight)
+            },
+        ))
+        .tdigest_merge()
+    }
+
+    /// Estimate the `q`-th quantile of the values associated with each key.
+    ///
+    /// Shorthand for [`Self::tdigest_aggregate`] followed by
+    /// [`TDigest::quantile`].  The same restrictions apply: the input stream
+    /// must be insert-only.
+    #[allow(clippy::type_complexity)]
+    pub fn tdigest_quantile<F>(
+        &self,
+        f: F,
+        q: f64,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, F64, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        u64: TryFrom<Z::R>,
+        F: Fn(&Z::Val) -> f64 + Clone + 'static,
+    {
+        self.tdigest_aggregate(f).map_index(move |(k, digest)| {
+            // Aggregation never produces empty digests.
+            (k.clone(), F64::new(digest.quantile(q).unwrap()))
+        })
+    }
+}
+
+impl<K, R> Stream<RootCircuit, OrdIndexedZSet<K, TDigest, R>>
+where
+    K: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Merge per-key digests of each clock cycle into the digests
+    /// accumulated for the same keys during previous clock cycles and output
+    /// changes to the accumulated digests.
+    ///
+    /// Must be applied to a sharded stream, which contains one digest per
+    /// key.
+    fn tdigest_merge(&self) -> Self {
+        // Digests can't be retracted, so we keep the current digest of each
+        // key instead of recomputing it from the trace of the input.
+        let mut digests: BTreeMap<K, TDigest> = BTreeMap::new();
+
+        self.apply_named("TDigestMerge", move |delta| {
+            let mut upserts = Vec::with_capacity(delta.key_count());
+
+            let mut cursor = delta.cursor();
+            while cursor.key_valid() {
+                let digest = digests.entry(cursor.key().clone()).or_default();
+                *digest += cursor.val();
+                upserts.push((cursor.key().clone(), Some(digest.clone())));
+                cursor.step_key();
+            }
+
+            upserts
+        })
+        .mark_sharded()
+        .upsert::<OrdIndexedZSet<K, TDigest, R>>()
+        .mark_sharded()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::FilterMap,
+        trace::{BatchReader, Cursor},
+        Runtime,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    const NUM_VALUES: i64 = 1_000;
+
+    #[test]
+    fn tdigest_median_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<usize, i64, isize>();
+            let output = input
+                .tdigest_aggregate(|v: &i64| *v as f64)
+                .map_index(|(k, digest)| (*k, digest.quantile(0.5).unwrap() as i64))
+                .integrate()
+                .output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        // Feed a permutation of `0..NUM_VALUES` for key 0 and of
+        // `0..2 * NUM_VALUES` for key 1 in several steps.
+        for step in 0..4 {
+            let mut tuples = Vec::new();
+            for i in (step * NUM_VALUES / 4)..((step + 1) * NUM_VALUES / 4) {
+                tuples.push((0, ((i * 397) % NUM_VALUES, 1)));
+                tuples.push((1, ((i * 397) % NUM_VALUES * 2, 1)));
+                tuples.push((1, ((i * 397) % NUM_VALUES * 2 + 1, 1)));
+            }
+            input.append(&mut tuples);
+            circuit.step().unwrap();
+        }
+
+        let medians = output.consolidate();
+        let expected = [(0, NUM_VALUES / 2), (1, NUM_VALUES)];
+        let mut cursor = medians.cursor();
+        for (key, median) in expected {
+            assert_eq!(*cursor.key(), key);
+            // t-digest estimates the median within a small fraction of the
+            // range of values.
+            assert!((*cursor.val() - median).abs() <= NUM_VALUES / 50);
+            cursor.step_key();
+        }
+        assert!(!cursor.key_valid());
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn tdigest_incremental_test() {
+        const STEPS: usize = 50;
+        const VALUES_PER_STEP: usize = 10;
+
+        // Number of values processed by the operator.
+        let evaluations = Arc::new(AtomicUsize::new(0));
+
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(2, {
+            let evaluations = evaluations.clone();
+            move |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<usize, i64, isize>();
+                let output = input
+                    .tdigest_aggregate(move |v: &i64| {
+                        evaluations.fetch_add(1, Ordering::Relaxed);
+                        *v as f64
+                    })
+                    .map_index(|(k, digest)| (*k, digest.count()))
+                    .output();
+
+                (input_handle, output)
+            }
+        })
+        .unwrap();
+
+        // Grow a single group by a few values per step.
+        for step in 0..STEPS {
+            let mut tuples = (0..VALUES_PER_STEP)
+                .map(|i| (0, ((step * VALUES_PER_STEP + i) as i64, 1)))
+                .collect();
+            input.append(&mut tuples);
+            circuit.step().unwrap();
+
+            // The output replaces the previous digest of the group.
+            let counts = output.consolidate();
+            let mut cursor = counts.cursor();
+            while cursor.val_valid() {
+                let expected_count = if cursor.weight() > 0 { step + 1 } else { step };
+                assert_eq!(*cursor.val(), (expected_count * VALUES_PER_STEP) as u64);
+                cursor.step_val();
+            }
+        }
+
+        // Each value was processed once, in the step it was added, rather
+        // than once per step for the entire group.
+        assert_eq!(evaluations.load(Ordering::Relaxed), STEPS * VALUES_PER_STEP);
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,666 @@
+use crate::{
+    algebra::{
+        AddAssignByRef, AddByRef, HasOne, HasZero, IndexedZSet, MulByRef, NegByRef, ZRingValue, F64,
+    },
+    circuit::WithClock,
+    operator::FilterMap,
+    Circuit, DBTimestamp, OrdIndexedZSet, Stream,
+};
+use num::traits::AsPrimitive;
+use size_of::SizeOf;
+use std::ops::{Add, AddAssign, Neg};
+
+/// Representation of a partially computed variance aggregate as a `(count,
+/// sum, sum_sq)` tuple.
+///
+/// This struct represents the result of the linear part of the variance
+/// aggregate (see [`Stream::variance`]): the number of values, their sum,
+/// and the sum of their squares.  `Variance` forms a commutative group with
+/// point-wise plus operation, which allows maintaining it incrementally,
+/// including retractions.
+///
+/// Values are integers and sums are accumulated exactly in `i128` as long
+/// as they fit (see [`VarianceSum`]), so that retracting all values of a key
+/// yields exactly zero, and the variance can be computed without
+/// catastrophic cancellation.  Sums that overflow `i128` fall back to `f64`
+/// arithmetic.
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, SizeOf)]
+pub struct Variance<R> {
+    count: R,
+    sum: VarianceSum,
+    sum_sq: VarianceSum,
+}
+
+impl<R> Variance<R> {
+    /// Create a new `Variance` object with the given `count`, `sum`, and
+    /// `sum_sq`.
+    pub const fn new(count: R, sum: i128, sum_sq: i128) -> Self {
+        Self {
+            count,
+            sum: VarianceSum::Exact(sum),
+            sum_sq: VarianceSum::Exact(sum_sq),
+        }
+    }
+
+    /// Create a `Variance` object for a single value.
+    pub fn from_value(value: i64) -> Self
+    where
+        R: HasOne,
+    {
+        let value = value as i128;
+        Self::new(R::one(), value, value * value)
+    }
+
+    /// Returns the `count` component of the `(count, sum, sum_sq)` tuple.
+    pub fn count(&self) -> R
+    where
+        R: Clone,
+    {
+        self.count.clone()
+    }
+
+    /// Returns the `sum` component of the `(count, sum, sum_sq)` tuple.
+    pub fn sum(&self) -> VarianceSum {
+        self.sum
+    }
+
+    /// Returns the `sum_sq` component of the `(count, sum, sum_sq)` tuple.
+    pub fn sum_sq(&self) -> VarianceSum {
+        self.sum_sq
+    }
+
+    /// Returns the population variance, or `None` if `count` is not
+    /// positive.
+    pub fn compute_variance(&self) -> Option<F64>
+    where
+        R: AsPrimitive<i128>,
+    {
+        let count: i128 = self.count.as_();
+        if count <= 0 {
+            return None;
+        }
+
+        // `variance = (count * sum_sq - sum^2) / count^2`.  Computing the
+        // numerator exactly avoids catastrophic cancellation between the two
+        // terms when values are large compared to their spread.
+        let numerator = match (self.sum, self.sum_sq) {
+            (VarianceSum::Exact(sum), VarianceSum::Exact(sum_sq)) => count
+                .checked_mul(sum_sq)
+                .zip(sum.checked_mul(sum))
+                .map(|(x, y)| x - y),
+            _ => None,
+        };
+
+        let count = count as f64;
+        let variance = match numerator {
+            Some(numerator) => numerator as f64 / (count * count),
+            // Fall back to floating point arithmetic on overflow.
+            None => {
+                let mean = self.sum.to_f64() / count;
+                (self.sum_sq.to_f64() / count - mean * mean).max(0.0)
+            }
+        };
+
+        Some(F64::new(variance))
+    }
+
+    /// Returns the population standard deviation, or `None` if `count` is
+    /// not positive.
+    pub fn compute_stddev(&self) -> Option<F64>
+    where
+        R: AsPrimitive<i128>,
+    {
+        self.compute_variance()
+            .map(|variance| F64::new(variance.into_inner().sqrt()))
+    }
+
+    /// Returns the sample variance, which divides by `count - 1` instead of
+    /// `count`, or `None` if `count` is less than 2.
+    pub fn compute_sample_variance(&self) -> Option<F64>
+    where
+        R: AsPrimitive<i128>,
+    {
+        let count: i128 = self.count.as_();
+        if count < 2 {
+            return None;
+        }
+
+        self.compute_variance()
+            .map(|variance| F64::new(variance.into_inner() * count as f64 / (count - 1) as f64))
+    }
+}
+
+/// A sum or sum of squares of a [`Variance`] aggregate.
+///
+/// Sums are computed exactly in `i128` arithmetic until an operation
+/// overflows, after which they are approximated in `f64` arithmetic.  An
+/// approximate sum stays approximate even if later updates bring it back
+/// into the range of `i128`, so the variance of a key that ever exceeded the
+/// range is subject to floating point rounding errors from then on.
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Ord, PartialOrd, SizeOf)]
+pub enum VarianceSum {
+    /// Exact sum.
+    Exact(i128),
+    /// Approximate sum, after an overflow of the exact sum.
+    Approx(F64),
+}
+
+impl VarianceSum {
+    /// Returns the sum as an `f64`.
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Self::Exact(sum) => sum as f64,
+            Self::Approx(sum) => sum.into_inner(),
+        }
+    }
+
+    /// Returns `true` if the sum is exact.
+    pub fn is_exact(self) -> bool {
+        matches!(self, Self::Exact(_))
+    }
+
+    fn exact_or_approx(exact: Option<i128>, approx: impl FnOnce() -> f64) -> Self {
+        match exact {
+            Some(sum) => Self::Exact(sum),
+            None => Self::Approx(F64::new(approx())),
+        }
+    }
+
+    fn plus(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Exact(x), Self::Exact(y)) => {
+                Self::exact_or_approx(x.checked_add(y), || x as f64 + y as f64)
+            }
+            _ => Self::Approx(F64::new(self.to_f64() + other.to_f64())),
+        }
+    }
+
+    fn negate(self) -> Self {
+        match self {
+            Self::Exact(x) => Self::exact_or_approx(x.checked_neg(), || -(x as f64)),
+            Self::Approx(x) => Self::Approx(F64::new(-x.into_inner())),
+        }
+    }
+
+    fn times(self, weight: i128) -> Self {
+        match self {
+            Self::Exact(x) => {
+                Self::exact_or_approx(x.checked_mul(weight), || x as f64 * weight as f64)
+            }
+            Self::Approx(x) => Self::Approx(F64::new(x.into_inner() * weight as f64)),
+        }
+    }
+}
+
+impl Default for VarianceSum {
+    fn default() -> Self {
+        Self::Exact(0)
+    }
+}
+
+impl HasZero for VarianceSum {
+    fn is_zero(&self) -> bool {
+        self.to_f64() == 0.0
+    }
+
+    fn zero() -> Self {
+        Self::Exact(0)
+    }
+}
+
+impl bincode::Encode for VarianceSum {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> core::result::Result<(), bincode::error::EncodeError> {
+        match self {
+            Self::Exact(sum) => {
+                bincode::Encode::encode(&0u8, encoder)?;
+                bincode::Encode::encode(sum, encoder)
+            }
+            Self::Approx(sum) => {
+                bincode::Encode::encode(&1u8, encoder)?;
+                bincode::Encode::encode(sum, encoder)
+            }
+        }
+    }
+}
+
+impl bincode::Decode for VarianceSum {
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let tag: u8 = bincode::Decode::decode(decoder)?;
+        match tag {
+            0 => Ok(Self::Exact(bincode::Decode::decode(decoder)?)),
+            1 => Ok(Self::Approx(bincode::Decode::decode(decoder)?)),
+            found => Err(bincode::error::DecodeError::UnexpectedVariant {
+                type_name: "VarianceSum",
+                allowed: &bincode::error::AllowedEnumVariants::Range { min: 0, max: 1 },
+                found: found as u32,
+            }),
+        }
+    }
+}
+
+impl<R> bincode::Encode for Variance<R>
+where
+    R: bincode::Encode + bincode::Decode,
+{
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> core::result::Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.count, encoder)?;
+        bincode::Encode::encode(&self.sum, encoder)?;
+        bincode::Encode::encode(&self.sum_sq, encoder)?;
+        Ok(())
+    }
+}
+
+impl<R> bincode::Decode for Variance<R>
+where
+    R: bincode::Encode + bincode::Decode,
+{
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let count: R = bincode::Decode::decode(decoder)?;
+        let sum: VarianceSum = bincode::Decode::decode(decoder)?;
+        let sum_sq: VarianceSum = bincode::Decode::decode(decoder)?;
+        Ok(Self { count, sum, sum_sq })
+    }
+}
+
+impl<R> HasZero for Variance<R>
+where
+    R: HasZero,
+{
+    fn is_zero(&self) -> bool {
+        self.count.is_zero() && self.sum.is_zero() && self.sum_sq.is_zero()
+    }
+
+    fn zero() -> Self {
+        Self::new(R::zero(), 0, 0)
+    }
+}
+
+impl<R> Add for Variance<R>
+where
+    R: Add<Output = R>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            count: self.count + rhs.count,
+            sum: self.sum.plus(rhs.sum),
+            sum_sq: self.sum_sq.plus(rhs.sum_sq),
+        }
+    }
+}
+
+impl<R> AddByRef for Variance<R>
+where
+    R: AddByRef,
+{
+    fn add_by_ref(&self, other: &Self) -> Self {
+        Self {
+            count: self.count.add_by_ref(&other.count),
+            sum: self.sum.plus(other.sum),
+            sum_sq: self.sum_sq.plus(other.sum_sq),
+        }
+    }
+}
+
+impl<R> AddAssign for Variance<R>
+where
+    R: AddAssign,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        self.count += rhs.count;
+        self.sum = self.sum.plus(rhs.sum);
+        self.sum_sq = self.sum_sq.plus(rhs.sum_sq);
+    }
+}
+
+impl<R> AddAssignByRef for Variance<R>
+where
+    R: AddAssignByRef,
+{
+    fn add_assign_by_ref(&mut self, rhs: &Self) {
+        self.count.add_assign_by_ref(&rhs.count);
+        self.sum = self.sum.plus(rhs.sum);
+        self.sum_sq = self.sum_sq.plus(rhs.sum_sq);
+    }
+}
+
+impl<R> Neg for Variance<R>
+where
+    R: Neg<Output = R>,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            count: self.count.neg(),
+            sum: self.sum.negate(),
+            sum_sq: self.sum_sq.negate(),
+        }
+    }
+}
+
+impl<R> NegByRef for Variance<R>
+where
+    R: NegByRef,
+{
+    fn neg_by_ref(&self) -> Self {
+        Self {
+            count: self.count.neg_by_ref(),
+            sum: self.sum.negate(),
+            sum_sq: self.sum_sq.negate(),
+        }
+    }
+}
+
+impl<R> MulByRef<R> for Variance<R>
+where
+    R: MulByRef<Output = R> + AsPrimitive<i128>,
+{
+    type Output = Self;
+
+    fn mul_by_ref(&self, rhs: &R) -> Self {
+        let weight: i128 = rhs.as_();
+        Self {
+            count: self.count.mul_by_ref(rhs),
+            sum: self.sum.times(weight),
+            sum_sq: self.sum_sq.times(weight),
+        }
+    }
+}
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: Clone + 'static,
+{
+    /// Incremental population variance aggregate.
+    ///
+    /// This operator is a specialization of [`Stream::aggregate`] that for
+    /// each key `k` in the input indexed Z-set computes the population
+    /// variance of values `f(k, v)` for all `(v, w)` in `Z[k]`, where each
+    /// value is counted `w` times.
+    ///
+    /// # Design
+    ///
+    /// Like [`Stream::average`], variance is a quasi-linear aggregate: it
+    /// is computed from the `(count, sum, sum_sq)` triple (see
+    /// [`Variance`]), which is maintained by a single
+    /// [`Stream::aggregate_linear`] operator, followed by a transformation
+    /// that computes the variance of each key.  Keys whose total weight is
+    /// not positive, which can only happen if the input is not a relation,
+    /// are omitted from the output.
+    #[track_caller]
+    pub fn variance<F>(&self, f: F) -> Stream<C, OrdIndexedZSet<Z::Key, F64, Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue + AsPrimitive<i128>,
+        F: Fn(&Z::Key, &Z::Val) -> i64 + Clone + 'static,
+    {
+        self.finalize_variance(f, Variance::compute_variance)
+    }
+
+    /// Incremental population standard deviation aggregate.
+    ///
+    /// Computes the square root of [`Stream::variance`] for each key.
+    #[track_caller]
+    pub fn stddev<F>(&self, f: F) -> Stream<C, OrdIndexedZSet<Z::Key, F64, Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue + AsPrimitive<i128>,
+        F: Fn(&Z::Key, &Z::Val) -> i64 + Clone + 'static,
+    {
+        self.finalize_variance(f, Variance::compute_stddev)
+    }
+
+    /// Incremental variance aggregate with population or sample semantics.
+    ///
+    /// Computes the population variance of values `f(k, v)` for each key,
+    /// like [`Stream::variance`], if `population` is `true`, and the sample
+    /// variance, which divides by `count - 1` instead of `count`, otherwise.
+    /// The sample variance of a key with a single value is undefined, so
+    /// such keys are omitted from the output.
+    #[track_caller]
+    pub fn aggregate_variance<F>(
+        &self,
+        population: bool,
+        f: F,
+    ) -> Stream<C, OrdIndexedZSet<Z::Key, F64, Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue + AsPrimitive<i128>,
+        F: Fn(&Z::Key, &Z::Val) -> i64 + Clone + 'static,
+    {
+        if population {
+            self.finalize_variance(f, Variance::compute_variance)
+        } else {
+            self.finalize_variance(f, Variance::compute_sample_variance)
+        }
+    }
+
+    #[track_caller]
+    fn finalize_variance<F>(
+        &self,
+        f: F,
+        finalize: fn(&Variance<Z::R>) -> Option<F64>,
+    ) -> Stream<C, OrdIndexedZSet<Z::Key, F64, Z::R>>
+    where
+        Z: IndexedZSet,
+        Z::R: ZRingValue + AsPrimitive<i128>,
+        F: Fn(&Z::Key, &Z::Val) -> i64 + Clone + 'static,
+    {
+        let aggregate = self.aggregate_linear(move |key, val| Variance::from_value(f(key, val)));
+
+        let result = aggregate.flat_map_index(move |(key, variance)| {
+            finalize(variance).map(|variance| (key.clone(), variance))
+        });
+        result.mark_sharded_if(&aggregate);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Variance;
+    use crate::{
+        algebra::{HasZero, MulByRef, NegByRef, F64},
+        trace::{BatchReader, Cursor},
+        OrdIndexedZSet, Runtime,
+    };
+    use std::collections::BTreeMap;
+
+    fn population_variance(values: &[i64]) -> f64 {
+        let count = values.len() as f64;
+        let mean = values.iter().map(|v| *v as f64).sum::<f64>() / count;
+        values
+            .iter()
+            .map(|v| (*v as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count
+    }
+
+    fn model_sample_variance(values: &[i64]) -> f64 {
+        let count = values.len() as f64;
+        population_variance(values) * count / (count - 1.0)
+    }
+
+    fn to_map(batch: &OrdIndexedZSet<u64, F64, isize>) -> BTreeMap<u64, f64> {
+        let mut result = BTreeMap::new();
+        let mut cursor = batch.cursor();
+        while cursor.key_valid() {
+            assert_eq!(cursor.weight(), 1);
+            result.insert(*cursor.key(), cursor.val().into_inner());
+            cursor.step_key();
+        }
+        result
+    }
+
+    #[test]
+    fn variance_test() {
+        let (mut circuit, (mut input, variance, stddev, sample_variance)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+                let variance = input.variance(|_k, v| *v).integrate().output();
+                let stddev = input.stddev(|_k, v| *v).integrate().output();
+                let sample_variance = input
+                    .aggregate_variance(false, |_k, v| *v)
+                    .integrate()
+                    .output();
+
+                (input_handle, variance, stddev, sample_variance)
+            })
+            .unwrap();
+
+        const BASE: i64 = 1_000_000_000;
+
+        let steps: Vec<Vec<(u64, (i64, isize))>> = vec![
+            vec![
+                (1, (BASE + 1, 1)),
+                (1, (BASE + 2, 1)),
+                (1, (BASE + 3, 1)),
+                (1, (BASE + 4, 1)),
+                (2, (10, 2)),
+                (2, (20, 1)),
+            ],
+            vec![(1, (BASE + 4, -1)), (2, (-5, 1)), (3, (7, 1))],
+            vec![(3, (7, -1)), (2, (10, -1))],
+        ];
+
+        let mut model: BTreeMap<u64, Vec<i64>> = BTreeMap::new();
+
+        for mut step in steps {
+            for (key, (val, weight)) in step.iter() {
+                let values = model.entry(*key).or_default();
+                if *weight > 0 {
+                    values.resize(values.len() + *weight as usize, *val);
+                } else {
+                    for _ in 0..-*weight {
+                        let pos = values.iter().position(|v| v == val).unwrap();
+                        values.remove(pos);
+                    }
+                }
+            }
+            model.retain(|_, values| !values.is_empty());
+
+            input.append(&mut step);
+            circuit.step().unwrap();
+
+            let variance = to_map(&variance.consolidate());
+            let stddev = to_map(&stddev.consolidate());
+            let sample_variance = to_map(&sample_variance.consolidate());
+            assert_eq!(
+                variance.keys().collect::<Vec<_>>(),
+                model.keys().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                stddev.keys().collect::<Vec<_>>(),
+                model.keys().collect::<Vec<_>>()
+            );
+            // Sample variance is undefined for a single value.
+            assert_eq!(
+                sample_variance.keys().collect::<Vec<_>>(),
+                model
+                    .iter()
+                    .filter(|(_, values)| values.len() >= 2)
+                    .map(|(key, _)| key)
+                    .collect::<Vec<_>>()
+            );
+
+            for (key, values) in model.iter() {
+                let expected = population_variance(values);
+                assert!((variance[key] - expected).abs() <= 1e-9 * expected.max(1.0));
+                assert!((stddev[key] - expected.sqrt()).abs() <= 1e-9 * expected.max(1.0));
+
+                if values.len() >= 2 {
+                    let expected = model_sample_variance(values);
+                    assert!((sample_variance[key] - expected).abs() <= 1e-9 * expected.max(1.0));
+                }
+            }
+        }
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn variance_overflow() {
+        // The sum of squares overflows `i128` and falls back to `f64`.
+        let variance = Variance::<isize>::from_value(i64::MAX).mul_by_ref(&isize::MAX);
+        assert!(variance.sum().is_exact());
+        assert!(!variance.sum_sq().is_exact());
+        let tolerance = 1e-9 * (i64::MAX as f64).powi(2);
+        assert!(variance.compute_variance().unwrap().into_inner() <= tolerance);
+
+        // Retracting the values restores a zero aggregate.
+        let retracted = variance.clone() + variance.neg_by_ref();
+        assert!(retracted.is_zero());
+    }
+
+    #[test]
+    fn variance_large_values() {
+        let (mut circuit, (mut input, variance)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let variance = input.variance(|_k, v| *v).integrate().output();
+
+            (input_handle, variance)
+        })
+        .unwrap();
+
+        let steps: Vec<Vec<(u64, (i64, isize))>> = vec![
+            vec![
+                (1, (i64::MAX, 2)),
+                (1, (i64::MIN, 1)),
+                (1, (0, 1)),
+                (2, (i64::MAX, 1)),
+            ],
+            vec![(1, (i64::MAX, 3)), (2, (i64::MAX - 2, 1))],
+            vec![(1, (i64::MIN, -1)), (1, (0, -1)), (2, (i64::MAX, 1))],
+        ];
+
+        let mut model: BTreeMap<u64, Vec<i64>> = BTreeMap::new();
+
+        for mut step in steps {
+            for (key, (val, weight)) in step.iter() {
+                let values = model.entry(*key).or_default();
+                if *weight > 0 {
+                    values.resize(values.len() + *weight as usize, *val);
+                } else {
+                    for _ in 0..-*weight {
+                        let pos = values.iter().position(|v| v == val).unwrap();
+                        values.remove(pos);
+                    }
+                }
+            }
+
+            input.append(&mut step);
+            circuit.step().unwrap();
+
+            // Once the sum of squares overflows `i128`, the variance is
+            // computed in `f64` and is only accurate relative to the square
+            // of the magnitude of the values.
+            let tolerance = 1e-9 * (i64::MAX as f64).powi(2);
+            let variance = to_map(&variance.consolidate());
+            assert_eq!(
+                variance.keys().collect::<Vec<_>>(),
+                model.keys().collect::<Vec<_>>()
+            );
+            for (key, values) in model.iter() {
+                let expected = population_variance(values);
+                assert!((variance[key] - expected).abs() <= tolerance);
+            }
+        }
+
+        circuit.kill().unwrap();
+    }
+}
@@ -1,9 +1,12 @@
 //! Operator that applies an arbitrary function to its input.
 
-use crate::circuit::{
-    metadata::OperatorLocation,
-    operator_traits::{Data, Operator, UnaryOperator},
-    Circuit, OwnershipPreference, Scope, Stream,
+use crate::{
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Data, Operator, UnaryOperator},
+        Circuit, OwnershipPreference, Scope, Stream,
+    },
+    trace::{Batch, BatchReader},
 };
 use std::{borrow::Cow, panic::Location};
 
@@ -85,6 +88,35 @@ where
     }
 }
 
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: BatchReader + Clone + 'static,
+{
+    /// Transform each batch in the stream as a whole.
+    ///
+    /// Unlike record-by-record operators like
+    /// [`FilterMap::map`](`crate::operator::FilterMap::map`), `func` gets
+    /// access to the entire input batch at once and returns a new batch,
+    /// typically assembled using the output type's
+    /// [`Builder`](`crate::trace::Builder`).  This is useful for
+    /// transformations that require global context of the batch, e.g., to
+    /// normalize weights.
+    ///
+    /// `func` must be a pure function of its input.  Note that it is applied
+    /// to each batch independently: in incremental circuits it sees changes
+    /// to the collection rather than its complete contents.
+    #[track_caller]
+    pub fn map_batch<F, B2>(&self, func: F) -> Stream<C, B2>
+    where
+        F: Fn(&B) -> B2 + 'static,
+        B2: Batch,
+    {
+        self.circuit()
+            .add_unary_operator(MapBatch::new(func, Location::caller()), self)
+    }
+}
+
 /// Operator that applies a user provided function to its input at each
 /// timestamp.
 pub struct Apply<F> {
@@ -258,3 +290,100 @@ where
         OwnershipPreference::STRONGLY_PREFER_OWNED
     }
 }
+/// Operator that applies a user provided function to each input batch as a
+/// whole.
+///
+/// See [`Stream::map_batch`].
+pub struct MapBatch<F> {
+    func: F,
+    location: &'static Location<'static>,
+}
+
+impl<F> MapBatch<F> {
+    pub const fn new(func: F, location: &'static Location<'static>) -> Self {
+        Self { func, location }
+    }
+}
+
+impl<F> Operator for MapBatch<F>
+where
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("MapBatch")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<B, B2, F> UnaryOperator<B, B2> for MapBatch<F>
+where
+    F: Fn(&B) -> B2 + 'static,
+{
+    fn eval(&mut self, input: &B) -> B2 {
+        (self.func)(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::Generator,
+        trace::{Batch, BatchReader, Builder, Cursor},
+        zset, Circuit, OrdZSet, RootCircuit,
+    };
+
+    // Retains only the key with the largest weight in the batch.
+    fn max_weight_key(batch: &OrdZSet<u64, isize>) -> OrdZSet<u64, isize> {
+        let mut max: Option<(u64, isize)> = None;
+
+        let mut cursor = batch.cursor();
+        while cursor.key_valid() {
+            let weight = cursor.weight();
+            let is_max = match max {
+                Some((_, max_weight)) => weight > max_weight,
+                None => true,
+            };
+            if is_max {
+                max = Some((*cursor.key(), weight));
+            }
+            cursor.step_key();
+        }
+
+        let mut builder = <OrdZSet<u64, isize> as Batch>::Builder::with_capacity((), 1);
+        if let Some((key, weight)) = max {
+            builder.push((key, weight));
+        }
+        builder.done()
+    }
+
+    #[test]
+    fn map_batch_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![
+                zset! { 1 => 1, 2 => 5, 3 => 2 },
+                zset! {},
+                zset! { 4 => -1, 5 => 3, 6 => 3 },
+            ]
+            .into_iter();
+            let mut expected = vec![zset! { 2 => 5 }, zset! {}, zset! { 5 => 3 }].into_iter();
+
+            circuit
+                .add_source(Generator::new(move || inputs.next().unwrap()))
+                .map_batch(max_weight_key)
+                .inspect(move |batch| assert_eq!(*batch, expected.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..3 {
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -42,23 +42,68 @@ where
 }
 
 /// Applies a user-provided binary function to its inputs at each timestamp.
-pub struct Apply2<F> {
+///
+/// By default, the operator never reports that it has reached a fixed point
+/// (see [`Operator::fixedpoint`]), so it can be used inside
+/// [`Circuit::iterate`](`crate::circuit::Circuit::iterate`), but prevents
+/// [`Circuit::fixedpoint`](`crate::circuit::Circuit::fixedpoint`) from
+/// terminating.  Use [`Apply2::with_fixedpoint`] to supply a custom fixed
+/// point check.
+pub struct Apply2<F, FP = fn(Scope) -> bool> {
     func: F,
+    fixedpoint: FP,
     location: &'static Location<'static>,
 }
 
 impl<F> Apply2<F> {
+    /// Create an `Apply2` operator that never reports that it has reached a
+    /// fixed point.
     pub const fn new(func: F, location: &'static Location<'static>) -> Self
     where
         F: 'static,
     {
-        Self { func, location }
+        Self {
+            func,
+            fixedpoint: not_fixedpoint,
+            location,
+        }
     }
 }
 
-impl<F> Operator for Apply2<F>
+impl<F, FP> Apply2<F, FP> {
+    /// Create an `Apply2` operator that uses `fixedpoint` as its fixed point
+    /// check.
+    ///
+    /// Since `func` is a pure function, `|_| true` is the right choice
+    /// unless `func` has side effects or depends on external state that
+    /// changes across iterations.
+    pub const fn with_fixedpoint(
+        func: F,
+        fixedpoint: FP,
+        location: &'static Location<'static>,
+    ) -> Self
+    where
+        F: 'static,
+        FP: Fn(Scope) -> bool + 'static,
+    {
+        Self {
+            func,
+            fixedpoint,
+            location,
+        }
+    }
+}
+
+/// Default fixed point check of [`Apply2`] and
+/// [`Apply3`](`crate::operator::apply3::Apply3`).
+pub(crate) fn not_fixedpoint(_scope: Scope) -> bool {
+    false
+}
+
+impl<F, FP> Operator for Apply2<F, FP>
 where
     F: 'static,
+    FP: Fn(Scope) -> bool + 'static,
 {
     fn name(&self) -> Cow<'static, str> {
         Cow::Borrowed("Apply2")
@@ -68,16 +113,15 @@ where
         Some(self.location)
     }
 
-    fn fixedpoint(&self, _scope: Scope) -> bool {
-        // TODO: either change `F` type to `Fn` from `FnMut` or
-        // parameterize the operator with custom fixed point check.
-        unimplemented!();
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        (self.fixedpoint)(scope)
     }
 }
 
-impl<T1, T2, T3, F> BinaryOperator<T1, T2, T3> for Apply2<F>
+impl<T1, T2, T3, F, FP> BinaryOperator<T1, T2, T3> for Apply2<F, FP>
 where
     F: Fn(&T1, &T2) -> T3 + 'static,
+    FP: Fn(Scope) -> bool + 'static,
 {
     fn eval(&mut self, i1: &T1, i2: &T2) -> T3 {
         (self.func)(i1, i2)
@@ -145,8 +189,11 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{operator::Generator, Circuit, RootCircuit};
-    use std::vec;
+    use super::Apply2;
+    use crate::{
+        algebra::AddByRef, operator::Generator, zset, Circuit, OrdZSet, RootCircuit, Stream,
+    };
+    use std::{panic::Location, vec};
 
     #[test]
     fn apply2_test() {
@@ -168,4 +215,49 @@ mod test {
             circuit.step().unwrap();
         }
     }
+
+    #[test]
+    fn apply2_fixedpoint_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs1 = vec![zset! { 1 => 1, 2 => 1 }, zset! { 3 => 1 }].into_iter();
+            let mut inputs2 = vec![zset! { 2 => 1 }, zset! { 1 => -1 }].into_iter();
+            let mut expected =
+                vec![zset! { 1 => 1, 2 => 2 }, zset! { 3 => 1, 1 => -1 }].into_iter();
+
+            let source1: Stream<_, OrdZSet<u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs1.next().unwrap()));
+            let source2: Stream<_, OrdZSet<u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs2.next().unwrap()));
+
+            // `fixedpoint` only terminates if all operators in the nested
+            // circuit, including `Apply2`, reach a fixed point.
+            let sum = circuit
+                .fixedpoint(|child| {
+                    let source1 = source1.delta0(child);
+                    let source2 = source2.delta0(child);
+
+                    let sum = child.add_binary_operator(
+                        Apply2::with_fixedpoint(
+                            |x: &OrdZSet<u64, isize>, y: &OrdZSet<u64, isize>| x.add_by_ref(y),
+                            |_| true,
+                            Location::caller(),
+                        ),
+                        &source1,
+                        &source2,
+                    );
+
+                    Ok(sum.integrate_trace().export())
+                })
+                .unwrap();
+
+            sum.consolidate()
+                .inspect(move |sum| assert_eq!(*sum, expected.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
 }
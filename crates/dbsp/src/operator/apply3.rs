@@ -0,0 +1,199 @@
+//! Ternary operator that applies an arbitrary ternary function to its inputs.
+
+use crate::{
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, TernaryOperator},
+        Circuit, Scope, Stream,
+    },
+    operator::apply2::not_fixedpoint,
+};
+use std::{borrow::Cow, panic::Location};
+
+impl<C, T1> Stream<C, T1>
+where
+    C: Circuit,
+    T1: Clone + 'static,
+{
+    /// Apply a user-provided ternary function to its inputs at each
+    /// timestamp.
+    #[track_caller]
+    pub fn apply3<F, T2, T3, T4>(
+        &self,
+        arg2: &Stream<C, T2>,
+        arg3: &Stream<C, T3>,
+        func: F,
+    ) -> Stream<C, T4>
+    where
+        T2: Clone + 'static,
+        T3: Clone + 'static,
+        T4: Clone + 'static,
+        F: Fn(&T1, &T2, &T3) -> T4 + 'static,
+    {
+        self.circuit()
+            .add_ternary_operator(Apply3::new(func, Location::caller()), self, arg2, arg3)
+    }
+}
+
+/// Applies a user-provided ternary function to its inputs at each timestamp.
+///
+/// Like [`Apply2`](`crate::operator::apply2::Apply2`), the operator never
+/// reports that it has reached a fixed point unless constructed with
+/// [`Apply3::with_fixedpoint`].
+pub struct Apply3<F, FP = fn(Scope) -> bool> {
+    func: F,
+    fixedpoint: FP,
+    location: &'static Location<'static>,
+}
+
+impl<F> Apply3<F> {
+    /// Create an `Apply3` operator that never reports that it has reached a
+    /// fixed point.
+    pub const fn new(func: F, location: &'static Location<'static>) -> Self
+    where
+        F: 'static,
+    {
+        Self {
+            func,
+            fixedpoint: not_fixedpoint,
+            location,
+        }
+    }
+}
+
+impl<F, FP> Apply3<F, FP> {
+    /// Create an `Apply3` operator that uses `fixedpoint` as its fixed point
+    /// check.
+    ///
+    /// See [`Apply2::with_fixedpoint`](`crate::operator::apply2::Apply2::with_fixedpoint`).
+    pub const fn with_fixedpoint(
+        func: F,
+        fixedpoint: FP,
+        location: &'static Location<'static>,
+    ) -> Self
+    where
+        F: 'static,
+        FP: Fn(Scope) -> bool + 'static,
+    {
+        Self {
+            func,
+            fixedpoint,
+            location,
+        }
+    }
+}
+
+impl<F, FP> Operator for Apply3<F, FP>
+where
+    F: 'static,
+    FP: Fn(Scope) -> bool + 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Apply3")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        (self.fixedpoint)(scope)
+    }
+}
+
+impl<T1, T2, T3, T4, F, FP> TernaryOperator<T1, T2, T3, T4> for Apply3<F, FP>
+where
+    T1: Clone,
+    T2: Clone,
+    T3: Clone,
+    F: Fn(&T1, &T2, &T3) -> T4 + 'static,
+    FP: Fn(Scope) -> bool + 'static,
+{
+    fn eval<'a>(&mut self, i1: Cow<'a, T1>, i2: Cow<'a, T2>, i3: Cow<'a, T3>) -> T4 {
+        (self.func)(i1.as_ref(), i2.as_ref(), i3.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Apply3;
+    use crate::{
+        algebra::AddByRef, operator::Generator, zset, Circuit, OrdZSet, RootCircuit, Stream,
+    };
+    use std::{panic::Location, vec};
+
+    #[test]
+    fn apply3_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs1 = vec![1, 2, 3, 4].into_iter();
+            let mut inputs2 = vec![10, 20, 30, 40].into_iter();
+            let mut inputs3 = vec![100, 200, 300, 400].into_iter();
+
+            let source1 = circuit.add_source(Generator::new(move || inputs1.next().unwrap()));
+            let source2 = circuit.add_source(Generator::new(move || inputs2.next().unwrap()));
+            let source3 = circuit.add_source(Generator::new(move || inputs3.next().unwrap()));
+
+            source1
+                .apply3(&source2, &source3, |x, y, z| *x + *y + *z)
+                .inspect(|z| assert_eq!(*z % 111, 0));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..4 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn apply3_fixedpoint_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs1 = vec![zset! { 1 => 1 }, zset! { 2 => 1 }].into_iter();
+            let mut inputs2 = vec![zset! { 1 => 1 }, zset! { 3 => 1 }].into_iter();
+            let mut inputs3 = vec![zset! { 2 => 1 }, zset! { 1 => -1 }].into_iter();
+            let mut expected =
+                vec![zset! { 1 => 2, 2 => 1 }, zset! { 1 => -1, 2 => 1, 3 => 1 }].into_iter();
+
+            let source1: Stream<_, OrdZSet<u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs1.next().unwrap()));
+            let source2: Stream<_, OrdZSet<u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs2.next().unwrap()));
+            let source3: Stream<_, OrdZSet<u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs3.next().unwrap()));
+
+            let sum = circuit
+                .fixedpoint(|child| {
+                    let source1 = source1.delta0(child);
+                    let source2 = source2.delta0(child);
+                    let source3 = source3.delta0(child);
+
+                    let sum = child.add_ternary_operator(
+                        Apply3::with_fixedpoint(
+                            |x: &OrdZSet<u64, isize>,
+                             y: &OrdZSet<u64, isize>,
+                             z: &OrdZSet<u64, isize>| {
+                                x.add_by_ref(y).add_by_ref(z)
+                            },
+                            |_| true,
+                            Location::caller(),
+                        ),
+                        &source1,
+                        &source2,
+                        &source3,
+                    );
+
+                    Ok(sum.integrate_trace().export())
+                })
+                .unwrap();
+
+            sum.consolidate()
+                .inspect(move |sum| assert_eq!(*sum, expected.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+}
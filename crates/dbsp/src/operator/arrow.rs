@@ -0,0 +1,293 @@
+//! Operators that convert between Z-sets and Apache Arrow record batches.
+
+use crate::{
+    algebra::{ZRingValue, ZSet},
+    operator::error_recovery::{report_input_error, report_operator_error},
+    trace::{BatchReader, Cursor},
+    Circuit, Stream,
+};
+use arrow::{
+    array::{Array, ArrayRef, Int64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use num::{FromPrimitive, ToPrimitive};
+use std::sync::Arc;
+
+/// Name of the weight column appended by [`Stream::to_arrow`] and read by
+/// [`Stream::from_arrow`].
+pub const ARROW_WEIGHT_COLUMN: &str = "weight";
+
+impl<C> Stream<C, RecordBatch>
+where
+    C: Circuit,
+{
+    /// Convert a stream of Arrow record batches into a stream of Z-sets.
+    ///
+    /// At each clock cycle, applies `row` to each row index of the current
+    /// record batch and yields a Z-set that contains the extracted values.
+    /// `row` is responsible for downcasting the columns of the record batch
+    /// to concrete array types.  Columns that can contain nulls are expected
+    /// to map to `Option` fields, with nulls mapping to `None`.
+    ///
+    /// If the record batch has an `Int64` column named
+    /// [`ARROW_WEIGHT_COLUMN`], as produced by [`Stream::to_arrow`], its
+    /// values are used as the weights of the corresponding rows.  Otherwise,
+    /// all rows have unit weights.
+    ///
+    /// # Errors
+    ///
+    /// If the weight column has a different type, contains nulls, or
+    /// contains a weight that doesn't fit in the weight type of `Z`, the
+    /// operator yields an empty Z-set and the current
+    /// [`CircuitHandle::step`](`crate::CircuitHandle::step`) invocation
+    /// returns [`SchedulerError::Input`](`crate::SchedulerError::Input`).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let zset = batches.from_arrow(|batch, i| {
+    ///     let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    ///     let names = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    ///     (
+    ///         ids.value(i),
+    ///         (!names.is_null(i)).then(|| names.value(i).to_string()),
+    ///     )
+    /// });
+    /// ```
+    #[track_caller]
+    pub fn from_arrow<Z, F>(&self, mut row: F) -> Stream<C, Z>
+    where
+        Z: ZSet,
+        Z::R: ZRingValue + FromPrimitive,
+        F: FnMut(&RecordBatch, usize) -> Z::Key + 'static,
+    {
+        self.apply_named("FromArrow", move |batch: &RecordBatch| {
+            let weights = match batch.schema().index_of(ARROW_WEIGHT_COLUMN) {
+                Ok(index) => match batch.column(index).as_any().downcast_ref::<Int64Array>() {
+                    Some(weights) if weights.null_count() == 0 => Some(weights.clone()),
+                    Some(_) => {
+                        report_input_error(format!(
+                            "column '{ARROW_WEIGHT_COLUMN}' contains nulls"
+                        ));
+                        return Z::zero();
+                    }
+                    None => {
+                        report_input_error(format!(
+                            "column '{ARROW_WEIGHT_COLUMN}' has type {}, expected Int64",
+                            batch.column(index).data_type()
+                        ));
+                        return Z::zero();
+                    }
+                },
+                Err(_) => None,
+            };
+
+            let mut records = Vec::with_capacity(batch.num_rows());
+            for i in 0..batch.num_rows() {
+                let weight = match &weights {
+                    Some(weights) => match Z::R::from_i64(weights.value(i)) {
+                        Some(weight) => weight,
+                        None => {
+                            report_input_error(format!(
+                                "weight {} in row {i} does not fit in the weight type",
+                                weights.value(i)
+                            ));
+                            return Z::zero();
+                        }
+                    },
+                    None => Z::R::one(),
+                };
+                records.push((row(batch, i), weight));
+            }
+
+            Z::from_keys((), records)
+        })
+    }
+}
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: ZSet,
+    B::R: ToPrimitive,
+{
+    /// Convert a stream of Z-sets into a stream of Arrow record batches.
+    ///
+    /// At each clock cycle, collects the elements of the current Z-set and
+    /// passes them to `columns`, which must return one array per field of
+    /// `schema`, in order.  The operator appends a non-nullable `Int64`
+    /// column named [`ARROW_WEIGHT_COLUMN`] with the weight of each element,
+    /// so the schema of the output record batches consists of the fields of
+    /// `schema` followed by the weight field.  `Option` fields of the
+    /// elements should map to nullable columns.
+    ///
+    /// # Errors
+    ///
+    /// If the arrays returned by `columns` don't match the schema or a
+    /// weight doesn't fit in an `i64`, the operator yields an empty record
+    /// batch and the error is reported by the scheduler, which returns
+    /// [`SchedulerError::OperatorPanic`](`crate::SchedulerError::OperatorPanic`)
+    /// from the current [`CircuitHandle::step`](`crate::CircuitHandle::step`)
+    /// invocation.
+    #[track_caller]
+    pub fn to_arrow<F>(&self, schema: SchemaRef, mut columns: F) -> Stream<C, RecordBatch>
+    where
+        F: FnMut(&[B::Key]) -> Vec<ArrayRef> + 'static,
+    {
+        let mut fields = schema.fields().clone();
+        fields.push(Field::new(ARROW_WEIGHT_COLUMN, DataType::Int64, false));
+        let schema = Arc::new(Schema::new(fields));
+
+        self.apply_named("ToArrow", move |batch: &B| {
+            let mut keys = Vec::with_capacity(batch.len());
+            let mut weights = Vec::with_capacity(batch.len());
+
+            let mut cursor = batch.cursor();
+            while cursor.key_valid() {
+                let weight = cursor.weight();
+                match weight.to_i64() {
+                    Some(weight) => weights.push(weight),
+                    None => {
+                        report_operator_error(format!(
+                            "weight {weight:?} of {:?} does not fit in an i64",
+                            cursor.key()
+                        ));
+                        return RecordBatch::new_empty(schema.clone());
+                    }
+                }
+                keys.push(cursor.key().clone());
+                cursor.step_key();
+            }
+
+            let mut arrays = columns(&keys);
+            arrays.push(Arc::new(Int64Array::from(weights)));
+
+            RecordBatch::try_new(schema.clone(), arrays).unwrap_or_else(|error| {
+                report_operator_error(format!("error building record batch: {error}"));
+                RecordBatch::new_empty(schema.clone())
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{operator::Generator, zset, Circuit, OrdZSet, RootCircuit, Stream};
+    use arrow::{
+        array::{Array, ArrayRef, Int64Array, StringArray},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+    type Row = (i64, Option<String>);
+
+    fn row(batch: &RecordBatch, i: usize) -> Row {
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let names = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+
+        (
+            ids.value(i),
+            (!names.is_null(i)).then(|| names.value(i).to_string()),
+        )
+    }
+
+    fn columns(rows: &[Row]) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(Int64Array::from_iter_values(rows.iter().map(|(id, _)| *id))),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|(_, name)| name.as_deref()),
+            )),
+        ]
+    }
+
+    #[test]
+    fn arrow_round_trip() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+
+        let inputs: Vec<OrdZSet<Row, isize>> = vec![
+            zset! { (1, Some("foo".to_string())) => 1, (2, None) => 1 },
+            zset! { (3, Some("bar".to_string())) => 3, (4, None) => -2 },
+        ];
+
+        let batches = Rc::new(RefCell::new(Vec::new()));
+        let round_trip = Rc::new(RefCell::new(Vec::new()));
+
+        let circuit = {
+            let batches = batches.clone();
+            let round_trip = round_trip.clone();
+            let mut inputs = inputs.clone().into_iter();
+
+            RootCircuit::build(move |circuit| {
+                let input: Stream<_, OrdZSet<Row, isize>> =
+                    circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+                let arrow = input.to_arrow(schema, columns);
+                arrow.inspect(move |batch| batches.borrow_mut().push(batch.clone()));
+                arrow
+                    .from_arrow(row)
+                    .inspect(move |zset: &OrdZSet<Row, isize>| {
+                        round_trip.borrow_mut().push(zset.clone())
+                    });
+            })
+            .unwrap()
+            .0
+        };
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+
+        assert_eq!(*round_trip.borrow(), inputs);
+
+        let batches = batches.borrow();
+        assert_eq!(batches[0].num_columns(), 3);
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema().field(2).name(), "weight");
+        assert!(batches[0].column(1).is_null(1));
+        assert_eq!(
+            batches[1]
+                .column(2)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .values(),
+            &[3, -2]
+        );
+    }
+
+    #[test]
+    fn from_arrow_unit_weights() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let rows = vec![(1, Some("foo".to_string())), (2, None)];
+        let mut batch = Some(RecordBatch::try_new(schema, columns(&rows)).unwrap());
+
+        let (circuit, output) = RootCircuit::build(move |circuit| {
+            circuit
+                .add_source(Generator::new(move || batch.take().unwrap()))
+                .from_arrow::<OrdZSet<Row, isize>, _>(row)
+                .output()
+        })
+        .unwrap();
+
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, Some("foo".to_string())) => 1, (2, None) => 1 }
+        );
+    }
+}
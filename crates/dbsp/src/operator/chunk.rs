@@ -0,0 +1,141 @@
+//! Operator that splits batches into chunks of bounded size.
+
+use crate::{
+    circuit::{Circuit, Stream},
+    trace::{Batch, Builder, Cursor},
+};
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: Batch<Time = ()>,
+{
+    /// Split each batch in the input stream into batches of at most
+    /// `max_tuples` tuples.
+    ///
+    /// Outputs a vector of batches whose union equals the input batch.
+    /// Tuples are assigned to chunks in order, so each chunk covers a
+    /// contiguous range of keys, and keys in earlier chunks are smaller than
+    /// (or equal to, for indexed Z-sets whose values span two chunks) keys
+    /// in later chunks.  An empty input batch produces an empty vector.
+    ///
+    /// This is useful for sinks that write fixed-size pages, which
+    /// otherwise struggle with huge output batches.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_tuples` is zero.
+    pub fn chunk_output(&self, max_tuples: usize) -> Stream<C, Vec<B>> {
+        assert!(max_tuples > 0, "chunks must contain at least one tuple");
+
+        self.apply_named("ChunkOutput", move |batch: &B| {
+            let mut chunks = Vec::new();
+
+            let mut builder = B::Builder::with_capacity((), max_tuples);
+            let mut chunk_len = 0;
+
+            let mut cursor = batch.cursor();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    if chunk_len == max_tuples {
+                        let next = B::Builder::with_capacity((), max_tuples);
+                        chunks.push(std::mem::replace(&mut builder, next).done());
+                        chunk_len = 0;
+                    }
+
+                    let weight = cursor.weight();
+                    builder.push((
+                        B::item_from(cursor.key().clone(), cursor.val().clone()),
+                        weight,
+                    ));
+                    chunk_len += 1;
+
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+
+            if chunk_len > 0 {
+                chunks.push(builder.done());
+            }
+
+            chunks
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::Generator,
+        trace::Batch,
+        Circuit, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+    };
+
+    #[test]
+    fn chunk_output_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let input: Stream<_, OrdZSet<u64, isize>> = circuit.add_source(Generator::new(|| {
+                OrdZSet::from_keys((), (0..1_000_000).map(|k| (k, 1)).collect())
+            }));
+
+            input.chunk_output(100_000).apply2(&input, |chunks, batch| {
+                assert_eq!(chunks.len(), 10);
+                assert!(chunks.iter().all(|chunk| chunk.len() == 100_000));
+
+                // Chunks are disjoint and add up to the original batch.
+                let mut union = OrdZSet::empty(());
+                for chunk in chunks {
+                    union = union.merge(chunk);
+                }
+                assert_eq!(&union, batch);
+            });
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn chunk_output_indexed() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![
+                OrdIndexedZSet::from_tuples(
+                    (),
+                    vec![
+                        ((1, 1), 1),
+                        ((1, 2), -1),
+                        ((1, 3), 2),
+                        ((2, 1), 1),
+                        ((3, 5), 1),
+                    ],
+                ),
+                OrdIndexedZSet::empty(()),
+            ]
+            .into_iter();
+            let expected: Vec<Vec<OrdIndexedZSet<u64, u64, isize>>> = vec![
+                vec![
+                    OrdIndexedZSet::from_tuples((), vec![((1, 1), 1), ((1, 2), -1)]),
+                    OrdIndexedZSet::from_tuples((), vec![((1, 3), 2), ((2, 1), 1)]),
+                    OrdIndexedZSet::from_tuples((), vec![((3, 5), 1)]),
+                ],
+                vec![],
+            ];
+            let mut expected = expected.into_iter();
+
+            let input: Stream<_, OrdIndexedZSet<u64, u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+
+            input
+                .chunk_output(2)
+                .inspect(move |chunks| assert_eq!(chunks, &expected.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Operators that convert the weights of a Z-set.
+
+use crate::{
+    algebra::HasOne,
+    circuit::{Circuit, Stream},
+    trace::{Batch, BatchReader, Builder, Cursor},
+    DBData, DBWeight, OrdIndexedZSet, OrdZSet,
+};
+
+impl<C, K, R> Stream<C, OrdZSet<K, R>>
+where
+    C: Circuit,
+    K: DBData,
+    R: DBWeight,
+{
+    /// Convert the weights in the input stream to type `R2`.
+    ///
+    /// Typically used to widen weights before an aggregation that might
+    /// overflow the original weight type, e.g., from `i32` to `i64`.  Keys
+    /// are preserved along with their order, so the output batch is built
+    /// without re-sorting.
+    #[track_caller]
+    pub fn coerce_weights<R2>(&self) -> Stream<C, OrdZSet<K, R2>>
+    where
+        R2: DBWeight + From<R>,
+    {
+        coerce_weights(self)
+    }
+
+    /// Materialize weights as part of the data.
+    ///
+    /// Maps each key `k` with weight `w` in the input batch to key `(k, w)`
+    /// with weight `1`, e.g., a key with weight `3` becomes `(k, 3)` and a
+    /// retraction becomes `(k, -1)`.  This is useful for sinks that write
+    /// weights explicitly, such as CDC streams or audit logs.
+    ///
+    /// The input stream is sharded first, so that all updates to a key are
+    /// annotated together even if they arrive at different workers.  Each
+    /// key occurs in a consolidated batch exactly once, so the output batch
+    /// is built without re-sorting.
+    pub fn annotate_weight(&self) -> Stream<C, OrdZSet<(K, R), R>>
+    where
+        R: HasOne,
+    {
+        self.shard()
+            .apply_named("AnnotateWeight", |batch: &OrdZSet<K, R>| {
+                let mut builder =
+                    <OrdZSet<(K, R), R> as Batch>::Builder::with_capacity((), batch.len());
+                let mut cursor = batch.cursor();
+                while cursor.key_valid() {
+                    builder.push(((cursor.key().clone(), cursor.weight()), R::one()));
+                    cursor.step_key();
+                }
+                builder.done()
+            })
+    }
+}
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+{
+    /// Convert the weights in the input stream to type `R2`, preserving keys
+    /// and values along with their order.
+    #[track_caller]
+    pub fn coerce_weights<R2>(&self) -> Stream<C, OrdIndexedZSet<K, V, R2>>
+    where
+        R2: DBWeight + From<R>,
+    {
+        coerce_weights(self)
+    }
+}
+
+#[track_caller]
+fn coerce_weights<C, B, O>(stream: &Stream<C, B>) -> Stream<C, O>
+where
+    C: Circuit,
+    B: BatchReader<Time = ()> + Clone + 'static,
+    O: Batch<Key = B::Key, Val = B::Val, Time = ()>,
+    O::R: From<B::R>,
+{
+    let coerced = stream
+        .try_sharded_version()
+        .apply_named("CoerceWeights", |batch: &B| {
+            let mut builder = O::Builder::with_capacity((), batch.len());
+            let mut cursor = batch.cursor();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    let weight = cursor.weight();
+                    builder.push((
+                        O::item_from(cursor.key().clone(), cursor.val().clone()),
+                        weight.into(),
+                    ));
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+            builder.done()
+        });
+
+    // Keys are unchanged, so the output is sharded iff the input is.
+    coerced.mark_sharded_if(stream);
+    coerced
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::CheckedInt, indexed_zset, operator::Generator, zset, Circuit, OrdZSet,
+        RootCircuit, Runtime, Stream,
+    };
+
+    #[test]
+    fn coerce_weights_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![
+                zset! { 1 => 1i32, 2 => i32::MAX, 3 => -5 },
+                zset! {},
+                zset! { 2 => i32::MIN },
+            ]
+            .into_iter();
+            let mut expected = vec![
+                zset! { 1 => 1i64, 2 => i32::MAX as i64, 3 => -5 },
+                zset! {},
+                zset! { 2 => i32::MIN as i64 },
+            ]
+            .into_iter();
+
+            let input: Stream<_, OrdZSet<u64, i32>> =
+                circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+
+            let widened = input.coerce_weights::<i64>();
+            widened.inspect(move |batch| assert_eq!(*batch, expected.next().unwrap()));
+
+            // Widened weights don't overflow when added up.
+            let mut expected_sum = vec![
+                zset! { 1 => 1i64, 2 => i32::MAX as i64, 3 => -5 },
+                zset! { 1 => 1i64, 2 => i32::MAX as i64, 3 => -5 },
+                zset! { 1 => 1i64, 2 => -1, 3 => -5 },
+            ]
+            .into_iter();
+            widened
+                .integrate()
+                .inspect(move |batch| assert_eq!(*batch, expected_sum.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..3 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn annotate_weight_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, i64>();
+            let output = input.annotate_weight().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![(1, 3), (2, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 3) => 1, (2, 1) => 1 });
+
+        // Retractions are annotated with negative weights.
+        input.append(&mut vec![(1, -1), (2, -1), (3, 1), (3, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, -1) => 1, (2, -1) => 1, (3, 2) => 1 }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn coerce_to_checked_int_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, i64>();
+            let output = input.coerce_weights::<CheckedInt<i64>>().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![(1, (10, 1)), (1, (11, -2)), (2, (20, 3))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => { 10 => CheckedInt::new(1), 11 => CheckedInt::new(-2) },
+                2 => { 20 => CheckedInt::new(3) }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! Operator that processes groups of values with the same key from two
+//! inputs together.
+
+use crate::{
+    algebra::{IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{Operator, QuaternaryOperator},
+        Scope,
+    },
+    operator::group::{read_group, subtract_delta},
+    trace::{Batch, BatchReader, Cursor, Spine},
+    Circuit, DBData, DBWeight, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+};
+use std::{borrow::Cow, marker::PhantomData};
+
+/// Cursor over the values associated with a key in one of the inputs of
+/// [`cogroup`](`Stream::cogroup`), indexed as Z-set keys.
+pub type CogroupCursor<'s, V, R> = <OrdZSet<V, R> as BatchReader>::Cursor<'s>;
+
+impl<Z1> Stream<RootCircuit, Z1>
+where
+    Z1: IndexedZSet + Send,
+    Z1::R: ZRingValue,
+{
+    /// Incrementally apply a function to the groups of values associated
+    /// with each key in `self` and `other`.
+    ///
+    /// For each key `k` present in either input, `cogroup_func` is invoked
+    /// with `k`, cursors over the values associated with `k` in `self` and
+    /// in `other` (either of which can be empty), and a callback that adds
+    /// a `(value, weight)` pair to the output for key `k`.  Unlike
+    /// [`join`](`crate::operator::Join::join`), which is applied to pairs of
+    /// values, `cogroup_func` sees both groups as a whole, which makes it
+    /// possible to implement operations like per-key set difference.
+    ///
+    /// The operator is incremental: when the values of a key change in
+    /// either input, it evaluates `cogroup_func` over the old and the new
+    /// contents of both groups, and outputs the difference between the two
+    /// results.  Hence `cogroup_func` must be deterministic.
+    pub fn cogroup<Z2, O, F>(
+        &self,
+        other: &Stream<RootCircuit, Z2>,
+        cogroup_func: F,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z1::Key, O, Z1::R>>
+    where
+        Z2: IndexedZSet<Key = Z1::Key, R = Z1::R> + Send,
+        O: DBData,
+        F: Fn(
+                &Z1::Key,
+                &mut CogroupCursor<'_, Z1::Val, Z1::R>,
+                &mut CogroupCursor<'_, Z2::Val, Z1::R>,
+                &mut dyn FnMut(O, Z1::R),
+            ) + 'static,
+    {
+        self.circuit().region("cogroup", || {
+            let left = self.shard();
+            let right = other.shard();
+
+            self.circuit()
+                .add_quaternary_operator(
+                    Cogroup::new(cogroup_func),
+                    &left,
+                    &right,
+                    &left.integrate_trace(),
+                    &right.integrate_trace(),
+                )
+                .mark_sharded()
+        })
+    }
+}
+
+/// Operator that applies a function to matching groups of two inputs.
+///
+/// Takes streams of changes to both inputs and their integrals, including
+/// the current changes.
+struct Cogroup<Z1, Z2, O, F> {
+    cogroup_func: F,
+    _type: PhantomData<(Z1, Z2, O)>,
+}
+
+impl<Z1, Z2, O, F> Cogroup<Z1, Z2, O, F> {
+    fn new(cogroup_func: F) -> Self {
+        Self {
+            cogroup_func,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<Z1, Z2, O, F> Operator for Cogroup<Z1, Z2, O, F>
+where
+    Z1: 'static,
+    Z2: 'static,
+    O: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("Cogroup")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+/// Compute the contents of the group associated with `key` before and
+/// after applying the changes in `delta_cursor` to it.
+fn old_and_new<C1, C2, K, V, R>(
+    trace_cursor: &mut C1,
+    delta_cursor: &mut C2,
+    key: &K,
+    new_values: &mut Vec<(V, R)>,
+    old_values: &mut Vec<(V, R)>,
+) -> (OrdZSet<V, R>, OrdZSet<V, R>)
+where
+    C1: Cursor<K, V, (), R>,
+    C2: Cursor<K, V, (), R>,
+    K: PartialEq,
+    V: DBData,
+    R: DBWeight + ZRingValue,
+{
+    read_group(trace_cursor, key, new_values);
+    if delta_cursor.key_valid() && delta_cursor.key() == key {
+        subtract_delta(new_values, delta_cursor, old_values);
+    } else {
+        old_values.clone_from(new_values);
+    }
+
+    (
+        OrdZSet::from_keys((), old_values.clone()),
+        OrdZSet::from_keys((), new_values.clone()),
+    )
+}
+
+impl<Z1, Z2, O, F>
+    QuaternaryOperator<Z1, Z2, Spine<Z1>, Spine<Z2>, OrdIndexedZSet<Z1::Key, O, Z1::R>>
+    for Cogroup<Z1, Z2, O, F>
+where
+    Z1: IndexedZSet,
+    Z1::R: ZRingValue,
+    Z2: IndexedZSet<Key = Z1::Key, R = Z1::R>,
+    O: DBData,
+    F: Fn(
+            &Z1::Key,
+            &mut CogroupCursor<'_, Z1::Val, Z1::R>,
+            &mut CogroupCursor<'_, Z2::Val, Z1::R>,
+            &mut dyn FnMut(O, Z1::R),
+        ) + 'static,
+{
+    fn eval<'a>(
+        &mut self,
+        left_delta: Cow<'a, Z1>,
+        right_delta: Cow<'a, Z2>,
+        left_trace: Cow<'a, Spine<Z1>>,
+        right_trace: Cow<'a, Spine<Z2>>,
+    ) -> OrdIndexedZSet<Z1::Key, O, Z1::R> {
+        let mut tuples = Vec::new();
+
+        let mut left_delta_cursor = left_delta.cursor();
+        let mut right_delta_cursor = right_delta.cursor();
+        let mut left_trace_cursor = left_trace.cursor();
+        let mut right_trace_cursor = right_trace.cursor();
+
+        // Buffers that hold the old and the new contents of a group.
+        let (mut left_new, mut left_old) = (Vec::new(), Vec::new());
+        let (mut right_new, mut right_old) = (Vec::new(), Vec::new());
+
+        // Iterate over keys that changed in either input.
+        loop {
+            let key = match (
+                left_delta_cursor.key_valid(),
+                right_delta_cursor.key_valid(),
+            ) {
+                (false, false) => break,
+                (true, false) => left_delta_cursor.key().clone(),
+                (false, true) => right_delta_cursor.key().clone(),
+                (true, true) => {
+                    if left_delta_cursor.key() <= right_delta_cursor.key() {
+                        left_delta_cursor.key().clone()
+                    } else {
+                        right_delta_cursor.key().clone()
+                    }
+                }
+            };
+
+            let (old_left, new_left) = old_and_new(
+                &mut left_trace_cursor,
+                &mut left_delta_cursor,
+                &key,
+                &mut left_new,
+                &mut left_old,
+            );
+            let (old_right, new_right) = old_and_new(
+                &mut right_trace_cursor,
+                &mut right_delta_cursor,
+                &key,
+                &mut right_new,
+                &mut right_old,
+            );
+
+            (self.cogroup_func)(
+                &key,
+                &mut old_left.cursor(),
+                &mut old_right.cursor(),
+                &mut |val, weight| tuples.push(((key.clone(), val), -weight)),
+            );
+            (self.cogroup_func)(
+                &key,
+                &mut new_left.cursor(),
+                &mut new_right.cursor(),
+                &mut |val, weight| tuples.push(((key.clone(), val), weight)),
+            );
+
+            if left_delta_cursor.key_valid() && left_delta_cursor.key() == &key {
+                left_delta_cursor.step_key();
+            }
+            if right_delta_cursor.key_valid() && right_delta_cursor.key() == &key {
+                right_delta_cursor.step_key();
+            }
+        }
+
+        OrdIndexedZSet::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{FilterMap, Generator},
+        trace::{Batch, Cursor},
+        Circuit, OrdIndexedZSet, RootCircuit, Stream,
+    };
+
+    fn test_input(
+        circuit: &RootCircuit,
+        seed: u64,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<u64, u64, isize>> {
+        // Pseudo-random insertions and deletions of `(key, value)` pairs.
+        let mut step = 0;
+        circuit.add_source(Generator::new(move || {
+            step += 1;
+            let tuples = (0..30u64)
+                .map(|i| {
+                    let x = (i * 7919 + step * 104729 + seed) % 997;
+                    let weight = if x % 3 == 0 { -1 } else { 1 };
+                    ((x % 10, x % 7), weight)
+                })
+                .collect::<Vec<_>>();
+            OrdIndexedZSet::from_tuples((), tuples)
+        }))
+    }
+
+    #[test]
+    fn cogroup_difference_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let left = test_input(circuit, 0);
+            let right = test_input(circuit, 500);
+
+            // Values present in the left group, but not in the right group.
+            let difference = left
+                .cogroup(&right, |_key, left, right, output| {
+                    while left.key_valid() {
+                        if left.weight() > 0 {
+                            right.seek_key(left.key());
+                            if !(right.key_valid()
+                                && right.key() == left.key()
+                                && right.weight() > 0)
+                            {
+                                output(*left.key(), 1);
+                            }
+                        }
+                        left.step_key();
+                    }
+                })
+                .integrate();
+
+            let expected = left
+                .distinct()
+                .map_index(|(k, v)| ((*k, *v), ()))
+                .antijoin(&right.map_index(|(k, v)| ((*k, *v), ())))
+                .map_index(|((k, v), ())| (*k, *v))
+                .integrate();
+
+            difference.apply2(&expected, |difference, expected| {
+                assert_eq!(difference, expected)
+            });
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..20 {
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Operator that replicates batches to all worker threads.
+
+use crate::{
+    circuit::GlobalNodeId,
+    circuit_cache_key,
+    operator::communication::exchange::new_exchange_operators,
+    trace::{Batch, Spine, Trace},
+    Circuit, Runtime, Stream,
+};
+use std::panic::Location;
+
+circuit_cache_key!(BroadcastId<C, D>(GlobalNodeId => Stream<C, D>));
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: Send + 'static,
+{
+    /// Replicate all shards of a stream to every worker.
+    ///
+    /// The output stream in each worker contains a union of all input
+    /// batches across all workers.  This is useful for distributing a small
+    /// relation, e.g., a reference table, to all workers, so that it can be
+    /// joined with a sharded stream without re-sharding the latter.
+    ///
+    /// The output of this operator is not sharded: every worker holds a
+    /// complete copy of the data.
+    ///
+    /// Like [`Self::shard`], this operator introduces a synchronization
+    /// barrier across all workers.
+    #[track_caller]
+    pub fn broadcast(&self) -> Stream<C, B>
+    where
+        // FIXME: Remove `Time = ()` restriction currently imposed by `.consolidate()`
+        B: Batch<Time = ()> + Send,
+    {
+        let location = Location::caller();
+
+        match Runtime::runtime() {
+            None => self.clone(),
+            Some(runtime) => {
+                let workers = runtime.num_workers();
+
+                if workers == 1 {
+                    self.clone()
+                } else {
+                    self.circuit()
+                        .cache_get_or_insert_with(
+                            BroadcastId::new(self.origin_node_id().clone()),
+                            move || {
+                                let (sender, receiver) = new_exchange_operators(
+                                    &runtime,
+                                    Runtime::worker_index(),
+                                    Some(location),
+                                    move |batch: B, batches: &mut Vec<B>| {
+                                        for _ in 1..workers {
+                                            batches.push(batch.clone());
+                                        }
+                                        batches.push(batch);
+                                    },
+                                    |trace: &mut Spine<B>, batch: B| trace.insert(batch),
+                                );
+
+                                self.circuit()
+                                    .add_exchange(sender, receiver, self)
+                                    .consolidate()
+                            },
+                        )
+                        .clone()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        operator::Generator,
+        trace::{Batch, BatchReader},
+        Circuit, OrdIndexedZSet, RootCircuit, Runtime,
+    };
+
+    fn test_data(worker_index: usize, num_workers: usize) -> OrdIndexedZSet<usize, usize, isize> {
+        let tuples: Vec<_> = (0..100)
+            .filter(|n| n % num_workers == worker_index)
+            .map(|n| ((n, n * 10), 1))
+            .collect();
+        <OrdIndexedZSet<usize, usize, isize>>::from_tuples((), tuples)
+    }
+
+    #[test]
+    fn test_broadcast() {
+        do_test_broadcast(2);
+        do_test_broadcast(4);
+    }
+
+    fn do_test_broadcast(workers: usize) {
+        let hruntime = Runtime::run(workers, move || {
+            let circuit = RootCircuit::build(move |circuit| {
+                let input = circuit.add_source(Generator::new(move || {
+                    test_data(Runtime::worker_index(), workers)
+                }));
+
+                // Every worker receives the union of all shards.
+                input
+                    .broadcast()
+                    .inspect(|batch| assert_eq!(batch, &test_data(0, 1)));
+
+                // Only the receiver of `gather` gets the union of all shards.
+                // `gather` doesn't synchronize workers by itself, so shard the
+                // input first to keep producers from running ahead of the
+                // receiver.
+                input.shard().gather(workers - 1).inspect(
+                    move |batch: &OrdIndexedZSet<usize, usize, isize>| {
+                        if Runtime::worker_index() == workers - 1 {
+                            assert_eq!(batch, &test_data(0, 1));
+                        } else {
+                            assert_eq!(batch.len(), 0);
+                        }
+                    },
+                );
+            })
+            .unwrap()
+            .0;
+
+            for _ in 0..3 {
+                circuit.step().unwrap();
+            }
+        });
+
+        hruntime.join().unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+mod broadcast;
 mod exchange;
 mod gather;
 mod shard;
@@ -7,7 +7,7 @@
 use crate::{
     circuit::GlobalNodeId,
     circuit_cache_key, default_hash,
-    operator::communication::exchange::new_exchange_operators,
+    operator::{communication::exchange::new_exchange_operators, trace::share_retention},
     trace::{cursor::Cursor, Batch, BatchReader, Builder, Spine, Trace},
     Circuit, Runtime, Stream,
 };
@@ -151,6 +151,14 @@ where
                                 output.clone(),
                             );
 
+                            // Sharding preserves keys, so traces of the sharded
+                            // stream follow the retention bound of `self`.
+                            share_retention::<C, IB::Key>(
+                                self.circuit(),
+                                self.origin_node_id(),
+                                output.origin_node_id(),
+                            );
+
                             output
                         },
                     )
@@ -161,6 +169,63 @@ where
         })
     }
 
+    /// Shard batches across multiple worker threads based on a user-supplied
+    /// function.
+    ///
+    /// Like [`Self::shard`], but routes each `(key, value)` pair based on
+    /// the hash of `f(key, value)` instead of the hash of the key.  Two
+    /// streams sharded with functions that return the same value for
+    /// matching records are co-located: all matching records from both
+    /// streams end up at the same worker, e.g., when sharding two relations
+    /// by a shared sub-field of their keys and values.  Such streams can be
+    /// combined by operators that process each worker's data locally, e.g.,
+    /// [`Stream::apply2`], without another exchange.
+    ///
+    /// Unlike [`Self::shard`], the output stream is not marked as sharded,
+    /// since it does not generally satisfy the requirements of operators
+    /// like `join` and `distinct`, which will re-shard it by key.  If `f`
+    /// only depends on the key, the output satisfies these requirements for
+    /// all streams sharded by the same function, but not for streams sharded
+    /// with [`Self::shard`].
+    ///
+    /// When the circuit is not running inside a multithreaded runtime or is
+    /// running in a runtime with a single worker thread, returns `self`.
+    #[track_caller]
+    pub fn shard_by<F>(&self, f: F) -> Stream<C, IB>
+    where
+        IB: Batch + Send,
+        F: Fn(&IB::Key, &IB::Val) -> u64 + 'static,
+    {
+        let location = Location::caller();
+
+        match Runtime::runtime() {
+            Some(runtime) if runtime.num_workers() > 1 => {
+                let num_workers = runtime.num_workers();
+                let mut builders = Vec::with_capacity(num_workers);
+                let (sender, receiver) = new_exchange_operators(
+                    &runtime,
+                    Runtime::worker_index(),
+                    Some(location),
+                    move |batch: IB, batches: &mut Vec<IB>| {
+                        Self::shard_batch_by(
+                            &batch,
+                            num_workers,
+                            |key, val| default_hash(&f(key, val)),
+                            &mut builders,
+                            batches,
+                        );
+                    },
+                    |trace: &mut Spine<IB>, batch: IB| trace.insert(batch),
+                );
+
+                self.circuit()
+                    .add_exchange(sender, receiver, self)
+                    .consolidate()
+            }
+            _ => self.clone(),
+        }
+    }
+
     // Partitions the batch into `nshards` partitions based on the hash of the key.
     fn shard_batch<OB>(
         batch: &IB,
@@ -197,6 +262,47 @@ where
             outputs.push(builder.done());
         }
     }
+
+    // Partitions the batch into `nshards` partitions based on the value of `hash`
+    // for each `(key, value)` pair.  Unlike `shard_batch`, which hashes each key
+    // once, this evaluates `hash` for every value.
+    fn shard_batch_by<OB, H>(
+        batch: &IB,
+        shards: usize,
+        hash: H,
+        builders: &mut Vec<OB::Builder>,
+        outputs: &mut Vec<OB>,
+    ) where
+        OB: Batch<Key = IB::Key, Val = IB::Val, Time = (), R = IB::R>,
+        H: Fn(&IB::Key, &IB::Val) -> u64,
+    {
+        builders.clear();
+
+        for _ in 0..shards {
+            // We iterate over tuples in the batch in order; hence tuples added
+            // to each shard are also ordered, so we can use the more efficient
+            // `Builder` API (instead of `Batcher`) to construct output batches.
+            builders.push(OB::Builder::with_capacity((), batch.len() / shards));
+        }
+
+        let mut cursor = batch.cursor();
+
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let batch_index = hash(cursor.key(), cursor.val()) as usize % shards;
+                builders[batch_index].push((
+                    OB::item_from(cursor.key().clone(), cursor.val().clone()),
+                    cursor.weight(),
+                ));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        for builder in builders.drain(..) {
+            outputs.push(builder.done());
+        }
+    }
 }
 
 impl<C, T> Stream<C, T>
@@ -257,7 +363,7 @@ where
 mod tests {
     use crate::{
         operator::Generator,
-        trace::{Batch, BatchReader},
+        trace::{Batch, BatchReader, Cursor},
         Circuit, OrdIndexedZSet, RootCircuit, Runtime,
     };
 
@@ -305,4 +411,85 @@ mod tests {
 
         hruntime.join().unwrap();
     }
+
+    #[test]
+    fn test_shard_by() {
+        do_test_shard_by(2);
+        do_test_shard_by(4);
+    }
+
+    // order id -> customer id
+    fn order_data(worker_index: usize, num_workers: usize) -> OrdIndexedZSet<usize, usize, isize> {
+        let tuples: Vec<_> = (0..100)
+            .filter(|n| n % num_workers == worker_index)
+            .map(|n| ((n, n % 10), 1))
+            .collect();
+        <OrdIndexedZSet<usize, usize, isize>>::from_tuples((), tuples)
+    }
+
+    // customer id -> customer name
+    fn customer_data(
+        worker_index: usize,
+        num_workers: usize,
+    ) -> OrdIndexedZSet<usize, usize, isize> {
+        let tuples: Vec<_> = (0..10)
+            .filter(|n| n % num_workers == worker_index)
+            .map(|n| ((n, 100 * n), 1))
+            .collect();
+        <OrdIndexedZSet<usize, usize, isize>>::from_tuples((), tuples)
+    }
+
+    fn do_test_shard_by(workers: usize) {
+        let hruntime = Runtime::run(workers, move || {
+            let circuit = RootCircuit::build(move |circuit| {
+                let orders = circuit
+                    .add_source(Generator::new(move || {
+                        order_data(Runtime::worker_index(), workers)
+                    }))
+                    .shard_by(|_order, customer| *customer as u64);
+                let customers = circuit
+                    .add_source(Generator::new(move || {
+                        customer_data(Runtime::worker_index(), workers)
+                    }))
+                    .shard_by(|customer, _name| *customer as u64);
+
+                // Every order is co-located with its customer.
+                orders.apply2(
+                    &customers,
+                    |orders: &OrdIndexedZSet<usize, usize, isize>,
+                     customers: &OrdIndexedZSet<usize, usize, isize>| {
+                        let mut orders = orders.cursor();
+                        let mut customers = customers.cursor();
+
+                        while orders.key_valid() {
+                            customers.rewind_keys();
+                            customers.seek_key(orders.val());
+                            assert!(customers.key_valid());
+                            assert_eq!(customers.key(), orders.val());
+                            orders.step_key();
+                        }
+                    },
+                );
+
+                // Sharding doesn't lose or duplicate records.
+                orders
+                    .gather(0)
+                    .inspect(|batch: &OrdIndexedZSet<usize, usize, isize>| {
+                        if Runtime::worker_index() == 0 {
+                            assert_eq!(batch, &order_data(0, 1))
+                        } else {
+                            assert_eq!(batch.len(), 0);
+                        }
+                    });
+            })
+            .unwrap()
+            .0;
+
+            for _ in 0..3 {
+                circuit.step().unwrap();
+            }
+        });
+
+        hruntime.join().unwrap();
+    }
 }
@@ -1,9 +1,7 @@
-//! Source operator that reads data from a CSV file.
+//! Source and sink operators that read and write data in CSV format.
 #![cfg(feature = "with-serde")]
 
 // TODO:
-// - Error handling (currently we just panic on reader error or deserialization
-//   error).
 // - Batching (don't read the whole file in one clock cycle)
 // - Async implementation (wait for data to become available in the reader)
 // - Sharded implementation (currently we feed all data on worker 0).
@@ -11,22 +9,43 @@
 use crate::{
     algebra::{ZRingValue, ZSet},
     circuit::{
-        operator_traits::{Data, Operator, SourceOperator},
+        operator_traits::{Data, Operator, SinkOperator, SourceOperator},
         Scope,
     },
-    Runtime,
+    operator::{error_recovery::report_input_error, input::batch_with_capacity},
+    trace::{BatchReader, Cursor},
+    Circuit, Runtime, Stream,
+};
+use csv::{Reader as CsvReader, ReaderBuilder, Writer as CsvWriter, WriterBuilder};
+use serde::{
+    de::{self, Deserializer, Visitor},
+    forward_to_deserialize_any, Deserialize, Serialize,
+};
+use std::{
+    borrow::Cow,
+    error::Error as StdError,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Read, Write},
+    marker::PhantomData,
+    path::Path,
 };
-use csv::Reader as CsvReader;
-use serde::Deserialize;
-use std::{borrow::Cow, io::Read, marker::PhantomData};
 
 /// A source operator that reads records of type `T` from a CSV file.
 ///
 /// The operator reads the entire file and yields its contents
 /// in the first clock cycle as a Z-set with unit weights.
+///
+/// # Errors
+///
+/// Read and deserialization errors are not skipped.  The operator reports
+/// the error, identifying the offending record, and the current
+/// [`CircuitHandle::step`](`crate::CircuitHandle::step`) invocation returns
+/// [`SchedulerError::Input`](`crate::SchedulerError::Input`).
 pub struct CsvSource<R, T, W, C> {
     reader: CsvReader<R>,
     time: usize,
+    capacity_hint: usize,
     _t: PhantomData<(C, T, W)>,
 }
 
@@ -46,9 +65,152 @@ where
         Self {
             reader,
             time: 0,
+            capacity_hint: 0,
             _t: PhantomData,
         }
     }
+
+    /// Presize the buffer that records are read into and the builder of the
+    /// output batch for `capacity` records.
+    ///
+    /// When the number of records in the file is known in advance, this
+    /// avoids growing the buffer repeatedly while reading the file.
+    pub fn with_capacity_hint(mut self, capacity: usize) -> Self {
+        self.capacity_hint = capacity;
+        self
+    }
+}
+
+impl<R, T, W, C> CsvSource<R, T, W, C>
+where
+    C: Clone,
+    T: for<'de> Deserialize<'de>,
+    R: Read,
+{
+    /// Create a [`CsvSource`] that maps columns to the fields of `T` by name.
+    ///
+    /// The first row of the file must be a header row.  Columns are
+    /// matched with the fields of `T`, which must be a struct, by their
+    /// serde names, so the order of columns in the file doesn't have to
+    /// match the order of fields in `T`.
+    ///
+    /// Validates the header row against the fields of `T` upfront and fails
+    /// if a field of `T` doesn't have a column in the file or the file has a
+    /// column that doesn't match any field of `T`.
+    pub fn with_headers(reader: R) -> Result<Self, CsvHeaderError> {
+        let mut reader = ReaderBuilder::new().has_headers(true).from_reader(reader);
+
+        let fields = struct_fields::<T>().ok_or(CsvHeaderError::NotAStruct)?;
+        let headers = reader.headers().map_err(CsvHeaderError::Csv)?;
+
+        if let Some(field) = fields
+            .iter()
+            .find(|field| !headers.iter().any(|header| header == **field))
+        {
+            return Err(CsvHeaderError::MissingColumn(field.to_string()));
+        }
+
+        if let Some(header) = headers
+            .iter()
+            .find(|header| !fields.iter().any(|field| field == header))
+        {
+            return Err(CsvHeaderError::UnexpectedColumn(header.to_string()));
+        }
+
+        Ok(Self::from_csv_reader(reader))
+    }
+}
+
+/// Error validating the header row of a CSV file in
+/// [`CsvSource::with_headers`].
+#[derive(Debug)]
+pub enum CsvHeaderError {
+    /// Error reading the header row.
+    Csv(csv::Error),
+    /// The record type is not a struct, so its fields can't be mapped to
+    /// columns by name.
+    NotAStruct,
+    /// The file doesn't have a column for the named field of the record
+    /// type.
+    MissingColumn(String),
+    /// The named column of the file doesn't match any field of the record
+    /// type.
+    UnexpectedColumn(String),
+}
+
+impl Display for CsvHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv(error) => write!(f, "error reading CSV header: {error}"),
+            Self::NotAStruct => f.write_str("record type is not a struct"),
+            Self::MissingColumn(column) => write!(f, "missing column '{column}'"),
+            Self::UnexpectedColumn(column) => write!(f, "unexpected column '{column}'"),
+        }
+    }
+}
+
+impl StdError for CsvHeaderError {}
+
+/// Returns the serde names of the fields of `T` if `T` deserializes from a
+/// struct.
+fn struct_fields<T>() -> Option<&'static [&'static str]>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let mut fields = None;
+    // The probe always fails after recording the field names.
+    let _ = T::deserialize(FieldNamesProbe(&mut fields));
+    fields
+}
+
+/// A deserializer that records the field names requested by a struct
+/// deserializer without producing any data.
+struct FieldNamesProbe<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de, 'a> Deserializer<'de> for FieldNamesProbe<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("field names recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+impl<R, T, W, C> CsvSource<R, T, W, C>
+where
+    T: for<'de> Deserialize<'de>,
+    W: ZRingValue,
+    R: Read,
+{
+    /// Read all records from the file with unit weights.
+    fn read_records(&mut self) -> Result<Vec<(T, W)>, csv::Error> {
+        let mut records = Vec::with_capacity(self.capacity_hint);
+        for record in self.reader.deserialize() {
+            records.push((record?, W::one()));
+        }
+        Ok(records)
+    }
 }
 
 impl<R, T, W, C> Operator for CsvSource<R, T, W, C>
@@ -71,20 +233,20 @@ where
 
 impl<R, T, W, C> SourceOperator<C> for CsvSource<R, T, W, C>
 where
-    T: for<'de> Deserialize<'de> + 'static,
+    T: for<'de> Deserialize<'de> + Ord + 'static,
     W: ZRingValue + 'static,
     R: Read + 'static,
-    C: Data + ZSet<Key = T, R = W>,
+    C: Data + ZSet<Key = T, R = W, Item = T>,
 {
     fn eval(&mut self) -> C {
         let source = if self.time == 0 && Runtime::worker_index() == 0 {
-            let data: Vec<_> = self
-                .reader
-                .deserialize()
-                .map(|x| (x.unwrap(), W::one()))
-                .collect();
-
-            C::from_keys((), data)
+            match self.read_records() {
+                Ok(records) => batch_with_capacity(records, self.capacity_hint),
+                Err(error) => {
+                    report_input_error(format!("error reading CSV: {error}"));
+                    C::zero()
+                }
+            }
         } else {
             C::zero()
         };
@@ -94,10 +256,167 @@ where
     }
 }
 
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: ZSet + Send,
+    B::Key: Serialize,
+    B::R: Serialize,
+{
+    /// Write the contents of `self` to a CSV file at `path`.
+    ///
+    /// Creates or truncates the file and, at each clock cycle, appends a
+    /// record for each element of the current batch, consisting of the
+    /// columns of the element followed by its weight.  Elements with
+    /// negative weights (retractions) are written as is.  See [`CsvSink`]
+    /// for details.
+    ///
+    /// In a multithreaded runtime, the stream is gathered at worker 0, which
+    /// is the only worker that writes to the file.
+    pub fn write_csv<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let gathered = self.gather(0);
+
+        if Runtime::worker_index() == 0 {
+            let file = File::create(path)?;
+            self.circuit()
+                .add_sink(CsvSink::from_writer(file), &gathered);
+        }
+
+        Ok(())
+    }
+}
+
+/// A sink operator that writes Z-sets to a CSV file.
+///
+/// At each clock cycle, the operator writes a record for each element of the
+/// input batch, consisting of the columns of the element followed by its
+/// weight, and flushes the writer.  The file does not have a header row.
+///
+/// # Errors
+///
+/// Serialization and I/O errors are reported by the scheduler, which returns
+/// [`SchedulerError::Input`](`crate::SchedulerError::Input`)
+/// from the current [`CircuitHandle::step`](`crate::CircuitHandle::step`)
+/// invocation.
+pub struct CsvSink<W, B>
+where
+    W: Write,
+{
+    writer: CsvWriter<W>,
+    _t: PhantomData<B>,
+}
+
+impl<W, B> CsvSink<W, B>
+where
+    W: Write,
+{
+    /// Create a [`CsvSink`] instance that writes to any writer.
+    pub fn from_writer(writer: W) -> Self {
+        Self::from_csv_writer(WriterBuilder::new().has_headers(false).from_writer(writer))
+    }
+
+    /// Create a [`CsvSink`] from a pre-configured `CsvWriter`.
+    pub fn from_csv_writer(writer: CsvWriter<W>) -> Self {
+        Self {
+            writer,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<W, B> CsvSink<W, B>
+where
+    W: Write,
+    B: BatchReader<Val = (), Time = ()>,
+    B::Key: Serialize,
+    B::R: Serialize,
+{
+    /// Write all elements of `batch` and flush the writer.
+    fn write_batch(&mut self, batch: &B) -> Result<(), csv::Error> {
+        let mut cursor = batch.cursor();
+
+        while cursor.key_valid() {
+            let weight = cursor.weight();
+            self.writer.serialize((cursor.key(), weight))?;
+            cursor.step_key();
+        }
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W, B> Operator for CsvSink<W, B>
+where
+    W: Write + 'static,
+    B: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("CsvSink")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<W, B> SinkOperator<B> for CsvSink<W, B>
+where
+    W: Write + 'static,
+    B: BatchReader<Val = (), Time = ()> + 'static,
+    B::Key: Serialize,
+    B::R: Serialize,
+{
+    fn eval(&mut self, batch: &B) {
+        if let Err(error) = self.write_batch(batch) {
+            report_input_error(format!("error writing CSV: {error}"));
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{operator::CsvSource, zset, Circuit, OrdZSet, RootCircuit};
-    use csv::ReaderBuilder;
+    use crate::{
+        operator::{CsvSource, Generator},
+        trace::Batch,
+        zset, Circuit, OrdZSet, RootCircuit, SchedulerError, Stream,
+    };
+    use csv::{Reader, ReaderBuilder};
+    use serde::Deserialize;
+    use size_of::SizeOf;
+    use std::fs;
+
+    const CSV_DATA: &str = "\
+18,3,237641
+237641,4,18
+18,5,21
+18,5,22
+18,5,23
+18,5,24
+18,5,25
+";
+
+    fn reader() -> Reader<&'static [u8]> {
+        ReaderBuilder::new()
+            .delimiter(b',')
+            .has_headers(false)
+            .from_reader(CSV_DATA.as_bytes())
+    }
+
+    fn expected() -> OrdZSet<(usize, usize, usize), isize> {
+        zset! {
+            (18, 3, 237641) => 1,
+            (237641, 4, 18) => 1,
+            (18, 5, 21) => 1,
+            (18, 5, 22) => 1,
+            (18, 5, 23) => 1,
+            (18, 5, 24) => 1,
+            (18, 5, 25) => 1,
+        }
+    }
 
     #[test]
     fn test_csv_reader() {
@@ -135,4 +454,162 @@ mod test {
 
         circuit.step().unwrap();
     }
+
+    #[test]
+    fn test_csv_capacity_hint() {
+        // The buffer is allocated once for all records in the file.
+        let mut source =
+            CsvSource::<_, _, isize, OrdZSet<(usize, usize, usize), isize>>::from_csv_reader(
+                reader(),
+            )
+            .with_capacity_hint(10);
+        let records = source.read_records().unwrap();
+        assert_eq!(records.len(), 7);
+        assert_eq!(records.capacity(), 10);
+        assert_eq!(OrdZSet::from_keys((), records), expected());
+
+        // The output batch is built by a builder presized by the hint.
+        let circuit = RootCircuit::build(move |circuit| {
+            let expected = expected();
+            circuit
+                .add_source(CsvSource::from_csv_reader(reader()).with_capacity_hint(10))
+                .inspect(move |data: &OrdZSet<(usize, usize, usize), isize>| {
+                    assert_eq!(data.layer.capacity(), 10);
+                    assert_eq!(data, &expected)
+                });
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let path = std::env::temp_dir().join(format!("dbsp_write_csv_{}.csv", std::process::id()));
+
+        let mut inputs = vec![
+            zset! { (1, "foo".to_string()) => 1, (2, "bar".to_string()) => 2 },
+            zset! {},
+            zset! { (1, "foo".to_string()) => -1 },
+        ]
+        .into_iter();
+
+        let circuit = {
+            let path = path.clone();
+            RootCircuit::build(move |circuit| {
+                let input: Stream<_, OrdZSet<(u64, String), isize>> =
+                    circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+                input.write_csv(path).unwrap();
+            })
+            .unwrap()
+            .0
+        };
+
+        // The file is flushed at every step.
+        circuit.step().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1,foo,1\n2,bar,2\n");
+
+        circuit.step().unwrap();
+        circuit.step().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "1,foo,1\n2,bar,2\n1,foo,-1\n"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[derive(
+        Clone,
+        Debug,
+        Deserialize,
+        Eq,
+        PartialEq,
+        Ord,
+        PartialOrd,
+        Hash,
+        SizeOf,
+        bincode::Decode,
+        bincode::Encode,
+    )]
+    struct Person {
+        name: String,
+        age: u32,
+        #[serde(rename = "home town")]
+        town: Option<String>,
+    }
+
+    type PersonSource = CsvSource<&'static [u8], Person, isize, OrdZSet<Person, isize>>;
+
+    #[test]
+    fn test_csv_with_headers() {
+        // Columns are in a different order than the fields of `Person`.
+        const PEOPLE: &str = "\
+home town,age,name
+Paris,30,alice
+,25,bob
+";
+
+        let circuit = RootCircuit::build(move |circuit| {
+            let expected = zset! {
+                Person { name: "alice".to_string(), age: 30, town: Some("Paris".to_string()) } => 1,
+                Person { name: "bob".to_string(), age: 25, town: None } => 1,
+            };
+            circuit
+                .add_source(PersonSource::with_headers(PEOPLE.as_bytes()).unwrap())
+                .inspect(move |data: &OrdZSet<Person, isize>| assert_eq!(data, &expected));
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn test_csv_with_headers_mismatch() {
+        let error = PersonSource::with_headers("age,name\n30,alice\n".as_bytes())
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "missing column 'home town'");
+
+        let error = PersonSource::with_headers("name,home town,age,email\n".as_bytes())
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "unexpected column 'email'");
+
+        let error =
+            CsvSource::<_, (u32, String), isize, OrdZSet<(u32, String), isize>>::with_headers(
+                "age,name\n".as_bytes(),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "record type is not a struct");
+    }
+
+    #[test]
+    fn test_csv_parse_error() {
+        let circuit = RootCircuit::build(move |circuit| {
+            circuit.add_source(CsvSource::<
+                _,
+                (usize, usize, usize),
+                isize,
+                OrdZSet<(usize, usize, usize), isize>,
+            >::from_csv_reader(
+                ReaderBuilder::new()
+                    .has_headers(false)
+                    .from_reader("1,2,3\n4,five,6\n".as_bytes()),
+            ));
+        })
+        .unwrap()
+        .0;
+
+        match circuit.step().unwrap_err() {
+            SchedulerError::Input { operator, error } => {
+                assert_eq!(operator, "CsvSource");
+                assert!(error.starts_with("error reading CSV"), "{error}");
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
 }
@@ -1,13 +1,16 @@
 //! Distinct operator.
 
 use crate::{
-    algebra::{AddByRef, HasOne, HasZero, IndexedZSet, Lattice, PartialOrder, Present, ZRingValue},
+    algebra::{
+        AddByRef, HasOne, HasZero, IndexedZSet, Lattice, PartialOrder, Present, ZRingValue, ZSet,
+    },
     circuit::{
         metadata::{MetaItem, OperatorMeta},
         operator_traits::{BinaryOperator, Operator, UnaryOperator},
         Circuit, GlobalNodeId, Scope, Stream, WithClock,
     },
     circuit_cache_key,
+    operator::Min,
     trace::{ord::OrdValSpine, Batch, BatchReader, Builder, Cursor as TraceCursor, Trace},
     DBTimestamp, OrdIndexedZSet, Timestamp,
 };
@@ -22,6 +25,7 @@ use std::{
 
 circuit_cache_key!(DistinctId<C, D>(GlobalNodeId => Stream<C, D>));
 circuit_cache_key!(DistinctIncrementalId<C, D>(GlobalNodeId => Stream<C, D>));
+circuit_cache_key!(UnionDistinctId<C, D>((GlobalNodeId, GlobalNodeId) => Stream<C, D>));
 
 impl<C, Z> Stream<C, Z>
 where
@@ -104,6 +108,77 @@ where
             )
             .clone()
     }
+
+    /// Incrementally deduplicate input stream, annotating each element with
+    /// its multiplicity.
+    ///
+    /// Given a stream of changes to Z-set `A`, computes a stream of changes
+    /// to indexed Z-set `A'`, that for each key `k` with non-zero weight `w`
+    /// in `A` contains a tuple `(k, w, 1)`.  In other words, each key appears
+    /// in the output exactly once, like in the output of
+    /// [`distinct`](`Self::distinct`), and the original weight of the key is
+    /// stored as its value.
+    ///
+    /// When the weight of a key changes without dropping to zero, the
+    /// output retracts the old count and inserts the new one, so the key
+    /// remains present with weight 1.
+    #[allow(clippy::type_complexity)]
+    pub fn distinct_with_count(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::R, Z::R>>
+    where
+        Z: ZSet,
+        Z::R: ZRingValue,
+        <C as WithClock>::Time: DBTimestamp,
+    {
+        self.index_with(|k: &Z::Key| (k.clone(), ())).count()
+    }
+
+    /// Incrementally select one value for each key in an indexed Z-set.
+    ///
+    /// Given a stream of changes to indexed Z-set `A`, computes a stream of
+    /// changes to indexed Z-set `A'`, that for each key `k` in `A` contains
+    /// a single tuple `(k, v, 1)`, where `v` is the smallest value associated
+    /// with `k` in `A`, similar to `SELECT DISTINCT ON (k)` in SQL.  Choosing
+    /// the smallest value makes the output deterministic.
+    ///
+    /// When the chosen value of a key is retracted, the operator retracts it
+    /// from the output and promotes the next smallest value of the key.
+    #[allow(clippy::type_complexity)]
+    pub fn distinct_by_key(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::Val, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        <C as WithClock>::Time: DBTimestamp,
+    {
+        self.aggregate(Min)
+    }
+
+    /// Incrementally compute the set union of two relations.
+    ///
+    /// Given streams of changes to relations `A` and `B`, computes a stream
+    /// of changes to their union, `SELECT * FROM A UNION SELECT * FROM B`
+    /// in SQL, which contains each tuple present in `A` or `B` with weight 1.
+    /// Unlike [`plus`](`Self::plus`), which implements `UNION ALL`, a tuple
+    /// present in both inputs has weight 1, not 2.  The tuple remains in the
+    /// union until it's removed from both inputs.
+    ///
+    /// Since the weights of tuples in relations are positive, the union is
+    /// computed as `distinct(A + B)`, which maintains a single trace.
+    pub fn union_distinct(&self, other: &Stream<C, Z>) -> Stream<C, Z>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        <C as WithClock>::Time: DBTimestamp,
+    {
+        self.circuit()
+            .cache_get_or_insert_with(
+                UnionDistinctId::new((
+                    self.origin_node_id().clone(),
+                    other.origin_node_id().clone(),
+                )),
+                || self.plus(other).distinct(),
+            )
+            .clone()
+    }
 }
 
 /// `Distinct` operator changes all weights in the support of a Z-set to 1.
@@ -889,6 +964,129 @@ mod test {
         circuit.kill().unwrap();
     }
 
+    #[test]
+    fn distinct_with_count_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<usize, isize>();
+            let output = input.distinct_with_count().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![(1, 1), (2, 2), (3, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 1 => 1 }, 2 => { 2 => 1 }, 3 => { 1 => 1 } }
+        );
+
+        // Changing the multiplicity of a key only updates its count.
+        input.append(&mut vec![(1, 2), (2, -1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 1 => -1, 3 => 1 }, 2 => { 2 => -1, 1 => 1 } }
+        );
+
+        // Weight dropping to zero removes the key.
+        input.append(&mut vec![(3, -1), (4, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 3 => { 1 => -1 }, 4 => { 1 => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn distinct_by_key_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<usize, isize, isize>();
+            let output = input.distinct_by_key().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (5, 1)),
+            (1, (3, 1)),
+            (1, (7, 2)),
+            (2, (1, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 3 => 1 }, 2 => { 1 => 1 } }
+        );
+
+        // Changes to values other than the chosen one don't affect the output.
+        input.append(&mut vec![(1, (5, -1)), (1, (4, 1)), (2, (2, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+
+        // Removing the chosen value promotes the next smallest value.
+        input.append(&mut vec![(1, (3, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 3 => -1, 4 => 1 } }
+        );
+
+        // Removing the last value of a key removes the key.
+        input.append(&mut vec![(2, (1, -1)), (2, (2, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! { 2 => { 1 => -1 } });
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn union_distinct_test() {
+        let (mut circuit, (mut input1, mut input2, union, sum)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (input1, input_handle1) = circuit.add_input_zset::<usize, isize>();
+                let (input2, input_handle2) = circuit.add_input_zset::<usize, isize>();
+                let union = input1.union_distinct(&input2);
+                // Repeated calls share the same operators.
+                assert_eq!(
+                    input1.union_distinct(&input2).origin_node_id(),
+                    union.origin_node_id()
+                );
+                let union = union.output();
+                let sum = input1.plus(&input2).output();
+
+                (input_handle1, input_handle2, union, sum)
+            })
+            .unwrap();
+
+        input1.append(&mut vec![(1, 1), (2, 1), (3, 2)]);
+        input2.append(&mut vec![(2, 1), (4, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            union.consolidate(),
+            zset! { 1 => 1, 2 => 1, 3 => 1, 4 => 1 }
+        );
+        // `plus` adds up weights of keys present in both inputs.
+        assert_eq!(sum.consolidate(), zset! { 1 => 1, 2 => 2, 3 => 2, 4 => 1 });
+
+        // A key retracted from one side but present in the other remains in
+        // the union.
+        input1.append(&mut vec![(2, -1), (3, -1)]);
+        circuit.step().unwrap();
+        assert_eq!(union.consolidate(), zset! {});
+
+        // A key removed from both sides leaves the union.
+        input2.append(&mut vec![(2, -1)]);
+        input1.append(&mut vec![(3, -1), (5, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(union.consolidate(), zset! { 2 => -1, 3 => -1, 5 => 1 });
+
+        circuit.kill().unwrap();
+    }
+
     use proptest::{collection, prelude::*};
 
     type TestZSet = OrdZSet<usize, isize>;
@@ -0,0 +1,270 @@
+//! Isolate panics in user closures of operators.
+
+use crate::circuit::{Circuit, Stream};
+use std::{any::Any, cell::RefCell};
+
+/// An error reported by the operator being evaluated by the current thread.
+pub(crate) enum OperatorError {
+    /// The operator panicked or failed to produce its output (see
+    /// [`report_operator_error`]).
+    #[cfg_attr(not(feature = "with-arrow"), allow(dead_code))]
+    Panic(String),
+    /// The operator failed to read, parse, or write external data (see
+    /// [`report_input_error`]).
+    Input(String),
+}
+
+thread_local! {
+    // Error reported by the node being evaluated in this thread.
+    static OPERATOR_ERROR: RefCell<Option<OperatorError>> = const { RefCell::new(None) };
+}
+
+/// Take the error reported while evaluating the current node, if any.
+///
+/// Invoked by the scheduler after evaluating each node, so that the error
+/// can be reported as a [`SchedulerError`](`crate::SchedulerError`)
+/// identifying the node.
+pub(crate) fn take_operator_error() -> Option<OperatorError> {
+    OPERATOR_ERROR.with(|error| error.borrow_mut().take())
+}
+
+/// Report an error in the operator being evaluated by the current thread.
+///
+/// The scheduler aborts the current step after evaluating the operator and
+/// returns the error as
+/// [`SchedulerError::OperatorPanic`](`crate::SchedulerError::OperatorPanic`),
+/// exactly as if the operator panicked inside
+/// [`Stream::with_error_recovery`].
+/// Used by operators that can fail without panicking.
+#[cfg_attr(not(feature = "with-arrow"), allow(dead_code))]
+pub(crate) fn report_operator_error(message: String) {
+    OPERATOR_ERROR.with(|error| *error.borrow_mut() = Some(OperatorError::Panic(message)));
+}
+
+/// Report an I/O error in the operator being evaluated by the current
+/// thread.
+///
+/// Source and sink operators call this function when they fail to read,
+/// parse, or write external data.  The scheduler aborts the current step after evaluating the
+/// operator and returns the error as
+/// [`SchedulerError::Input`](`crate::SchedulerError::Input`).
+pub fn report_input_error(error: String) {
+    OPERATOR_ERROR.with(|slot| *slot.borrow_mut() = Some(OperatorError::Input(error)));
+}
+
+/// Extract the message from the payload of a panic.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
+
+impl<C, D> Stream<C, D>
+where
+    C: Circuit,
+{
+    /// Build operators with `constructor`, converting panics in their user
+    /// closures into errors.
+    ///
+    /// Invokes `constructor` with `self` and returns its result.  Panics
+    /// raised during the evaluation of any operator or subcircuit that
+    /// `constructor` adds to the circuit, e.g., in the closures passed to
+    /// [`flat_map`](`crate::operator::FilterMap::flat_map`) or
+    /// [`aggregate`](`Self::aggregate`), do not unwind the worker thread.
+    /// Instead, the panic is caught and the current
+    /// [`CircuitHandle::step`](`crate::CircuitHandle::step`) or
+    /// [`DBSPHandle::step`](`crate::DBSPHandle::step`) invocation returns
+    /// [`SchedulerError::OperatorPanic`](`crate::SchedulerError::OperatorPanic`)
+    /// with the id and name of the operator that panicked and the panic
+    /// message.
+    ///
+    /// ```
+    /// # use dbsp::{operator::FilterMap, RootCircuit, SchedulerError};
+    /// let (circuit, input) = RootCircuit::build(|circuit| {
+    ///     let (input, input_handle) = circuit.add_input_zset::<i64, isize>();
+    ///     input.with_error_recovery(|input| {
+    ///         input.flat_map(|n| {
+    ///             assert!(*n >= 0, "negative input");
+    ///             0..*n
+    ///         })
+    ///     });
+    ///     input_handle
+    /// })
+    /// .unwrap();
+    ///
+    /// input.push(-1, 1);
+    /// assert!(matches!(
+    ///     circuit.step(),
+    ///     Err(SchedulerError::OperatorPanic { .. })
+    /// ));
+    /// ```
+    ///
+    /// Operators that `constructor` reuses from the circuit cache rather
+    /// than creating, such as a [`shard`](`Self::shard`) or
+    /// [`integrate`](`Self::integrate`) of `self` built earlier, are not
+    /// affected.
+    ///
+    /// # Recovering from errors
+    ///
+    /// The step that failed is aborted, so operators scheduled after the
+    /// failed one are not evaluated, and the operator that panicked is left
+    /// in an unspecified state.  The host must treat the circuit as
+    /// poisoned and rebuild it, possibly after fixing the input that
+    /// triggered the panic.  A multithreaded runtime is terminated by
+    /// [`DBSPHandle::step`](`crate::DBSPHandle::step`) on any error.
+    ///
+    /// # Unwind safety
+    ///
+    /// User closures do not need to be
+    /// [`UnwindSafe`](`std::panic::UnwindSafe`), since the circuit is never
+    /// evaluated again after a panic.  However, state shared with the host,
+    /// e.g., via `Arc<Mutex<_>>` or output handles, can be observed after the
+    /// panic and may violate its invariants if the closure panicked while
+    /// modifying it.
+    ///
+    /// Panics can only be caught when the program is built with
+    /// `panic = "unwind"` (the default).  The panic hook is invoked as usual
+    /// before the panic is caught, so the panic message is still printed to
+    /// stderr by default.
+    pub fn with_error_recovery<F, T>(&self, constructor: F) -> T
+    where
+        F: FnOnce(&Self) -> T,
+    {
+        let circuit = self.circuit();
+        let num_nodes = circuit.num_nodes();
+
+        let result = constructor(self);
+
+        for id in circuit.node_ids().into_iter().skip(num_nodes) {
+            circuit.enable_error_recovery(id);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::DefaultSemigroup,
+        operator::{FilterMap, Fold},
+        trace::{BatchReader, Cursor},
+        Error as DBSPError, OrdZSet, RootCircuit, Runtime, SchedulerError,
+    };
+
+    #[test]
+    fn error_recovery_test() {
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<i64, isize>();
+            input.with_error_recovery(|input| {
+                input.flat_map(|n| {
+                    if *n == 3 {
+                        panic!("division by zero at input {n}");
+                    }
+                    [100 / (3 - *n)]
+                })
+            });
+            input_handle
+        })
+        .unwrap();
+
+        input_handle.push(1, 1);
+        circuit.step().unwrap();
+        input_handle.push(2, 1);
+        circuit.step().unwrap();
+
+        input_handle.push(3, 1);
+        match circuit.step().unwrap_err() {
+            SchedulerError::OperatorPanic {
+                operator, message, ..
+            } => {
+                assert_eq!(operator, "FlatMap");
+                assert_eq!(message, "division by zero at input 3");
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+
+    #[test]
+    fn error_recovery_aggregate() {
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            input.with_error_recovery(|input| {
+                input.aggregate(<Fold<_, DefaultSemigroup<_>, _, _>>::new(
+                    0i64,
+                    |sum: &mut i64, v: &i64, w: isize| {
+                        *sum = sum.checked_add(*v * w as i64).expect("aggregate overflow")
+                    },
+                ))
+            });
+            input_handle
+        })
+        .unwrap();
+
+        input_handle.push(1, (i64::MAX, 1));
+        circuit.step().unwrap();
+
+        input_handle.push(1, (1, 1));
+        match circuit.step().unwrap_err() {
+            SchedulerError::OperatorPanic {
+                operator, message, ..
+            } => {
+                assert_eq!(operator, "AggregateIncremental");
+                assert_eq!(message, "aggregate overflow");
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unrecovered panic")]
+    fn error_recovery_scope() {
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            input.with_error_recovery(|input| input.map(|n| *n + 1));
+            input.map(|_| -> u64 { panic!("unrecovered panic") });
+            input_handle
+        })
+        .unwrap();
+
+        input_handle.push(1, 1);
+        let _ = circuit.step();
+    }
+
+    #[test]
+    fn error_recovery_multithreaded() {
+        let (mut handle, mut input_handle) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            input.with_error_recovery(|input| {
+                input.apply(|batch: &OrdZSet<u64, isize>| {
+                    let mut cursor = batch.cursor();
+                    while cursor.key_valid() {
+                        assert!(*cursor.key() < 100, "key out of range");
+                        cursor.step_key();
+                    }
+                    batch.len()
+                })
+            });
+            input_handle
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, 1), (2, 1)]);
+        handle.step().unwrap();
+
+        input_handle.append(&mut vec![(3, 1), (100, 1)]);
+        match handle.step().unwrap_err() {
+            DBSPError::Scheduler(SchedulerError::OperatorPanic {
+                operator, message, ..
+            }) => {
+                assert_eq!(operator, "Apply");
+                assert_eq!(message, "key out of range");
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+}
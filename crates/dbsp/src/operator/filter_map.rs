@@ -1,6 +1,7 @@
 //! Filter and transform data record-by-record.
 
 use crate::{
+    algebra::{AddAssignByRef, HasZero, IndexedZSet},
     circuit::{
         operator_traits::{Operator, UnaryOperator},
         Circuit, OwnershipPreference, Scope, Stream,
@@ -291,6 +292,134 @@ where
     }
 }
 
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: IndexedZSet,
+{
+    /// Retain only the records whose keys belong to the closed range
+    /// `[lo, hi]`.
+    ///
+    /// This is equivalent to `filter(|(k, _)| lo <= k && k <= hi)`, but
+    /// more efficient for selective ranges: the operator seeks directly to
+    /// `lo` and stops at the first key greater than `hi`, so it never
+    /// touches keys outside the range.  Returns empty batches if `lo > hi`.
+    pub fn filter_index_range(&self, lo: B::Key, hi: B::Key) -> Self {
+        let filtered = self
+            .circuit()
+            .add_unary_operator(FilterRange::new(lo, hi), &self.try_sharded_version());
+        filtered.mark_sharded_if(self);
+        filtered
+    }
+
+    /// Order the values of each key by `sort_key`.
+    ///
+    /// Batches are always sorted by value within each key, so values can
+    /// only be enumerated in a custom order by making the order part of the
+    /// value.  This operator replaces each value `v` with `(sort_key(v), v)`,
+    /// so that the cursor over the output batch yields the values of each
+    /// key ordered by `sort_key` first and by the original value second,
+    /// e.g., by a secondary field of the value.  Keys and weights are
+    /// preserved.
+    #[allow(clippy::type_complexity)]
+    pub fn sort_within_key<F, S>(
+        &self,
+        sort_key: F,
+    ) -> Stream<C, OrdIndexedZSet<B::Key, (S, B::Val), B::R>>
+    where
+        F: Fn(&B::Val) -> S + 'static,
+        S: DBData,
+    {
+        let sorted = self
+            .try_sharded_version()
+            .apply_named("SortWithinKey", move |batch: &B| {
+                let mut builder =
+                    <OrdIndexedZSet<B::Key, (S, B::Val), B::R> as Batch>::Builder::with_capacity(
+                        (),
+                        batch.len(),
+                    );
+                let mut values = Vec::new();
+
+                // Keys don't change, so only the values of each key need to be
+                // re-sorted.
+                let mut cursor = batch.cursor();
+                while cursor.key_valid() {
+                    while cursor.val_valid() {
+                        let val = cursor.val().clone();
+                        values.push(((sort_key(&val), val), cursor.weight()));
+                        cursor.step_val();
+                    }
+
+                    values.sort_unstable_by(|(val1, _), (val2, _)| val1.cmp(val2));
+                    for (val, weight) in values.drain(..) {
+                        builder.push(((cursor.key().clone(), val), weight));
+                    }
+                    cursor.step_key();
+                }
+
+                builder.done()
+            });
+
+        sorted.mark_sharded_if(self);
+        sorted
+    }
+
+    /// Project each record to its key.
+    ///
+    /// The weight of each key in the output is the sum of the weights of all
+    /// its values in the input.  Keys whose weights add up to zero are
+    /// omitted.  Since the keys of the input batch are already sorted, the
+    /// output batch is built in a single pass without re-sorting.
+    pub fn keys(&self) -> Stream<C, OrdZSet<B::Key, B::R>> {
+        let keys = self
+            .try_sharded_version()
+            .apply_named("Keys", move |batch: &B| {
+                let mut builder =
+                    <OrdZSet<B::Key, B::R> as Batch>::Builder::with_capacity((), batch.key_count());
+
+                let mut cursor = batch.cursor();
+                while cursor.key_valid() {
+                    let mut weight = B::R::zero();
+                    while cursor.val_valid() {
+                        weight.add_assign_by_ref(&cursor.weight());
+                        cursor.step_val();
+                    }
+
+                    if !weight.is_zero() {
+                        builder.push((cursor.key().clone(), weight));
+                    }
+                    cursor.step_key();
+                }
+
+                builder.done()
+            });
+
+        keys.mark_sharded_if(self);
+        keys
+    }
+
+    /// Project each record to its value.
+    ///
+    /// Equal values associated with different keys are consolidated into a
+    /// single output record whose weight is the sum of their weights.
+    pub fn values(&self) -> Stream<C, OrdZSet<B::Val, B::R>> {
+        self.apply_named("Values", move |batch: &B| {
+            let mut values = Vec::with_capacity(batch.len());
+
+            let mut cursor = batch.cursor();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    values.push((cursor.val().clone(), cursor.weight()));
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+
+            OrdZSet::from_keys((), values)
+        })
+    }
+}
+
 /// Internal implementation for filtering [`BatchReader`]s
 pub struct FilterKeys<CI, CO, F> {
     filter: F,
@@ -520,6 +649,67 @@ where
     }
 }
 
+/// Internal implementation of [`Stream::filter_index_range`].
+pub struct FilterRange<B>
+where
+    B: BatchReader,
+{
+    lo: B::Key,
+    hi: B::Key,
+}
+
+impl<B> FilterRange<B>
+where
+    B: BatchReader,
+{
+    pub fn new(lo: B::Key, hi: B::Key) -> Self {
+        Self { lo, hi }
+    }
+}
+
+impl<B> Operator for FilterRange<B>
+where
+    B: BatchReader,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("FilterRange")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<B> UnaryOperator<B, B> for FilterRange<B>
+where
+    B: Batch<Time = ()>,
+{
+    fn eval(&mut self, input: &B) -> B {
+        let mut builder = B::Builder::new_builder(());
+
+        if self.lo > self.hi {
+            return builder.done();
+        }
+
+        // `seek_key` uses galloping search to skip over keys below the
+        // range.
+        let mut cursor = input.cursor();
+        cursor.seek_key(&self.lo);
+
+        while cursor.key_valid() && cursor.key() <= &self.hi {
+            while cursor.val_valid() {
+                let val = cursor.val().clone();
+                let w = cursor.weight();
+                builder.push((B::item_from(cursor.key().clone(), val), w));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
 /// Internal implementation of `OrdIndexedZSet::map`,
 /// `OrdIndexedZSet::map_index`.
 pub struct Map<CI, CO, F> {
@@ -729,8 +919,8 @@ mod test {
     use crate::{
         indexed_zset,
         operator::{FilterMap, Generator},
-        trace::ord::OrdZSet,
-        zset, Circuit, RootCircuit,
+        trace::{ord::OrdZSet, Batch, BatchReader, Cursor},
+        zset, Circuit, OrdIndexedZSet, RootCircuit, Stream,
     };
     use std::vec;
 
@@ -887,4 +1077,198 @@ mod test {
             circuit.step().unwrap();
         }
     }
+
+    #[test]
+    fn filter_index_range_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut input = vec![
+                indexed_zset! { 1 => {1 => 1}, 3 => {3 => 1, 30 => -1}, 5 => {5 => 2}, 7 => {7 => 1}, 9 => {9 => 1} },
+                indexed_zset! { 0 => {0 => 1}, 10 => {10 => 1} },
+            ]
+            .into_iter();
+
+            let mut range_output = vec![
+                indexed_zset! { 3 => {3 => 1, 30 => -1}, 5 => {5 => 2}, 7 => {7 => 1} },
+                indexed_zset! {},
+            ]
+            .into_iter();
+            let mut empty_output = vec![indexed_zset! {}, indexed_zset! {}].into_iter();
+
+            let input: Stream<_, OrdIndexedZSet<isize, isize, isize>> =
+                circuit.add_source(Generator::new(move || input.next().unwrap()));
+
+            input.filter_index_range(2, 8).inspect(move |batch| {
+                assert_eq!(*batch, range_output.next().unwrap());
+            });
+            input.filter_index_range(8, 2).inspect(move |batch| {
+                assert_eq!(*batch, empty_output.next().unwrap());
+            });
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn sort_within_key_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            // Values are `(name, priority)` pairs to be ordered by descending
+            // priority.
+            let mut input = vec![
+                indexed_zset! {
+                    1 => { ("a".to_string(), 1) => 1, ("b".to_string(), 3) => 2, ("c".to_string(), 2) => -1 },
+                    2 => { ("d".to_string(), 5) => 1 }
+                },
+                indexed_zset! {},
+            ]
+            .into_iter();
+
+            let mut expected = vec![
+                vec![
+                    (1, ("b".to_string(), 3), 2),
+                    (1, ("c".to_string(), 2), -1),
+                    (1, ("a".to_string(), 1), 1),
+                    (2, ("d".to_string(), 5), 1),
+                ],
+                vec![],
+            ]
+            .into_iter();
+
+            let input: Stream<_, OrdIndexedZSet<u64, (String, i64), isize>> =
+                circuit.add_source(Generator::new(move || input.next().unwrap()));
+
+            input
+                .sort_within_key(|(_name, priority)| -priority)
+                .inspect(move |batch| {
+                    let mut contents = Vec::new();
+                    let mut cursor = batch.cursor();
+                    while cursor.key_valid() {
+                        while cursor.val_valid() {
+                            let (sort_key, val) = cursor.val().clone();
+                            assert_eq!(sort_key, -val.1);
+                            contents.push((*cursor.key(), val, cursor.weight()));
+                            cursor.step_val();
+                        }
+                        cursor.step_key();
+                    }
+                    assert_eq!(contents, expected.next().unwrap());
+                });
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn flat_map_index_matches_flat_map_index() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut step = 0;
+            let input: Stream<_, OrdIndexedZSet<u64, u64, isize>> =
+                circuit.add_source(Generator::new(move || {
+                    step += 1;
+                    let tuples = (0..100u64)
+                        .map(|i| {
+                            let x = (i * 7919 + step * 104729) % 997;
+                            let weight = if x % 5 == 0 { -1 } else { 1 };
+                            ((x % 10, x), weight)
+                        })
+                        .collect::<Vec<_>>();
+                    OrdIndexedZSet::from_tuples((), tuples)
+                }));
+
+            // Each record produces zero or more `(key, value)` pairs, some of
+            // which collide across records.
+            let func = |(&k, &v): (&u64, &u64)| (0..v % 3).map(move |i| (v % 7 + i, k));
+
+            let fused = input.flat_map_index(func);
+            let expected = input.flat_map(func).index();
+
+            fused.apply2(&expected, |fused, expected| assert_eq!(fused, expected));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..10 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn keys_values_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![
+                indexed_zset! {
+                    1 => { 10 => 1, 11 => 2, 12 => 1 },
+                    2 => { 10 => 1, 20 => -1 },
+                    3 => { 30 => 1 },
+                },
+                indexed_zset! { 1 => { 10 => -1, 11 => -2, 12 => -1 }, 3 => { 10 => 1 } },
+                // The weights of the values of key 4 cancel out.
+                indexed_zset! { 4 => { 40 => 1, 41 => -1 } },
+            ]
+            .into_iter();
+
+            let mut expected_keys = vec![
+                zset! { 1 => 4, 3 => 1 },
+                zset! { 1 => -4, 3 => 1 },
+                zset! {},
+            ]
+            .into_iter();
+
+            let mut expected_values = vec![
+                zset! { 10 => 2, 11 => 2, 12 => 1, 20 => -1, 30 => 1 },
+                zset! { 11 => -2, 12 => -1 },
+                zset! { 40 => 1, 41 => -1 },
+            ]
+            .into_iter();
+
+            let input: Stream<_, OrdIndexedZSet<u64, u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+
+            input
+                .keys()
+                .inspect(move |keys| assert_eq!(*keys, expected_keys.next().unwrap()));
+            input
+                .values()
+                .inspect(move |values| assert_eq!(*values, expected_values.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..3 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn map_index_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![indexed_zset! {
+                1 => { 10 => 1, 11 => 1 },
+                2 => { 10 => 2 },
+            }]
+            .into_iter();
+
+            let input: Stream<_, OrdIndexedZSet<u64, u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+
+            // Swap keys and values in a single pass.
+            input.map_index(|(&k, &v)| (v, k)).inspect(move |swapped| {
+                assert_eq!(
+                    *swapped,
+                    indexed_zset! { 10 => { 1 => 1, 2 => 2 }, 11 => { 1 => 1 } }
+                )
+            });
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+    }
 }
@@ -0,0 +1,102 @@
+//! Operator that unnests indexed Z-sets whose values are Z-sets.
+
+use crate::{
+    algebra::MulByRef,
+    trace::{Batch, BatchReader, Cursor},
+    Circuit, DBData, DBWeight, OrdIndexedZSet, OrdZSet, Stream,
+};
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, OrdZSet<V, R>, R>>
+where
+    C: Circuit,
+    K: DBData,
+    V: DBData,
+    R: DBWeight + MulByRef<Output = R>,
+{
+    /// Flatten an indexed Z-set of Z-sets into an indexed Z-set.
+    ///
+    /// For each tuple `(k, inner, w1)` in the input and each `(v, w2)` in
+    /// Z-set `inner`, the output contains tuple `(k, v, w1 * w2)`.  Weights
+    /// of identical `(k, v)` pairs produced by different inner Z-sets are
+    /// added up, and pairs whose weight is zero are dropped.
+    ///
+    /// This operator is linear, so it can be applied to streams of changes
+    /// as well as to integrated streams.
+    pub fn flatten_nested_zset(&self) -> Stream<C, OrdIndexedZSet<K, V, R>> {
+        let flattened = self.try_sharded_version().apply_named(
+            "FlattenNestedZSet",
+            |batch: &OrdIndexedZSet<K, OrdZSet<V, R>, R>| {
+                let mut tuples = Vec::with_capacity(batch.len());
+
+                let mut cursor = batch.cursor();
+                while cursor.key_valid() {
+                    while cursor.val_valid() {
+                        let outer_weight = cursor.weight();
+
+                        let mut inner = cursor.val().cursor();
+                        while inner.key_valid() {
+                            let weight = outer_weight.mul_by_ref(&inner.weight());
+                            if !weight.is_zero() {
+                                tuples.push(((cursor.key().clone(), inner.key().clone()), weight));
+                            }
+                            inner.step_key();
+                        }
+
+                        cursor.step_val();
+                    }
+                    cursor.step_key();
+                }
+
+                OrdIndexedZSet::from_tuples((), tuples)
+            },
+        );
+
+        flattened.mark_sharded_if(self);
+        flattened
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        indexed_zset, operator::Generator, zset, Circuit, OrdIndexedZSet, OrdZSet, RootCircuit,
+        Stream,
+    };
+
+    #[test]
+    fn flatten_nested_zset_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![
+                indexed_zset! {
+                    1 => { zset! { 10 => 2, 20 => -1 } => 3, zset! { 10 => 1, 30 => 1 } => -1 },
+                    2 => { zset! { 10 => 1 } => 1 }
+                },
+                // Contributions of different inner Z-sets cancel out.
+                indexed_zset! {
+                    1 => { zset! { 10 => 1 } => 2, zset! { 10 => 2, 20 => 1 } => -1 },
+                    2 => { zset! { 10 => 1, 20 => 1 } => -1, zset! { 10 => 1 } => 1 }
+                },
+            ]
+            .into_iter();
+
+            let mut outputs = vec![
+                indexed_zset! { 1 => { 10 => 5, 20 => -3, 30 => -1 }, 2 => { 10 => 1 } },
+                indexed_zset! { 1 => { 20 => -1 }, 2 => { 20 => -1 } },
+            ]
+            .into_iter();
+
+            let input: Stream<_, OrdIndexedZSet<u64, OrdZSet<u64, isize>, isize>> =
+                circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+
+            input
+                .flatten_nested_zset()
+                .inspect(move |batch| assert_eq!(batch, &outputs.next().unwrap()));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+}
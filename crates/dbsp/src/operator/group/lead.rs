@@ -0,0 +1,209 @@
+use crate::{
+    algebra::{IndexedZSet, ZRingValue},
+    operator::group::{rows, NonIncrementalGroupTransformer},
+    DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::{traits::AsPrimitive, FromPrimitive};
+use std::{cmp::min, marker::PhantomData};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+{
+    /// Pair each value with the value `offset` positions ahead of it in the
+    /// same group.
+    ///
+    /// Values in each group are ordered in ascending order, with a value of
+    /// weight `w` occupying `w` consecutive positions.  For the value at
+    /// position `i`, outputs `(v, Some(v'))`, where `v'` is the value at
+    /// position `i + offset`, or `(v, None)` if the group contains fewer
+    /// than `i + offset + 1` values.  This is the equivalent of SQL's
+    /// `LEAD(v, offset) OVER (PARTITION BY k ORDER BY v)`.  Positions of a
+    /// value that are paired with the same value are output as a single
+    /// tuple whose weight is the number of such positions.
+    ///
+    /// Values with non-positive weights are ignored.
+    #[allow(clippy::type_complexity)]
+    pub fn lead(
+        &self,
+        offset: usize,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (Z::Val, Option<Z::Val>), Z::R>> {
+        self.group_transform(Lead::new(offset))
+    }
+}
+
+/// Group transformer that implements [`Stream::lead`].
+struct Lead<V> {
+    offset: usize,
+    _type: PhantomData<V>,
+}
+
+impl<V> Lead<V> {
+    fn new(offset: usize) -> Self {
+        Self {
+            offset,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<V, R> NonIncrementalGroupTransformer<V, (V, Option<V>), R> for Lead<V>
+where
+    V: DBData,
+    R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+{
+    fn name(&self) -> &'static str {
+        "Lead"
+    }
+
+    fn transform(&mut self, input: &[(V, R)], output_cb: &mut dyn FnMut((V, Option<V>), R)) {
+        let total: usize = input.iter().map(|(_, weight)| rows(weight)).sum();
+
+        // Index and start position of the value that contains the current
+        // lead position.
+        let mut lead_index = 0;
+        let mut lead_start = 0;
+
+        let mut start = 0;
+        for (val, weight) in input.iter() {
+            let end = start + rows(weight);
+
+            // Positions `start..end` of `val` are paired with positions
+            // `start + offset..end + offset`.
+            let mut pos = start + self.offset;
+            let lead_end = end + self.offset;
+            while pos < lead_end {
+                if pos >= total {
+                    output_cb((val.clone(), None), R::from_usize(lead_end - pos).unwrap());
+                    break;
+                }
+
+                while lead_start + rows(&input[lead_index].1) <= pos {
+                    lead_start += rows(&input[lead_index].1);
+                    lead_index += 1;
+                }
+
+                let count = min(lead_start + rows(&input[lead_index].1), lead_end) - pos;
+                output_cb(
+                    (val.clone(), Some(input[lead_index].0.clone())),
+                    R::from_usize(count).unwrap(),
+                );
+                pos += count;
+            }
+
+            start = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn lead_test() {
+        let (mut circuit, (mut input, lead1, lead2)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let lead1 = input.lead(1).integrate().output();
+            let lead2 = input.lead(2).integrate().output();
+
+            (input_handle, lead1, lead2)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (1, 1)),
+            (1, (3, 1)),
+            (1, (5, 1)),
+            (1, (7, 1)),
+            (2, (10, 2)),
+            (2, (20, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            lead1.consolidate(),
+            indexed_zset! {
+                1 => { (1, Some(3)) => 1, (3, Some(5)) => 1, (5, Some(7)) => 1, (7, None) => 1 },
+                2 => { (10, Some(10)) => 1, (10, Some(20)) => 1, (20, None) => 1 }
+            }
+        );
+        assert_eq!(
+            lead2.consolidate(),
+            indexed_zset! {
+                1 => { (1, Some(5)) => 1, (3, Some(7)) => 1, (5, None) => 1, (7, None) => 1 },
+                2 => { (10, Some(20)) => 1, (10, None) => 1, (20, None) => 1 }
+            }
+        );
+
+        // Insert a value in the middle of group 1 and retract one of the
+        // duplicate values in group 2.
+        input.append(&mut vec![(1, (4, 1)), (2, (10, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            lead1.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (1, Some(3)) => 1,
+                    (3, Some(4)) => 1,
+                    (4, Some(5)) => 1,
+                    (5, Some(7)) => 1,
+                    (7, None) => 1
+                },
+                2 => { (10, Some(20)) => 1, (20, None) => 1 }
+            }
+        );
+        assert_eq!(
+            lead2.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (1, Some(4)) => 1,
+                    (3, Some(5)) => 1,
+                    (4, Some(7)) => 1,
+                    (5, None) => 1,
+                    (7, None) => 1
+                },
+                2 => { (10, None) => 1, (20, None) => 1 }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn lead_weights_test() {
+        let (mut circuit, (mut input, lead)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let lead = input.lead(3).integrate().output();
+
+            (input_handle, lead)
+        })
+        .unwrap();
+
+        input.append(&mut vec![(1, (1, 1_000_000)), (1, (2, 2))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            lead.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (1, Some(1)) => 999_997,
+                    (1, Some(2)) => 2,
+                    (1, None) => 1,
+                    (2, None) => 2
+                }
+            }
+        );
+
+        // Retracting copies of a value shifts the positions that follow it.
+        input.append(&mut vec![(1, (1, -999_999)), (1, (3, 2))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            lead.consolidate(),
+            indexed_zset! {
+                1 => { (1, Some(3)) => 1, (2, Some(3)) => 1, (2, None) => 1, (3, None) => 2 }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,374 @@
+//! Operators that transform groups of values associated with each key.
+
+use crate::{
+    algebra::{HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    trace::{Batch, BatchReader, Cursor, Spine},
+    Circuit, DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::traits::AsPrimitive;
+use std::{borrow::Cow, cmp::max, marker::PhantomData};
+
+mod lead;
+mod rank;
+mod rolling_aggregate;
+mod topk;
+
+/// A transformer that maps the contents of a group of values associated
+/// with a key to a set of output values.
+///
+/// The transformer is non-incremental: it is applied to the complete
+/// contents of a group.  [`Stream::group_transform`] makes it incremental
+/// by applying the transformer to the old and the new contents of each
+/// modified group and computing the difference between the two outputs.
+pub trait NonIncrementalGroupTransformer<I, O, R>: 'static {
+    /// Name of the transformer, used as the name of the operator.
+    fn name(&self) -> &'static str;
+
+    /// Compute the output of the transformer for a group.
+    ///
+    /// `input` contains the values in the group, sorted in ascending
+    /// order, with their non-zero weights.  The transformer invokes
+    /// `output_cb` for each `(value, weight)` pair in its output.  Output
+    /// values don't need to be sorted or unique.
+    fn transform(&mut self, input: &[(I, R)], output_cb: &mut dyn FnMut(O, R));
+}
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incrementally apply `transformer` to the group of values associated
+    /// with each key in the input indexed Z-set.
+    ///
+    /// The output contains the union of the outputs of `transformer` for
+    /// all groups, indexed by key.  When a group changes, `transformer` is
+    /// evaluated over its old and new contents, and the output contains
+    /// the difference between the two results.  Hence `transformer` must
+    /// be deterministic.
+    pub fn group_transform<T, O>(
+        &self,
+        transformer: T,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, O, Z::R>>
+    where
+        T: NonIncrementalGroupTransformer<Z::Val, O, Z::R>,
+        O: DBData,
+    {
+        self.circuit().region("group_transform", || {
+            let stream = self.shard();
+
+            self.circuit()
+                .add_binary_operator(
+                    DiffGroupTransformer::new(transformer, false),
+                    &stream,
+                    &stream.integrate_trace(),
+                )
+                .mark_sharded()
+        })
+    }
+
+    /// Incrementally apply `transformer` to the distinct values in each
+    /// group of the input indexed Z-set.
+    ///
+    /// Equivalent to `self.distinct().group_transform(transformer)`: values
+    /// with positive weights are passed to `transformer` with weight `1`,
+    /// and all other values are ignored.  Unlike this composition, the
+    /// operator doesn't materialize the distinct values of the input in a
+    /// separate batch and trace, and it doesn't evaluate `transformer` for
+    /// groups whose distinct values didn't change.
+    pub fn group_transform_fused_distinct<T, O>(
+        &self,
+        transformer: T,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, O, Z::R>>
+    where
+        T: NonIncrementalGroupTransformer<Z::Val, O, Z::R>,
+        O: DBData,
+    {
+        self.circuit().region("group_transform_fused_distinct", || {
+            let stream = self.shard();
+
+            self.circuit()
+                .add_binary_operator(
+                    DiffGroupTransformer::new(transformer, true),
+                    &stream,
+                    &stream.integrate_trace(),
+                )
+                .mark_sharded()
+        })
+    }
+}
+
+/// Replace the weights of values with positive weights in `values` with `1`
+/// and remove all other values.
+fn distinct_values<V, R>(values: &mut Vec<(V, R)>)
+where
+    R: ZRingValue,
+{
+    values.retain(|(_, weight)| weight.ge0() && !weight.is_zero());
+    for (_, weight) in values.iter_mut() {
+        *weight = R::one();
+    }
+}
+
+/// Number of rows occupied by a value with weight `weight` in transformers
+/// that treat a value of weight `w > 0` as `w` identical rows.
+pub(super) fn rows<R>(weight: &R) -> usize
+where
+    R: AsPrimitive<i64>,
+{
+    max(weight.as_(), 0) as usize
+}
+
+/// Read the values associated with `key` in `cursor`, along with their
+/// non-zero weights, into `values`.
+pub(super) fn read_group<C, K, V, R>(cursor: &mut C, key: &K, values: &mut Vec<(V, R)>)
+where
+    C: Cursor<K, V, (), R>,
+    K: PartialEq,
+    V: Clone,
+    R: HasZero,
+{
+    values.clear();
+
+    cursor.seek_key(key);
+    if cursor.key_valid() && cursor.key() == key {
+        while cursor.val_valid() {
+            let weight = cursor.weight();
+            if !weight.is_zero() {
+                values.push((cursor.val().clone(), weight));
+            }
+            cursor.step_val();
+        }
+    }
+}
+
+/// Compute the contents of a group before applying changes in
+/// `delta_cursor` to it by subtracting them from `new_values`.
+///
+/// `delta_cursor` must point to the key of the group.  Both `new_values`
+/// and the values in `delta_cursor` are sorted, so this is a linear merge.
+pub(super) fn subtract_delta<C, K, V, R>(
+    new_values: &[(V, R)],
+    delta_cursor: &mut C,
+    old_values: &mut Vec<(V, R)>,
+) where
+    C: Cursor<K, V, (), R>,
+    V: Ord + Clone,
+    R: ZRingValue,
+{
+    old_values.clear();
+
+    let mut new_values = new_values.iter().peekable();
+    while delta_cursor.val_valid() {
+        let delta_weight = delta_cursor.weight();
+        let delta_val = delta_cursor.val();
+
+        while let Some((val, weight)) = new_values.next_if(|(val, _)| val < delta_val) {
+            old_values.push((val.clone(), weight.clone()));
+        }
+
+        let weight = match new_values.next_if(|(val, _)| val == delta_val) {
+            Some((_, weight)) => weight.clone() + -delta_weight,
+            None => -delta_weight,
+        };
+        if !weight.is_zero() {
+            old_values.push((delta_val.clone(), weight));
+        }
+
+        delta_cursor.step_val();
+    }
+    old_values.extend(new_values.cloned());
+}
+
+/// Operator that applies a [`NonIncrementalGroupTransformer`] to groups
+/// modified by each input delta and outputs changes to its output.
+///
+/// Takes the stream of changes to the input and its integral, including
+/// the current changes.
+struct DiffGroupTransformer<Z, O, T>
+where
+    Z: IndexedZSet,
+{
+    transformer: T,
+    // Apply the transformer to the distinct values of each group.
+    distinct: bool,
+    // Buffers that hold the old and the new contents of a group.
+    old_values: Vec<(Z::Val, Z::R)>,
+    new_values: Vec<(Z::Val, Z::R)>,
+    _type: PhantomData<O>,
+}
+
+impl<Z, O, T> DiffGroupTransformer<Z, O, T>
+where
+    Z: IndexedZSet,
+{
+    fn new(transformer: T, distinct: bool) -> Self {
+        Self {
+            transformer,
+            distinct,
+            old_values: Vec::new(),
+            new_values: Vec::new(),
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<Z, O, T> Operator for DiffGroupTransformer<Z, O, T>
+where
+    Z: IndexedZSet,
+    O: 'static,
+    T: NonIncrementalGroupTransformer<Z::Val, O, Z::R>,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(self.transformer.name())
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, O, T> BinaryOperator<Z, Spine<Z>, OrdIndexedZSet<Z::Key, O, Z::R>>
+    for DiffGroupTransformer<Z, O, T>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    O: DBData,
+    T: NonIncrementalGroupTransformer<Z::Val, O, Z::R>,
+{
+    fn eval(&mut self, delta: &Z, trace: &Spine<Z>) -> OrdIndexedZSet<Z::Key, O, Z::R> {
+        let mut tuples = Vec::new();
+
+        let mut delta_cursor = delta.cursor();
+        let mut trace_cursor = trace.cursor();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key().clone();
+
+            // The trace includes the current delta, so it contains the new
+            // contents of the group.
+            read_group(&mut trace_cursor, &key, &mut self.new_values);
+            subtract_delta(&self.new_values, &mut delta_cursor, &mut self.old_values);
+
+            if self.distinct {
+                distinct_values(&mut self.old_values);
+                distinct_values(&mut self.new_values);
+
+                if self.old_values == self.new_values {
+                    delta_cursor.step_key();
+                    continue;
+                }
+            }
+
+            if !self.old_values.is_empty() {
+                self.transformer
+                    .transform(&self.old_values, &mut |val, weight| {
+                        tuples.push(((key.clone(), val), -weight))
+                    });
+            }
+            if !self.new_values.is_empty() {
+                self.transformer
+                    .transform(&self.new_values, &mut |val, weight| {
+                        tuples.push(((key.clone(), val), weight))
+                    });
+            }
+
+            delta_cursor.step_key();
+        }
+
+        OrdIndexedZSet::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        topk::{Order, TopK},
+        NonIncrementalGroupTransformer,
+    };
+    use crate::Runtime;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Counts rows in a group, recording the number of evaluations.
+    struct Count(Arc<AtomicUsize>);
+
+    impl NonIncrementalGroupTransformer<i64, i64, isize> for Count {
+        fn name(&self) -> &'static str {
+            "Count"
+        }
+
+        fn transform(&mut self, input: &[(i64, isize)], output_cb: &mut dyn FnMut(i64, isize)) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            output_cb(input.iter().map(|(_, weight)| *weight as i64).sum(), 1);
+        }
+    }
+
+    #[test]
+    fn group_transform_fused_distinct_test() {
+        let evals = Arc::new(AtomicUsize::new(0));
+
+        let (mut circuit, (mut input, count, topk)) = Runtime::init_circuit(4, {
+            let evals = evals.clone();
+            move |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+                let distinct = input.distinct();
+
+                let count = (
+                    input
+                        .group_transform_fused_distinct(Count(evals))
+                        .integrate()
+                        .output(),
+                    distinct
+                        .group_transform(Count(Arc::new(AtomicUsize::new(0))))
+                        .integrate()
+                        .output(),
+                );
+                let topk = (
+                    input
+                        .group_transform_fused_distinct(TopK::new(2, Order::Descending, false))
+                        .integrate()
+                        .output(),
+                    distinct
+                        .group_transform(TopK::new(2, Order::Descending, false))
+                        .integrate()
+                        .output(),
+                );
+
+                (input_handle, count, topk)
+            }
+        })
+        .unwrap();
+
+        let steps: Vec<Vec<(u64, (i64, isize))>> = vec![
+            vec![(1, (1, 1)), (1, (2, 2)), (1, (3, 1)), (2, (5, 3))],
+            // More copies of existing values don't change distinct values.
+            vec![(1, (2, 1)), (2, (5, 1))],
+            // Retract all copies of a value and insert a new one.
+            vec![(1, (2, -3)), (1, (4, 1))],
+            // Values with negative weights are ignored.
+            vec![(2, (6, -1)), (2, (5, -4)), (2, (7, 1))],
+        ];
+
+        for (step, mut changes) in steps.into_iter().enumerate() {
+            let evals_before = evals.load(Ordering::Relaxed);
+
+            input.append(&mut changes);
+            circuit.step().unwrap();
+
+            assert_eq!(count.0.consolidate(), count.1.consolidate());
+            assert_eq!(topk.0.consolidate(), topk.1.consolidate());
+            if step == 1 {
+                assert_eq!(evals.load(Ordering::Relaxed), evals_before);
+            }
+        }
+
+        circuit.kill().unwrap();
+    }
+}
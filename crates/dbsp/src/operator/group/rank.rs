@@ -0,0 +1,391 @@
+use crate::{
+    algebra::{HasOne, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    operator::group::{read_group, subtract_delta},
+    trace::{Batch, BatchReader, Cursor, Spine},
+    Circuit, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::traits::AsPrimitive;
+use std::borrow::Cow;
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue + AsPrimitive<i64>,
+{
+    /// Number the values in each group sequentially.
+    ///
+    /// Values in each group are ordered in ascending order, with a value of
+    /// weight `w` occupying `w` consecutive rows.  Outputs a `(v, n)` pair
+    /// for each row, where `n` is the 1-based position of the row in the
+    /// group.  This is the equivalent of SQL's `ROW_NUMBER() OVER
+    /// (PARTITION BY k ORDER BY v)`.
+    ///
+    /// Values with non-positive weights are ignored.
+    #[allow(clippy::type_complexity)]
+    pub fn row_number(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (Z::Val, i64), Z::R>> {
+        self.rank_by(RankKind::RowNumber)
+    }
+
+    /// Rank the values in each group, leaving gaps after ties.
+    ///
+    /// Like [`Stream::row_number`], but all rows with equal values get the
+    /// same rank, which is the position of the first of these rows.  Hence
+    /// the rank of the next value exceeds it by the number of tied rows.
+    /// This is the equivalent of SQL's `RANK() OVER (PARTITION BY k ORDER BY
+    /// v)`.
+    #[allow(clippy::type_complexity)]
+    pub fn rank(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (Z::Val, i64), Z::R>> {
+        self.rank_by(RankKind::Rank)
+    }
+
+    /// Rank the values in each group without leaving gaps after ties.
+    ///
+    /// Like [`Stream::rank`], but ranks of consecutive distinct values
+    /// differ by one.  This is the equivalent of SQL's `DENSE_RANK() OVER
+    /// (PARTITION BY k ORDER BY v)`.
+    #[allow(clippy::type_complexity)]
+    pub fn dense_rank(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (Z::Val, i64), Z::R>> {
+        self.rank_by(RankKind::DenseRank)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn rank_by(
+        &self,
+        kind: RankKind,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (Z::Val, i64), Z::R>> {
+        self.circuit().region("rank", || {
+            let stream = self.shard();
+
+            self.circuit()
+                .add_binary_operator(Rank::new(kind), &stream, &stream.integrate_trace())
+                .mark_sharded()
+        })
+    }
+}
+
+/// Ranking function computed by [`Rank`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RankKind {
+    RowNumber,
+    Rank,
+    DenseRank,
+}
+
+/// Operator that implements [`Stream::row_number`], [`Stream::rank`], and
+/// [`Stream::dense_rank`].
+///
+/// Takes the stream of changes to the input and its integral, including
+/// the current changes.  Ranks are computed from the cumulative weights of
+/// the values in a group.  When a group changes, only the ranks of the
+/// values starting from the first changed value are recomputed, and the
+/// ranks of the values after the last changed value are only recomputed if
+/// the changes shift them.
+struct Rank<Z>
+where
+    Z: IndexedZSet,
+{
+    kind: RankKind,
+    // Buffers that hold the old and the new contents of a group.
+    old_values: Vec<(Z::Val, Z::R)>,
+    new_values: Vec<(Z::Val, Z::R)>,
+}
+
+impl<Z> Rank<Z>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue + AsPrimitive<i64>,
+{
+    fn new(kind: RankKind) -> Self {
+        Self {
+            kind,
+            old_values: Vec::new(),
+            new_values: Vec::new(),
+        }
+    }
+
+    /// Output the ranks of `values`, where the first of them occupies
+    /// 1-based position `position` in the group and has dense rank
+    /// `dense_rank`.  Returns the position and the dense rank of the value
+    /// that follows `values`.
+    fn rank_values(
+        &self,
+        values: &[(Z::Val, Z::R)],
+        mut position: i64,
+        mut dense_rank: i64,
+        output_cb: &mut dyn FnMut((Z::Val, i64), Z::R),
+    ) -> (i64, i64) {
+        for (val, weight) in values {
+            let count: i64 = weight.as_();
+            if count <= 0 {
+                continue;
+            }
+
+            match self.kind {
+                RankKind::RowNumber => {
+                    for row in position..position + count {
+                        output_cb((val.clone(), row), Z::R::one());
+                    }
+                }
+                RankKind::Rank => output_cb((val.clone(), position), *weight),
+                RankKind::DenseRank => output_cb((val.clone(), dense_rank), *weight),
+            }
+
+            position += count;
+            dense_rank += 1;
+        }
+
+        (position, dense_rank)
+    }
+}
+
+impl<Z> Operator for Rank<Z>
+where
+    Z: IndexedZSet,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from(match self.kind {
+            RankKind::RowNumber => "RowNumber",
+            RankKind::Rank => "Rank",
+            RankKind::DenseRank => "DenseRank",
+        })
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z> BinaryOperator<Z, Spine<Z>, OrdIndexedZSet<Z::Key, (Z::Val, i64), Z::R>> for Rank<Z>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue + AsPrimitive<i64>,
+{
+    fn eval(&mut self, delta: &Z, trace: &Spine<Z>) -> OrdIndexedZSet<Z::Key, (Z::Val, i64), Z::R> {
+        let mut tuples = Vec::new();
+
+        let mut delta_cursor = delta.cursor();
+        let mut trace_cursor = trace.cursor();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key().clone();
+
+            // The trace includes the current delta, so it contains the new
+            // contents of the group.
+            read_group(&mut trace_cursor, &key, &mut self.new_values);
+            subtract_delta(&self.new_values, &mut delta_cursor, &mut self.old_values);
+            let (old_values, new_values) = (&self.old_values, &self.new_values);
+
+            // Values before the first and after the last changed value are
+            // the same in the old and the new group.
+            let prefix = old_values
+                .iter()
+                .zip(new_values.iter())
+                .take_while(|(old, new)| old == new)
+                .count();
+            let suffix = old_values[prefix..]
+                .iter()
+                .rev()
+                .zip(new_values[prefix..].iter().rev())
+                .take_while(|(old, new)| old == new)
+                .count();
+            let old_suffix = old_values.len() - suffix;
+            let new_suffix = new_values.len() - suffix;
+
+            // Values before the first changed value keep their ranks.
+            let position = 1 + new_values[..prefix]
+                .iter()
+                .map(|(_, weight)| weight.as_().max(0))
+                .sum::<i64>();
+            let dense_rank = 1 + new_values[..prefix]
+                .iter()
+                .filter(|(_, weight)| weight.as_() > 0)
+                .count() as i64;
+
+            let mut retract = |val, weight: Z::R| tuples.push(((key.clone(), val), -weight));
+            let old_end = self.rank_values(
+                &old_values[prefix..old_suffix],
+                position,
+                dense_rank,
+                &mut retract,
+            );
+            let mut insert = |val, weight| tuples.push(((key.clone(), val), weight));
+            let new_end = self.rank_values(
+                &new_values[prefix..new_suffix],
+                position,
+                dense_rank,
+                &mut insert,
+            );
+
+            // Values after the last changed value keep their ranks unless
+            // the changes shift them.
+            let shifted = match self.kind {
+                RankKind::RowNumber | RankKind::Rank => old_end.0 != new_end.0,
+                RankKind::DenseRank => old_end.1 != new_end.1,
+            };
+            if shifted {
+                self.rank_values(
+                    &old_values[old_suffix..],
+                    old_end.0,
+                    old_end.1,
+                    &mut |val, weight| tuples.push(((key.clone(), val), -weight)),
+                );
+                self.rank_values(
+                    &new_values[new_suffix..],
+                    new_end.0,
+                    new_end.1,
+                    &mut |val, weight| tuples.push(((key.clone(), val), weight)),
+                );
+            }
+
+            delta_cursor.step_key();
+        }
+
+        OrdIndexedZSet::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RankKind;
+    use crate::{indexed_zset, IndexedZSet, Runtime};
+    use std::collections::BTreeMap;
+
+    // Compute ranks from scratch.
+    fn model(
+        contents: &BTreeMap<(u64, i64), isize>,
+        kind: RankKind,
+    ) -> Vec<(u64, (i64, i64), isize)> {
+        let mut groups: BTreeMap<u64, Vec<(i64, isize)>> = BTreeMap::new();
+        for ((key, val), weight) in contents {
+            groups.entry(*key).or_default().push((*val, *weight));
+        }
+
+        let mut result = Vec::new();
+        for (key, values) in groups {
+            let mut rows = Vec::new();
+            for (val, weight) in values {
+                rows.extend(std::iter::repeat(val).take(weight as usize));
+            }
+
+            let mut ranks = Vec::new();
+            for (i, val) in rows.iter().enumerate() {
+                let rank = match kind {
+                    RankKind::RowNumber => i as i64 + 1,
+                    RankKind::Rank => rows.iter().position(|v| v == val).unwrap() as i64 + 1,
+                    RankKind::DenseRank => {
+                        let mut distinct = rows[..=i].to_vec();
+                        distinct.dedup();
+                        distinct.len() as i64
+                    }
+                };
+                ranks.push((key, (*val, rank), 1));
+            }
+
+            // Consolidate.
+            ranks.sort();
+            for (key, val, weight) in ranks {
+                match result.last_mut() {
+                    Some((k, v, w)) if *k == key && *v == val => *w += weight,
+                    _ => result.push((key, val, weight)),
+                }
+            }
+        }
+
+        result
+    }
+
+    #[test]
+    fn rank_test() {
+        let (mut circuit, (mut input, row_number, rank, dense_rank)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+                let row_number = input.row_number().integrate().output();
+                let rank = input.rank().integrate().output();
+                let dense_rank = input.dense_rank().integrate().output();
+
+                (input_handle, row_number, rank, dense_rank)
+            })
+            .unwrap();
+
+        let steps: Vec<Vec<(u64, (i64, isize))>> = vec![
+            vec![(1, (10, 1)), (1, (20, 2)), (1, (30, 1)), (2, (5, 1))],
+            // Insert at the beginning of the group.
+            vec![(1, (1, 1))],
+            // Insert in the middle of the group, including a new tie.
+            vec![(1, (20, 1)), (1, (25, 1)), (2, (5, 1))],
+            // Insert at the end of the group, retract from the beginning.
+            vec![(1, (40, 1)), (1, (1, -1)), (2, (3, 1))],
+        ];
+
+        let mut contents = BTreeMap::new();
+
+        for mut step in steps {
+            for (key, (val, weight)) in step.iter() {
+                *contents.entry((*key, *val)).or_default() += weight;
+            }
+            contents.retain(|_, weight| *weight != 0);
+
+            input.append(&mut step);
+            circuit.step().unwrap();
+
+            for (output, kind) in [
+                (&row_number, RankKind::RowNumber),
+                (&rank, RankKind::Rank),
+                (&dense_rank, RankKind::DenseRank),
+            ] {
+                assert_eq!(
+                    output.consolidate().iter().collect::<Vec<_>>(),
+                    model(&contents, kind)
+                );
+            }
+        }
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn rank_delta_test() {
+        let (mut circuit, (mut input, rank)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let rank = input.rank().output();
+
+            (input_handle, rank)
+        })
+        .unwrap();
+
+        input.append(&mut (1..=10).map(|v| (1, (v, 1))).collect());
+        circuit.step().unwrap();
+
+        // Changes that don't shift the values that follow them only update
+        // the ranks of the changed values.
+        input.append(&mut vec![(1, (5, 1)), (1, (6, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            rank.consolidate(),
+            indexed_zset! { 1 => { (5, 5) => 1, (6, 6) => -1 } }
+        );
+
+        // A value with a large weight shifts the ranks of the values that
+        // follow it by its weight.
+        input.append(&mut vec![(1, (8, 999_999))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            rank.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (8, 8) => 999_999,
+                    (9, 9) => -1,
+                    (9, 1_000_008) => 1,
+                    (10, 10) => -1,
+                    (10, 1_000_009) => 1
+                }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
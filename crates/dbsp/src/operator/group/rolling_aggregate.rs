@@ -0,0 +1,432 @@
+use crate::{
+    algebra::{HasOne, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    operator::group::{read_group, rows, subtract_delta},
+    trace::{Batch, BatchReader, Cursor, Spine},
+    Circuit, DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::{traits::AsPrimitive, FromPrimitive};
+use std::{
+    borrow::Cow,
+    cmp::{max, min},
+};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+{
+    /// Aggregate each value in a group together with the values that
+    /// precede it in the group.
+    ///
+    /// Values in each group are ordered in ascending order, with a value of
+    /// weight `w` occupying `w` consecutive rows.  For each row, folds the
+    /// values of the last `width` rows up to and including the row using
+    /// `fold`, starting from `init`, and outputs `(v, aggregate)`.  This is
+    /// the equivalent of SQL's `AGG(v) OVER (PARTITION BY k ORDER BY v ROWS
+    /// BETWEEN width - 1 PRECEDING AND CURRENT ROW)`.
+    ///
+    /// When a value is inserted or retracted, the operator only recomputes
+    /// aggregates for the rows whose frame overlaps the changed rows, so
+    /// only the outputs for these rows change.  Rows of a value whose frame
+    /// only contains copies of the same value share one aggregate, so the
+    /// cost of a value does not depend on its weight.
+    ///
+    /// Values with non-positive weights are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    #[allow(clippy::type_complexity)]
+    pub fn rolling_aggregate_rows<A, F>(
+        &self,
+        width: usize,
+        init: A,
+        fold: F,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, (Z::Val, A), Z::R>>
+    where
+        A: DBData,
+        F: Fn(&mut A, &Z::Val) + 'static,
+    {
+        assert!(width > 0, "frame must contain at least one row");
+
+        self.circuit().region("rolling_aggregate_rows", || {
+            let stream = self.shard();
+
+            self.circuit()
+                .add_binary_operator(
+                    RollingAggregateRows::new(width, init, fold),
+                    &stream,
+                    &stream.integrate_trace(),
+                )
+                .mark_sharded()
+        })
+    }
+}
+
+/// Operator that implements [`Stream::rolling_aggregate_rows`].
+///
+/// Takes the stream of changes to the input and its integral, including
+/// the current changes.
+struct RollingAggregateRows<Z, A, F>
+where
+    Z: IndexedZSet,
+{
+    width: usize,
+    init: A,
+    fold: F,
+    // Buffers that hold the old and the new contents of a group.
+    old_values: Vec<(Z::Val, Z::R)>,
+    new_values: Vec<(Z::Val, Z::R)>,
+}
+
+impl<Z, A, F> RollingAggregateRows<Z, A, F>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+    A: DBData,
+    F: Fn(&mut A, &Z::Val),
+{
+    fn new(width: usize, init: A, fold: F) -> Self {
+        Self {
+            width,
+            init,
+            fold,
+            old_values: Vec::new(),
+            new_values: Vec::new(),
+        }
+    }
+
+    /// Fold the rows in `frame`, which lists the values in the frame of a
+    /// row with the number of rows they occupy in descending order.
+    fn aggregate_frame(&self, frame: &[(Z::Val, usize)]) -> A {
+        let mut aggregate = self.init.clone();
+        for (val, count) in frame.iter().rev() {
+            for _ in 0..*count {
+                (self.fold)(&mut aggregate, val);
+            }
+        }
+        aggregate
+    }
+
+    /// Compute aggregates for rows `from..to` of the group `values`.
+    fn aggregate_rows(
+        &self,
+        values: &[(Z::Val, Z::R)],
+        from: usize,
+        to: usize,
+        output_cb: &mut dyn FnMut((Z::Val, A), Z::R),
+    ) {
+        let mut frame = Vec::with_capacity(self.width);
+        let mut start = 0;
+
+        for (index, (val, weight)) in values.iter().enumerate() {
+            if start >= to {
+                break;
+            }
+
+            let end = start + rows(weight);
+            let mut row = max(start, from);
+            let last = min(end, to);
+
+            while row < last {
+                let offset = row - start;
+
+                if offset + 1 >= self.width {
+                    // The frames of all remaining rows of the value only
+                    // contain copies of the value.
+                    frame.clear();
+                    frame.push((val.clone(), self.width));
+                    let aggregate = self.aggregate_frame(&frame);
+                    output_cb(
+                        (val.clone(), aggregate),
+                        Z::R::from_usize(last - row).unwrap(),
+                    );
+                    break;
+                }
+
+                // The frame contains `offset + 1` copies of the value, preceded
+                // by the last rows of preceding values.
+                frame.clear();
+                frame.push((val.clone(), offset + 1));
+                let mut needed = self.width - offset - 1;
+                for (val, weight) in values[..index].iter().rev() {
+                    if needed == 0 {
+                        break;
+                    }
+                    let count = min(rows(weight), needed);
+                    if count > 0 {
+                        frame.push((val.clone(), count));
+                        needed -= count;
+                    }
+                }
+
+                let aggregate = self.aggregate_frame(&frame);
+                output_cb((val.clone(), aggregate), Z::R::one());
+                row += 1;
+            }
+
+            start = end;
+        }
+    }
+}
+
+impl<Z, A, F> Operator for RollingAggregateRows<Z, A, F>
+where
+    Z: IndexedZSet,
+    A: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("RollingAggregateRows")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, A, F> BinaryOperator<Z, Spine<Z>, OrdIndexedZSet<Z::Key, (Z::Val, A), Z::R>>
+    for RollingAggregateRows<Z, A, F>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+    A: DBData,
+    F: Fn(&mut A, &Z::Val) + 'static,
+{
+    fn eval(&mut self, delta: &Z, trace: &Spine<Z>) -> OrdIndexedZSet<Z::Key, (Z::Val, A), Z::R> {
+        let mut tuples = Vec::new();
+
+        let mut delta_cursor = delta.cursor();
+        let mut trace_cursor = trace.cursor();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key().clone();
+
+            // The trace includes the current delta, so it contains the new
+            // contents of the group.
+            read_group(&mut trace_cursor, &key, &mut self.new_values);
+            subtract_delta(&self.new_values, &mut delta_cursor, &mut self.old_values);
+            let (old_values, new_values) = (&self.old_values, &self.new_values);
+
+            // Values before the first and after the last changed value are
+            // the same in the old and the new group.
+            let prefix = old_values
+                .iter()
+                .zip(new_values.iter())
+                .take_while(|(old, new)| old == new)
+                .count();
+            let suffix = old_values[prefix..]
+                .iter()
+                .rev()
+                .zip(new_values[prefix..].iter().rev())
+                .take_while(|(old, new)| old == new)
+                .count();
+
+            // Rows before the first changed row keep their aggregates; rows
+            // more than `width - 1` rows after the last changed row have the
+            // same frames in the old and the new group.
+            let from: usize = new_values[..prefix].iter().map(|(_, w)| rows(w)).sum();
+            let old_to = old_values[..old_values.len() - suffix]
+                .iter()
+                .map(|(_, w)| rows(w))
+                .sum::<usize>()
+                + self.width
+                - 1;
+            let new_to = new_values[..new_values.len() - suffix]
+                .iter()
+                .map(|(_, w)| rows(w))
+                .sum::<usize>()
+                + self.width
+                - 1;
+
+            self.aggregate_rows(old_values, from, old_to, &mut |val, weight| {
+                tuples.push(((key.clone(), val), -weight))
+            });
+            self.aggregate_rows(new_values, from, new_to, &mut |val, weight| {
+                tuples.push(((key.clone(), val), weight))
+            });
+
+            delta_cursor.step_key();
+        }
+
+        OrdIndexedZSet::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, operator::group::NonIncrementalGroupTransformer, Runtime};
+    use proptest::{collection, prelude::*};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Reference implementation of rolling sums over the complete contents
+    /// of a group.
+    struct RollingSum {
+        width: usize,
+    }
+
+    impl NonIncrementalGroupTransformer<i64, (i64, i64), isize> for RollingSum {
+        fn name(&self) -> &'static str {
+            "RollingSum"
+        }
+
+        fn transform(
+            &mut self,
+            input: &[(i64, isize)],
+            output_cb: &mut dyn FnMut((i64, i64), isize),
+        ) {
+            let rows: Vec<i64> = input
+                .iter()
+                .flat_map(|(val, weight)| std::iter::repeat(*val).take((*weight).max(0) as usize))
+                .collect();
+
+            for (i, val) in rows.iter().enumerate() {
+                let sum = rows[(i + 1).saturating_sub(self.width)..=i].iter().sum();
+                output_cb((*val, sum), 1);
+            }
+        }
+    }
+
+    fn test_input() -> impl Strategy<Value = Vec<Vec<(u64, (i64, isize))>>> {
+        collection::vec(
+            collection::vec((0..3u64, (0..20i64, -2..=3isize)), 0..10),
+            0..20,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_rolling_sum(inputs in test_input(), width in 1..5usize) {
+            let (mut circuit, (mut input, output, expected)) = Runtime::init_circuit(2, move |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+                let output = input
+                    .rolling_aggregate_rows(width, 0, |sum: &mut i64, v| *sum += v)
+                    .integrate()
+                    .output();
+                let expected = input
+                    .group_transform(RollingSum { width })
+                    .integrate()
+                    .output();
+
+                (input_handle, output, expected)
+            })
+            .unwrap();
+
+            for mut batch in inputs {
+                input.append(&mut batch);
+                circuit.step().unwrap();
+                assert_eq!(output.consolidate(), expected.consolidate());
+            }
+
+            circuit.kill().unwrap();
+        }
+    }
+
+    #[test]
+    fn rolling_sum_recomputes_changed_rows() {
+        let folds = Arc::new(AtomicUsize::new(0));
+
+        let (mut circuit, (mut input, delta)) = Runtime::init_circuit(1, {
+            let folds = folds.clone();
+            move |circuit| {
+                let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+                let sums = input.rolling_aggregate_rows(3, 0, move |sum: &mut i64, v| {
+                    folds.fetch_add(1, Ordering::Relaxed);
+                    *sum += v
+                });
+
+                (input_handle, sums.output())
+            }
+        })
+        .unwrap();
+
+        // A value with a large weight only produces one aggregate per
+        // distinct frame.
+        input.append(&mut vec![(2, (2000, 1_000_000))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! { 2 => { (2000, 2000) => 1, (2000, 4000) => 1, (2000, 6000) => 999_998 } }
+        );
+        assert_eq!(folds.load(Ordering::Relaxed), 1 + 2 + 3);
+
+        input.append(&mut (0..1000).map(|v| (1, (v, 1))).collect());
+        circuit.step().unwrap();
+
+        // Inserting a value only recomputes the `width` rows that follow it
+        // in the old group and the `width + 1` rows that follow it in the new
+        // group, with `width` folds per row.
+        folds.store(0, Ordering::Relaxed);
+        input.append(&mut vec![(1, (500, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! { 1 => { (500, 1499) => 1, (501, 1500) => -1, (501, 1501) => 1 } }
+        );
+        assert_eq!(folds.load(Ordering::Relaxed), 3 * (3 + 4));
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn rolling_sum_test() {
+        let (mut circuit, (mut input, delta, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let sums = input.rolling_aggregate_rows(3, 0, |sum: &mut i64, v| *sum += v);
+
+            (input_handle, sums.output(), sums.integrate().output())
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (1, 1)),
+            (1, (2, 1)),
+            (1, (4, 1)),
+            (1, (8, 1)),
+            (1, (16, 1)),
+            (2, (5, 2)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => { (1, 1) => 1, (2, 3) => 1, (4, 7) => 1, (8, 14) => 1, (16, 28) => 1 },
+                2 => { (5, 5) => 1, (5, 10) => 1 }
+            }
+        );
+
+        // Out-of-order insertion only affects the rows whose frames contain
+        // the new value.
+        input.append(&mut vec![(1, (3, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! {
+                1 => { (3, 6) => 1, (4, 7) => -1, (4, 9) => 1, (8, 14) => -1, (8, 15) => 1 }
+            }
+        );
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (1, 1) => 1,
+                    (2, 3) => 1,
+                    (3, 6) => 1,
+                    (4, 9) => 1,
+                    (8, 15) => 1,
+                    (16, 28) => 1
+                },
+                2 => { (5, 5) => 1, (5, 10) => 1 }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{
+    algebra::{IndexedZSet, ZRingValue},
+    operator::group::NonIncrementalGroupTransformer,
+    DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::{traits::AsPrimitive, FromPrimitive};
+use std::marker::PhantomData;
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+{
+    /// Retain the `k` largest values in each group.
+    ///
+    /// A value of weight `w` counts as `w` rows, so a value at the boundary
+    /// can be retained with a smaller weight than in the input.  Values with
+    /// non-positive weights are ignored.
+    #[allow(clippy::type_complexity)]
+    pub fn top_k(&self, k: usize) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        self.group_transform(TopK::new(k, Order::Descending, false))
+    }
+
+    /// Retain the `k` largest values in each group, along with all rows tied
+    /// with the `k`-th row.
+    ///
+    /// Like [`Stream::top_k`], but a value at the boundary is always
+    /// retained with its full weight, so the output can contain more than
+    /// `k` rows.  This is the equivalent of SQL's `FETCH FIRST k ROWS WITH
+    /// TIES`.
+    #[allow(clippy::type_complexity)]
+    pub fn top_k_with_ties(
+        &self,
+        k: usize,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        self.group_transform(TopK::new(k, Order::Descending, true))
+    }
+
+    /// Retain the `k` smallest values in each group.
+    ///
+    /// Like [`Stream::top_k`], but in ascending order.
+    #[allow(clippy::type_complexity)]
+    pub fn bottom_k(&self, k: usize) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        self.group_transform(TopK::new(k, Order::Ascending, false))
+    }
+}
+
+/// Order in which [`TopK`] selects values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Order {
+    Ascending,
+    Descending,
+}
+
+/// Group transformer that implements [`Stream::top_k`],
+/// [`Stream::top_k_with_ties`], and [`Stream::bottom_k`].
+pub(super) struct TopK<V> {
+    k: usize,
+    order: Order,
+    // Retain the value at the boundary with its full weight.
+    with_ties: bool,
+    _type: PhantomData<V>,
+}
+
+impl<V> TopK<V> {
+    pub(super) fn new(k: usize, order: Order, with_ties: bool) -> Self {
+        Self {
+            k,
+            order,
+            with_ties,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<V, R> NonIncrementalGroupTransformer<V, V, R> for TopK<V>
+where
+    V: DBData,
+    R: ZRingValue + AsPrimitive<i64> + FromPrimitive,
+{
+    fn name(&self) -> &'static str {
+        match (self.order, self.with_ties) {
+            (Order::Ascending, false) => "BottomK",
+            (Order::Ascending, true) => "BottomKWithTies",
+            (Order::Descending, false) => "TopK",
+            (Order::Descending, true) => "TopKWithTies",
+        }
+    }
+
+    fn transform(&mut self, input: &[(V, R)], output_cb: &mut dyn FnMut(V, R)) {
+        let values: Box<dyn Iterator<Item = &(V, R)> + '_> = match self.order {
+            Order::Ascending => Box::new(input.iter()),
+            Order::Descending => Box::new(input.iter().rev()),
+        };
+
+        let mut remaining = self.k as i64;
+        for (val, weight) in values {
+            if remaining == 0 {
+                break;
+            }
+
+            let count: i64 = weight.as_();
+            if count <= 0 {
+                continue;
+            }
+
+            if self.with_ties || count <= remaining {
+                output_cb(val.clone(), *weight);
+            } else {
+                output_cb(val.clone(), R::from_i64(remaining).unwrap());
+            }
+            remaining -= count.min(remaining);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn bottom_k_test() {
+        let (mut circuit, (mut input, bottom, top)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let bottom = input.bottom_k(3).integrate().output();
+            let top = input.top_k(3).integrate().output();
+
+            (input_handle, bottom, top)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, (10, 1)),
+            (1, (20, 1)),
+            (1, (30, 2)),
+            (1, (40, 1)),
+            (2, (5, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            bottom.consolidate(),
+            indexed_zset! { 1 => { 10 => 1, 20 => 1, 30 => 1 }, 2 => { 5 => 1 } }
+        );
+        assert_eq!(
+            top.consolidate(),
+            indexed_zset! { 1 => { 30 => 2, 40 => 1 }, 2 => { 5 => 1 } }
+        );
+
+        // A new small value pushes the tied values at the boundary out.
+        input.append(&mut vec![(1, (15, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            bottom.consolidate(),
+            indexed_zset! { 1 => { 10 => 1, 15 => 1, 20 => 1 }, 2 => { 5 => 1 } }
+        );
+
+        // Retracting retained values brings them back.
+        input.append(&mut vec![(1, (10, -1)), (1, (15, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            bottom.consolidate(),
+            indexed_zset! { 1 => { 20 => 1, 30 => 2 }, 2 => { 5 => 1 } }
+        );
+        assert_eq!(
+            top.consolidate(),
+            indexed_zset! { 1 => { 30 => 2, 40 => 1 }, 2 => { 5 => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn top_k_with_ties_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let output = input.top_k_with_ties(2).integrate().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        // Three rows with value 30 straddle position 2.
+        input.append(&mut vec![(1, (40, 1)), (1, (30, 3)), (1, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 30 => 3, 40 => 1 } }
+        );
+
+        // Removing one of the tied rows keeps the remaining ones.
+        input.append(&mut vec![(1, (30, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 30 => 2, 40 => 1 } }
+        );
+
+        // Without the top row, the tied rows fill all `k` positions.
+        input.append(&mut vec![(1, (40, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! { 1 => { 30 => 2 } });
+
+        // `k` rows above the cutoff push all tied rows out.
+        input.append(&mut vec![(1, (50, 1)), (1, (60, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 50 => 1, 60 => 1 } }
+        );
+
+        // Only one row remains above the cutoff, so all tied rows return.
+        input.append(&mut vec![(1, (60, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { 30 => 2, 50 => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn top_k_weights_test() {
+        let (mut circuit, (mut input, top, top_with_ties)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+            let top = input.top_k(1_000).integrate().output();
+            let top_with_ties = input.top_k_with_ties(1_000).integrate().output();
+
+            (input_handle, top, top_with_ties)
+        })
+        .unwrap();
+
+        // Values with large weights are retained with a single tuple each.
+        input.append(&mut vec![
+            (1, (30, 600)),
+            (1, (20, 1_000_000)),
+            (1, (10, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            top.consolidate(),
+            indexed_zset! { 1 => { 20 => 400, 30 => 600 } }
+        );
+        assert_eq!(
+            top_with_ties.consolidate(),
+            indexed_zset! { 1 => { 20 => 1_000_000, 30 => 600 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
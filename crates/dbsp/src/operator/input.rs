@@ -4,12 +4,14 @@ use crate::{
         operator_traits::{Operator, SourceOperator},
         LocalStoreMarker, RootCircuit, Scope,
     },
-    default_hash,
-    trace::Batch,
+    circuit_cache_key, default_hash,
+    trace::{consolidation::consolidate, Batch, Builder},
     Circuit, DBData, DBWeight, OrdIndexedZSet, OrdZSet, Runtime, Stream,
 };
 use std::{
+    any::Any,
     borrow::Cow,
+    collections::BTreeMap,
     hash::{Hash, Hasher},
     marker::PhantomData,
     mem::{swap, take},
@@ -50,7 +52,7 @@ impl RootCircuit {
     where
         T: Default + Clone + Send + 'static,
     {
-        let (input, input_handle) = Input::new(|x| x);
+        let (input, input_handle) = Input::new(|x, _| x);
         let stream = self.add_source(input);
         (stream, input_handle)
     }
@@ -77,7 +79,7 @@ impl RootCircuit {
         K: DBData,
         R: DBWeight,
     {
-        let (input, input_handle) = Input::new(|tuples| OrdZSet::from_keys((), tuples));
+        let (input, input_handle) = Input::new(batch_with_capacity);
         let stream = self.add_source(input);
 
         let zset_handle = <CollectionHandle<K, R>>::new(input_handle);
@@ -112,10 +114,10 @@ impl RootCircuit {
         V: DBData,
         R: DBWeight,
     {
-        let (input, input_handle) = Input::new(|tuples: Vec<(K, (V, R))>| {
-            OrdIndexedZSet::from_tuples(
-                (),
+        let (input, input_handle) = Input::new(|tuples: Vec<(K, (V, R))>, capacity_hint| {
+            batch_with_capacity(
                 tuples.into_iter().map(|(k, (v, w))| ((k, v), w)).collect(),
+                capacity_hint,
             )
         });
         let stream = self.add_source(input);
@@ -125,6 +127,77 @@ impl RootCircuit {
         (stream, zset_handle)
     }
 
+    /// Like [`Self::add_input_zset`], but also registers the input handle
+    /// under `name`.
+    ///
+    /// Named input handles can be enumerated using [`Self::input_handles`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an input with the same name has already been registered.
+    pub fn add_named_input_zset<K, R>(
+        &self,
+        name: &str,
+    ) -> (Stream<Self, OrdZSet<K, R>>, CollectionHandle<K, R>)
+    where
+        K: DBData,
+        R: DBWeight,
+    {
+        let (stream, handle) = self.add_input_zset();
+        self.register_input_handle(name, handle.clone());
+        (stream, handle)
+    }
+
+    /// Like [`Self::add_input_indexed_zset`], but also registers the input
+    /// handle under `name`.
+    ///
+    /// Named input handles can be enumerated using [`Self::input_handles`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an input with the same name has already been registered.
+    #[allow(clippy::type_complexity)]
+    pub fn add_named_input_indexed_zset<K, V, R>(
+        &self,
+        name: &str,
+    ) -> (IndexedZSetStream<K, V, R>, CollectionHandle<K, (V, R)>)
+    where
+        K: DBData,
+        V: DBData,
+        R: DBWeight,
+    {
+        let (stream, handle) = self.add_input_indexed_zset();
+        self.register_input_handle(name, handle.clone());
+        (stream, handle)
+    }
+
+    /// Register `handle` under `name`, so that it can be retrieved using
+    /// [`Self::input_handles`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if an input with the same name has already been registered.
+    pub fn register_input_handle<H>(&self, name: &str, handle: H)
+    where
+        H: DynCollectionHandle + 'static,
+    {
+        let mut handles = self.cache_get_or_insert_with(InputHandlesId::new(()), BTreeMap::new);
+        if handles.contains_key(name) {
+            panic!("input '{name}' is already registered");
+        }
+        handles.insert(name.to_string(), Box::new(handle));
+    }
+
+    /// Returns the handles of all named inputs of the circuit indexed by
+    /// name.
+    ///
+    /// Allows generic drivers to feed data to a circuit without threading
+    /// each input handle out of the circuit constructor individually.
+    pub fn input_handles(&self) -> BTreeMap<String, Box<dyn DynCollectionHandle>> {
+        self.cache_get_or_insert_with(InputHandlesId::new(()), BTreeMap::new)
+            .clone()
+    }
+
     fn add_upsert<K, VI, V, F, B>(
         &self,
         input_stream: Stream<Self, Vec<(K, VI)>>,
@@ -233,7 +306,7 @@ impl RootCircuit {
         R: DBData + ZRingValue,
     {
         self.region("input_set", || {
-            let (input, input_handle) = Input::new(|tuples: Vec<(K, bool)>| tuples);
+            let (input, input_handle) = Input::new(|tuples: Vec<(K, bool)>, _| tuples);
             let input_stream = self.add_source(input);
             let upsert_handle = <UpsertHandle<K, bool>>::new(input_handle);
 
@@ -317,7 +390,7 @@ impl RootCircuit {
         R: DBData + ZRingValue,
     {
         self.region("input_map", || {
-            let (input, input_handle) = Input::new(|tuples: Vec<(K, Option<V>)>| tuples);
+            let (input, input_handle) = Input::new(|tuples: Vec<(K, Option<V>)>, _| tuples);
             let input_stream = self.add_source(input);
             let zset_handle = <UpsertHandle<K, Option<V>>>::new(input_handle);
 
@@ -460,6 +533,9 @@ where
 
 struct InputHandleInternal<T> {
     mailbox: Vec<Mailbox<T>>,
+    // Number of tuples each worker's batch builder is presized for (see
+    // `CollectionHandle::with_capacity_hint`).
+    capacity_hint: AtomicUsize,
 }
 
 impl<T> InputHandleInternal<T>
@@ -474,7 +550,10 @@ where
             mailbox.push(Mailbox::new());
         }
 
-        Self { mailbox }
+        Self {
+            mailbox,
+            capacity_hint: AtomicUsize::new(0),
+        }
     }
 
     fn set_for_worker(&self, worker: usize, v: T) {
@@ -521,6 +600,16 @@ where
 #[derive(Clone)]
 pub struct InputHandle<T>(Arc<InputHandleInternal<T>>);
 
+impl<T> InputHandle<T> {
+    fn capacity_hint(&self) -> usize {
+        self.0.capacity_hint.load(Ordering::Acquire)
+    }
+
+    fn set_capacity_hint(&self, capacity: usize) {
+        self.0.capacity_hint.store(capacity, Ordering::Release);
+    }
+}
+
 impl<T> InputHandle<T>
 where
     T: Default + Send + Clone + 'static,
@@ -627,6 +716,21 @@ where
         self.buffers.len()
     }
 
+    /// Presize the batches assembled from the buffered updates for
+    /// `capacity` updates per clock cycle.
+    ///
+    /// When the number of updates fed to the circuit in each clock cycle is
+    /// known in advance, e.g., the input batch size of a benchmark harness,
+    /// this allocates the batch builder of each worker upfront for its
+    /// share of `capacity` updates instead of sizing it from the buffered
+    /// updates.  The hint is shared by all clones of the handle.
+    pub fn with_capacity_hint(self, capacity: usize) -> Self {
+        let num_partitions = self.num_partitions();
+        self.input_handle
+            .set_capacity_hint(capacity.div_ceil(num_partitions));
+        self
+    }
+
     /// Push a single `(key,value)` pair to the input stream.
     pub fn push(&self, k: K, v: V) {
         let num_partitions = self.num_partitions();
@@ -723,6 +827,61 @@ where
     }
 }
 
+circuit_cache_key!(InputHandlesId(() => BTreeMap<String, Box<dyn DynCollectionHandle>>));
+
+/// Type-erased [`CollectionHandle`].
+///
+/// Allows handles with different key and value types to be stored in the
+/// same collection; see [`RootCircuit::input_handles`].
+pub trait DynCollectionHandle: Send {
+    /// Push multiple updates to the input stream.
+    ///
+    /// `updates` must be a `Vec<(K, V)>`, where `K` and `V` are the type
+    /// arguments of the underlying [`CollectionHandle<K, V>`].  Returns
+    /// `false` without modifying `updates` if it has any other type.
+    ///
+    /// See [`CollectionHandle::append`].
+    fn append_dyn(&mut self, updates: &mut dyn Any) -> bool;
+
+    /// Clear all inputs buffered since the start of the last clock cycle.
+    ///
+    /// See [`CollectionHandle::clear_input`].
+    fn clear_input_dyn(&self);
+
+    /// Create a new handle connected to the same input stream.
+    fn fork(&self) -> Box<dyn DynCollectionHandle>;
+}
+
+impl<K, V> DynCollectionHandle for CollectionHandle<K, V>
+where
+    K: DBData,
+    V: DBData,
+{
+    fn append_dyn(&mut self, updates: &mut dyn Any) -> bool {
+        match updates.downcast_mut::<Vec<(K, V)>>() {
+            Some(updates) => {
+                self.append(updates);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn clear_input_dyn(&self) {
+        self.clear_input()
+    }
+
+    fn fork(&self) -> Box<dyn DynCollectionHandle> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn DynCollectionHandle> {
+    fn clone(&self) -> Self {
+        self.fork()
+    }
+}
+
 pub trait HashFunc<K>: Fn(&K) -> u32 + Send + Sync {}
 
 impl<K, F> HashFunc<K> for F where F: Fn(&K) -> u32 + Send + Sync {}
@@ -895,6 +1054,7 @@ where
 /// ```
 struct Input<IT, OT, F> {
     mailbox: Mailbox<IT>,
+    input_handle: InputHandle<IT>,
     input_func: F,
     phantom: PhantomData<OT>,
 }
@@ -909,6 +1069,7 @@ where
 
         let input = Self {
             mailbox,
+            input_handle: handle.clone(),
             input_func,
             phantom: PhantomData,
         };
@@ -936,19 +1097,40 @@ impl<IT, OT, F> SourceOperator<OT> for Input<IT, OT, F>
 where
     IT: Default + 'static,
     OT: 'static,
-    F: Fn(IT) -> OT + 'static,
+    F: Fn(IT, usize) -> OT + 'static,
 {
     fn eval(&mut self) -> OT {
         let v = self.mailbox.take();
-        (self.input_func)(v)
+        (self.input_func)(v, self.input_handle.capacity_hint())
     }
 }
 
+/// Assemble unordered `tuples` into a batch.
+///
+/// If `capacity_hint` is not zero, the batch is built by a builder presized
+/// for at least `capacity_hint` tuples.
+pub(crate) fn batch_with_capacity<B>(mut tuples: Vec<(B::Item, B::R)>, capacity_hint: usize) -> B
+where
+    B: Batch<Time = ()>,
+    B::Item: Ord,
+{
+    if capacity_hint == 0 {
+        return B::from_tuples((), tuples);
+    }
+
+    consolidate(&mut tuples);
+    let mut builder = B::Builder::with_capacity((), capacity_hint.max(tuples.len()));
+    for tuple in tuples {
+        builder.push(tuple);
+    }
+    builder.done()
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         indexed_zset,
-        trace::{cursor::Cursor, BatchReader},
+        trace::{cursor::Cursor, Batch, BatchReader},
         zset, CollectionHandle, InputHandle, OrdIndexedZSet, OrdZSet, RootCircuit, Runtime,
         UpsertHandle,
     };
@@ -1136,6 +1318,35 @@ mod test {
         zset_test_mt(4);
     }
 
+    #[test]
+    fn zset_capacity_hint_test() {
+        let (mut dbsp, (input_handle, output_handle)) = Runtime::init_circuit(4, |circuit| {
+            let (stream, handle) = circuit.add_input_zset::<usize, isize>();
+
+            // Each worker's batch builder is presized for its share of the
+            // hint, which exceeds the size of each worker's batch.
+            stream.inspect(|batch| {
+                if !batch.is_empty() {
+                    assert!(batch.len() <= 3);
+                    assert_eq!(batch.layer.capacity(), 4);
+                }
+            });
+
+            (handle.with_capacity_hint(16), stream.output())
+        })
+        .unwrap();
+
+        let mut input_handle = input_handle;
+        for mut vec in input_vecs().into_iter() {
+            let expected = OrdZSet::from_keys((), vec.clone());
+            input_handle.append(&mut vec);
+            dbsp.step().unwrap();
+            assert_eq!(output_handle.consolidate(), expected);
+        }
+
+        dbsp.kill().unwrap();
+    }
+
     fn input_indexed_batches() -> Vec<OrdIndexedZSet<usize, usize, isize>> {
         vec![
             indexed_zset! { 1 => {1 => 1, 2 => 1}, 2 => { 3 => 1 }, 3 => {4 => -1, 5 => 5} },
@@ -1397,6 +1608,49 @@ mod test {
         dbsp.kill().unwrap();
     }
 
+    #[test]
+    fn input_handles_test() {
+        let (mut dbsp, (handles, zset_output, indexed_output)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (zset, _) = circuit.add_named_input_zset::<usize, isize>("zset");
+                let (indexed, _) =
+                    circuit.add_named_input_indexed_zset::<usize, usize, isize>("indexed");
+
+                (
+                    circuit.input_handles(),
+                    zset.integrate().output(),
+                    indexed.integrate().output(),
+                )
+            })
+            .unwrap();
+
+        assert_eq!(handles.keys().collect::<Vec<_>>(), vec!["indexed", "zset"]);
+
+        let mut zset_handle = handles["zset"].clone();
+        let mut indexed_handle = handles["indexed"].clone();
+
+        // Updates of the wrong type are rejected.
+        let mut wrong_type = vec![(1u64, 1isize)];
+        assert!(!zset_handle.append_dyn(&mut wrong_type));
+        assert_eq!(wrong_type.len(), 1);
+
+        assert!(zset_handle.append_dyn(&mut vec![(1usize, 1isize), (2, 2)]));
+        assert!(indexed_handle.append_dyn(&mut vec![(1usize, (10usize, 1isize))]));
+        dbsp.step().unwrap();
+
+        assert!(zset_handle.append_dyn(&mut vec![(2usize, -1isize)]));
+        assert!(indexed_handle.append_dyn(&mut vec![(2usize, (20usize, 1isize))]));
+        dbsp.step().unwrap();
+
+        assert_eq!(zset_output.consolidate(), zset! { 1 => 1, 2 => 1 });
+        assert_eq!(
+            indexed_output.consolidate(),
+            indexed_zset! { 1 => { 10 => 1 }, 2 => { 20 => 1 } }
+        );
+
+        dbsp.kill().unwrap();
+    }
+
     #[test]
     fn map_test_mt1() {
         map_test_mt(1);
@@ -1,11 +1,14 @@
 //! Defines a sink operator that inspects every element of its input stream by
 //! applying a user-provided callback to it.
 
-use crate::circuit::{
-    operator_traits::{Operator, UnaryOperator},
-    Circuit, Scope, Stream,
+use crate::{
+    circuit::{
+        operator_traits::{Operator, SinkOperator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    trace::{BatchReader, Cursor},
 };
-use std::{borrow::Cow, marker::PhantomData};
+use std::{borrow::Cow, cell::RefCell, marker::PhantomData, rc::Rc};
 
 impl<C, D> Stream<C, D>
 where
@@ -43,6 +46,112 @@ where
         inspected.mark_sharded_if(self);
         inspected
     }
+
+    /// Fold every element of `self` into a caller-provided `sink`.
+    ///
+    /// At each clock cycle, applies `f` to the sink and the current value
+    /// of the stream.  Unlike [`Self::output`], which makes a copy of each
+    /// output batch available outside the circuit, this operator lets the
+    /// caller build up an external data structure of their choice directly
+    /// from borrowed batches, e.g., to maintain an integrated view of the
+    /// stream without cloning.
+    ///
+    /// When used in a multithreaded runtime, each worker folds its own shard
+    /// of the stream into the sink created by its instance of the circuit
+    /// constructor.
+    pub fn accumulate_into<S, F>(&self, sink: Rc<RefCell<S>>, f: F)
+    where
+        S: 'static,
+        F: FnMut(&mut S, &D) + 'static,
+    {
+        self.circuit().add_sink(AccumulateInto::new(sink, f), self);
+    }
+}
+
+impl<C, B> Stream<C, B>
+where
+    B: BatchReader + Clone + 'static,
+    C: Circuit,
+{
+    /// Check that the keys of each batch in `self` are strictly increasing.
+    ///
+    /// Diagnostic operator for custom sources and operators that assemble
+    /// batches by hand, e.g., by pushing tuples to a
+    /// [`Builder`](`crate::trace::Builder`) without sorting them first.
+    /// Walks the keys of each batch and panics with the first pair of keys
+    /// that are out of order.  Only checks key order, not values or
+    /// weights.
+    ///
+    /// The check is only performed in debug builds.  In release builds, the
+    /// operator returns `self`.
+    pub fn assert_key_sorted(&self) -> Self {
+        if cfg!(debug_assertions) {
+            self.inspect(|batch| {
+                if let Some((key1, key2)) = first_unsorted_keys(batch) {
+                    panic!("batch keys are not sorted: key {key1:?} is followed by {key2:?}")
+                }
+            })
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Log every `(key, value, weight)` tuple in each batch of `self`.
+    ///
+    /// Debugging aid that writes one line per tuple, prefixed with `label`,
+    /// to the [`log`] crate at `debug` level, and returns `self` unchanged.
+    /// When `debug` logging is disabled, the operator doesn't iterate over
+    /// the batch.
+    pub fn trace_changes(&self, label: &str) -> Self {
+        let label = label.to_string();
+
+        self.inspect(move |batch| {
+            if log::log_enabled!(log::Level::Debug) {
+                trace_batch(&label, batch);
+            }
+        })
+    }
+}
+
+/// Logs every tuple in `batch` at `debug` level (see
+/// [`Stream::trace_changes`]).
+fn trace_batch<B>(label: &str, batch: &B)
+where
+    B: BatchReader,
+{
+    let mut cursor = batch.cursor();
+
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let key = cursor.key().clone();
+            let val = cursor.val().clone();
+            cursor.map_times(|_, weight| log::debug!("{label}: ({key:?}, {val:?}) => {weight:?}"));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+}
+
+/// Returns the first pair of adjacent keys in `batch` that are not strictly
+/// increasing.
+fn first_unsorted_keys<B>(batch: &B) -> Option<(B::Key, B::Key)>
+where
+    B: BatchReader,
+{
+    let mut cursor = batch.cursor();
+    let mut prev: Option<B::Key> = None;
+
+    while cursor.key_valid() {
+        if let Some(prev) = &prev {
+            if prev >= cursor.key() {
+                return Some((prev.clone(), cursor.key().clone()));
+            }
+        }
+        prev = Some(cursor.key().clone());
+        cursor.step_key();
+    }
+
+    None
 }
 
 /// Sink operator that consumes a stream of values of type `T` and
@@ -94,3 +203,224 @@ where
         i
     }
 }
+
+/// Sink operator that folds each input of type `T` into a shared
+/// accumulator of type `S` using a user-provided function.
+pub struct AccumulateInto<T, S, F> {
+    sink: Rc<RefCell<S>>,
+    f: F,
+    phantom: PhantomData<T>,
+}
+
+impl<T, S, F> AccumulateInto<T, S, F>
+where
+    F: FnMut(&mut S, &T),
+{
+    /// Create a new instance of the `AccumulateInto` operator that will fold
+    /// each value in the input stream into `sink` using `f`.
+    pub fn new(sink: Rc<RefCell<S>>, f: F) -> Self {
+        Self {
+            sink,
+            f,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, S, F> Operator for AccumulateInto<T, S, F>
+where
+    T: 'static,
+    S: 'static,
+    F: FnMut(&mut S, &T) + 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("AccumulateInto")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<T, S, F> SinkOperator<T> for AccumulateInto<T, S, F>
+where
+    T: 'static,
+    S: 'static,
+    F: FnMut(&mut S, &T) + 'static,
+{
+    fn eval(&mut self, i: &T) {
+        (self.f)(&mut self.sink.borrow_mut(), i);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        indexed_zset,
+        operator::Generator,
+        trace::{Batch, BatchReader, Builder, Cursor},
+        zset, Circuit, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+    };
+    use log::{LevelFilter, Log, Metadata, Record};
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc, sync::Mutex};
+
+    /// Logger that records all messages in memory.
+    struct CaptureLogger {
+        lines: Mutex<Vec<String>>,
+    }
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.lines.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger {
+        lines: Mutex::new(Vec::new()),
+    };
+
+    #[test]
+    fn accumulate_into_test() {
+        let inputs: Vec<OrdZSet<u64, isize>> = vec![
+            zset! { 1 => 1, 2 => 2, 3 => 1 },
+            zset! { 2 => -2, 4 => 1 },
+            zset! { 1 => 1, 3 => -1, 5 => 3 },
+        ];
+        let expected = zset! { 1 => 2, 4 => 1, 5 => 3 };
+
+        let sink = Rc::new(RefCell::new(BTreeMap::<u64, isize>::new()));
+        let integral = Rc::new(RefCell::new(OrdZSet::<u64, isize>::empty(())));
+
+        let circuit = {
+            let sink = sink.clone();
+            let integral = integral.clone();
+            let mut inputs = inputs.into_iter();
+
+            RootCircuit::build(move |circuit| {
+                let input = circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+
+                input.accumulate_into(sink, |map, batch: &OrdZSet<u64, isize>| {
+                    let mut cursor = batch.cursor();
+                    while cursor.key_valid() {
+                        let weight = map.entry(*cursor.key()).or_insert(0);
+                        *weight += cursor.weight();
+                        if *weight == 0 {
+                            map.remove(cursor.key());
+                        }
+                        cursor.step_key();
+                    }
+                });
+                input
+                    .integrate()
+                    .inspect(move |batch| *integral.borrow_mut() = batch.clone());
+            })
+            .unwrap()
+            .0
+        };
+
+        for _ in 0..3 {
+            circuit.step().unwrap();
+        }
+
+        let integral = integral.borrow();
+        assert_eq!(*integral, expected);
+
+        let mut integral_map = BTreeMap::new();
+        let mut cursor = integral.cursor();
+        while cursor.key_valid() {
+            integral_map.insert(*cursor.key(), cursor.weight());
+            cursor.step_key();
+        }
+        assert_eq!(*sink.borrow(), integral_map);
+    }
+
+    #[test]
+    fn assert_key_sorted_test() {
+        let mut inputs = vec![
+            zset! { 1 => 1, 2 => -1, 5 => 2 },
+            zset! {},
+            zset! { 3 => 1 },
+        ]
+        .into_iter();
+
+        let circuit = RootCircuit::build(move |circuit| {
+            let input: Stream<_, OrdZSet<u64, isize>> =
+                circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+            input.assert_key_sorted();
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..3 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic = "batch keys are not sorted: key 3 is followed by 2"]
+    fn assert_key_sorted_unsorted() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let input: Stream<_, OrdZSet<u64, isize>> = circuit.add_source(Generator::new(|| {
+                // Bypass sorting and consolidation.
+                let mut builder = <OrdZSet<u64, isize> as Batch>::Builder::with_capacity((), 3);
+                builder.push((1, 1));
+                builder.push((3, 1));
+                builder.push((2, 1));
+                builder.done()
+            }));
+            input.assert_key_sorted();
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn trace_changes_test() {
+        // Other tests don't install a logger, so this can only fail if the test
+        // is executed more than once in the same process.
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(LevelFilter::Debug);
+
+        let input: OrdIndexedZSet<u64, u64, isize> =
+            indexed_zset! { 1 => { 10 => 1, 11 => -1 }, 2 => { 20 => 2 } };
+        let expected = input.clone();
+        let mut inputs = vec![input].into_iter();
+
+        let circuit = RootCircuit::build(move |circuit| {
+            circuit
+                .add_source(Generator::new(move || inputs.next().unwrap()))
+                .trace_changes("trace_changes_test")
+                .inspect(move |batch| assert_eq!(batch, &expected));
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+
+        let lines: Vec<String> = LOGGER
+            .lines
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| line.starts_with("trace_changes_test:"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "trace_changes_test: (1, 10) => 1",
+                "trace_changes_test: (1, 11) => -1",
+                "trace_changes_test: (2, 20) => 2",
+            ]
+        );
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
         Circuit, GlobalNodeId, RootCircuit, Scope, Stream, WithClock,
     },
     circuit_cache_key,
-    operator::FilterMap,
+    operator::{FilterMap, Map},
     time::Timestamp,
     trace::{cursor::Cursor as TraceCursor, Batch, BatchReader, Batcher, Builder, Spine, Trace},
     DBData, DBTimestamp, OrdIndexedZSet, OrdZSet,
@@ -25,6 +25,7 @@ use std::{
 };
 
 circuit_cache_key!(AntijoinId<C, D>((GlobalNodeId, GlobalNodeId) => Stream<C, D>));
+circuit_cache_key!(SemijoinId<C, D>((GlobalNodeId, GlobalNodeId) => Stream<C, D>));
 
 impl<C, I1> Stream<C, I1>
 where
@@ -99,6 +100,56 @@ where
         )
     }
 
+    /// Join two streams of batches by merging their keys.
+    ///
+    /// Computes the same result as [`Self::stream_join`], but instead of
+    /// seeking each cursor to the current key of the other one, advances
+    /// both cursors in lockstep, one key at a time.  This is faster than
+    /// seeking when the key sets of the two inputs largely overlap, e.g.,
+    /// when both inputs are outputs of aggregates over the same collection.
+    ///
+    /// Like `stream_join`, this is a non-incremental batch operator: it
+    /// joins the pair of batches that arrive in each clock cycle and does
+    /// not maintain traces of its inputs.  To join two collections
+    /// represented by streams of changes, either apply it to the integrals
+    /// of both streams, which recomputes the entire join in every clock
+    /// cycle, or use the incremental
+    /// [`join`](`crate::circuit::Stream::join`) operator.
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn merge_join<F, I2, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join: F,
+    ) -> Stream<C, OrdZSet<V, <I1::R as MulByRef<I2::R>>::Output>>
+    where
+        I1: Batch<Time = ()> + Send,
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+        I1::R: MulByRef<I2::R>,
+        <I1::R as MulByRef<I2::R>>::Output: DBData + ZRingValue,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + 'static,
+        V: DBData,
+    {
+        self.merge_join_generic(other, join)
+    }
+
+    /// Like [`Self::merge_join`], but can return any batch type.
+    #[track_caller]
+    pub fn merge_join_generic<F, I2, Z>(&self, other: &Stream<C, I2>, join: F) -> Stream<C, Z>
+    where
+        I1: Batch<Time = ()> + Send,
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+        Z: ZSet,
+        I1::R: MulByRef<I2::R, Output = Z::R>,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> Z::Key + 'static,
+    {
+        self.circuit().add_binary_operator(
+            MergeJoin::new(join, Location::caller()),
+            &self.shard(),
+            &other.shard(),
+        )
+    }
+
     fn stream_join_inner<F, I2, Z>(
         &self,
         other: &Stream<C, I2>,
@@ -294,7 +345,11 @@ where
     /// Incremental anti-join operator.
     ///
     /// Returns indexed Z-set consisting of the contents of `self`,
-    /// excluding keys that are not present in `other`.
+    /// excluding keys that are present in `other`.
+    ///
+    /// A value in `self` is retracted from the output when its key appears
+    /// in `other` and inserted back when the last value with this key is
+    /// removed from `other`.
     pub fn antijoin<I2>(&self, other: &Stream<C, I2>) -> Stream<C, I1>
     where
         I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
@@ -307,12 +362,46 @@ where
                 )),
                 move || {
                     let stream1 = self.shard();
-                    let stream2 = other.distinct().shard();
 
-                    stream1
-                        .minus(&stream1.join_generic(&stream2, |k, v1, _v2| {
-                            std::iter::once((k.clone(), v1.clone()))
-                        }))
+                    stream1.minus(&stream1.semijoin(other)).mark_sharded()
+                },
+            )
+            .clone()
+    }
+
+    /// Incremental semi-join operator.
+    ///
+    /// Returns indexed Z-set consisting of the contents of `self`,
+    /// restricted to keys that are present in `other`.
+    ///
+    /// Each value in `self` occurs in the output with its original weight,
+    /// regardless of the number of values associated with its key in
+    /// `other`.  The value is inserted into the output when its key appears
+    /// in `other` and retracted when the last value with this key is removed
+    /// from `other`.
+    pub fn semijoin<I2>(&self, other: &Stream<C, I2>) -> Stream<C, I1>
+    where
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+    {
+        self.circuit()
+            .cache_get_or_insert_with(
+                SemijoinId::new((
+                    self.origin_node_id().clone(),
+                    other.origin_node_id().clone(),
+                )),
+                move || {
+                    // Project `other` to its set of keys before computing
+                    // `distinct`, so that each key contributes weight 1
+                    // regardless of how many values it has.
+                    let keys = self.circuit().add_unary_operator(
+                        Map::new(|(k, _v): (&I1::Key, &I2::Val)| (k.clone(), ())),
+                        other,
+                    )
+                        as Stream<C, OrdIndexedZSet<I1::Key, (), I1::R>>;
+                    let stream2 = keys.distinct().shard();
+
+                    self.shard()
+                        .join_generic(&stream2, |k, v1, _v2| once((k.clone(), v1.clone())))
                         .mark_sharded()
                 },
             )
@@ -386,6 +475,76 @@ where
     }
 }
 
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Left join that pads unmatched rows with a default value.
+    ///
+    /// Returns the output of `join_func` for each pair of matching values in
+    /// `self` and `other`.  Values in `self` whose key does not occur in
+    /// `other` are joined with `default_right` instead, which corresponds to
+    /// SQL `LEFT JOIN` with `COALESCE` applied to the columns of the right
+    /// relation.
+    ///
+    /// The operator is incremental: when a matching key appears in `other`,
+    /// the padded output rows for this key are retracted and replaced with
+    /// the output of the real join, and vice versa when the key is removed
+    /// from `other`.
+    pub fn left_join_default<Z2, F, O>(
+        &self,
+        other: &Stream<C, Z2>,
+        default_right: Z2::Val,
+        join_func: F,
+    ) -> Stream<C, OrdZSet<O, Z::R>>
+    where
+        Self: for<'a> FilterMap<C, R = Z::R, ItemRef<'a> = (&'a Z::Key, &'a Z::Val)>,
+        Z2: IndexedZSet<Key = Z::Key, R = Z::R> + Send,
+        O: DBData,
+        F: Fn(&Z::Key, &Z::Val, &Z2::Val) -> O + Clone + 'static,
+    {
+        let join_func_left = join_func.clone();
+        let matched = self.join_generic(other, move |k, v1, v2| {
+            std::iter::once((join_func(k, v1, v2), ()))
+        });
+        let unmatched = self
+            .antijoin(other)
+            .map_generic(move |(k, v1)| join_func_left(k, v1, &default_right));
+        matched.plus(&unmatched)
+    }
+
+    /// Left outer join.
+    ///
+    /// Returns an indexed Z-set that maps each key in `self` to pairs
+    /// `(v1, Some(v2))` for all matching values `v1` in `self` and `v2` in
+    /// `other`, or to `(v1, None)` for all values `v1` in `self` if the key
+    /// does not occur in `other`.
+    ///
+    /// The operator is incremental: when the first matching value for a key
+    /// appears in `other`, the `(v1, None)` rows for the key are retracted,
+    /// and they are inserted back when the last matching value is removed.
+    #[allow(clippy::type_complexity)]
+    pub fn outer_join_left<Z2>(
+        &self,
+        other: &Stream<C, Z2>,
+    ) -> Stream<C, OrdIndexedZSet<Z::Key, (Z::Val, Option<Z2::Val>), Z::R>>
+    where
+        Self: for<'a> FilterMap<C, R = Z::R, ItemRef<'a> = (&'a Z::Key, &'a Z::Val)>,
+        Z2: IndexedZSet<Key = Z::Key, R = Z::R> + Send,
+    {
+        let matched = self.join_index(other, |k, v1, v2| {
+            once((k.clone(), (v1.clone(), Some(v2.clone()))))
+        });
+        let unmatched = self
+            .antijoin(other)
+            .map_index(|(k, v1)| (k.clone(), (v1.clone(), None)));
+        matched.plus(&unmatched)
+    }
+}
+
 /// Join two streams of batches.
 ///
 /// See [`Stream::join`](`crate::circuit::Stream::join`).
@@ -475,6 +634,95 @@ where
     // TODO: Impls using consumers
 }
 
+/// Join two streams of batches by advancing their cursors in lockstep.
+///
+/// This operator is not incremental: it only joins the two batches it
+/// receives in each clock cycle.  See
+/// [`Stream::merge_join`](`crate::circuit::Stream::merge_join`).
+pub struct MergeJoin<F, I1, I2, Z> {
+    join_func: F,
+    location: &'static Location<'static>,
+    _types: PhantomData<(I1, I2, Z)>,
+}
+
+impl<F, I1, I2, Z> MergeJoin<F, I1, I2, Z> {
+    pub fn new(join_func: F, location: &'static Location<'static>) -> Self {
+        Self {
+            join_func,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<F, I1, I2, Z> Operator for MergeJoin<F, I1, I2, Z>
+where
+    I1: 'static,
+    I2: 'static,
+    F: 'static,
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("MergeJoin")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<F, I1, I2, Z> BinaryOperator<I1, I2, Z> for MergeJoin<F, I1, I2, Z>
+where
+    I1: BatchReader<Time = ()>,
+    I1::R: MulByRef<I2::R, Output = Z::R>,
+    I2: BatchReader<Key = I1::Key, Time = ()>,
+    F: Fn(&I1::Key, &I1::Val, &I2::Val) -> Z::Key + 'static,
+    Z: ZSet,
+{
+    fn eval(&mut self, i1: &I1, i2: &I2) -> Z {
+        let mut cursor1 = i1.cursor();
+        let mut cursor2 = i2.cursor();
+
+        // Choose capacity heuristically.
+        let mut batch = Vec::with_capacity(min(i1.len(), i2.len()));
+
+        while cursor1.key_valid() && cursor2.key_valid() {
+            match cursor1.key().cmp(cursor2.key()) {
+                Ordering::Less => cursor1.step_key(),
+                Ordering::Greater => cursor2.step_key(),
+                Ordering::Equal => {
+                    while cursor1.val_valid() {
+                        let w1 = cursor1.weight();
+                        let v1 = cursor1.val();
+                        while cursor2.val_valid() {
+                            let w2 = cursor2.weight();
+                            let v2 = cursor2.val();
+
+                            batch.push((
+                                (self.join_func)(cursor1.key(), v1, v2),
+                                w1.mul_by_ref(&w2),
+                            ));
+                            cursor2.step_val();
+                        }
+
+                        cursor2.rewind_vals();
+                        cursor1.step_val();
+                    }
+
+                    cursor1.step_key();
+                    cursor2.step_key();
+                }
+            }
+        }
+
+        Z::from_keys((), batch)
+    }
+}
+
 pub struct MonotonicJoin<F, I1, I2, Z> {
     join_func: F,
     location: &'static Location<'static>,
@@ -1383,4 +1631,276 @@ mod test {
 
         circuit.kill().unwrap();
     }
+
+    #[test]
+    fn semijoin_antijoin_test() {
+        let (mut circuit, (mut input1, mut input2, semijoin, antijoin)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (input1, input_handle1) =
+                    circuit.add_input_indexed_zset::<usize, usize, isize>();
+                let (input2, input_handle2) =
+                    circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+                let semijoin = input1.semijoin(&input2).output();
+                let antijoin = input1.antijoin(&input2).output();
+
+                (input_handle1, input_handle2, semijoin, antijoin)
+            })
+            .unwrap();
+
+        input1.append(&mut vec![(1, (0, 1)), (1, (1, 2)), (2, (0, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(semijoin.consolidate(), indexed_zset! {});
+        assert_eq!(
+            antijoin.consolidate(),
+            indexed_zset! { 1 => { 0 => 1, 1 => 2 }, 2 => { 0 => 1 } }
+        );
+
+        // Key 1 appears in the right input.
+        input2.append(&mut vec![(1, (5, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            semijoin.consolidate(),
+            indexed_zset! { 1 => { 0 => 1, 1 => 2 } }
+        );
+        assert_eq!(
+            antijoin.consolidate(),
+            indexed_zset! { 1 => { 0 => -1, 1 => -2 } }
+        );
+
+        // More values for key 1 don't affect either output.
+        input2.append(&mut vec![(1, (5, 1)), (1, (6, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(semijoin.consolidate(), indexed_zset! {});
+        assert_eq!(antijoin.consolidate(), indexed_zset! {});
+
+        // New values in the left input are routed according to the current
+        // contents of the right input.
+        input1.append(&mut vec![(1, (2, 1)), (2, (1, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(semijoin.consolidate(), indexed_zset! { 1 => { 2 => 1 } });
+        assert_eq!(antijoin.consolidate(), indexed_zset! { 2 => { 1 => 1 } });
+
+        // Key 1 disappears from the right input only once all of its values
+        // are removed.
+        input2.append(&mut vec![(1, (5, -2))]);
+        circuit.step().unwrap();
+        assert_eq!(semijoin.consolidate(), indexed_zset! {});
+        assert_eq!(antijoin.consolidate(), indexed_zset! {});
+
+        input2.append(&mut vec![(1, (6, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            semijoin.consolidate(),
+            indexed_zset! { 1 => { 0 => -1, 1 => -2, 2 => -1 } }
+        );
+        assert_eq!(
+            antijoin.consolidate(),
+            indexed_zset! { 1 => { 0 => 1, 1 => 2, 2 => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn left_join_default_test() {
+        let (mut circuit, (mut input1, mut input2, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let output = input1
+                .left_join_default(&input2, 0, |k, v1, v2| (*k, *v1, *v2))
+                .integrate()
+                .output();
+
+            (input_handle1, input_handle2, output)
+        })
+        .unwrap();
+
+        // Unmatched rows are padded with the default.
+        input1.append(&mut vec![(1, (1, 1)), (2, (2, 1))]);
+        input2.append(&mut vec![(3, (30, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 1, 0) => 1, (2, 2, 0) => 1 }
+        );
+
+        // A match for key 1 replaces the padded row.
+        input2.append(&mut vec![(1, (10, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 1, 10) => 1, (2, 2, 0) => 1 }
+        );
+
+        // Removing the match restores the padded row.
+        input2.append(&mut vec![(1, (10, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 1, 0) => 1, (2, 2, 0) => 1 }
+        );
+
+        // Matching values are added and removed independently.
+        input2.append(&mut vec![(2, (20, 1)), (2, (21, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 1, 0) => 1, (2, 2, 20) => 1, (2, 2, 21) => 1 }
+        );
+        input2.append(&mut vec![(2, (20, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 1, 0) => 1, (2, 2, 21) => 1 }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn outer_join_left_test() {
+        let (mut circuit, (mut input1, mut input2, delta, output)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (input1, input_handle1) =
+                    circuit.add_input_indexed_zset::<usize, usize, isize>();
+                let (input2, input_handle2) =
+                    circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+                let join = input1.outer_join_left(&input2);
+
+                (
+                    input_handle1,
+                    input_handle2,
+                    join.output(),
+                    join.integrate().output(),
+                )
+            })
+            .unwrap();
+
+        input1.append(&mut vec![(1, (1, 1)), (1, (2, 1)), (2, (3, 1))]);
+        input2.append(&mut vec![(2, (30, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (1, None) => 1, (2, None) => 1 }, 2 => { (3, Some(30)) => 1 } }
+        );
+
+        // The first match for key 1 retracts the unmatched rows.
+        input2.append(&mut vec![(1, (10, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! {
+                1 => { (1, None) => -1, (1, Some(10)) => 1, (2, None) => -1, (2, Some(10)) => 1 }
+            }
+        );
+
+        // Another match only adds joined rows.
+        input2.append(&mut vec![(1, (11, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! { 1 => { (1, Some(11)) => 1, (2, Some(11)) => 1 } }
+        );
+
+        input2.append(&mut vec![(1, (10, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! { 1 => { (1, Some(10)) => -1, (2, Some(10)) => -1 } }
+        );
+
+        // Removing the last match restores the unmatched rows.
+        input2.append(&mut vec![(1, (11, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! {
+                1 => { (1, None) => 1, (1, Some(11)) => -1, (2, None) => 1, (2, Some(11)) => -1 }
+            }
+        );
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (1, None) => 1, (2, None) => 1 }, 2 => { (3, Some(30)) => 1 } }
+        );
+
+        // Several matches for key 1 arriving at once retract each unmatched
+        // row exactly once.
+        input2.append(&mut vec![(1, (10, 1)), (1, (11, 1)), (1, (12, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (1, Some(10)) => 1, (1, Some(11)) => 1, (1, Some(12)) => 1,
+                    (2, Some(10)) => 1, (2, Some(11)) => 1, (2, Some(12)) => 1
+                },
+                2 => { (3, Some(30)) => 1 }
+            }
+        );
+
+        input2.append(&mut vec![(1, (10, -1)), (1, (11, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            delta.consolidate(),
+            indexed_zset! {
+                1 => { (1, Some(10)) => -1, (1, Some(11)) => -1, (2, Some(10)) => -1, (2, Some(11)) => -1 }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn merge_join_test() {
+        let (mut circuit, (mut input1, mut input2, merge_output, join_output)) =
+            Runtime::init_circuit(4, |circuit| {
+                let (input1, input_handle1) =
+                    circuit.add_input_indexed_zset::<usize, usize, isize>();
+                let (input2, input_handle2) =
+                    circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+                let merge_output = input1
+                    .integrate()
+                    .merge_join(&input2.integrate(), |k, v1, v2| (*k, *v1, *v2))
+                    .output();
+                let join_output = input1
+                    .join(&input2, |k, v1, v2| (*k, *v1, *v2))
+                    .integrate()
+                    .output();
+
+                (input_handle1, input_handle2, merge_output, join_output)
+            })
+            .unwrap();
+
+        // Keys 1 and 4 only appear on one side.
+        input1.append(&mut vec![
+            (1, (1, 1)),
+            (2, (1, 1)),
+            (2, (2, 1)),
+            (3, (1, 2)),
+        ]);
+        input2.append(&mut vec![(2, (10, 1)), (3, (10, 1)), (4, (10, 1))]);
+        circuit.step().unwrap();
+        let output = merge_output.consolidate();
+        assert_eq!(
+            output,
+            zset! { (2, 1, 10) => 1, (2, 2, 10) => 1, (3, 1, 10) => 2 }
+        );
+        assert_eq!(output, join_output.consolidate());
+
+        input1.append(&mut vec![(4, (1, 1)), (2, (2, -1))]);
+        input2.append(&mut vec![(1, (10, 1)), (5, (10, 1))]);
+        circuit.step().unwrap();
+        let output = merge_output.consolidate();
+        assert_eq!(
+            output,
+            zset! { (1, 1, 10) => 1, (2, 1, 10) => 1, (3, 1, 10) => 2, (4, 1, 10) => 1 }
+        );
+        assert_eq!(output, join_output.consolidate());
+
+        circuit.kill().unwrap();
+    }
 }
@@ -13,12 +13,17 @@
 //! * For each `((k1, v1), w1)` in `z1` and `((k2, v2), w2)` in `z2` where `k2 ∈
 //!   join_range(k1)`, add all values in `join_func(k1,v1,k2,v2)` to the output
 //!   batch with weight `w1 * w2`.
+//!
+//! [`Stream::stream_join_range`] and its variants compute this function for
+//! each pair of input batches independently, while [`Stream::join_range`]
+//! and its variants maintain it incrementally over the relations that the
+//! input streams describe changes to.
 
 use crate::{
-    algebra::{MulByRef, ZRingValue},
+    algebra::{IndexedZSet, MulByRef, ZRingValue},
     circuit::{
         operator_traits::{BinaryOperator, Operator},
-        Circuit, Scope, Stream,
+        Circuit, RootCircuit, Scope, Stream,
     },
     trace::{cursor::Cursor, Batch, BatchReader},
     DBData, OrdIndexedZSet, OrdZSet,
@@ -107,6 +112,105 @@ where
     }
 }
 
+impl<I1> Stream<RootCircuit, I1>
+where
+    I1: IndexedZSet + Send,
+    I1::R: ZRingValue,
+{
+    /// Incrementally range-join two streams into an `OrdZSet`.
+    ///
+    /// See module documentation for the definition of the range-join operator
+    /// and its arguments.
+    ///
+    /// Given streams `self` and `other` of changes to relations `A` and `B`,
+    /// computes the stream of changes to the range-join of `A` and `B`.
+    ///
+    /// Since a key in `self` can match keys in `other` that belong to
+    /// different shards, this operator shards `self` across workers and
+    /// [broadcasts](`Stream::broadcast`) `other` to all workers.  Each worker
+    /// joins its shard of `self` against the complete contents of `other`,
+    /// so the work of evaluating the join is split across workers, but every
+    /// worker stores a copy of the integral of `other`.  Make sure that
+    /// `other` is the smaller of the two relations.
+    pub fn join_range<RF, JF, It, I2>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        range_func: RF,
+        join_func: JF,
+    ) -> Stream<RootCircuit, OrdZSet<It::Item, I1::R>>
+    where
+        I2: IndexedZSet<R = I1::R> + Send,
+        RF: Fn(&I1::Key) -> (I2::Key, I2::Key) + Clone + 'static,
+        JF: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> It + Clone + 'static,
+        It: IntoIterator + 'static,
+        It::Item: DBData,
+    {
+        self.join_range_generic(other, range_func, move |k1, v1, k2, v2| {
+            join_func(k1, v1, k2, v2).into_iter().map(|k| (k, ()))
+        })
+    }
+
+    /// Incrementally range-join two streams into an `OrdIndexedZSet`.
+    ///
+    /// Like [`Self::join_range`], but the `join_func` closure returns an
+    /// iterator over `(key, value)` pairs used to assemble the output indexed
+    /// Z-set.
+    ///
+    /// Like [`Self::join_range`], shards `self` and broadcasts `other` to all
+    /// workers.
+    pub fn join_range_index<RF, JF, It, K, V, I2>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        range_func: RF,
+        join_func: JF,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<K, V, I1::R>>
+    where
+        I2: IndexedZSet<R = I1::R> + Send,
+        RF: Fn(&I1::Key) -> (I2::Key, I2::Key) + Clone + 'static,
+        JF: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> It + Clone + 'static,
+        K: DBData,
+        V: DBData,
+        It: IntoIterator<Item = (K, V)> + 'static,
+    {
+        self.join_range_generic(other, range_func, join_func)
+    }
+
+    /// Like [`Self::join_range`], but can return any indexed Z-set type.
+    ///
+    /// Like [`Self::join_range`], shards `self` and broadcasts `other` to all
+    /// workers.
+    pub fn join_range_generic<RF, JF, It, I2, O>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        range_func: RF,
+        join_func: JF,
+    ) -> Stream<RootCircuit, O>
+    where
+        I2: IndexedZSet<R = I1::R> + Send,
+        O: IndexedZSet<R = I1::R>,
+        RF: Fn(&I1::Key) -> (I2::Key, I2::Key) + Clone + 'static,
+        JF: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> It + Clone + 'static,
+        It: IntoIterator<Item = (O::Key, O::Val)> + 'static,
+    {
+        self.circuit().region("join_range", || {
+            let left = self.shard();
+            let right = other.broadcast();
+
+            // `(A + ΔA) <> (B + ΔB) - A <> B = ΔA <> (B + ΔB) + A <> ΔB`.
+            let left_trace = left.integrate_trace();
+            let right_trace = right.integrate_trace();
+
+            let left_delta =
+                left.stream_join_range_generic(&right_trace, range_func.clone(), join_func.clone());
+            let right_delta = left_trace
+                .delay_trace()
+                .stream_join_range_generic(&right, range_func, join_func);
+
+            left_delta.plus(&right_delta)
+        })
+    }
+}
+
 pub struct StreamJoinRange<RF, JF, It, I1, I2, O> {
     range_func: RF,
     join_func: JF,
@@ -198,7 +302,8 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{operator::Generator, zset, Circuit, RootCircuit};
+    use crate::{operator::Generator, zset, Circuit, IndexedZSet, RootCircuit, Runtime};
+    use std::collections::BTreeMap;
 
     #[test]
     fn stream_join_range_test() {
@@ -297,4 +402,101 @@ mod test {
             circuit.step().unwrap();
         }
     }
+
+    // Range-join the contents of two relations using a nested loop.
+    #[allow(clippy::type_complexity)]
+    fn join_range_model(
+        left: &BTreeMap<(u64, char), isize>,
+        right: &BTreeMap<(u64, char), isize>,
+    ) -> Vec<((u64, char, u64, char), (), isize)> {
+        let mut result = BTreeMap::new();
+        for ((k1, v1), w1) in left {
+            for ((k2, v2), w2) in right {
+                if k1.saturating_sub(1) <= *k2 && *k2 < k1 + 2 {
+                    *result.entry((*k1, *v1, *k2, *v2)).or_insert(0) += w1 * w2;
+                }
+            }
+        }
+
+        result
+            .into_iter()
+            .filter(|(_, w)| *w != 0)
+            .map(|(k, w)| (k, (), w))
+            .collect()
+    }
+
+    #[test]
+    fn join_range_test() {
+        let (mut circuit, (mut input1, mut input2, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, char, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, char, isize>();
+            let output = input1
+                .join_range(
+                    &input2,
+                    |&k| (k.saturating_sub(1), k + 2),
+                    |&k1, &v1, &k2, &v2| Some((k1, v1, k2, v2)),
+                )
+                .integrate()
+                .output();
+
+            (input_handle1, input_handle2, output)
+        })
+        .unwrap();
+
+        type Changes = Vec<(u64, (char, isize))>;
+        let steps: Vec<(Changes, Changes)> = vec![
+            (
+                vec![(1, ('a', 1)), (1, ('b', 2)), (3, ('c', 1))],
+                vec![(0, ('x', 1)), (2, ('y', 1)), (4, ('z', 3))],
+            ),
+            // Changes to one side only.
+            (vec![(2, ('d', 1)), (5, ('e', 1))], vec![]),
+            (vec![], vec![(1, ('w', 2)), (6, ('v', 1))]),
+            // Simultaneous changes to both sides, including retractions.
+            (
+                vec![(1, ('b', -2)), (4, ('f', 1))],
+                vec![(2, ('y', -1)), (3, ('u', 1))],
+            ),
+            // Retract everything.
+            (
+                vec![
+                    (1, ('a', -1)),
+                    (3, ('c', -1)),
+                    (2, ('d', -1)),
+                    (5, ('e', -1)),
+                    (4, ('f', -1)),
+                ],
+                vec![
+                    (0, ('x', -1)),
+                    (4, ('z', -3)),
+                    (1, ('w', -2)),
+                    (6, ('v', -1)),
+                    (3, ('u', -1)),
+                ],
+            ),
+        ];
+
+        let mut left = BTreeMap::new();
+        let mut right = BTreeMap::new();
+
+        for (mut changes1, mut changes2) in steps {
+            for (contents, changes) in [(&mut left, &changes1), (&mut right, &changes2)] {
+                for (k, (v, w)) in changes {
+                    *contents.entry((*k, *v)).or_insert(0) += w;
+                }
+                contents.retain(|_, w| *w != 0);
+            }
+
+            input1.append(&mut changes1);
+            input2.append(&mut changes2);
+            circuit.step().unwrap();
+
+            assert_eq!(
+                output.consolidate().iter().collect::<Vec<_>>(),
+                join_range_model(&left, &right)
+            );
+        }
+
+        circuit.kill().unwrap();
+    }
 }
@@ -0,0 +1,485 @@
+//! Source and sink operators that read and write newline-delimited JSON.
+
+// TODO:
+// - Async implementation (wait for data to become available in the reader)
+// - Sharded implementation (currently we feed all data on worker 0).
+
+use crate::{
+    algebra::{ZRingValue, ZSet},
+    circuit::{
+        operator_traits::{Data, Operator, SinkOperator, SourceOperator},
+        Scope,
+    },
+    operator::error_recovery::report_input_error,
+    trace::{BatchReader, Cursor},
+    Circuit, Runtime, Stream,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+/// A source operator that reads records of type `T` from a stream of
+/// newline-delimited JSON.
+///
+/// Each non-empty line of the input must contain exactly one JSON value,
+/// which is deserialized into `T`.  By default, the operator reads the
+/// entire input and yields its contents in the first clock cycle as a Z-set
+/// with unit weights.  Use [`Self::with_batch_size`] to read at most a given
+/// number of lines per clock cycle instead.
+///
+/// # Errors
+///
+/// Parse and I/O errors are not skipped.  Records read before the offending
+/// line in the same clock cycle are yielded as usual.  In the next clock
+/// cycle, the operator reports the error, identifying the offending line,
+/// and the [`CircuitHandle::step`](`crate::CircuitHandle::step`) invocation
+/// returns [`SchedulerError::Input`](`crate::SchedulerError::Input`).
+pub struct JsonSource<R, T, W, C> {
+    reader: BufReader<R>,
+    // Maximal number of lines to read per clock cycle.
+    batch_size: usize,
+    // Number of lines read so far.
+    line_number: usize,
+    // Buffer the current line is read into.
+    line: String,
+    exhausted: bool,
+    // Error encountered after reading some records during the previous clock
+    // cycle, reported in the current clock cycle.
+    pending_error: Option<String>,
+    _t: PhantomData<(C, T, W)>,
+}
+
+impl<R, T, W, C> JsonSource<R, T, W, C>
+where
+    R: Read,
+{
+    /// Create a [`JsonSource`] instance from any reader.
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            batch_size: usize::MAX,
+            line_number: 0,
+            line: String::new(),
+            exhausted: false,
+            pending_error: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Read at most `batch_size` lines per clock cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert_ne!(batch_size, 0, "batch size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+impl<R, T, W, C> JsonSource<R, T, W, C>
+where
+    T: DeserializeOwned,
+    W: ZRingValue,
+    R: Read,
+{
+    /// Read the next batch of records with unit weights.
+    ///
+    /// Returns an error message identifying the offending line on failure.
+    /// If the batch contains valid records before the offending line, returns
+    /// those records and defers the error to the next call.
+    fn read_records(&mut self) -> Result<Vec<(T, W)>, String> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        let mut records = Vec::new();
+        if let Err(error) = self.read_batch(&mut records) {
+            if records.is_empty() {
+                return Err(error);
+            }
+            self.pending_error = Some(error);
+        }
+
+        Ok(records)
+    }
+
+    /// Append up to `batch_size` records to `records`.
+    fn read_batch(&mut self, records: &mut Vec<(T, W)>) -> Result<(), String> {
+        let mut lines = 0;
+
+        while lines < self.batch_size && !self.exhausted {
+            self.line.clear();
+            let bytes = self
+                .reader
+                .read_line(&mut self.line)
+                .map_err(|e| format!("error reading line {}: {e}", self.line_number + 1))?;
+            if bytes == 0 {
+                self.exhausted = true;
+                break;
+            }
+
+            self.line_number += 1;
+            lines += 1;
+
+            let line = self.line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let record = serde_json::from_str(line)
+                .map_err(|e| format!("error parsing JSON at line {}: {e}", self.line_number))?;
+            records.push((record, W::one()));
+        }
+
+        Ok(())
+    }
+}
+
+impl<R, T, W, C> Operator for JsonSource<R, T, W, C>
+where
+    C: Data,
+    R: 'static,
+    T: 'static,
+    W: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("JsonSource")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.exhausted && self.pending_error.is_none()
+    }
+}
+
+impl<R, T, W, C> SourceOperator<C> for JsonSource<R, T, W, C>
+where
+    T: DeserializeOwned + 'static,
+    W: ZRingValue + 'static,
+    R: Read + 'static,
+    C: Data + ZSet<Key = T, R = W>,
+{
+    fn eval(&mut self) -> C {
+        if Runtime::worker_index() != 0 {
+            return C::zero();
+        }
+
+        match self.read_records() {
+            Ok(records) => C::from_keys((), records),
+            Err(error) => {
+                report_input_error(error);
+                C::zero()
+            }
+        }
+    }
+}
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: ZSet + Send,
+    B::Key: Serialize,
+    B::R: Serialize,
+{
+    /// Write the contents of `self` to a newline-delimited JSON file at
+    /// `path`.
+    ///
+    /// Creates or truncates the file and, at each clock cycle, appends a
+    /// line for each element of the current batch, containing a JSON object
+    /// with the element in its `value` field and its weight in its `weight`
+    /// field.  Elements with negative weights (retractions) are written as
+    /// is.  See [`JsonSink`] for details.
+    ///
+    /// In a multithreaded runtime, the stream is gathered at worker 0, which
+    /// is the only worker that writes to the file.
+    pub fn write_json<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let gathered = self.gather(0);
+
+        if Runtime::worker_index() == 0 {
+            let file = File::create(path)?;
+            self.circuit()
+                .add_sink(JsonSink::from_writer(file), &gathered);
+        }
+
+        Ok(())
+    }
+}
+
+/// A line of output written by [`JsonSink`].
+#[derive(Serialize)]
+struct JsonRecord<'a, K, R> {
+    value: &'a K,
+    weight: R,
+}
+
+/// A sink operator that writes Z-sets as newline-delimited JSON.
+///
+/// At each clock cycle, the operator writes a line for each element of the
+/// input batch, containing an object of the form
+/// `{"value": <element>, "weight": <weight>}`, and flushes the writer.
+///
+/// # Errors
+///
+/// Serialization and I/O errors are reported by the scheduler, which returns
+/// [`SchedulerError::Input`](`crate::SchedulerError::Input`)
+/// from the current [`CircuitHandle::step`](`crate::CircuitHandle::step`)
+/// invocation.
+pub struct JsonSink<W, B>
+where
+    W: Write,
+{
+    writer: BufWriter<W>,
+    _t: PhantomData<B>,
+}
+
+impl<W, B> JsonSink<W, B>
+where
+    W: Write,
+{
+    /// Create a [`JsonSink`] instance that writes to any writer.
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<W, B> JsonSink<W, B>
+where
+    W: Write,
+    B: BatchReader<Val = (), Time = ()>,
+    B::Key: Serialize,
+    B::R: Serialize,
+{
+    /// Write all elements of `batch` and flush the writer.
+    fn write_batch(&mut self, batch: &B) -> Result<(), serde_json::Error> {
+        let mut cursor = batch.cursor();
+
+        while cursor.key_valid() {
+            let weight = cursor.weight();
+            serde_json::to_writer(
+                &mut self.writer,
+                &JsonRecord {
+                    value: cursor.key(),
+                    weight,
+                },
+            )?;
+            self.writer
+                .write_all(b"\n")
+                .map_err(serde_json::Error::io)?;
+            cursor.step_key();
+        }
+
+        self.writer.flush().map_err(serde_json::Error::io)
+    }
+}
+
+impl<W, B> Operator for JsonSink<W, B>
+where
+    W: Write + 'static,
+    B: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("JsonSink")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<W, B> SinkOperator<B> for JsonSink<W, B>
+where
+    W: Write + 'static,
+    B: BatchReader<Val = (), Time = ()> + 'static,
+    B::Key: Serialize,
+    B::R: Serialize,
+{
+    fn eval(&mut self, batch: &B) {
+        if let Err(error) = self.write_batch(batch) {
+            report_input_error(format!("error writing JSON: {error}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{Generator, JsonSink, JsonSource},
+        zset, Circuit, OrdZSet, RootCircuit, SchedulerError, Stream,
+    };
+    use std::{
+        fs,
+        io::{self, Write},
+    };
+
+    const JSON_DATA: &str = r#"[1, "foo"]
+[2, "bar"]
+
+[3, "baz"]
+[1, "foo"]
+"#;
+
+    #[test]
+    fn test_json_source() {
+        let (circuit, output) = RootCircuit::build(move |circuit| {
+            circuit
+                .add_source(JsonSource::from_reader(JSON_DATA.as_bytes()).with_batch_size(2))
+                .output()
+        })
+        .unwrap();
+
+        let mut outputs: Vec<OrdZSet<(u64, String), isize>> = Vec::new();
+        for _ in 0..4 {
+            circuit.step().unwrap();
+            outputs.push(output.consolidate());
+        }
+
+        assert_eq!(
+            outputs,
+            vec![
+                zset! { (1, "foo".to_string()) => 1, (2, "bar".to_string()) => 1 },
+                // Empty lines count towards the batch size.
+                zset! { (3, "baz".to_string()) => 1 },
+                zset! { (1, "foo".to_string()) => 1 },
+                zset! {},
+            ]
+        );
+    }
+
+    fn assert_parse_error(error: SchedulerError, line: usize) {
+        match error {
+            SchedulerError::Input { operator, error } => {
+                assert_eq!(operator, "JsonSource");
+                assert!(error.starts_with(&format!("error parsing JSON at line {line}")));
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+
+    #[test]
+    fn test_json_parse_error() {
+        let circuit = RootCircuit::build(move |circuit| {
+            circuit.add_source(
+                JsonSource::<_, (u64, String), isize, OrdZSet<_, _>>::from_reader(
+                    "{\"bad\": true}\n[1, \"foo\"]\n".as_bytes(),
+                ),
+            );
+        })
+        .unwrap()
+        .0;
+
+        assert_parse_error(circuit.step().unwrap_err(), 1);
+    }
+
+    #[test]
+    fn test_json_parse_error_after_records() {
+        let (circuit, output) = RootCircuit::build(move |circuit| {
+            circuit
+                .add_source(JsonSource::from_reader(
+                    "[1, \"foo\"]\n[2, \"bar\"]\n{\"bad\": true}\n[3, \"baz\"]\n".as_bytes(),
+                ))
+                .output()
+        })
+        .unwrap();
+
+        // Records preceding the bad line are not lost.
+        circuit.step().unwrap();
+        let records: OrdZSet<(u64, String), isize> = output.consolidate();
+        assert_eq!(
+            records,
+            zset! { (1, "foo".to_string()) => 1, (2, "bar".to_string()) => 1 }
+        );
+
+        assert_parse_error(circuit.step().unwrap_err(), 3);
+    }
+
+    #[test]
+    fn test_write_json() {
+        let path =
+            std::env::temp_dir().join(format!("dbsp_write_json_{}.json", std::process::id()));
+
+        let mut inputs = vec![
+            zset! { (1, "foo".to_string()) => 1, (2, "bar".to_string()) => 2 },
+            zset! {},
+            zset! { (1, "foo".to_string()) => -1 },
+        ]
+        .into_iter();
+
+        let circuit = {
+            let path = path.clone();
+            RootCircuit::build(move |circuit| {
+                let input: Stream<_, OrdZSet<(u64, String), isize>> =
+                    circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+                input.write_json(path).unwrap();
+            })
+            .unwrap()
+            .0
+        };
+
+        // The file is flushed at every step.
+        circuit.step().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            concat!(
+                "{\"value\":[1,\"foo\"],\"weight\":1}\n",
+                "{\"value\":[2,\"bar\"],\"weight\":2}\n",
+            )
+        );
+
+        circuit.step().unwrap();
+        circuit.step().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            concat!(
+                "{\"value\":[1,\"foo\"],\"weight\":1}\n",
+                "{\"value\":[2,\"bar\"],\"weight\":2}\n",
+                "{\"value\":[1,\"foo\"],\"weight\":-1}\n",
+            )
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// A writer that fails on every write.
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_sink_error() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let input: Stream<_, OrdZSet<(u64, String), isize>> =
+                circuit.add_source(Generator::new(|| zset! { (1, "foo".to_string()) => 1 }));
+            circuit.add_sink(JsonSink::from_writer(FailingWriter), &input);
+        })
+        .unwrap()
+        .0;
+
+        match circuit.step().unwrap_err() {
+            SchedulerError::Input { operator, error } => {
+                assert_eq!(operator, "JsonSink");
+                assert!(error.starts_with("error writing JSON"), "{error}");
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+}
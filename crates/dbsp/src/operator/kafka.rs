@@ -0,0 +1,658 @@
+//! Source operator that consumes JSON-encoded records from Kafka.
+
+// TODO:
+// - Async implementation (wait for messages to become available)
+// - Sharded implementation (currently we feed all data on worker 0).
+
+use crate::{
+    algebra::{ZRingValue, ZSet},
+    circuit::{
+        operator_traits::{Data, Operator, SourceOperator},
+        trace::SchedulerEvent,
+        Scope,
+    },
+    operator::error_recovery::report_input_error,
+    Circuit, RootCircuit, Runtime, Stream,
+};
+use rdkafka::{
+    consumer::{BaseConsumer, CommitMode, Consumer},
+    error::KafkaResult,
+    ClientConfig, Message, Offset, TopicPartitionList,
+};
+use serde::de::DeserializeOwned;
+use std::{borrow::Cow, cell::RefCell, marker::PhantomData, rc::Rc, time::Duration};
+
+/// A message consumed from a Kafka partition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KafkaMessage {
+    /// Partition the message was consumed from.
+    pub partition: i32,
+    /// Offset of the message within its partition.
+    pub offset: i64,
+    /// Message payload.  Empty if the message doesn't have a payload.
+    pub payload: Vec<u8>,
+}
+
+/// A consumer of Kafka messages used by [`KafkaSource`].
+///
+/// Abstracts the Kafka client, so that [`KafkaSource`] can be tested
+/// without a broker.  [`KafkaPartitionConsumer`] implements this trait on top
+/// of `rdkafka`.
+pub trait MessageConsumer {
+    /// Returns the next message without blocking, or `None` if no message is
+    /// currently available.
+    fn poll(&mut self) -> Option<Result<KafkaMessage, String>>;
+
+    /// Commits the offsets of all messages returned by [`Self::poll`] so far.
+    fn commit(&mut self) -> Result<(), String>;
+
+    /// Rewinds the consumer to the first message returned by [`Self::poll`]
+    /// since the last [`Self::commit`].
+    ///
+    /// These messages will be returned by [`Self::poll`] again and are not
+    /// committed by the next call to [`Self::commit`].
+    fn rewind(&mut self) -> Result<(), String>;
+}
+
+// Maximal time to wait for the consumer to seek to a new offset.
+const SEEK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A [`MessageConsumer`] that consumes a single topic partition.
+pub struct KafkaPartitionConsumer {
+    consumer: BaseConsumer,
+    topic: String,
+    partition: i32,
+    // Offset of the first message consumed since the last commit, if any.
+    first_uncommitted: Option<i64>,
+    // Offset of the next message to consume, if any messages were consumed
+    // since the last commit.
+    uncommitted: Option<i64>,
+}
+
+impl KafkaPartitionConsumer {
+    /// Create a consumer for partition `partition` of `topic`.
+    ///
+    /// Consumption starts from the committed offset of the consumer group
+    /// configured in `config` (`group.id`).  Offsets are committed
+    /// explicitly by [`KafkaSource`], so `config` should set
+    /// `enable.auto.commit` to `false`.
+    pub fn new(config: &ClientConfig, topic: &str, partition: i32) -> KafkaResult<Self> {
+        let consumer: BaseConsumer = config.create()?;
+
+        let mut assignment = TopicPartitionList::new();
+        assignment.add_partition(topic, partition);
+        consumer.assign(&assignment)?;
+
+        Ok(Self {
+            consumer,
+            topic: topic.to_string(),
+            partition,
+            first_uncommitted: None,
+            uncommitted: None,
+        })
+    }
+}
+
+impl MessageConsumer for KafkaPartitionConsumer {
+    fn poll(&mut self) -> Option<Result<KafkaMessage, String>> {
+        let message = match self.consumer.poll(Duration::ZERO)? {
+            Ok(message) => message,
+            Err(error) => return Some(Err(error.to_string())),
+        };
+
+        self.first_uncommitted.get_or_insert(message.offset());
+        self.uncommitted = Some(message.offset() + 1);
+        Some(Ok(KafkaMessage {
+            partition: message.partition(),
+            offset: message.offset(),
+            payload: message.payload().unwrap_or_default().to_vec(),
+        }))
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        if let Some(offset) = self.uncommitted {
+            let mut offsets = TopicPartitionList::new();
+            offsets
+                .add_partition_offset(&self.topic, self.partition, Offset::Offset(offset))
+                .map_err(|error| error.to_string())?;
+            self.consumer
+                .commit(&offsets, CommitMode::Async)
+                .map_err(|error| error.to_string())?;
+        }
+
+        self.first_uncommitted = None;
+        self.uncommitted = None;
+        Ok(())
+    }
+
+    fn rewind(&mut self) -> Result<(), String> {
+        if let Some(offset) = self.first_uncommitted.take() {
+            self.consumer
+                .seek(
+                    &self.topic,
+                    self.partition,
+                    Offset::Offset(offset),
+                    SEEK_TIMEOUT,
+                )
+                .map_err(|error| error.to_string())?;
+            self.uncommitted = None;
+        }
+
+        Ok(())
+    }
+}
+
+impl RootCircuit {
+    /// Create a stream of records consumed from Kafka by `source`.
+    ///
+    /// See [`KafkaSource`] for details.  Offsets of the messages consumed
+    /// during a clock cycle are committed when the circuit completes the
+    /// clock cycle.
+    pub fn add_kafka_source<M, T, W, C>(&self, source: KafkaSource<M, T, W, C>) -> Stream<Self, C>
+    where
+        M: MessageConsumer + 'static,
+        T: DeserializeOwned + 'static,
+        W: ZRingValue + 'static,
+        C: Data + ZSet<Key = T, R = W>,
+    {
+        let consumer = source.consumer.clone();
+        let stream = self.add_source(source);
+
+        // The root circuit signals the end of a clock cycle only if all
+        // operators have been evaluated successfully.
+        self.register_scheduler_event_handler(
+            &format!("KafkaSource{}", stream.origin_node_id()),
+            move |event| {
+                if let SchedulerEvent::StepEnd { circuit_id } = event {
+                    if circuit_id.path().is_empty() {
+                        consumer.borrow_mut().step_completed();
+                    }
+                }
+            },
+        );
+
+        stream
+    }
+}
+
+/// Consumer state shared by [`KafkaSource`] and the scheduler event handler
+/// that commits offsets at the end of each clock cycle.
+struct ConsumerState<M> {
+    consumer: M,
+    // `true` if messages consumed during the current clock cycle haven't been
+    // committed yet.
+    uncommitted: bool,
+    // Error committing offsets at the end of the previous clock cycle.
+    commit_error: Option<String>,
+}
+
+impl<M> ConsumerState<M>
+where
+    M: MessageConsumer,
+{
+    fn new(consumer: M) -> Self {
+        Self {
+            consumer,
+            uncommitted: false,
+            commit_error: None,
+        }
+    }
+
+    /// Commit offsets consumed during the clock cycle that has just completed.
+    fn step_completed(&mut self) {
+        if self.uncommitted {
+            self.uncommitted = false;
+            if let Err(error) = self.consumer.commit() {
+                self.commit_error = Some(format!("error committing Kafka offsets: {error}"));
+            }
+        }
+    }
+}
+
+/// A source operator that consumes records of type `T` from Kafka.
+///
+/// At each clock cycle, the operator consumes all messages currently
+/// available from the consumer, up to a configurable maximum (see
+/// [`Self::with_max_batch_size`]), deserializes the payload of each message
+/// from JSON, and yields the records as a Z-set with unit weights.  If no
+/// messages are available, the operator yields an empty Z-set without
+/// waiting.
+///
+/// The source must be added to a circuit using
+/// [`RootCircuit::add_kafka_source`], which commits the offsets of messages
+/// consumed during a clock cycle once the circuit has evaluated the entire
+/// clock cycle successfully.  If the clock cycle fails in any operator, the
+/// offsets are not committed, so a consumer created for a rebuilt circuit
+/// resumes from the first message consumed during the failed clock cycle.
+/// If the circuit is stepped again instead, the next clock cycle rewinds the
+/// consumer to that message.
+///
+/// # Errors
+///
+/// Kafka and deserialization errors are not skipped.  The operator reports
+/// the error, identifying the offending message, and the current
+/// [`CircuitHandle::step`](`crate::CircuitHandle::step`) invocation returns
+/// [`SchedulerError::Input`](`crate::SchedulerError::Input`).  An error
+/// committing offsets is reported by the next clock cycle.
+pub struct KafkaSource<M, T, W, C> {
+    consumer: Rc<RefCell<ConsumerState<M>>>,
+    // Maximal number of messages to consume per clock cycle.
+    max_batch_size: usize,
+    _t: PhantomData<(C, T, W)>,
+}
+
+impl<M, T, W, C> KafkaSource<M, T, W, C>
+where
+    M: MessageConsumer,
+{
+    /// Create a [`KafkaSource`] instance that consumes messages from
+    /// `consumer`.
+    pub fn new(consumer: M) -> Self {
+        Self {
+            consumer: Rc::new(RefCell::new(ConsumerState::new(consumer))),
+            max_batch_size: usize::MAX,
+            _t: PhantomData,
+        }
+    }
+
+    /// Consume at most `max_batch_size` messages per clock cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_batch_size` is zero.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        assert_ne!(max_batch_size, 0, "batch size must be positive");
+        self.max_batch_size = max_batch_size;
+        self
+    }
+}
+
+impl<T, W, C> KafkaSource<KafkaPartitionConsumer, T, W, C> {
+    /// Create a [`KafkaSource`] instance that consumes partition `partition`
+    /// of `topic` (see [`KafkaPartitionConsumer::new`]).
+    pub fn from_config(config: &ClientConfig, topic: &str, partition: i32) -> KafkaResult<Self> {
+        Ok(Self::new(KafkaPartitionConsumer::new(
+            config, topic, partition,
+        )?))
+    }
+}
+
+impl<M, T, W, C> KafkaSource<M, T, W, C>
+where
+    M: MessageConsumer,
+    T: DeserializeOwned,
+    W: ZRingValue,
+{
+    /// Consume the next batch of records with unit weights.
+    ///
+    /// Returns an error message identifying the offending message on failure.
+    fn read_records(&mut self) -> Result<Vec<(T, W)>, String> {
+        let mut state = self.consumer.borrow_mut();
+
+        if let Some(error) = state.commit_error.take() {
+            return Err(error);
+        }
+
+        // The previous clock cycle consumed messages, but didn't complete.
+        if state.uncommitted {
+            state
+                .consumer
+                .rewind()
+                .map_err(|error| format!("error rewinding Kafka consumer: {error}"))?;
+        }
+
+        state.uncommitted = true;
+        Self::poll_records(&mut state.consumer, self.max_batch_size)
+    }
+
+    /// Poll up to `max_batch_size` messages and deserialize their payloads.
+    fn poll_records(consumer: &mut M, max_batch_size: usize) -> Result<Vec<(T, W)>, String> {
+        let mut records = Vec::new();
+        while records.len() < max_batch_size {
+            let message = match consumer.poll() {
+                Some(message) => {
+                    message.map_err(|error| format!("error consuming from Kafka: {error}"))?
+                }
+                None => break,
+            };
+
+            let record = serde_json::from_slice(&message.payload).map_err(|error| {
+                format!(
+                    "error parsing Kafka message at partition {}, offset {}: {error}",
+                    message.partition, message.offset
+                )
+            })?;
+            records.push((record, W::one()));
+        }
+
+        Ok(records)
+    }
+}
+
+impl<M, T, W, C> Operator for KafkaSource<M, T, W, C>
+where
+    C: Data,
+    M: 'static,
+    T: 'static,
+    W: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("KafkaSource")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        // More messages can arrive at any time.
+        false
+    }
+}
+
+impl<M, T, W, C> SourceOperator<C> for KafkaSource<M, T, W, C>
+where
+    M: MessageConsumer + 'static,
+    T: DeserializeOwned + 'static,
+    W: ZRingValue + 'static,
+    C: Data + ZSet<Key = T, R = W>,
+{
+    fn eval(&mut self) -> C {
+        if Runtime::worker_index() != 0 {
+            return C::zero();
+        }
+
+        match self.read_records() {
+            Ok(records) => C::from_keys((), records),
+            Err(error) => {
+                report_input_error(error);
+                C::zero()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KafkaMessage, MessageConsumer};
+    use crate::{
+        circuit::schedule::StaticScheduler,
+        operator::{error_recovery::report_operator_error, KafkaSource},
+        trace::Batch,
+        zset, CircuitHandle, OrdZSet, RootCircuit, SchedulerError,
+    };
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+
+    /// Mock consumer that returns messages pushed to a shared log.
+    #[derive(Clone, Default)]
+    struct MockConsumer {
+        log: Arc<Mutex<Vec<KafkaMessage>>>,
+        // Offset of the next message to return.
+        position: Arc<Mutex<i64>>,
+        first_uncommitted: Arc<Mutex<Option<i64>>>,
+        uncommitted: Arc<Mutex<Option<i64>>>,
+        committed: Arc<Mutex<Option<i64>>>,
+    }
+
+    impl MockConsumer {
+        fn push(&self, payload: &str) {
+            let mut log = self.log.lock().unwrap();
+            let offset = log.len() as i64;
+            log.push(KafkaMessage {
+                partition: 0,
+                offset,
+                payload: payload.as_bytes().to_vec(),
+            });
+        }
+
+        /// Replace the payload of the message at `offset`.
+        fn replace(&self, offset: i64, payload: &str) {
+            self.log.lock().unwrap()[offset as usize].payload = payload.as_bytes().to_vec();
+        }
+
+        fn committed(&self) -> Option<i64> {
+            *self.committed.lock().unwrap()
+        }
+    }
+
+    impl MessageConsumer for MockConsumer {
+        fn poll(&mut self) -> Option<Result<KafkaMessage, String>> {
+            let mut position = self.position.lock().unwrap();
+            let message = self.log.lock().unwrap().get(*position as usize)?.clone();
+            *position += 1;
+
+            self.first_uncommitted
+                .lock()
+                .unwrap()
+                .get_or_insert(message.offset);
+            *self.uncommitted.lock().unwrap() = Some(message.offset + 1);
+            Some(Ok(message))
+        }
+
+        fn commit(&mut self) -> Result<(), String> {
+            *self.first_uncommitted.lock().unwrap() = None;
+            if let Some(offset) = self.uncommitted.lock().unwrap().take() {
+                *self.committed.lock().unwrap() = Some(offset);
+            }
+            Ok(())
+        }
+
+        fn rewind(&mut self) -> Result<(), String> {
+            if let Some(offset) = self.first_uncommitted.lock().unwrap().take() {
+                *self.position.lock().unwrap() = offset;
+                *self.uncommitted.lock().unwrap() = None;
+            }
+            Ok(())
+        }
+    }
+
+    type Output = Rc<RefCell<OrdZSet<(u64, String), isize>>>;
+
+    /// Build a circuit that consumes messages from `consumer` and fails
+    /// downstream of the source while `fail` is set.
+    fn test_circuit(
+        consumer: MockConsumer,
+        max_batch_size: usize,
+    ) -> (CircuitHandle, Output, Rc<Cell<bool>>) {
+        let output: Output = Rc::new(RefCell::new(zset! {}));
+        let fail = Rc::new(Cell::new(false));
+
+        let circuit = {
+            let output = output.clone();
+            let fail = fail.clone();
+            // The static scheduler restarts each step from scratch, so the
+            // circuit can be stepped again after a failed step.
+            RootCircuit::build_with_scheduler::<_, _, StaticScheduler>(move |circuit| {
+                circuit
+                    .add_kafka_source(
+                        KafkaSource::new(consumer).with_max_batch_size(max_batch_size),
+                    )
+                    .apply(move |batch: &OrdZSet<(u64, String), isize>| {
+                        if fail.get() {
+                            report_operator_error("downstream failure".to_string());
+                        }
+                        batch.clone()
+                    })
+                    .inspect(move |batch: &OrdZSet<(u64, String), isize>| {
+                        *output.borrow_mut() = batch.clone()
+                    });
+            })
+            .unwrap()
+            .0
+        };
+
+        (circuit, output, fail)
+    }
+
+    #[test]
+    fn kafka_source_mock() {
+        let consumer = MockConsumer::default();
+        let (circuit, output, _fail) = test_circuit(consumer.clone(), 2);
+
+        // No messages available.
+        circuit.step().unwrap();
+        assert_eq!(*output.borrow(), zset! {});
+
+        consumer.push(r#"[1, "foo"]"#);
+        consumer.push(r#"[2, "bar"]"#);
+        consumer.push(r#"[1, "foo"]"#);
+
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.borrow(),
+            zset! { (1, "foo".to_string()) => 1, (2, "bar".to_string()) => 1 }
+        );
+        // Offsets are committed once the step has completed.
+        assert_eq!(consumer.committed(), Some(2));
+
+        circuit.step().unwrap();
+        assert_eq!(*output.borrow(), zset! { (1, "foo".to_string()) => 1 });
+        assert_eq!(consumer.committed(), Some(3));
+
+        circuit.step().unwrap();
+        assert_eq!(*output.borrow(), zset! {});
+        assert_eq!(consumer.committed(), Some(3));
+    }
+
+    fn assert_parse_error(error: SchedulerError, offset: i64) {
+        match error {
+            SchedulerError::Input { operator, error } => {
+                assert_eq!(operator, "KafkaSource");
+                assert!(error.starts_with(&format!(
+                    "error parsing Kafka message at partition 0, offset {offset}"
+                )));
+            }
+            error => panic!("unexpected error {error}"),
+        }
+    }
+
+    #[test]
+    fn kafka_source_parse_error() {
+        let consumer = MockConsumer::default();
+        let (circuit, output, _fail) = test_circuit(consumer.clone(), usize::MAX);
+
+        consumer.push(r#"[1, "foo"]"#);
+        circuit.step().unwrap();
+        assert_eq!(*output.borrow(), zset! { (1, "foo".to_string()) => 1 });
+        assert_eq!(consumer.committed(), Some(1));
+
+        consumer.push(r#"[2, "bar"]"#);
+        consumer.push(r#"{"bad": true}"#);
+
+        // Messages consumed by the failed step are not committed.
+        assert_parse_error(circuit.step().unwrap_err(), 2);
+        assert_eq!(consumer.committed(), Some(1));
+
+        // The next step consumes the same messages again.
+        assert_parse_error(circuit.step().unwrap_err(), 2);
+        assert_eq!(consumer.committed(), Some(1));
+
+        consumer.replace(2, r#"[3, "baz"]"#);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.borrow(),
+            zset! { (2, "bar".to_string()) => 1, (3, "baz".to_string()) => 1 }
+        );
+        assert_eq!(consumer.committed(), Some(3));
+    }
+
+    #[test]
+    fn kafka_source_downstream_error() {
+        let consumer = MockConsumer::default();
+        let (circuit, output, fail) = test_circuit(consumer.clone(), usize::MAX);
+
+        consumer.push(r#"[1, "foo"]"#);
+        circuit.step().unwrap();
+        assert_eq!(consumer.committed(), Some(1));
+
+        consumer.push(r#"[2, "bar"]"#);
+        consumer.push(r#"[3, "baz"]"#);
+
+        // A step that fails after the source has consumed messages doesn't
+        // commit their offsets.
+        fail.set(true);
+        match circuit.step().unwrap_err() {
+            SchedulerError::OperatorPanic { message, .. } => {
+                assert_eq!(message, "downstream failure")
+            }
+            error => panic!("unexpected error {error}"),
+        }
+        assert_eq!(consumer.committed(), Some(1));
+
+        // The next step consumes the same messages again and commits them.
+        fail.set(false);
+        circuit.step().unwrap();
+        assert_eq!(
+            *output.borrow(),
+            zset! { (2, "bar".to_string()) => 1, (3, "baz".to_string()) => 1 }
+        );
+        assert_eq!(consumer.committed(), Some(3));
+    }
+
+    /// Requires a Kafka broker at `$REDPANDA_BROKERS` (`localhost` by
+    /// default).  Run with `cargo test --features with-kafka -- --ignored`.
+    #[test]
+    #[ignore = "requires a running Kafka broker"]
+    fn kafka_source_broker() {
+        use rdkafka::{
+            producer::{BaseProducer, BaseRecord, Producer},
+            ClientConfig,
+        };
+        use std::{
+            env, process,
+            time::{Duration, Instant},
+        };
+
+        let brokers = env::var("REDPANDA_BROKERS").unwrap_or_else(|_| "localhost".to_string());
+        let topic = format!("dbsp_kafka_source_test_{}", process::id());
+
+        let producer: BaseProducer = ClientConfig::new()
+            .set("bootstrap.servers", &brokers)
+            .create()
+            .unwrap();
+        for payload in [r#"[1, "foo"]"#, r#"[2, "bar"]"#, r#"[3, "baz"]"#] {
+            producer
+                .send(<BaseRecord<(), str, ()>>::to(&topic).payload(payload))
+                .unwrap();
+        }
+        producer.flush(Duration::from_secs(10)).unwrap();
+
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &brokers)
+            .set("group.id", &topic)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest");
+
+        let received = Rc::new(RefCell::new(OrdZSet::<(u64, String), isize>::empty(())));
+        let circuit = {
+            let received = received.clone();
+            RootCircuit::build(move |circuit| {
+                circuit
+                    .add_kafka_source(KafkaSource::from_config(&config, &topic, 0).unwrap())
+                    .integrate()
+                    .inspect(move |batch: &OrdZSet<(u64, String), isize>| {
+                        *received.borrow_mut() = batch.clone()
+                    });
+            })
+            .unwrap()
+            .0
+        };
+
+        let expected = zset! {
+            (1, "foo".to_string()) => 1,
+            (2, "bar".to_string()) => 1,
+            (3, "baz".to_string()) => 1,
+        };
+
+        let start = Instant::now();
+        while *received.borrow() != expected {
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "timeout waiting for Kafka messages"
+            );
+            circuit.step().unwrap();
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
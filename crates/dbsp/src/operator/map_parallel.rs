@@ -0,0 +1,158 @@
+//! Value transform that runs on multiple threads within an operator.
+
+use crate::{
+    trace::{consolidation::consolidate, Batch, BatchReader, Builder, Cursor},
+    Circuit, DBData, DBWeight, OrdIndexedZSet, Stream,
+};
+use rayon::prelude::*;
+use std::cmp::max;
+
+/// Number of key ranges per rayon thread.  Using several ranges per thread
+/// helps to balance the load when values are unevenly distributed across
+/// keys.
+const RANGES_PER_THREAD: usize = 4;
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    K: DBData + Sync,
+    V: DBData + Sync,
+    R: DBWeight + Sync,
+{
+    /// Apply `map_func` to all values in the input stream in parallel.
+    ///
+    /// Equivalent to `self.map_index(|(k, v)| (k.clone(), map_func(v)))`,
+    /// but splits each input batch into key ranges and transforms the values
+    /// of different ranges in parallel on the rayon thread pool.  Keys are
+    /// preserved, so the output batch is assembled from transformed ranges
+    /// without re-sorting; values of each key are re-consolidated, since
+    /// `map_func` can map distinct values to the same output.
+    ///
+    /// This is useful for expensive transforms of large batches, which
+    /// would otherwise leave cores idle while a single worker thread
+    /// evaluates the operator.
+    pub fn map_index_parallel<V2, F>(&self, map_func: F) -> Stream<C, OrdIndexedZSet<K, V2, R>>
+    where
+        V2: DBData,
+        F: Fn(&V) -> V2 + Send + Sync + 'static,
+    {
+        let mapped = self.try_sharded_version().apply_named(
+            "MapIndexParallel",
+            move |batch: &OrdIndexedZSet<K, V, R>| map_values_parallel(batch, &map_func),
+        );
+
+        // Keys are unchanged, so the output is sharded iff the input is.
+        mapped.mark_sharded_if(self);
+        mapped
+    }
+}
+
+fn map_values_parallel<K, V, V2, R, F>(
+    batch: &OrdIndexedZSet<K, V, R>,
+    map_func: &F,
+) -> OrdIndexedZSet<K, V2, R>
+where
+    K: DBData + Sync,
+    V: DBData + Sync,
+    V2: DBData,
+    R: DBWeight + Sync,
+    F: Fn(&V) -> V2 + Sync,
+{
+    // Split keys into ranges of equal size, identified by their first keys.
+    let num_ranges = rayon::current_num_threads() * RANGES_PER_THREAD;
+    let range_size = max(batch.key_count() / num_ranges, 1);
+
+    let mut bounds = Vec::with_capacity(num_ranges + 1);
+    let mut cursor = batch.cursor();
+    let mut index = 0;
+    while cursor.key_valid() {
+        if index % range_size == 0 {
+            bounds.push(cursor.key().clone());
+        }
+        index += 1;
+        cursor.step_key();
+    }
+
+    let ranges: Vec<Vec<((K, V2), R)>> = (0..bounds.len())
+        .into_par_iter()
+        .map(|range| {
+            let end = bounds.get(range + 1);
+
+            let mut tuples = Vec::new();
+            let mut values = Vec::new();
+
+            let mut cursor = batch.cursor();
+            cursor.seek_key(&bounds[range]);
+            while cursor.key_valid() && !matches!(end, Some(end) if cursor.key() >= end) {
+                while cursor.val_valid() {
+                    values.push((map_func(cursor.val()), cursor.weight()));
+                    cursor.step_val();
+                }
+
+                // `map_func` may map distinct values to the same output value.
+                consolidate(&mut values);
+                tuples.extend(
+                    values
+                        .drain(..)
+                        .map(|(val, weight)| ((cursor.key().clone(), val), weight)),
+                );
+                cursor.step_key();
+            }
+
+            tuples
+        })
+        .collect();
+
+    let mut builder = <OrdIndexedZSet<K, V2, R> as Batch>::Builder::with_capacity(
+        (),
+        ranges.iter().map(Vec::len).sum(),
+    );
+    for tuple in ranges.into_iter().flatten() {
+        builder.push(tuple);
+    }
+    builder.done()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{FilterMap, Generator},
+        trace::Batch,
+        Circuit, OrdIndexedZSet, RootCircuit, Stream,
+    };
+
+    #[test]
+    fn map_index_parallel_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut step = 0;
+            let input: Stream<_, OrdIndexedZSet<u64, u64, isize>> =
+                circuit.add_source(Generator::new(move || {
+                    step += 1;
+                    let tuples = (0..10_000u64)
+                        .map(|i| {
+                            let x = (i * 7919 + step * 104729) % 100_003;
+                            ((x % 1000, x % 97), if x % 5 == 0 { -1 } else { 1 })
+                        })
+                        .collect::<Vec<_>>();
+                    OrdIndexedZSet::from_tuples((), tuples)
+                }));
+
+            // Injective transform.
+            let parallel = input.map_index_parallel(|v| v * 2 + 1);
+            let serial = input.map_index(|(k, v)| (*k, v * 2 + 1));
+            parallel.apply2(&serial, |parallel, serial| assert_eq!(parallel, serial));
+
+            // Transform that collapses distinct values, so that weights of
+            // each key must be re-consolidated.
+            let parallel = input.map_index_parallel(|v| v / 10);
+            let serial = input.map_index(|(k, v)| (*k, v / 10));
+            parallel.apply2(&serial, |parallel, serial| assert_eq!(parallel, serial));
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..5 {
+            circuit.step().unwrap();
+        }
+    }
+}
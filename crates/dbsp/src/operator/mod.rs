@@ -1,6 +1,7 @@
 //! Some basic operators.
 
 pub mod apply2;
+pub mod apply3;
 pub mod communication;
 pub mod recursive;
 
@@ -9,6 +10,11 @@ pub(crate) mod inspect;
 pub(crate) mod upsert;
 
 mod aggregate;
+#[cfg(feature = "with-arrow")]
+mod arrow;
+mod chunk;
+mod coerce_weights;
+mod cogroup;
 mod condition;
 mod consolidate;
 #[cfg(feature = "with-csv")]
@@ -16,16 +22,28 @@ mod csv;
 mod delta0;
 mod differentiate;
 mod distinct;
+mod error_recovery;
 mod filter_map;
+mod flatten;
 mod generator;
+mod group;
 mod index;
 mod input;
 mod integrate;
 mod join;
 mod join_range;
+#[cfg(feature = "with-json")]
+mod json;
+#[cfg(feature = "with-kafka")]
+mod kafka;
+#[cfg(feature = "with-rayon")]
+mod map_parallel;
 mod neg;
 mod output;
 mod plus;
+mod project;
+mod replay;
+mod retract;
 mod semijoin;
 mod stream_fold;
 mod sum;
@@ -34,22 +52,38 @@ mod trace;
 mod z1;
 
 #[cfg(feature = "with-csv")]
-pub use self::csv::CsvSource;
-pub use aggregate::{Aggregator, Avg, Fold, Max, MaxSemigroup, Min, MinSemigroup};
+pub use self::csv::{CsvHeaderError, CsvSink, CsvSource};
+pub use aggregate::{
+    Aggregator, All, AllSemigroup, Any, AnySemigroup, ArgMax, ArgMaxSemigroup, ArgMin,
+    ArgMinSemigroup, Avg, BitwiseAnd, BitwiseAndSemigroup, BitwiseOr, BitwiseOrSemigroup, Fold,
+    Max, MaxSemigroup, Min, MinSemigroup, Pivot, PivotSemigroup, StringAgg, StringAggSemigroup,
+    Variance, VarianceSum, XorSum,
+};
 pub use apply::Apply;
+#[cfg(feature = "with-arrow")]
+pub use arrow::ARROW_WEIGHT_COLUMN;
+pub use cogroup::CogroupCursor;
 pub use condition::Condition;
 pub use delta0::Delta0;
 pub use distinct::Distinct;
+pub use error_recovery::report_input_error;
+pub(crate) use error_recovery::{panic_message, take_operator_error, OperatorError};
 pub use filter_map::{FilterKeys, FilterMap, FilterVals, FlatMap, Map, MapKeys};
 pub use generator::{Generator, GeneratorNested};
+pub use group::NonIncrementalGroupTransformer;
 pub use index::Index;
 use input::Mailbox;
-pub use input::{CollectionHandle, InputHandle, UpsertHandle};
-pub use inspect::Inspect;
-pub use join::Join;
+pub use input::{CollectionHandle, DynCollectionHandle, InputHandle, UpsertHandle};
+pub use inspect::{AccumulateInto, Inspect};
+pub use join::{Join, MergeJoin};
 pub use join_range::StreamJoinRange;
+#[cfg(feature = "with-json")]
+pub use json::{JsonSink, JsonSource};
+#[cfg(feature = "with-kafka")]
+pub use kafka::{KafkaMessage, KafkaPartitionConsumer, KafkaSource, MessageConsumer};
 pub use neg::UnaryMinus;
 pub use output::OutputHandle;
 pub use plus::{Minus, Plus};
+pub use replay::Replay;
 pub use sum::Sum;
 pub use z1::{DelayedFeedback, DelayedNestedFeedback, Z1Nested, Z1};
@@ -0,0 +1,115 @@
+//! Projection of tuple-valued records.
+
+/// Project a stream of Z-sets of tuples onto a subset of tuple fields.
+///
+/// SQL projections over tuple-valued records are usually written as
+/// `stream.map(|r| (r.0, r.2))`.  This macro generates the projecting
+/// closure from a list of field indices.  Since the generated code accesses
+/// the fields directly, invalid indices are rejected at compile time.
+/// Fields are cloned, and weights are preserved.
+///
+/// The macro supports three forms:
+///
+/// * `project!(stream, i, j, ...)` - projects each record onto the tuple of
+///   fields `(r.i, r.j, ...)` and returns a stream of
+///   [`OrdZSet`](`crate::OrdZSet`)s, like [`map`](`crate::operator::FilterMap::map`).
+///   Projecting onto a single field yields a 1-tuple.
+///
+/// * `project!(stream, k => (i, j, ...))` - indexes the projected records
+///   by field `k` and returns a stream of
+///   [`OrdIndexedZSet`](`crate::OrdIndexedZSet`)s of `(r.k, (r.i, r.j, ...))`
+///   pairs, like [`map_index`](`crate::operator::FilterMap::map_index`).
+///
+/// * `project!(stream, (k1, k2, ...) => (i, j, ...))` - same as above, but
+///   indexes the records by a tuple of fields `(r.k1, r.k2, ...)`.
+///
+/// The result is a regular stream, so it composes with other operators, e.g.,
+/// `project!(stream.filter(|r| r.1 > 10), 0, 2)`.
+///
+/// # Example
+///
+/// ```
+/// use dbsp::{operator::FilterMap, project, OrdZSet, RootCircuit, Stream};
+///
+/// fn bids(
+///     bids: &Stream<RootCircuit, OrdZSet<(u64, u64, usize, String), isize>>,
+/// ) -> Stream<RootCircuit, OrdZSet<(u64, usize), isize>> {
+///     // SELECT auction, price FROM bids WHERE price > 100
+///     project!(bids.filter(|bid| bid.2 > 100), 0, 2)
+/// }
+/// ```
+#[macro_export]
+macro_rules! project {
+    ($stream:expr, ($($key:tt),+ $(,)?) => ($($val:tt),* $(,)?) $(,)?) => {
+        $crate::operator::FilterMap::map_index(&$stream, |record| {
+            (($(record.$key.clone(),)+), ($(record.$val.clone(),)*))
+        })
+    };
+    ($stream:expr, $key:tt => ($($val:tt),* $(,)?) $(,)?) => {
+        $crate::operator::FilterMap::map_index(&$stream, |record| {
+            (record.$key.clone(), ($(record.$val.clone(),)*))
+        })
+    };
+    ($stream:expr, $($field:tt),+ $(,)?) => {
+        $crate::operator::FilterMap::map(&$stream, |record| ($(record.$field.clone(),)+))
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{FilterMap, Generator},
+        zset, Circuit, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+    };
+
+    type Record = (u64, String, i64, bool, u32);
+
+    #[test]
+    fn project_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let input: Stream<_, OrdZSet<Record, isize>> =
+                circuit.add_source(Generator::new(|| {
+                    zset! {
+                        (1, "a".to_string(), 10, true, 100) => 1,
+                        (2, "b".to_string(), 20, false, 200) => 2,
+                        (3, "c".to_string(), 30, true, 300) => -1,
+                        (4, "a".to_string(), 10, false, 400) => 1,
+                    }
+                }));
+
+            // Plain projection preserves weights and consolidates duplicates.
+            let projected = project!(input, 1, 2);
+            let expected = input.map(|r| (r.1.clone(), r.2));
+            projected.apply2(&expected, |projected, expected| {
+                assert_eq!(projected, expected);
+                assert_eq!(
+                    projected,
+                    &zset! {
+                        ("a".to_string(), 10) => 2,
+                        ("b".to_string(), 20) => 2,
+                        ("c".to_string(), 30) => -1,
+                    }
+                );
+            });
+
+            // Projection composes with `filter`.
+            project!(input.filter(|r| r.3), 0, 4).inspect(|projected| {
+                assert_eq!(projected, &zset! { (1, 100) => 1, (3, 300) => -1 });
+            });
+
+            // Indexed projections.
+            let indexed: Stream<_, OrdIndexedZSet<u64, (i64,), isize>> =
+                project!(input, 0 => (2));
+            let expected = input.map_index(|r| (r.0, (r.2,)));
+            indexed.apply2(&expected, |indexed, expected| assert_eq!(indexed, expected));
+
+            let indexed = project!(input, (1, 3) => (0, 4));
+            let expected = input.map_index(|r| ((r.1.clone(), r.3), (r.0, r.4)));
+            indexed.apply2(&expected, |indexed, expected| assert_eq!(indexed, expected));
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+    }
+}
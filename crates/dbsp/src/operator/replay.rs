@@ -0,0 +1,142 @@
+//! Source operator that replays a recorded sequence of batches.
+
+use crate::{
+    circuit::{
+        operator_traits::{Operator, SourceOperator},
+        Scope,
+    },
+    trace::Batch,
+    Circuit, RootCircuit, Stream,
+};
+use std::{borrow::Cow, collections::VecDeque};
+
+impl RootCircuit {
+    /// Create a stream that replays a recorded sequence of batches.
+    ///
+    /// Yields `batches[0]` at the first clock cycle, `batches[1]` at the
+    /// second clock cycle, and so on.  Once all batches have been replayed,
+    /// the stream yields empty batches.
+    ///
+    /// Together with a capture of the batches a circuit received, e.g.,
+    /// using [`Stream::inspect`], this allows reproducing the exact input
+    /// sequence of one circuit in another circuit, for instance to debug an
+    /// issue observed in production in a test.
+    pub fn replay_from<B>(&self, batches: Vec<B>) -> Stream<Self, B>
+    where
+        B: Batch<Time = ()>,
+    {
+        self.add_source(Replay::new(batches))
+    }
+}
+
+/// Source operator that yields a sequence of batches followed by an
+/// infinite sequence of empty batches.
+pub struct Replay<B> {
+    batches: VecDeque<B>,
+}
+
+impl<B> Replay<B> {
+    /// Creates a source that replays `batches`.
+    pub fn new(batches: Vec<B>) -> Self {
+        Self {
+            batches: batches.into(),
+        }
+    }
+}
+
+impl<B> Operator for Replay<B>
+where
+    B: Batch<Time = ()>,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("Replay")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.batches.is_empty()
+    }
+}
+
+impl<B> SourceOperator<B> for Replay<B>
+where
+    B: Batch<Time = ()>,
+{
+    fn eval(&mut self) -> B {
+        self.batches.pop_front().unwrap_or_else(|| B::empty(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::FilterMap, trace::Batch, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    fn test_circuit(
+        input: &Stream<RootCircuit, OrdZSet<u64, isize>>,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<u64, isize, isize>> {
+        input
+            .filter(|x| x % 5 != 0)
+            .index_with(|x| (x % 3, *x))
+            .aggregate_linear(|_, x| *x as isize)
+    }
+
+    #[test]
+    fn replay_test() {
+        let inputs = vec![
+            vec![(1, 1), (2, 1), (3, 1), (5, 1)],
+            vec![],
+            vec![(2, -1), (4, 1), (7, 2)],
+            vec![(1, -1), (10, 1), (11, 1)],
+        ];
+
+        // Run the original circuit, capturing its input and output batches.
+        let captured = Rc::new(RefCell::new(Vec::new()));
+        let outputs = Rc::new(RefCell::new(Vec::new()));
+
+        let (circuit, mut input_handle) = {
+            let captured = captured.clone();
+            let outputs = outputs.clone();
+
+            RootCircuit::build(move |circuit| {
+                let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+                input.inspect(move |batch| captured.borrow_mut().push(batch.clone()));
+                test_circuit(&input).inspect(move |batch| outputs.borrow_mut().push(batch.clone()));
+                input_handle
+            })
+            .unwrap()
+        };
+
+        for mut tuples in inputs {
+            input_handle.append(&mut tuples);
+            circuit.step().unwrap();
+        }
+
+        // Replay captured inputs into a fresh circuit.
+        let captured = captured.take();
+        let replayed_outputs = Rc::new(RefCell::new(Vec::new()));
+
+        let circuit = {
+            let replayed_outputs = replayed_outputs.clone();
+
+            RootCircuit::build(move |circuit| {
+                let input = circuit.replay_from(captured);
+                test_circuit(&input)
+                    .inspect(move |batch| replayed_outputs.borrow_mut().push(batch.clone()));
+            })
+            .unwrap()
+            .0
+        };
+
+        for _ in 0..4 {
+            circuit.step().unwrap();
+        }
+        assert_eq!(*replayed_outputs.borrow(), *outputs.borrow());
+
+        // The replay source yields empty batches once exhausted.
+        circuit.step().unwrap();
+        assert_eq!(replayed_outputs.borrow().len(), 5);
+        assert_eq!(replayed_outputs.borrow()[4], OrdIndexedZSet::empty(()));
+    }
+}
@@ -0,0 +1,186 @@
+//! Operator that retracts the accumulated contents of a stream on demand.
+
+use crate::{
+    algebra::{HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{Operator, TernaryOperator},
+        OwnershipPreference, Scope,
+    },
+    operator::trace::{DelayedTraceId, IntegrateTraceId, TraceBounds, UntimedTraceAppend, Z1Trace},
+    trace::{Builder, Cursor, Spine, Trace},
+    Circuit, RootCircuit, Stream,
+};
+use std::{borrow::Cow, marker::PhantomData, ops::Neg};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+{
+    /// Forward changes in `self`, retracting all accumulated changes when
+    /// `trigger` is `true`.
+    ///
+    /// Outputs changes in `self` unmodified until `trigger` becomes `true`.
+    /// At this clock cycle, instead of the current change, the operator
+    /// outputs the negation of all changes it has forwarded so far, bringing
+    /// the integral of the output stream to zero.  This is useful to clear
+    /// downstream state, e.g., on shutdown or before a full refresh.
+    ///
+    /// Changes received during the clock cycle when `trigger` is `true` are
+    /// dropped along with the accumulated state.  After that, the operator
+    /// starts accumulating changes from scratch, so the state can be rebuilt
+    /// by new inserts and retracted again.
+    ///
+    /// The accumulated changes are stored in the integral trace of the output
+    /// stream, which is shared with other operators that call
+    /// [`integrate_trace`](`Stream::integrate_trace`) on the output.
+    pub fn retract_all(&self, trigger: &Stream<RootCircuit, bool>) -> Stream<RootCircuit, Z> {
+        let circuit = self.circuit();
+        let stream = self.try_sharded_version();
+
+        circuit.region("retract_all", || {
+            let (output_trace_delayed, z1feedback) = circuit.add_feedback(
+                <Z1Trace<Spine<Z>>>::new(false, circuit.root_scope(), TraceBounds::unbounded()),
+            );
+
+            let output = circuit.add_ternary_operator(
+                RetractAll::new(),
+                &stream,
+                trigger,
+                &output_trace_delayed,
+            );
+
+            let output_trace = circuit.add_binary_operator_with_preference(
+                <UntimedTraceAppend<Spine<Z>>>::new(),
+                (
+                    &output_trace_delayed,
+                    OwnershipPreference::STRONGLY_PREFER_OWNED,
+                ),
+                (&output, OwnershipPreference::PREFER_OWNED),
+            );
+
+            output_trace_delayed.mark_sharded_if(self);
+            output.mark_sharded_if(self);
+            output_trace.mark_sharded_if(self);
+
+            z1feedback
+                .connect_with_preference(&output_trace, OwnershipPreference::STRONGLY_PREFER_OWNED);
+
+            circuit.cache_insert(
+                DelayedTraceId::new(output_trace.origin_node_id().clone()),
+                output_trace_delayed,
+            );
+            circuit.cache_insert(
+                IntegrateTraceId::new(output.origin_node_id().clone()),
+                (output_trace, <TraceBounds<Z::Key, Z::Val>>::unbounded()),
+            );
+
+            output
+        })
+    }
+}
+
+/// Operator that forwards its input and outputs the negation of the integral
+/// of its output on demand.
+struct RetractAll<Z> {
+    _phantom: PhantomData<Z>,
+}
+
+impl<Z> RetractAll<Z> {
+    fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Z> Operator for RetractAll<Z>
+where
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("RetractAll")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, T> TernaryOperator<Z, bool, T, Z> for RetractAll<Z>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    T: Trace<Key = Z::Key, Val = Z::Val, Time = (), R = Z::R> + Clone,
+{
+    fn eval(&mut self, delta: Cow<'_, Z>, trigger: Cow<'_, bool>, trace: Cow<'_, T>) -> Z {
+        if !*trigger {
+            return delta.into_owned();
+        }
+
+        // Negate the integral of the output stream.
+        let mut builder = Z::Builder::with_capacity((), trace.len());
+        let mut cursor = trace.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let weight = cursor.weight();
+                if !weight.is_zero() {
+                    builder.push((
+                        Z::item_from(cursor.key().clone(), cursor.val().clone()),
+                        weight.neg(),
+                    ));
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{zset, Runtime};
+
+    #[test]
+    fn retract_all_test() {
+        let (mut circuit, (mut input, trigger, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            let (trigger, trigger_handle) = circuit.add_input_stream::<bool>();
+            let output = input.retract_all(&trigger).integrate().output();
+
+            (input_handle, trigger_handle, output)
+        })
+        .unwrap();
+
+        trigger.set_for_all(false);
+        input.append(&mut vec![(1, 1), (2, 2), (3, 1)]);
+        circuit.step().unwrap();
+        input.append(&mut vec![(2, -1), (4, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { 1 => 1, 2 => 1, 3 => 1, 4 => 1 }
+        );
+
+        // Retraction zeroes the integrated relation, including changes
+        // received in the same step.
+        trigger.set_for_all(true);
+        input.append(&mut vec![(5, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        // Subsequent inserts rebuild the relation from scratch.
+        trigger.set_for_all(false);
+        input.append(&mut vec![(1, 1), (6, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { 1 => 1, 6 => 1 });
+
+        trigger.set_for_all(true);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        circuit.kill().unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    algebra::ZRingValue,
+    trace::{Batch, BatchReader, Builder, Cursor},
+    DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::PrimInt;
+use std::collections::{BTreeMap, BTreeSet};
+
+impl<K, TS, V, R> Stream<RootCircuit, OrdIndexedZSet<K, (TS, V), R>>
+where
+    K: DBData,
+    TS: DBData + PrimInt,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Drop duplicate events within a trailing time window.
+    ///
+    /// The input stream consists of events `(ts, v)` indexed by key.  An
+    /// event is dropped if an event with the same key and value was seen
+    /// within the last `window` time units, i.e., at a timestamp `>= ts -
+    /// window`.  Other events are output with weight `+1`.  Dropped events
+    /// still count as seen, so a value that keeps recurring more often than
+    /// every `window` time units is only output once.
+    ///
+    /// The operator remembers the last time each value was seen for each
+    /// key.  Its watermark is the largest timestamp seen so far.  Values
+    /// that have not been seen for longer than `window` time units before
+    /// the watermark are forgotten, along with keys that have no remaining
+    /// values, which bounds the size of the state by the number of distinct
+    /// events within the window.  As a consequence, an event that arrives
+    /// more than `window` time units behind the watermark may be output
+    /// even if it duplicates an earlier event.
+    ///
+    /// Retractions in the input stream are ignored.
+    pub fn dedup_window(&self, window: TS) -> Stream<RootCircuit, OrdIndexedZSet<K, (TS, V), R>> {
+        let mut history = DedupHistory::new(window);
+
+        self.shard()
+            .apply_named(
+                "DedupWindow",
+                move |batch: &OrdIndexedZSet<K, (TS, V), R>| {
+                    let mut builder =
+                        <OrdIndexedZSet<K, (TS, V), R> as Batch>::Builder::with_capacity((), 0);
+
+                    let mut cursor = batch.cursor();
+                    while cursor.key_valid() {
+                        let key = cursor.key().clone();
+                        while cursor.val_valid() {
+                            if cursor.weight().ge0() {
+                                let (ts, val) = cursor.val();
+                                if history.insert(&key, *ts, val) {
+                                    builder.push(((key.clone(), (*ts, val.clone())), R::one()));
+                                }
+                            }
+                            cursor.step_val();
+                        }
+                        cursor.step_key();
+                    }
+                    history.gc();
+
+                    builder.done()
+                },
+            )
+            .mark_sharded()
+    }
+}
+
+/// State of the `dedup_window` operator.
+struct DedupHistory<K, TS, V> {
+    window: TS,
+    /// Largest timestamp seen so far.
+    watermark: Option<TS>,
+    /// The last time each value was seen, indexed by key.
+    keys: BTreeMap<K, BTreeMap<V, TS>>,
+    /// The same entries as `keys`, ordered by the last time they were seen.
+    by_time: BTreeSet<(TS, K, V)>,
+}
+
+impl<K, TS, V> DedupHistory<K, TS, V>
+where
+    K: Ord + Clone,
+    TS: PrimInt,
+    V: Ord + Clone,
+{
+    fn new(window: TS) -> Self {
+        Self {
+            window,
+            watermark: None,
+            keys: BTreeMap::new(),
+            by_time: BTreeSet::new(),
+        }
+    }
+
+    /// Record that `val` was seen for `key` at time `ts`.  Returns `false`
+    /// if this is a duplicate of an event within the window.
+    fn insert(&mut self, key: &K, ts: TS, val: &V) -> bool {
+        self.watermark = Some(self.watermark.map_or(ts, |watermark| watermark.max(ts)));
+
+        let values = self.keys.entry(key.clone()).or_default();
+        match values.get_mut(val) {
+            Some(last_seen) => {
+                let duplicate = ts <= last_seen.saturating_add(self.window);
+                if ts > *last_seen {
+                    self.by_time.remove(&(*last_seen, key.clone(), val.clone()));
+                    self.by_time.insert((ts, key.clone(), val.clone()));
+                    *last_seen = ts;
+                }
+                !duplicate
+            }
+            None => {
+                values.insert(val.clone(), ts);
+                self.by_time.insert((ts, key.clone(), val.clone()));
+                true
+            }
+        }
+    }
+
+    /// Forget values that fell out of the window ending at the watermark.
+    fn gc(&mut self) {
+        let window_start = match self.watermark {
+            Some(watermark) => watermark.saturating_sub(self.window),
+            None => return,
+        };
+
+        while let Some((ts, _, _)) = self.by_time.first() {
+            if *ts >= window_start {
+                break;
+            }
+
+            let (_, key, val) = self.by_time.pop_first().unwrap();
+            if let Some(values) = self.keys.get_mut(&key) {
+                values.remove(&val);
+                if values.is_empty() {
+                    self.keys.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DedupHistory;
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn dedup_window_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, (u64, u64), isize>();
+            let output = input.dedup_window(10).output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, ((0, 100), 1)),
+            (1, ((3, 100), 1)),
+            (2, ((5, 100), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (0, 100) => 1 }, 2 => { (5, 100) => 1 } }
+        );
+
+        // Duplicate within the window of the last occurrence is dropped.
+        input.append(&mut vec![(1, ((13, 100), 1)), (1, ((13, 200), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (13, 200) => 1 } }
+        );
+
+        // Duplicate after the window has passed is kept.
+        input.append(&mut vec![(1, ((24, 100), 1)), (2, ((16, 100), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! { 1 => { (24, 100) => 1 }, 2 => { (16, 100) => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    #[test]
+    fn dedup_history_gc() {
+        let mut history = DedupHistory::<u64, u64, u64>::new(10);
+
+        assert!(history.insert(&1, 0, &100));
+        assert!(history.insert(&1, 5, &200));
+        assert!(!history.insert(&1, 8, &100));
+        history.gc();
+        assert_eq!(history.keys[&1].len(), 2);
+        assert_eq!(history.by_time.len(), 2);
+
+        // Value `100` was last seen at 8 and falls out of the window ending
+        // at 19; value `200` was last seen at 5.
+        assert!(history.insert(&1, 19, &300));
+        history.gc();
+        assert_eq!(history.keys[&1].keys().collect::<Vec<_>>(), vec![&300]);
+        assert_eq!(history.by_time.len(), 1);
+
+        // Forgotten values are no longer considered duplicates.
+        assert!(history.insert(&1, 20, &100));
+    }
+
+    #[test]
+    fn dedup_history_idle_keys() {
+        let mut history = DedupHistory::<u64, u64, u64>::new(10);
+
+        for key in 0..100 {
+            assert!(history.insert(&key, key, &100));
+        }
+        history.gc();
+        assert_eq!(history.keys.len(), 11);
+        assert_eq!(history.by_time.len(), 11);
+
+        // Events of other keys advance the watermark past the window of
+        // idle keys, which are forgotten.
+        assert!(history.insert(&1000, 200, &100));
+        history.gc();
+        assert_eq!(history.keys.keys().collect::<Vec<_>>(), vec![&1000]);
+        assert_eq!(history.by_time.len(), 1);
+    }
+}
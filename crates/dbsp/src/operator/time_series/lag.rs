@@ -0,0 +1,136 @@
+use crate::{
+    algebra::ZRingValue,
+    operator::{
+        time_series::{OrdPartitionedIndexedZSet, RelOffset, RelRange},
+        FilterMap, Fold, MaxSemigroup,
+    },
+    Circuit, DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use num::{Bounded, PrimInt};
+
+impl<PK, TS, V, R> Stream<RootCircuit, OrdIndexedZSet<PK, (TS, V), R>>
+where
+    PK: DBData,
+    TS: DBData + PrimInt + Default,
+    V: DBData + Default,
+    R: DBData + ZRingValue,
+{
+    /// Pair each value in a partitioned time series with the value that was
+    /// current `delta` time units earlier.
+    ///
+    /// For each input record `(ts, v)` in partition `pk`, finds the most
+    /// recent record `(ts', v')` in the same partition such that
+    /// `ts' <= ts - delta` and outputs `(ts, (v, Some(v')))`.  If the
+    /// partition does not contain a record that is old enough, outputs
+    /// `(ts, (v, None))`.  If there are multiple values with timestamp
+    /// `ts'`, the largest of them is used.
+    ///
+    /// Unlike positional lag, which looks a fixed number of records back,
+    /// this operator is not affected by irregular sampling intervals.
+    ///
+    /// This operator is incremental: inserting or retracting a record
+    /// updates the outputs of all later records whose lagged value it
+    /// affects.
+    #[allow(clippy::type_complexity)]
+    pub fn lag_over_time(
+        &self,
+        delta: TS,
+    ) -> Stream<RootCircuit, OrdPartitionedIndexedZSet<PK, TS, (V, Option<V>), R>> {
+        self.circuit().region("lag_over_time", || {
+            // Compute the latest `(ts', v')` pair with `ts' <= ts - delta` for
+            // each timestamp `ts` in the input stream.
+            let with_times = self.map_index(|(pk, (ts, v))| (pk.clone(), (*ts, (*ts, v.clone()))));
+            let range = RelRange::new(
+                RelOffset::Before(Bounded::max_value()),
+                RelOffset::Before(delta),
+            );
+            // `Max` scans values backwards, which the partitioned cursors used
+            // by the rolling aggregate don't support.  Values are visited in
+            // ascending order, so the last one is the largest.
+            let latest = <Fold<(TS, V), MaxSemigroup<(TS, V)>, _, _>>::new(
+                Default::default(),
+                |latest: &mut (TS, V), val: &(TS, V), _weight| *latest = val.clone(),
+            );
+            let lagged = with_times
+                .partitioned_rolling_aggregate::<TS, (TS, V), _>(latest, range)
+                .map_index(|(pk, (ts, lagged))| {
+                    ((pk.clone(), *ts), lagged.as_ref().map(|(_, v)| v.clone()))
+                });
+
+            let joined = self
+                .map_index(|(pk, (ts, v))| ((pk.clone(), *ts), v.clone()))
+                .join_index(&lagged, |(pk, ts), v, lagged| {
+                    Some((pk.clone(), (*ts, (v.clone(), lagged.clone()))))
+                });
+
+            // The rolling aggregate doesn't produce outputs for timestamps
+            // whose lag range is outside the range of `TS`; these records
+            // don't have a lagged value.
+            let early = self
+                .filter(move |(_, (ts, _))| ts.checked_sub(&delta).is_none())
+                .map_index(|(pk, (ts, v))| (pk.clone(), (*ts, (v.clone(), None))));
+
+            joined.plus(&early)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, Runtime};
+
+    #[test]
+    fn lag_over_time_test() {
+        let (mut circuit, (mut input, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, (u64, i64), isize>();
+            let output = input.lag_over_time(10).integrate().output();
+
+            (input_handle, output)
+        })
+        .unwrap();
+
+        input.append(&mut vec![
+            (1, ((0, 1), 1)),
+            (1, ((5, 2), 1)),
+            (1, ((12, 3), 1)),
+            (1, ((30, 4), 1)),
+            (1, ((41, 5), 1)),
+            (2, ((100, 7), 1)),
+            (2, ((103, 8), 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (0, (1, None)) => 1,
+                    (5, (2, None)) => 1,
+                    (12, (3, Some(1))) => 1,
+                    (30, (4, Some(3))) => 1,
+                    (41, (5, Some(4))) => 1
+                },
+                2 => { (100, (7, None)) => 1, (103, (8, None)) => 1 }
+            }
+        );
+
+        // Retracting the value at time 12 changes the baseline of the value
+        // at time 30; a new value at time 35 sees the same baseline.
+        input.append(&mut vec![(1, ((12, 3), -1)), (1, ((35, 6), 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {
+                    (0, (1, None)) => 1,
+                    (5, (2, None)) => 1,
+                    (30, (4, Some(2))) => 1,
+                    (35, (6, Some(2))) => 1,
+                    (41, (5, Some(4))) => 1
+                },
+                2 => { (100, (7, None)) => 1, (103, (8, None)) => 1 }
+            }
+        );
+
+        circuit.kill().unwrap();
+    }
+}
@@ -1,7 +1,10 @@
+mod dedup;
+mod lag;
 mod partitioned;
 mod radix_tree;
 mod range;
 mod rolling_aggregate;
+mod session;
 mod watermark;
 mod window;
 
@@ -0,0 +1,238 @@
+use crate::{
+    algebra::{IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    trace::{Batch, Cursor},
+    Circuit, DBData, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+};
+use num::PrimInt;
+use std::{
+    borrow::Cow,
+    cmp::max,
+    collections::BTreeMap,
+    marker::PhantomData,
+    ops::Bound::{Excluded, Unbounded},
+};
+
+impl<PK, TS, V, R> Stream<RootCircuit, OrdIndexedZSet<PK, (TS, V), R>>
+where
+    PK: DBData,
+    TS: DBData + PrimInt,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Signal when sessions of a partitioned time series are closed.
+    ///
+    /// Groups the events of each partition `pk` into sessions: maximal sets
+    /// of events where each event is at most `gap` time units apart from the
+    /// previous one.  A session is identified by the partition and the
+    /// timestamp of its first event, `(pk, start)`.
+    ///
+    /// A session is definitively closed once it can no longer receive
+    /// events, i.e., once the watermark exceeds the timestamp of its last
+    /// event plus `gap`.  At this point, the operator outputs `(pk, start)`
+    /// with weight `+1`, so downstream operators can finalize the session.
+    /// Each session is closed exactly once.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - insert-only stream of events indexed by partition and
+    ///   timestamp.  Retractions are ignored.  Events whose timestamps are
+    ///   below the watermark at the previous clock cycle are late and are
+    ///   discarded.
+    ///
+    /// * `watermark` - monotonically growing watermark of the stream, e.g.,
+    ///   computed using
+    ///   [`watermark_monotonic`](`Stream::watermark_monotonic`).
+    ///
+    /// * `gap` - session gap.
+    pub fn emit_watermark_closing(
+        &self,
+        watermark: &Stream<RootCircuit, TS>,
+        gap: TS,
+    ) -> Stream<RootCircuit, OrdZSet<(PK, TS), R>> {
+        self.circuit().region("emit_watermark_closing", || {
+            self.circuit()
+                .add_binary_operator(SessionClosing::new(gap), &self.shard(), watermark)
+        })
+    }
+}
+
+/// Operator that tracks open sessions of each partition against the
+/// watermark.
+struct SessionClosing<Z, PK, TS> {
+    gap: TS,
+    // Watermark at the previous clock cycle.
+    watermark: Option<TS>,
+    // Open sessions of each partition as `start -> last event time` maps.
+    sessions: BTreeMap<PK, BTreeMap<TS, TS>>,
+    _type: PhantomData<Z>,
+}
+
+impl<Z, PK, TS> SessionClosing<Z, PK, TS>
+where
+    PK: Ord,
+    TS: PrimInt,
+{
+    fn new(gap: TS) -> Self {
+        Self {
+            gap,
+            watermark: None,
+            sessions: BTreeMap::new(),
+            _type: PhantomData,
+        }
+    }
+
+    /// Add an event with timestamp `ts` to `sessions`, merging the sessions
+    /// that it connects.
+    fn add_event(sessions: &mut BTreeMap<TS, TS>, ts: TS, gap: TS) {
+        let mut start = ts;
+        let mut end = ts;
+
+        // Sessions are more than `gap` apart, so the event can only connect
+        // the last session that starts before it and the first session that
+        // starts after it.
+        if let Some((&prev_start, &prev_end)) = sessions.range(..=ts).next_back() {
+            if ts <= prev_end.saturating_add(gap) {
+                sessions.remove(&prev_start);
+                start = prev_start;
+                end = max(prev_end, ts);
+            }
+        }
+        if let Some((&next_start, &next_end)) = sessions.range((Excluded(ts), Unbounded)).next() {
+            if next_start <= ts.saturating_add(gap) {
+                sessions.remove(&next_start);
+                end = max(end, next_end);
+            }
+        }
+
+        sessions.insert(start, end);
+    }
+}
+
+impl<Z, PK, TS> Operator for SessionClosing<Z, PK, TS>
+where
+    Z: 'static,
+    PK: 'static,
+    TS: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("SessionClosing")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, PK, TS, V, R> BinaryOperator<Z, TS, OrdZSet<(PK, TS), R>> for SessionClosing<Z, PK, TS>
+where
+    Z: IndexedZSet<Key = PK, Val = (TS, V), R = R>,
+    PK: DBData,
+    TS: DBData + PrimInt,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    fn eval(&mut self, events: &Z, watermark: &TS) -> OrdZSet<(PK, TS), R> {
+        let mut timestamps = Vec::new();
+
+        let mut cursor = events.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let ts = cursor.val().0;
+                let late = matches!(self.watermark, Some(watermark) if ts < watermark);
+
+                if cursor.weight().ge0() && !late {
+                    timestamps.push(ts);
+                }
+                cursor.step_val();
+            }
+
+            if !timestamps.is_empty() {
+                let sessions = self.sessions.entry(cursor.key().clone()).or_default();
+                for ts in timestamps.drain(..) {
+                    Self::add_event(sessions, ts, self.gap);
+                }
+            }
+            cursor.step_key();
+        }
+
+        // Close sessions that can no longer receive events.
+        let gap = self.gap;
+        let mut closed = Vec::new();
+        self.sessions.retain(|pk, sessions| {
+            sessions.retain(|start, end| {
+                let is_closed = *watermark > end.saturating_add(gap);
+                if is_closed {
+                    closed.push(((pk.clone(), *start), R::one()));
+                }
+                !is_closed
+            });
+            !sessions.is_empty()
+        });
+
+        self.watermark = Some(*watermark);
+
+        OrdZSet::from_keys((), closed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{zset, Runtime};
+
+    #[test]
+    fn emit_watermark_closing_test() {
+        let (mut circuit, (mut input, watermark, output)) = Runtime::init_circuit(4, |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, (u64, u64), isize>();
+            let (watermark, watermark_handle) = circuit.add_input_stream::<u64>();
+            let output = input.emit_watermark_closing(&watermark, 10).output();
+
+            (input_handle, watermark_handle, output)
+        })
+        .unwrap();
+
+        // Two sessions in partition 1: [0, 5] and [20, 20]; one in partition 2.
+        input.append(&mut vec![
+            (1, ((0, 0), 1)),
+            (1, ((5, 0), 1)),
+            (1, ((20, 0), 1)),
+            (2, ((5, 0), 1)),
+        ]);
+        watermark.set_for_all(0);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        // The watermark reaches, but doesn't exceed, the end of sessions
+        // [0, 5] plus the gap.
+        input.append(&mut vec![(1, ((31, 0), 1))]);
+        watermark.set_for_all(15);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        // An event that connects sessions [20, 20] and [31, 31] merges them.
+        input.append(&mut vec![(1, ((25, 0), 1))]);
+        watermark.set_for_all(16);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 0) => 1, (2, 5) => 1 });
+
+        // Late events are discarded and don't reopen closed sessions.
+        input.append(&mut vec![(1, ((4, 0), 1))]);
+        watermark.set_for_all(41);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        watermark.set_for_all(42);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 20) => 1 });
+
+        // Each session is closed exactly once.
+        watermark.set_for_all(100);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        circuit.kill().unwrap();
+    }
+}
@@ -10,11 +10,12 @@ use crate::{
     DBData, Timestamp,
 };
 use size_of::SizeOf;
-use std::{borrow::Cow, cell::RefCell, marker::PhantomData, ops::DerefMut, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, cmp::max, marker::PhantomData, ops::DerefMut, rc::Rc};
 
 circuit_cache_key!(TraceId<B, D, K, V>(GlobalNodeId => (Stream<B, D>, TraceBounds<K, V>)));
 circuit_cache_key!(DelayedTraceId<B, D>(GlobalNodeId => Stream<B, D>));
 circuit_cache_key!(IntegrateTraceId<B, D, K, V>(GlobalNodeId => (Stream<B, D>, TraceBounds<K, V>)));
+circuit_cache_key!(RetentionId<C, K>(GlobalNodeId => TraceBound<K>));
 
 /// Lower bound on keys or values in a trace.
 ///
@@ -75,6 +76,7 @@ where
         Self(Rc::new(RefCell::new(TraceBoundsInner {
             key_bounds: Vec::new(),
             val_bounds: Vec::new(),
+            retention: TraceBound::new(),
         })))
     }
 
@@ -84,6 +86,7 @@ where
         Self(Rc::new(RefCell::new(TraceBoundsInner {
             key_bounds: vec![TraceBound::new()],
             val_bounds: vec![TraceBound::new()],
+            retention: TraceBound::new(),
         })))
     }
 
@@ -95,14 +98,23 @@ where
         self.0.borrow_mut().val_bounds.push(bound);
     }
 
+    /// Set the retention bound of the trace (see [`Stream::with_retention`]).
+    pub(crate) fn set_retention(&self, bound: TraceBound<K>) {
+        self.0.borrow_mut().retention = bound;
+    }
+
     pub(crate) fn effective_key_bound(&self) -> Option<K> {
-        self.0
-            .borrow()
+        let inner = self.0.borrow();
+        let bound = inner
             .key_bounds
             .iter()
             .min()
             .expect("At least one trace bound must be set")
-            .get()
+            .get();
+
+        // The retention bound applies regardless of the bounds requested by
+        // individual consumers.
+        max(bound, inner.retention.get())
     }
 
     pub(crate) fn effective_val_bound(&self) -> Option<V> {
@@ -119,6 +131,39 @@ where
 struct TraceBoundsInner<K, V> {
     key_bounds: Vec<TraceBound<K>>,
     val_bounds: Vec<TraceBound<V>>,
+    retention: TraceBound<K>,
+}
+
+/// Returns the retention bound on the keys of the stream with origin
+/// `node_id`.
+///
+/// The bound is shared by all traces of the stream and is only set if the
+/// stream has been passed to [`Stream::with_retention`].
+pub(crate) fn retention_bound<C, K>(circuit: &C, node_id: &GlobalNodeId) -> TraceBound<K>
+where
+    C: Circuit,
+    K: Clone + 'static,
+{
+    circuit
+        .cache_get_or_insert_with(
+            RetentionId::<C, K>::new(node_id.clone()),
+            TraceBound::default,
+        )
+        .clone()
+}
+
+/// Makes traces of the stream with origin `to` follow the retention bound of
+/// the stream with origin `from`.
+///
+/// Used by operators whose output has the same keys as their input, e.g.,
+/// `shard`.
+pub(crate) fn share_retention<C, K>(circuit: &C, from: &GlobalNodeId, to: &GlobalNodeId)
+where
+    C: Circuit,
+    K: Clone + 'static,
+{
+    let bound = retention_bound::<C, K>(circuit, from);
+    circuit.cache_insert(RetentionId::<C, K>::new(to.clone()), bound);
 }
 
 // TODO: add infrastructure to compact the trace during slack time.
@@ -192,6 +237,7 @@ where
             || {
                 let circuit = self.circuit();
                 let bounds = TraceBounds::new();
+                bounds.set_retention(retention_bound(circuit, self.origin_node_id()));
 
                 circuit.region("trace", || {
                     let (ExportStream { local, export }, z1feedback) = circuit
@@ -258,6 +304,7 @@ where
             || {
                 let circuit = self.circuit();
                 let bounds = TraceBounds::new();
+                bounds.set_retention(retention_bound(circuit, self.origin_node_id()));
 
                 circuit.region("integrate_trace", || {
                     let (ExportStream { local, export }, z1feedback) = circuit
@@ -302,6 +349,46 @@ where
 
         trace.clone()
     }
+
+    /// Integrate `self` into a trace, discarding keys that fall below a
+    /// user-controlled retention frontier.
+    ///
+    /// At every clock cycle, the operator invokes `retention` with a mutable
+    /// reference to the current frontier.  The frontier is initially `None`,
+    /// meaning that no state can be discarded.  The callback can advance it,
+    /// e.g., to `now - 1h` for a stream indexed by time.  Keys below the
+    /// frontier are removed from the trace.  The frontier must grow
+    /// monotonically.
+    ///
+    /// The frontier applies to all traces of `self` and of its sharded
+    /// version, including the internal traces of stateful operators such as
+    /// [`join`](`Self::join`), [`distinct`](`Self::distinct`) and
+    /// [`aggregate`](`Self::aggregate`) applied to `self`, regardless of
+    /// whether these operators are created before or after calling this
+    /// method.  It overrides the bounds requested by individual consumers of
+    /// these traces, i.e., the caller promises that no consumer needs keys
+    /// below the frontier.  Updates to the frontier may be applied with a
+    /// delay of one clock cycle.  The method should be called at most once
+    /// per stream.
+    #[track_caller]
+    pub fn with_retention<F>(&self, mut retention: F) -> Stream<C, Spine<B>>
+    where
+        B: Batch,
+        Spine<B>: SizeOf,
+        F: FnMut(&mut Option<B::Key>) + 'static,
+    {
+        let bound = retention_bound(self.circuit(), self.origin_node_id());
+
+        let mut frontier = None;
+        self.apply_named("Retention", move |_| {
+            retention(&mut frontier);
+            if let Some(frontier) = &frontier {
+                bound.set(frontier.clone());
+            }
+        });
+
+        self.integrate_trace()
+    }
 }
 
 impl<C, T> Stream<C, T>
@@ -608,3 +695,111 @@ where
         OwnershipPreference::PREFER_OWNED
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::Min,
+        trace::{cursor::Cursor, ord::OrdValSpine, BatchReader, Spine},
+        OrdIndexedZSet, RootCircuit, Stream,
+    };
+    use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+    type Contents = Rc<RefCell<Vec<(usize, usize, isize)>>>;
+
+    /// Record the contents of a trace after each step, skipping tuples whose
+    /// weights have cancelled out but haven't been merged away yet.
+    fn track_contents<T>(trace: &Stream<RootCircuit, T>) -> Contents
+    where
+        T: BatchReader<Key = usize, Val = usize, Time = (), R = isize> + Clone + 'static,
+    {
+        let contents = Contents::default();
+        let contents_clone = contents.clone();
+
+        trace.apply(move |trace| {
+            let mut cursor = trace.cursor();
+            let mut tuples = Vec::new();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    let weight = cursor.weight();
+                    if weight != 0 {
+                        tuples.push((*cursor.key(), *cursor.val(), weight));
+                    }
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+            *contents_clone.borrow_mut() = tuples;
+        });
+
+        contents
+    }
+
+    #[test]
+    fn retention_test() {
+        let (circuit, (mut input, traces)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            // Stateful operators created before and after the retention
+            // bound is registered.
+            input.distinct();
+
+            // Retain the last 20 time units.
+            let mut now: usize = 0;
+            let retained = input.with_retention(move |frontier| {
+                *frontier = now.checked_sub(20);
+                now += 10;
+            });
+
+            input.join(&input, |k, v1, v2| (*k, *v1, *v2));
+            input.aggregate(Min);
+
+            let traces = vec![
+                // The trace returned by `with_retention`, also used by
+                // `distinct`.
+                track_contents(&retained),
+                // Used by `distinct`.
+                track_contents(&input.trace::<OrdValSpine<usize, usize, (), isize>>()),
+                // Used by `join` and `aggregate`.
+                track_contents(&input.trace::<Spine<OrdIndexedZSet<usize, usize, isize>>>()),
+            ];
+
+            (input_handle, traces)
+        })
+        .unwrap();
+
+        let mut expected = BTreeMap::new();
+
+        for step in 0..10 {
+            let mut changes = vec![(step * 10, (1, 1)), (step * 10 + 5, (2, 1))];
+            // Update keys that are still inside the retention window.
+            if step > 0 {
+                changes.push((step * 10 - 10, (1, 1)));
+                changes.push((step * 10 - 5, (2, -1)));
+                changes.push((step * 10 - 5, (3, 1)));
+            }
+            for &(k, (v, w)) in changes.iter() {
+                *expected.entry((k, v)).or_insert(0) += w;
+            }
+            expected.retain(|_, w| *w != 0);
+
+            input.append(&mut changes);
+            circuit.step().unwrap();
+
+            // The frontier set at the previous step, i.e., `10 * (step - 1) -
+            // 20`, is applied at the current step.  Keys below it are
+            // discarded, while keys inside the window contain exactly the
+            // integral of their updates.
+            let frontier = (step * 10).saturating_sub(30);
+            let expected = expected
+                .iter()
+                .filter(|((k, _), _)| *k >= frontier)
+                .map(|(&(k, v), &w)| (k, v, w))
+                .collect::<Vec<_>>();
+
+            for contents in traces.iter() {
+                assert_eq!(*contents.borrow(), expected, "step {step}");
+            }
+        }
+    }
+}
@@ -4,14 +4,16 @@ use crate::{
         operator_traits::{BinaryOperator, Operator},
         ExportId, ExportStream, OwnershipPreference, Scope, WithClock,
     },
-    operator::trace::{DelayedTraceId, TraceAppend, TraceBounds, TraceId, Z1Trace},
+    operator::trace::{
+        retention_bound, DelayedTraceId, TraceAppend, TraceBounds, TraceId, Z1Trace,
+    },
     trace::{
         consolidation::consolidate, cursor::Cursor, Batch, BatchReader, Builder, Spine, Trace,
     },
     utils::VecExt,
-    Circuit, DBData, DBTimestamp, Stream, Timestamp,
+    Circuit, DBData, DBTimestamp, RootCircuit, Stream, Timestamp,
 };
-use std::{borrow::Cow, marker::PhantomData, ops::Neg};
+use std::{borrow::Cow, collections::BTreeMap, marker::PhantomData, ops::Neg};
 
 impl<C, K, V> Stream<C, Vec<(K, Option<V>)>>
 where
@@ -64,6 +66,7 @@ where
         // ```
         circuit.region("upsert", || {
             let bounds = <TraceBounds<K, V>>::unbounded();
+            bounds.set_retention(retention_bound(circuit, self.origin_node_id()));
 
             let (ExportStream { local, export }, z1feedback) = circuit.add_feedback_with_export(
                 Z1Trace::new(false, circuit.root_scope(), bounds.clone()),
@@ -107,6 +110,59 @@ where
     }
 }
 
+impl<K, V> Stream<RootCircuit, Vec<(K, Option<V>)>>
+where
+    K: DBData,
+    V: DBData,
+{
+    /// Drop upserts that don't change the value of their key.
+    ///
+    /// Operators that recompute a value for a key, such as non-linear
+    /// aggregates, may re-emit the value the key already has.  Downstream,
+    /// such an upsert turns into a retraction of the old value immediately
+    /// canceled by an insertion of the same value, i.e., no change at all,
+    /// but still costs a trace lookup in [`Self::upsert`] and any operators
+    /// in between.  This operator remembers the last value emitted for each
+    /// key and filters out upserts that assign a key its current value or
+    /// remove a key that is not present.
+    ///
+    /// The output is a subset of the input, so it retains the ordering
+    /// guarantees expected by `upsert`.
+    pub fn emit_on_change_only(&self) -> Self {
+        let mut current: BTreeMap<K, V> = BTreeMap::new();
+
+        let output = self.apply_named("EmitOnChangeOnly", move |upserts| {
+            let mut changed = Vec::with_capacity(upserts.len());
+
+            for (key, val) in upserts.iter() {
+                let is_change = match val {
+                    Some(val) => match current.get_mut(key) {
+                        Some(old) if old == val => false,
+                        Some(old) => {
+                            *old = val.clone();
+                            true
+                        }
+                        None => {
+                            current.insert(key.clone(), val.clone());
+                            true
+                        }
+                    },
+                    None => current.remove(key).is_some(),
+                };
+
+                if is_change {
+                    changed.push((key.clone(), val.clone()));
+                }
+            }
+
+            changed
+        });
+
+        output.mark_sharded_if(self);
+        output
+    }
+}
+
 pub struct Upsert<T, B>
 where
     T: BatchReader,
@@ -214,3 +270,52 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{operator::Generator, Circuit, OrdIndexedZSet, RootCircuit};
+
+    #[test]
+    fn emit_on_change_only_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            // Upserts with some values re-emitted unchanged, e.g., by an
+            // aggregate recomputing the same result.
+            let mut inputs = vec![
+                vec![(1, Some(10)), (2, Some(20))],
+                vec![(1, Some(10)), (2, Some(21))],
+                vec![(1, None), (3, None)],
+                vec![(1, Some(10)), (2, Some(21))],
+                vec![(1, None), (2, None)],
+            ]
+            .into_iter();
+
+            let mut expected_changes = vec![
+                vec![(1, Some(10)), (2, Some(20))],
+                vec![(2, Some(21))],
+                vec![(1, None)],
+                vec![(1, Some(10))],
+                vec![(1, None), (2, None)],
+            ]
+            .into_iter();
+
+            let upserts = circuit.add_source(Generator::new(move || inputs.next().unwrap()));
+            let changes = upserts.emit_on_change_only();
+
+            changes.inspect(move |changes: &Vec<(u64, Option<u64>)>| {
+                assert_eq!(*changes, expected_changes.next().unwrap())
+            });
+
+            // Suppressed upserts did not affect the resulting updates.
+            changes.upsert::<OrdIndexedZSet<u64, u64, isize>>().apply2(
+                &upserts.upsert::<OrdIndexedZSet<u64, u64, isize>>(),
+                |filtered, all| assert_eq!(filtered, all),
+            );
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..5 {
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Histogram of latencies with bounded relative error.
+
+use std::time::Duration;
+
+/// Number of bits of the mantissa used to split each power-of-two range of
+/// latencies into sub-buckets.  With 3 bits, each range is split into 8
+/// buckets, bounding the relative error of reported percentiles by 12.5%.
+const SUB_BUCKET_BITS: u32 = 3;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// Number of buckets needed to cover all `u64` nanosecond values.
+const NUM_BUCKETS: usize = ((64 - SUB_BUCKET_BITS + 1) as usize) << SUB_BUCKET_BITS;
+
+/// Histogram of latencies.
+///
+/// Latencies are recorded with nanosecond resolution into log-scale
+/// buckets, so that the histogram uses constant space regardless of the
+/// number and range of recorded values, while percentiles are reported
+/// with a relative error of at most 12.5%.
+#[derive(Clone, Debug)]
+pub struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    /// Create an empty histogram.
+    pub fn new() -> Self {
+        Self {
+            buckets: vec![0; NUM_BUCKETS],
+            count: 0,
+            total: Duration::ZERO,
+            min: Duration::MAX,
+            max: Duration::ZERO,
+        }
+    }
+
+    /// Record a single latency measurement.
+    pub fn record(&mut self, latency: Duration) {
+        self.buckets[bucket_index(duration_nanos(latency))] += 1;
+        self.count += 1;
+        self.total = self.total.saturating_add(latency);
+        self.min = self.min.min(latency);
+        self.max = self.max.max(latency);
+    }
+
+    /// Forget all recorded measurements.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Number of recorded measurements.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Smallest recorded latency or `None` if the histogram is empty.
+    pub fn min(&self) -> Option<Duration> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Largest recorded latency or `None` if the histogram is empty.
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Average recorded latency or `None` if the histogram is empty.
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0)
+            .then(|| Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64))
+    }
+
+    /// Approximate latency below which `percentile` percent of
+    /// measurements fall, or `None` if the histogram is empty.
+    ///
+    /// The result is an upper bound of the exact percentile, which exceeds
+    /// it by at most 12.5%, and never exceeds the largest recorded latency.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not in the range `[0, 100]`.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "percentile {percentile} is out of range [0, 100]"
+        );
+
+        if self.count == 0 {
+            return None;
+        }
+
+        // Rank of the measurement to report, counting from 1.
+        let rank = ((percentile / 100.0 * self.count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let upper = Duration::from_nanos(bucket_upper_bound(index));
+                return Some(upper.clamp(self.min, self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+}
+
+fn duration_nanos(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+/// Index of the bucket that `nanos` belongs to.
+///
+/// Values below `SUB_BUCKETS` have dedicated buckets.  Larger values are
+/// grouped by their most significant bit, and further split by the next
+/// `SUB_BUCKET_BITS` bits.
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS {
+        return nanos as usize;
+    }
+
+    let exponent = 63 - nanos.leading_zeros();
+    let mantissa = (nanos >> (exponent - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+
+    (((exponent - SUB_BUCKET_BITS + 1) as usize) << SUB_BUCKET_BITS) + mantissa as usize
+}
+
+/// Smallest value that belongs to bucket `index`.
+fn bucket_lower_bound(index: usize) -> u64 {
+    if (index as u64) < SUB_BUCKETS {
+        return index as u64;
+    }
+
+    let exponent = (index >> SUB_BUCKET_BITS) as u32 + SUB_BUCKET_BITS - 1;
+    let mantissa = index as u64 & (SUB_BUCKETS - 1);
+
+    (SUB_BUCKETS + mantissa) << (exponent - SUB_BUCKET_BITS)
+}
+
+/// Largest value that belongs to bucket `index`.
+fn bucket_upper_bound(index: usize) -> u64 {
+    if index + 1 == NUM_BUCKETS {
+        u64::MAX
+    } else {
+        bucket_lower_bound(index + 1) - 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        bucket_index, bucket_lower_bound, bucket_upper_bound, LatencyHistogram, NUM_BUCKETS,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn bucket_bounds() {
+        for index in 0..NUM_BUCKETS {
+            assert_eq!(bucket_index(bucket_lower_bound(index)), index);
+            assert_eq!(bucket_index(bucket_upper_bound(index)), index);
+        }
+        assert_eq!(bucket_index(u64::MAX), NUM_BUCKETS - 1);
+
+        // Relative error is bounded by 1/8.
+        for nanos in [9, 100, 12_345, 1_000_000_007, u64::MAX / 3] {
+            let upper = bucket_upper_bound(bucket_index(nanos));
+            assert!(upper >= nanos);
+            assert!((upper - nanos) as f64 <= nanos as f64 / 8.0);
+        }
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.percentile(50.0), None);
+        assert_eq!(histogram.max(), None);
+
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.min(), Some(Duration::from_micros(1)));
+        assert_eq!(histogram.max(), Some(Duration::from_micros(1000)));
+        assert_eq!(histogram.mean(), Some(Duration::from_nanos(500_500)));
+        assert_eq!(
+            histogram.percentile(100.0),
+            Some(Duration::from_micros(1000))
+        );
+
+        for (percentile, exact) in [(0.0, 1), (50.0, 500), (90.0, 900), (99.0, 990)] {
+            let exact = Duration::from_micros(exact);
+            let reported = histogram.percentile(percentile).unwrap();
+            assert!(reported >= exact);
+            assert!(reported <= exact + exact / 8);
+        }
+
+        histogram.reset();
+        assert_eq!(histogram.count(), 0);
+    }
+}
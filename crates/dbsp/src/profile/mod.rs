@@ -12,7 +12,10 @@ use crate::{
 use std::{borrow::Cow, collections::HashMap, fmt::Write};
 
 mod cpu;
+mod latency;
+
 pub use cpu::CPUProfiler;
+pub use latency::LatencyHistogram;
 
 /// Rudimentary circuit profiler.
 ///
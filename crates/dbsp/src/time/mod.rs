@@ -3,6 +3,7 @@
 mod antichain;
 mod nested_ts32;
 mod product;
+mod product3;
 
 use crate::{
     algebra::{Lattice, PartialOrder},
@@ -16,6 +17,7 @@ use std::{fmt::Debug, hash::Hash};
 pub use antichain::{Antichain, AntichainRef};
 pub use nested_ts32::NestedTimestamp32;
 pub use product::Product;
+pub use product3::Product3;
 
 /// Logical timestamp.
 ///
@@ -244,7 +246,9 @@ impl Timestamp for u32 {
         0
     }
     fn advance(&self, _scope: Scope) -> Self {
-        self + 1
+        // Saturate instead of wrapping around, which would break the
+        // ordering of timestamps.
+        self.saturating_add(1)
     }
     fn recede(&self, _scope: Scope) -> Self {
         self - 1
@@ -259,3 +263,66 @@ impl Timestamp for u32 {
         Self::MAX
     }
 }
+
+/// Flat timestamp for circuits whose clock represents real time, e.g., event
+/// time in milliseconds since the epoch.
+///
+/// Like `u32`, `u64` only models a single scope, so the `scope` argument is
+/// ignored by all methods.
+impl Timestamp for u64 {
+    type Nested = NestedTimestamp32;
+
+    type OrdValBatch<K: DBData, V: DBData, R: DBWeight> = OrdValBatch<K, V, Self, R>;
+
+    fn minimum() -> Self {
+        0
+    }
+    fn advance(&self, _scope: Scope) -> Self {
+        // Saturate instead of wrapping around, which would break the
+        // ordering of timestamps.
+        self.saturating_add(1)
+    }
+    fn checked_recede(&self, _scope: Scope) -> Option<Self> {
+        self.checked_sub(1)
+    }
+    fn epoch_start(&self, _scope: Scope) -> Self {
+        0
+    }
+    fn epoch_end(&self, _scope: Scope) -> Self {
+        Self::MAX
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Timestamp;
+    use crate::algebra::{Lattice, PartialOrder};
+
+    #[test]
+    fn u64_timestamp() {
+        let time: u64 = 1_000;
+
+        assert_eq!(time.advance(0), 1_001);
+        assert_eq!(time.recede(0), 999);
+        assert_eq!(time.advance(0).recede(0), time);
+        assert_eq!(u64::MAX.advance(0), u64::MAX);
+
+        assert_eq!(u64::minimum(), 0);
+        assert_eq!(u64::clock_start(), 0);
+        assert_eq!(0u64.checked_recede(0), None);
+        assert_eq!(time.checked_recede(0), Some(999));
+
+        assert_eq!(time.epoch_start(0), 0);
+        assert_eq!(time.epoch_end(0), u64::MAX);
+
+        assert!(time.less_equal(&time.advance(0)));
+        assert_eq!(time.join(&5_000), 5_000);
+        assert_eq!(time.meet(&5_000), 1_000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn u64_recede_below_zero() {
+        0u64.recede(0);
+    }
+}
@@ -91,11 +91,22 @@ where
         Self::new(TOuter::clock_start(), TInner::clock_start())
     }
 
+    /// Advance the clock at the specified nesting level.
+    ///
+    /// Clocks that cannot represent the next clock tick saturate at their
+    /// largest value.  If the outer clock saturates, the inner clock is
+    /// moved to the end of its epoch instead of being reset, so that the
+    /// resulting timestamp never precedes `self`.
     fn advance(&self, scope: Scope) -> Self {
         if scope == 0 {
             Self::new(self.outer.clone(), self.inner.advance(0))
         } else {
-            Self::new(self.outer.advance(scope - 1), TInner::minimum())
+            let outer = self.outer.advance(scope - 1);
+            if outer == self.outer {
+                Self::new(outer, self.inner.epoch_end(0))
+            } else {
+                Self::new(outer, TInner::minimum())
+            }
         }
     }
 
@@ -135,3 +146,57 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Product;
+    use crate::{
+        algebra::{Lattice, PartialOrder},
+        time::Timestamp,
+    };
+
+    #[test]
+    fn advance_saturates() {
+        // Inner clock saturates instead of wrapping around.
+        let ts0 = Product::new(1u32, u32::MAX - 1);
+        let ts1 = ts0.advance(0);
+        let ts2 = ts1.advance(0);
+        assert_eq!(ts1, Product::new(1, u32::MAX));
+        assert_eq!(ts2, Product::new(1, u32::MAX));
+        assert_eq!(ts2, ts0.epoch_end(0));
+        assert!(ts0.less_equal(&ts1));
+        assert!(ts1.less_equal(&ts2));
+
+        // Once the outer clock saturates, advancing it doesn't reset the
+        // inner clock to a smaller value.
+        let ts3 = Product::new(u32::MAX - 1, 5u32).advance(1);
+        let ts4 = ts3.advance(1);
+        assert_eq!(ts3, Product::new(u32::MAX, 0));
+        assert_eq!(ts4, Product::new(u32::MAX, u32::MAX));
+        assert!(ts3.less_equal(&ts4));
+        assert_eq!(ts4.advance(0), ts4);
+        assert_eq!(ts4.advance(1), ts4);
+
+        // Lattice operations are consistent with the partial order at the
+        // boundary.
+        assert_eq!(ts2.join(&ts3), Product::new(u32::MAX, u32::MAX));
+        assert_eq!(ts2.meet(&ts3), Product::new(1, 0));
+        for ts in [ts0, ts1, ts2, ts3] {
+            assert!(ts.less_equal(&ts.join(&ts4)));
+            assert!(ts.meet(&ts4).less_equal(&ts));
+            assert_eq!(ts.join(&ts4), ts4);
+            assert_eq!(ts.meet(&ts4), ts);
+        }
+
+        // Nested products saturate at every level.
+        let nested = Product::new(Product::new(u32::MAX, u32::MAX), 3u32);
+        assert_eq!(
+            nested.advance(1),
+            Product::new(Product::new(u32::MAX, u32::MAX), u32::MAX)
+        );
+        assert_eq!(
+            nested.advance(2),
+            Product::new(Product::new(u32::MAX, u32::MAX), u32::MAX)
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    algebra::{Lattice, PartialOrder},
+    circuit::Scope,
+    time::{Product, Timestamp},
+    trace::ord::OrdValBatch,
+    DBData, DBTimestamp, DBWeight,
+};
+use size_of::SizeOf;
+use std::fmt::{Debug, Display, Formatter};
+
+/// A timestamp with three coordinates, for circuits with two levels of
+/// nesting.
+///
+/// `Product3<A, B, C>` behaves exactly like `Product<Product<A, B>, C>`, but
+/// stores its coordinates flat and formats them as `(a, b, c)` instead of
+/// `((a, b), c)`.
+#[derive(
+    Copy,
+    Clone,
+    Hash,
+    Eq,
+    PartialEq,
+    Default,
+    Ord,
+    PartialOrd,
+    SizeOf,
+    bincode::Decode,
+    bincode::Encode,
+)]
+pub struct Product3<TOuter, TMiddle, TInner> {
+    /// Outer timestamp.
+    pub outer: TOuter,
+    /// Middle timestamp.
+    pub middle: TMiddle,
+    /// Inner timestamp.
+    pub inner: TInner,
+}
+
+impl<TOuter, TMiddle, TInner> Product3<TOuter, TMiddle, TInner> {
+    /// Creates a new product from outer, middle, and inner coordinates.
+    pub fn new(outer: TOuter, middle: TMiddle, inner: TInner) -> Self {
+        Product3 {
+            outer,
+            middle,
+            inner,
+        }
+    }
+}
+
+impl<TOuter, TMiddle, TInner> From<Product<Product<TOuter, TMiddle>, TInner>>
+    for Product3<TOuter, TMiddle, TInner>
+{
+    fn from(product: Product<Product<TOuter, TMiddle>, TInner>) -> Self {
+        Self::new(product.outer.outer, product.outer.inner, product.inner)
+    }
+}
+
+impl<TOuter, TMiddle, TInner> From<Product3<TOuter, TMiddle, TInner>>
+    for Product<Product<TOuter, TMiddle>, TInner>
+{
+    fn from(product: Product3<TOuter, TMiddle, TInner>) -> Self {
+        Product::new(Product::new(product.outer, product.middle), product.inner)
+    }
+}
+
+impl<T1: Lattice, T2: Lattice, T3: Lattice> Lattice for Product3<T1, T2, T3> {
+    #[inline]
+    fn join(&self, other: &Self) -> Self {
+        Product3 {
+            outer: self.outer.join(&other.outer),
+            middle: self.middle.join(&other.middle),
+            inner: self.inner.join(&other.inner),
+        }
+    }
+
+    #[inline]
+    fn meet(&self, other: &Self) -> Self {
+        Product3 {
+            outer: self.outer.meet(&other.outer),
+            middle: self.middle.meet(&other.middle),
+            inner: self.inner.meet(&other.inner),
+        }
+    }
+}
+
+/// Debug implementation to avoid seeing fully qualified path names.
+impl<TOuter: Debug, TMiddle: Debug, TInner: Debug> Debug for Product3<TOuter, TMiddle, TInner> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(&format!(
+            "({:?}, {:?}, {:?})",
+            self.outer, self.middle, self.inner
+        ))
+    }
+}
+
+impl<TOuter: Display, TMiddle: Display, TInner: Display> Display
+    for Product3<TOuter, TMiddle, TInner>
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str(&format!(
+            "({}, {}, {})",
+            self.outer, self.middle, self.inner
+        ))
+    }
+}
+
+impl<TOuter: PartialOrder, TMiddle: PartialOrder, TInner: PartialOrder> PartialOrder
+    for Product3<TOuter, TMiddle, TInner>
+{
+    #[inline(always)]
+    fn less_equal(&self, other: &Self) -> bool {
+        self.outer.less_equal(&other.outer)
+            && self.middle.less_equal(&other.middle)
+            && self.inner.less_equal(&other.inner)
+    }
+}
+
+impl<TOuter, TMiddle, TInner> Timestamp for Product3<TOuter, TMiddle, TInner>
+where
+    TOuter: DBTimestamp,
+    TMiddle: DBTimestamp,
+    TInner: DBTimestamp,
+{
+    type Nested = Product<Self, u32>;
+
+    type OrdValBatch<K: DBData, V: DBData, R: DBWeight> = OrdValBatch<K, V, Self, R>;
+
+    fn minimum() -> Self {
+        Self::new(TOuter::minimum(), TMiddle::minimum(), TInner::minimum())
+    }
+
+    fn clock_start() -> Self {
+        Self::new(
+            TOuter::clock_start(),
+            TMiddle::clock_start(),
+            TInner::clock_start(),
+        )
+    }
+
+    /// Advance the clock at the specified nesting level.
+    ///
+    /// Like [`Product::advance`], clocks that cannot represent the next clock
+    /// tick saturate at their largest value, in which case the clocks at
+    /// deeper nesting levels are moved to the end of their epochs instead of
+    /// being reset.
+    fn advance(&self, scope: Scope) -> Self {
+        match scope {
+            0 => Self::new(
+                self.outer.clone(),
+                self.middle.clone(),
+                self.inner.advance(0),
+            ),
+            1 => {
+                let middle = self.middle.advance(0);
+                if middle == self.middle {
+                    Self::new(self.outer.clone(), middle, self.inner.epoch_end(0))
+                } else {
+                    Self::new(self.outer.clone(), middle, TInner::minimum())
+                }
+            }
+            _ => {
+                let outer = self.outer.advance(scope - 2);
+                if outer == self.outer {
+                    let middle = self.middle.epoch_end(0);
+                    if middle == self.middle {
+                        Self::new(outer, middle, self.inner.epoch_end(0))
+                    } else {
+                        Self::new(outer, middle, TInner::minimum())
+                    }
+                } else {
+                    Self::new(outer, TMiddle::minimum(), TInner::minimum())
+                }
+            }
+        }
+    }
+
+    fn recede(&self, scope: Scope) -> Self {
+        match scope {
+            0 => Self::new(
+                self.outer.clone(),
+                self.middle.clone(),
+                self.inner.recede(0),
+            ),
+            1 => Self::new(
+                self.outer.clone(),
+                self.middle.recede(0),
+                self.inner.clone(),
+            ),
+            _ => Self::new(
+                self.outer.recede(scope - 2),
+                self.middle.clone(),
+                self.inner.clone(),
+            ),
+        }
+    }
+
+    fn checked_recede(&self, scope: Scope) -> Option<Self> {
+        match scope {
+            0 => self
+                .inner
+                .checked_recede(0)
+                .map(|inner| Self::new(self.outer.clone(), self.middle.clone(), inner)),
+            1 => self
+                .middle
+                .checked_recede(0)
+                .map(|middle| Self::new(self.outer.clone(), middle, self.inner.clone())),
+            _ => self
+                .outer
+                .checked_recede(scope - 2)
+                .map(|outer| Self::new(outer, self.middle.clone(), self.inner.clone())),
+        }
+    }
+
+    fn epoch_start(&self, scope: Scope) -> Self {
+        match scope {
+            0 => Self::new(self.outer.clone(), self.middle.clone(), TInner::minimum()),
+            1 => Self::new(self.outer.clone(), TMiddle::minimum(), TInner::minimum()),
+            _ => Self::new(
+                self.outer.epoch_start(scope - 2),
+                TMiddle::minimum(),
+                TInner::minimum(),
+            ),
+        }
+    }
+
+    fn epoch_end(&self, scope: Scope) -> Self {
+        match scope {
+            0 => Self::new(
+                self.outer.clone(),
+                self.middle.clone(),
+                self.inner.epoch_end(0),
+            ),
+            1 => Self::new(
+                self.outer.clone(),
+                self.middle.epoch_end(0),
+                self.inner.epoch_end(0),
+            ),
+            _ => Self::new(
+                self.outer.epoch_end(scope - 2),
+                self.middle.epoch_end(0),
+                self.inner.epoch_end(0),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Product3;
+    use crate::{
+        algebra::{Lattice, PartialOrder},
+        time::{Product, Timestamp},
+    };
+
+    type Nested = Product<Product<u32, u32>, u32>;
+
+    fn timestamps() -> Vec<Product3<u32, u32, u32>> {
+        let coordinates = [0, 1, 5, u32::MAX - 1, u32::MAX];
+
+        let mut result = Vec::new();
+        for outer in coordinates {
+            for middle in coordinates {
+                for inner in coordinates {
+                    result.push(Product3::new(outer, middle, inner));
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn matches_nested_product() {
+        let timestamps = timestamps();
+
+        for &ts1 in timestamps.iter() {
+            let nested1 = Nested::from(ts1);
+            assert_eq!(Product3::from(nested1), ts1);
+
+            for &ts2 in timestamps.iter() {
+                let nested2 = Nested::from(ts2);
+
+                assert_eq!(ts1.cmp(&ts2), nested1.cmp(&nested2));
+                assert_eq!(ts1.less_equal(&ts2), nested1.less_equal(&nested2));
+                assert_eq!(Nested::from(ts1.join(&ts2)), nested1.join(&nested2));
+                assert_eq!(Nested::from(ts1.meet(&ts2)), nested1.meet(&nested2));
+            }
+
+            for scope in 0..3 {
+                assert_eq!(Nested::from(ts1.advance(scope)), nested1.advance(scope));
+                assert_eq!(
+                    ts1.checked_recede(scope).map(Nested::from),
+                    nested1.checked_recede(scope)
+                );
+                assert_eq!(
+                    Nested::from(ts1.epoch_start(scope)),
+                    nested1.epoch_start(scope)
+                );
+                assert_eq!(Nested::from(ts1.epoch_end(scope)), nested1.epoch_end(scope));
+            }
+        }
+
+        assert_eq!(
+            Nested::from(Product3::<u32, u32, u32>::clock_start()),
+            Nested::clock_start()
+        );
+    }
+
+    #[test]
+    fn advance_and_recede() {
+        let ts = Product3::new(1u32, 2u32, 3u32);
+
+        assert_eq!(ts.advance(0), Product3::new(1, 2, 4));
+        assert_eq!(ts.advance(1), Product3::new(1, 3, 0));
+        assert_eq!(ts.advance(2), Product3::new(2, 0, 0));
+        assert_eq!(ts.recede(0), Product3::new(1, 2, 2));
+        assert_eq!(ts.recede(1), Product3::new(1, 1, 3));
+        assert_eq!(ts.recede(2), Product3::new(0, 2, 3));
+        assert_eq!(Product3::new(0u32, 2u32, 3u32).checked_recede(2), None);
+    }
+
+    #[test]
+    fn formatting() {
+        let ts = Product3::new(1u32, 2u32, 3u32);
+
+        assert_eq!(format!("{ts:?}"), "(1, 2, 3)");
+        assert_eq!(format!("{ts}"), "(1, 2, 3)");
+        assert_eq!(format!("{:?}", Nested::from(ts)), "((1, 2), 3)");
+    }
+}
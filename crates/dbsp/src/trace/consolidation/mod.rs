@@ -15,15 +15,23 @@ pub mod utils;
 
 use crate::{
     algebra::{AddAssignByRef, HasZero, MonoidValue},
+    default_hash,
     utils::assume,
+    Runtime,
 };
 use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    hash::Hash,
     mem::{replace, size_of},
     ops::AddAssign,
     ptr,
 };
 use utils::{dedup_payload_starting_at, retain_payload_starting_at, retain_starting_at};
 
+#[cfg(feature = "with-rayon")]
+use rayon::prelude::*;
+
 /// Sorts and consolidates `vec`.
 ///
 /// This method will sort `vec` and then consolidate runs of more than one entry
@@ -51,6 +59,168 @@ where
     vec.retain(|(_, data)| !data.is_zero());
 }
 
+/// Sorts and consolidates `vec`, dropping merged elements eagerly.
+///
+/// Computes the same result as [`consolidate`], but combines compaction and
+/// the removal of zero weights into a single pass over the sorted vector,
+/// which drops each element as soon as it is merged into the preceding
+/// element with the same key or its accumulated weight turns out to be
+/// zero.  Unlike [`consolidate_slice`], which swaps such elements to the
+/// end of the slice, this releases resources owned by the elements, e.g.,
+/// large keys, while consolidation is still in progress.
+///
+/// If comparing keys or adding weights panics, the elements of `vec` are
+/// leaked.
+pub fn consolidate_vec_dropping<T, R>(vec: &mut Vec<(T, R)>)
+where
+    T: Ord,
+    R: HasZero + AddAssign,
+{
+    if vec.is_empty() {
+        return;
+    }
+
+    vec.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+
+    let len = vec.len();
+    let ptr = vec.as_mut_ptr();
+
+    // Elements are moved out of the vector while we traverse it, so the
+    // vector doesn't own any of them until the end of consolidation.
+    unsafe { vec.set_len(0) };
+
+    // Elements in `[0, write)` are consolidated, elements in `[read, len)`
+    // haven't been visited yet and everything in between has been moved out.
+    let mut write = 0;
+    let mut read = 0;
+    while read < len {
+        unsafe {
+            let (key, mut diff) = ptr::read(ptr.add(read));
+            read += 1;
+
+            while read < len && (*ptr.add(read)).0 == key {
+                // Drops the duplicate key.
+                let (_, next_diff) = ptr::read(ptr.add(read));
+                read += 1;
+                diff += next_diff;
+            }
+
+            if !diff.is_zero() {
+                ptr::write(ptr.add(write), (key, diff));
+                write += 1;
+            }
+        }
+    }
+
+    unsafe { vec.set_len(write) };
+}
+
+/// Sorts and consolidates `vec` using multiple threads.
+///
+/// Computes the same result as [`consolidate`].  When called from a worker
+/// thread of a [`Runtime`] with more than one worker, splits `vec` into one
+/// partition per worker by the hash of its keys, sorts and consolidates the
+/// partitions in parallel, and merges the results.  Otherwise, falls back to
+/// [`consolidate`].
+///
+/// Partitions are consolidated on the global `rayon` thread pool when the
+/// `with-rayon` feature is enabled and on scoped threads otherwise.
+pub fn consolidate_parallel<T, R>(vec: &mut Vec<(T, R)>)
+where
+    T: Ord + Hash + Send,
+    R: MonoidValue + Send,
+{
+    match Runtime::runtime() {
+        Some(runtime) if runtime.num_workers() > 1 => {
+            consolidate_partitioned(vec, runtime.num_workers())
+        }
+        _ => consolidate(vec),
+    }
+}
+
+/// The innards of `consolidate_parallel()`, which split `vec` into
+/// `partitions` partitions regardless of the current runtime.
+// Public for benchmarks
+#[doc(hidden)]
+pub fn consolidate_partitioned<T, R>(vec: &mut Vec<(T, R)>, partitions: usize)
+where
+    T: Ord + Hash + Send,
+    R: MonoidValue + Send,
+{
+    if partitions <= 1 || vec.len() < partitions {
+        consolidate(vec);
+        return;
+    }
+
+    // Equal keys land in the same partition, so partitions can be consolidated
+    // independently.
+    let mut parts: Vec<Vec<(T, R)>> = (0..partitions)
+        .map(|_| Vec::with_capacity(vec.len() / partitions + 1))
+        .collect();
+    for (key, diff) in vec.drain(..) {
+        let part = (default_hash(&key) % partitions as u64) as usize;
+        parts[part].push((key, diff));
+    }
+
+    #[cfg(feature = "with-rayon")]
+    parts.par_iter_mut().for_each(|part| consolidate(part));
+
+    #[cfg(not(feature = "with-rayon"))]
+    std::thread::scope(|scope| {
+        for part in parts.iter_mut() {
+            scope.spawn(move || consolidate(part));
+        }
+    });
+
+    // Merge sorted partitions, which contain disjoint sets of keys.
+    vec.reserve(parts.iter().map(Vec::len).sum());
+    let mut parts: Vec<_> = parts.into_iter().map(Vec::into_iter).collect();
+    let mut heads = BinaryHeap::with_capacity(parts.len());
+    for (part, tuples) in parts.iter_mut().enumerate() {
+        if let Some((key, diff)) = tuples.next() {
+            heads.push(MergeHead { key, diff, part });
+        }
+    }
+
+    while let Some(MergeHead { key, diff, part }) = heads.pop() {
+        vec.push((key, diff));
+        if let Some((key, diff)) = parts[part].next() {
+            heads.push(MergeHead { key, diff, part });
+        }
+    }
+}
+
+/// The smallest remaining tuple of a partition in the merge performed by
+/// `consolidate_partitioned()`.
+///
+/// Ordered in reverse by key, so that [`BinaryHeap`] yields the smallest key
+/// first.
+struct MergeHead<T, R> {
+    key: T,
+    diff: R,
+    part: usize,
+}
+
+impl<T: Ord, R> PartialEq for MergeHead<T, R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T: Ord, R> Eq for MergeHead<T, R> {}
+
+impl<T: Ord, R> PartialOrd for MergeHead<T, R> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord, R> Ord for MergeHead<T, R> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.cmp(&self.key)
+    }
+}
+
 /// Sorts and consolidate `vec[offset..]`.
 ///
 /// This method will sort `vec[offset..]` and then consolidate runs of more than
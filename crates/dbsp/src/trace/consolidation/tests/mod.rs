@@ -2,13 +2,18 @@
 
 mod proptests;
 
-use std::fmt::Debug;
+use std::{cell::Cell, cmp::Ordering, fmt::Debug, rc::Rc};
 
 use itertools::Itertools;
 
-use crate::trace::consolidation::{
-    consolidate, consolidate_from, consolidate_paired_slices, consolidate_payload_from,
-    consolidate_slice, dedup_payload_starting_at, quicksort::quicksort, retain_starting_at,
+use crate::{
+    trace::consolidation::{
+        consolidate, consolidate_from, consolidate_paired_slices, consolidate_parallel,
+        consolidate_partitioned, consolidate_payload_from, consolidate_slice,
+        consolidate_vec_dropping, dedup_payload_starting_at, quicksort::quicksort,
+        retain_starting_at,
+    },
+    Runtime,
 };
 
 #[test]
@@ -27,6 +32,148 @@ fn test_consolidate() {
     }
 }
 
+fn parallel_consolidation_input() -> Vec<((u64, u64), isize)> {
+    (0..100_000u64)
+        .map(|i| (((i * 7919) % 1000, i % 7), if i % 3 == 0 { -1 } else { 1 }))
+        .collect()
+}
+
+#[test]
+fn test_consolidate_parallel_sequential_fallback() {
+    let mut expected = parallel_consolidation_input();
+    consolidate(&mut expected);
+
+    // Outside of a runtime, `consolidate_parallel` is the same as
+    // `consolidate`.
+    let mut input = parallel_consolidation_input();
+    consolidate_parallel(&mut input);
+    assert_eq!(input, expected);
+
+    // Likewise in a runtime with a single worker.
+    Runtime::run(1, move || {
+        let mut input = parallel_consolidation_input();
+        consolidate_parallel(&mut input);
+        assert_eq!(input, expected);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_consolidate_parallel() {
+    let mut expected = parallel_consolidation_input();
+    consolidate(&mut expected);
+
+    Runtime::run(4, {
+        let expected = expected.clone();
+        move || {
+            let mut input = parallel_consolidation_input();
+            consolidate_parallel(&mut input);
+            assert_eq!(input, expected);
+
+            let mut empty: Vec<(u64, isize)> = Vec::new();
+            consolidate_parallel(&mut empty);
+            assert!(empty.is_empty());
+        }
+    })
+    .join()
+    .unwrap();
+
+    // The number of partitions doesn't depend on the runtime.
+    for partitions in [2, 3, 16] {
+        let mut input = parallel_consolidation_input();
+        consolidate_partitioned(&mut input, partitions);
+        assert_eq!(input, expected);
+    }
+}
+
+#[test]
+fn test_consolidate_vec_dropping() {
+    let test_cases = vec![
+        (vec![("a", -1), ("b", -2), ("a", 1)], vec![("b", -2)]),
+        (vec![("a", -1), ("b", 0), ("a", 1)], vec![]),
+        (vec![("a", 0)], vec![]),
+        (vec![("a", 0), ("b", 0)], vec![]),
+        (vec![("a", 1), ("b", 1)], vec![("a", 1), ("b", 1)]),
+        (
+            vec![("c", 1), ("a", 2), ("c", 1), ("b", 1), ("a", -2)],
+            vec![("b", 1), ("c", 2)],
+        ),
+    ];
+
+    for (mut input, output) in test_cases {
+        consolidate_vec_dropping(&mut input);
+        assert_eq!(input, output);
+    }
+}
+
+/// A key that counts how many times it has been dropped.
+#[derive(Debug)]
+struct DropCounter {
+    key: u64,
+    drops: Rc<Cell<usize>>,
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+impl PartialEq for DropCounter {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for DropCounter {}
+
+impl PartialOrd for DropCounter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DropCounter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+#[test]
+fn test_consolidate_vec_dropping_drops_eagerly() {
+    let drops = Rc::new(Cell::new(0));
+    let key = |key| DropCounter {
+        key,
+        drops: drops.clone(),
+    };
+
+    let mut input = vec![
+        (key(1), 1),
+        (key(2), 1),
+        (key(1), 1),
+        (key(3), 1),
+        (key(2), -1),
+        (key(1), 1),
+    ];
+    consolidate_vec_dropping(&mut input);
+
+    // Two duplicates of key 1, and both copies of key 2, whose weights add
+    // up to zero, are dropped before the vector itself is dropped.
+    assert_eq!(drops.get(), 4);
+    assert_eq!(
+        input
+            .iter()
+            .map(|(key, diff)| (key.key, *diff))
+            .collect::<Vec<_>>(),
+        vec![(1, 3), (3, 1)]
+    );
+
+    drop(input);
+    assert_eq!(drops.get(), 6);
+}
+
 #[test]
 fn test_consolidate_from_start() {
     let test_cases = vec![
@@ -0,0 +1,202 @@
+//! A cursor adapter that unifies two cursor types.
+
+use crate::trace::cursor::Cursor;
+
+/// A cursor that is either a `C1` or a `C2`.
+///
+/// Cursors over different concrete batch and trace types with the same
+/// `(key, val, time, diff)` types implement the same [`Cursor`] trait but are
+/// still distinct types.  This adapter projects both of them onto a single
+/// type, so they can be used where one cursor type is expected, e.g., to
+/// merge a cursor over a delta batch with a cursor over a trace of a
+/// different type using [`CursorList`](`crate::trace::cursor::CursorList`).
+#[derive(Debug)]
+pub enum CursorEither<C1, C2> {
+    Left(C1),
+    Right(C2),
+}
+
+macro_rules! delegate {
+    ($self:expr, $cursor:ident => $body:expr) => {
+        match $self {
+            CursorEither::Left($cursor) => $body,
+            CursorEither::Right($cursor) => $body,
+        }
+    };
+}
+
+impl<K, V, T, R, C1, C2> Cursor<K, V, T, R> for CursorEither<C1, C2>
+where
+    C1: Cursor<K, V, T, R>,
+    C2: Cursor<K, V, T, R>,
+{
+    fn key_valid(&self) -> bool {
+        delegate!(self, cursor => cursor.key_valid())
+    }
+
+    fn val_valid(&self) -> bool {
+        delegate!(self, cursor => cursor.val_valid())
+    }
+
+    fn key(&self) -> &K {
+        delegate!(self, cursor => cursor.key())
+    }
+
+    fn val(&self) -> &V {
+        delegate!(self, cursor => cursor.val())
+    }
+
+    fn map_times<L>(&mut self, logic: L)
+    where
+        L: FnMut(&T, &R),
+    {
+        delegate!(self, cursor => cursor.map_times(logic))
+    }
+
+    fn fold_times<F, U>(&mut self, init: U, fold: F) -> U
+    where
+        F: FnMut(U, &T, &R) -> U,
+    {
+        delegate!(self, cursor => cursor.fold_times(init, fold))
+    }
+
+    fn map_times_through<L>(&mut self, upper: &T, logic: L)
+    where
+        L: FnMut(&T, &R),
+    {
+        delegate!(self, cursor => cursor.map_times_through(upper, logic))
+    }
+
+    fn fold_times_through<F, U>(&mut self, upper: &T, init: U, fold: F) -> U
+    where
+        F: FnMut(U, &T, &R) -> U,
+    {
+        delegate!(self, cursor => cursor.fold_times_through(upper, init, fold))
+    }
+
+    fn weight(&mut self) -> R
+    where
+        T: PartialEq<()>,
+    {
+        delegate!(self, cursor => cursor.weight())
+    }
+
+    fn step_key(&mut self) {
+        delegate!(self, cursor => cursor.step_key())
+    }
+
+    fn step_key_reverse(&mut self) {
+        delegate!(self, cursor => cursor.step_key_reverse())
+    }
+
+    fn seek_key(&mut self, key: &K) {
+        delegate!(self, cursor => cursor.seek_key(key))
+    }
+
+    fn seek_key_reverse(&mut self, key: &K) {
+        delegate!(self, cursor => cursor.seek_key_reverse(key))
+    }
+
+    fn step_val(&mut self) {
+        delegate!(self, cursor => cursor.step_val())
+    }
+
+    fn step_val_reverse(&mut self) {
+        delegate!(self, cursor => cursor.step_val_reverse())
+    }
+
+    fn seek_val(&mut self, val: &V) {
+        delegate!(self, cursor => cursor.seek_val(val))
+    }
+
+    fn seek_val_reverse(&mut self, val: &V) {
+        delegate!(self, cursor => cursor.seek_val_reverse(val))
+    }
+
+    fn seek_val_with<P>(&mut self, predicate: P)
+    where
+        P: Fn(&V) -> bool + Clone,
+    {
+        delegate!(self, cursor => cursor.seek_val_with(predicate))
+    }
+
+    fn seek_val_with_reverse<P>(&mut self, predicate: P)
+    where
+        P: Fn(&V) -> bool + Clone,
+    {
+        delegate!(self, cursor => cursor.seek_val_with_reverse(predicate))
+    }
+
+    fn rewind_keys(&mut self) {
+        delegate!(self, cursor => cursor.rewind_keys())
+    }
+
+    fn fast_forward_keys(&mut self) {
+        delegate!(self, cursor => cursor.fast_forward_keys())
+    }
+
+    fn rewind_vals(&mut self) {
+        delegate!(self, cursor => cursor.rewind_vals())
+    }
+
+    fn fast_forward_vals(&mut self) {
+        delegate!(self, cursor => cursor.fast_forward_vals())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CursorEither;
+    use crate::{
+        indexed_zset,
+        trace::{
+            cursor::{Cursor, CursorDebug, CursorList},
+            BatchReader, Spine, Trace,
+        },
+        OrdIndexedZSet,
+    };
+
+    #[test]
+    fn batch_and_trace() {
+        let delta: OrdIndexedZSet<u64, u64, isize> =
+            indexed_zset! { 1 => { 10 => 1 }, 2 => { 20 => 1, 21 => -1 }, 4 => { 40 => 2 } };
+
+        let mut trace = Spine::<OrdIndexedZSet<u64, u64, isize>>::new(None);
+        trace.insert(indexed_zset! { 1 => { 11 => 1 }, 2 => { 20 => 1 } });
+        trace.insert(indexed_zset! { 3 => { 30 => 1 }, 4 => { 40 => -2 } });
+
+        let mut cursor = CursorList::new(vec![
+            CursorEither::Left(delta.cursor()),
+            CursorEither::Right(trace.cursor()),
+        ]);
+
+        // Weights of matching key/value pairs in the two cursors are added up;
+        // pairs whose weights cancel out are still visited.
+        let mut contents = Vec::new();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                contents.push((*cursor.key(), *cursor.val(), cursor.weight()));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        assert_eq!(
+            contents,
+            vec![
+                (1, 10, 1),
+                (1, 11, 1),
+                (2, 20, 2),
+                (2, 21, -1),
+                (3, 30, 1),
+                (4, 40, 0),
+            ]
+        );
+
+        // Seeking works across both cursors.
+        cursor.rewind_keys();
+        cursor.seek_key(&3);
+        assert_eq!(cursor.key(), &3);
+        assert_eq!(cursor.val_to_vec(), vec![(30, vec![((), 1)])]);
+    }
+}
@@ -0,0 +1,227 @@
+//! A cursor that skips keys that don't satisfy a predicate.
+
+use crate::trace::cursor::Cursor;
+use std::marker::PhantomData;
+
+/// A `CursorFilter` restricts a base cursor of type `C: Cursor<K, V, T, R>`
+/// to keys that satisfy a predicate, without materializing a new batch.
+///
+/// Keys for which `predicate` returns `false` are skipped by key navigation
+/// in both directions.  Value navigation within the current key is passed
+/// through to the base cursor unchanged.
+pub struct CursorFilter<K, V, T, R, C, P> {
+    cursor: C,
+    predicate: P,
+    phantom: PhantomData<(K, V, T, R)>,
+}
+
+impl<K, V, T, R, C, P> CursorFilter<K, V, T, R, C, P>
+where
+    C: Cursor<K, V, T, R>,
+    P: Fn(&K) -> bool,
+{
+    /// Creates a cursor over keys of `cursor` that satisfy `predicate`,
+    /// positioned at the first such key at or after the current position of
+    /// `cursor`.
+    pub fn new(cursor: C, predicate: P) -> Self {
+        let mut result = Self {
+            cursor,
+            predicate,
+            phantom: PhantomData,
+        };
+
+        result.skip_forward();
+        result
+    }
+
+    /// Returns the base cursor.
+    pub fn into_inner(self) -> C {
+        self.cursor
+    }
+
+    // Move the base cursor forward to the next key that satisfies the
+    // predicate.
+    fn skip_forward(&mut self) {
+        while self.cursor.key_valid() && !(self.predicate)(self.cursor.key()) {
+            self.cursor.step_key();
+        }
+    }
+
+    // Move the base cursor back to the previous key that satisfies the
+    // predicate.
+    fn skip_reverse(&mut self) {
+        while self.cursor.key_valid() && !(self.predicate)(self.cursor.key()) {
+            self.cursor.step_key_reverse();
+        }
+    }
+}
+
+impl<K, V, T, R, C, P> Cursor<K, V, T, R> for CursorFilter<K, V, T, R, C, P>
+where
+    C: Cursor<K, V, T, R>,
+    P: Fn(&K) -> bool,
+{
+    fn key_valid(&self) -> bool {
+        self.cursor.key_valid()
+    }
+
+    fn val_valid(&self) -> bool {
+        self.cursor.val_valid()
+    }
+
+    fn key(&self) -> &K {
+        self.cursor.key()
+    }
+
+    fn val(&self) -> &V {
+        self.cursor.val()
+    }
+
+    fn fold_times<F, U>(&mut self, init: U, fold: F) -> U
+    where
+        F: FnMut(U, &T, &R) -> U,
+    {
+        self.cursor.fold_times(init, fold)
+    }
+
+    fn fold_times_through<F, U>(&mut self, upper: &T, init: U, fold: F) -> U
+    where
+        F: FnMut(U, &T, &R) -> U,
+    {
+        self.cursor.fold_times_through(upper, init, fold)
+    }
+
+    fn weight(&mut self) -> R
+    where
+        T: PartialEq<()>,
+    {
+        self.cursor.weight()
+    }
+
+    fn step_key(&mut self) {
+        self.cursor.step_key();
+        self.skip_forward();
+    }
+
+    fn step_key_reverse(&mut self) {
+        self.cursor.step_key_reverse();
+        self.skip_reverse();
+    }
+
+    fn seek_key(&mut self, key: &K) {
+        self.cursor.seek_key(key);
+        self.skip_forward();
+    }
+
+    fn seek_key_reverse(&mut self, key: &K) {
+        self.cursor.seek_key_reverse(key);
+        self.skip_reverse();
+    }
+
+    fn step_val(&mut self) {
+        self.cursor.step_val();
+    }
+
+    fn step_val_reverse(&mut self) {
+        self.cursor.step_val_reverse();
+    }
+
+    fn seek_val(&mut self, val: &V) {
+        self.cursor.seek_val(val);
+    }
+
+    fn seek_val_reverse(&mut self, val: &V) {
+        self.cursor.seek_val_reverse(val);
+    }
+
+    fn seek_val_with<PV>(&mut self, predicate: PV)
+    where
+        PV: Fn(&V) -> bool + Clone,
+    {
+        self.cursor.seek_val_with(predicate);
+    }
+
+    fn seek_val_with_reverse<PV>(&mut self, predicate: PV)
+    where
+        PV: Fn(&V) -> bool + Clone,
+    {
+        self.cursor.seek_val_with_reverse(predicate);
+    }
+
+    fn rewind_keys(&mut self) {
+        self.cursor.rewind_keys();
+        self.skip_forward();
+    }
+
+    fn fast_forward_keys(&mut self) {
+        self.cursor.fast_forward_keys();
+        self.skip_reverse();
+    }
+
+    fn rewind_vals(&mut self) {
+        self.cursor.rewind_vals();
+    }
+
+    fn fast_forward_vals(&mut self) {
+        self.cursor.fast_forward_vals();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CursorFilter;
+    use crate::{
+        indexed_zset,
+        trace::{
+            cursor::{Cursor, CursorDebug},
+            BatchReader,
+        },
+        OrdIndexedZSet,
+    };
+
+    #[test]
+    fn even_keys() {
+        let batch: OrdIndexedZSet<u64, u64, isize> = indexed_zset! {
+            1 => { 10 => 1 },
+            2 => { 20 => 1, 21 => -1 },
+            3 => { 30 => 1 },
+            4 => { 40 => 2 },
+            5 => { 50 => 1 },
+        };
+
+        let mut cursor = CursorFilter::new(batch.cursor(), |key: &u64| key % 2 == 0);
+
+        let mut keys = Vec::new();
+        while cursor.key_valid() {
+            keys.push(*cursor.key());
+            cursor.step_key();
+        }
+        assert_eq!(keys, vec![2, 4]);
+
+        keys.clear();
+        cursor.fast_forward_keys();
+        while cursor.key_valid() {
+            keys.push(*cursor.key());
+            cursor.step_key_reverse();
+        }
+        assert_eq!(keys, vec![4, 2]);
+
+        // Seeking to a key that doesn't satisfy the predicate lands on the
+        // next key that does.
+        cursor.rewind_keys();
+        cursor.seek_key(&3);
+        assert_eq!(cursor.key(), &4);
+        cursor.seek_key_reverse(&3);
+        assert_eq!(cursor.key(), &2);
+
+        // Values are not filtered.
+        cursor.rewind_vals();
+        assert_eq!(
+            cursor.val_to_vec(),
+            vec![(20, vec![((), 1)]), (21, vec![((), -1)])]
+        );
+
+        cursor.seek_key(&5);
+        assert!(!cursor.key_valid());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{trace::cursor::Cursor, Timestamp};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, ops::Range};
 
 /// A `CursorGroup` iterates over values associated with a single key of a base
 /// cursor of type `C: Cursor<K, V, R, T>`.
@@ -27,6 +27,31 @@ impl<'c, K, T, C> CursorGroup<'c, K, T, C> {
             phantom: PhantomData,
         }
     }
+
+    /// Creates a cursor over values in the range `bounds.start..bounds.end`
+    /// associated with the current key of the `base` cursor restricted to
+    /// times `<= upper`.
+    ///
+    /// Rewinds the values of `base` and positions the cursor at the first
+    /// value `>= bounds.start`.  This is useful for scanning only a part of a
+    /// group without materializing the entire group.
+    pub fn with_bounds<V, R>(
+        base: &'c mut C,
+        upper: T,
+        bounds: Range<V>,
+    ) -> BoundedCursorGroup<'c, K, V, T, C>
+    where
+        C: Cursor<K, V, T, R>,
+    {
+        debug_assert!(base.key_valid());
+        base.rewind_vals();
+        base.seek_val(&bounds.start);
+
+        BoundedCursorGroup {
+            group: Self::new(base, upper),
+            bounds,
+        }
+    }
 }
 
 impl<'c, K, V, T, R, C> Cursor<V, (), T, R> for CursorGroup<'c, K, T, C>
@@ -149,3 +174,222 @@ where
         self.val_valid = true;
     }
 }
+
+/// A [`CursorGroup`] that only iterates over values within a range.
+///
+/// Created by [`CursorGroup::with_bounds`].
+pub struct BoundedCursorGroup<'c, K, V, T, C> {
+    group: CursorGroup<'c, K, T, C>,
+    /// The cursor filters out values outside of this range.
+    bounds: Range<V>,
+}
+
+impl<'c, K, V, T, C> BoundedCursorGroup<'c, K, V, T, C>
+where
+    V: Eq,
+{
+    /// Move the base cursor back to the last value `< bounds.end`, starting
+    /// from its current position.
+    fn seek_before_end<R>(&mut self)
+    where
+        C: Cursor<K, V, T, R>,
+    {
+        let base = &mut *self.group.base;
+        base.seek_val_reverse(&self.bounds.end);
+        if base.val_valid() && base.val() == &self.bounds.end {
+            base.step_val_reverse();
+        }
+    }
+}
+
+impl<'c, K, V, T, R, C> Cursor<V, (), T, R> for BoundedCursorGroup<'c, K, V, T, C>
+where
+    T: Timestamp,
+    C: Cursor<K, V, T, R>,
+    K: PartialEq,
+    V: Ord,
+{
+    fn key_valid(&self) -> bool {
+        self.group.key_valid() && self.bounds.contains(self.group.key())
+    }
+
+    fn val_valid(&self) -> bool {
+        self.group.val_valid()
+    }
+
+    fn key(&self) -> &V {
+        self.group.key()
+    }
+
+    fn val(&self) -> &() {
+        self.group.val()
+    }
+
+    fn map_times<L>(&mut self, logic: L)
+    where
+        L: FnMut(&T, &R),
+    {
+        self.group.map_times(logic)
+    }
+
+    fn fold_times<F, U>(&mut self, init: U, fold: F) -> U
+    where
+        F: FnMut(U, &T, &R) -> U,
+    {
+        self.group.fold_times(init, fold)
+    }
+
+    fn map_times_through<L>(&mut self, upper: &T, logic: L)
+    where
+        L: FnMut(&T, &R),
+    {
+        self.group.map_times_through(upper, logic)
+    }
+
+    fn fold_times_through<F, U>(&mut self, upper: &T, init: U, fold: F) -> U
+    where
+        F: FnMut(U, &T, &R) -> U,
+    {
+        self.group.fold_times_through(upper, init, fold)
+    }
+
+    fn weight(&mut self) -> R
+    where
+        T: PartialEq<()>,
+    {
+        self.group.weight()
+    }
+
+    fn step_key(&mut self) {
+        self.group.step_key();
+    }
+
+    fn step_key_reverse(&mut self) {
+        self.group.step_key_reverse();
+    }
+
+    fn seek_key(&mut self, val: &V) {
+        if val < &self.bounds.start {
+            self.group.base.seek_val(&self.bounds.start);
+        } else {
+            self.group.seek_key(val);
+        }
+    }
+
+    fn seek_key_reverse(&mut self, val: &V) {
+        if val >= &self.bounds.end {
+            self.seek_before_end::<R>();
+        } else {
+            self.group.seek_key_reverse(val);
+        }
+    }
+
+    fn step_val(&mut self) {
+        self.group.step_val();
+    }
+
+    fn seek_val(&mut self, val: &()) {
+        self.group.seek_val(val);
+    }
+
+    fn seek_val_with<P>(&mut self, predicate: P)
+    where
+        P: Fn(&()) -> bool + Clone,
+    {
+        self.group.seek_val_with(predicate);
+    }
+
+    fn rewind_keys(&mut self) {
+        self.group.rewind_keys();
+        self.group.base.seek_val(&self.bounds.start);
+    }
+
+    fn fast_forward_keys(&mut self) {
+        self.group.fast_forward_keys();
+        self.seek_before_end::<R>();
+    }
+
+    fn rewind_vals(&mut self) {
+        self.group.rewind_vals();
+    }
+
+    fn step_val_reverse(&mut self) {
+        self.group.step_val_reverse();
+    }
+
+    fn seek_val_reverse(&mut self, val: &()) {
+        self.group.seek_val_reverse(val);
+    }
+
+    fn seek_val_with_reverse<P>(&mut self, predicate: P)
+    where
+        P: Fn(&()) -> bool + Clone,
+    {
+        self.group.seek_val_with_reverse(predicate);
+    }
+
+    fn fast_forward_vals(&mut self) {
+        self.group.fast_forward_vals();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CursorGroup;
+    use crate::{
+        indexed_zset,
+        trace::{cursor::Cursor, BatchReader},
+        OrdIndexedZSet,
+    };
+
+    #[test]
+    fn bounded_group() {
+        let batch: OrdIndexedZSet<u64, u64, isize> = indexed_zset! {
+            1 => { 10 => 1, 11 => 1 },
+            2 => { 20 => 1, 21 => -1, 22 => 2, 23 => 1, 24 => 1 },
+            3 => { 30 => 1 },
+        };
+
+        let mut cursor = batch.cursor();
+        cursor.seek_key(&2);
+
+        let mut group = CursorGroup::with_bounds(&mut cursor, (), 21..24);
+
+        let mut vals = Vec::new();
+        while group.key_valid() {
+            vals.push((*group.key(), group.weight()));
+            group.step_key();
+        }
+        assert_eq!(vals, vec![(21, -1), (22, 2), (23, 1)]);
+
+        vals.clear();
+        group.fast_forward_keys();
+        while group.key_valid() {
+            vals.push((*group.key(), group.weight()));
+            group.step_key_reverse();
+        }
+        assert_eq!(vals, vec![(23, 1), (22, 2), (21, -1)]);
+
+        // Seeking outside of the bounds lands on the nearest value within
+        // the bounds.
+        group.rewind_keys();
+        group.seek_key(&10);
+        assert_eq!(group.key(), &21);
+        group.fast_forward_keys();
+        assert_eq!(group.key(), &23);
+        group.seek_key_reverse(&22);
+        assert_eq!(group.key(), &22);
+        group.seek_key(&24);
+        assert!(!group.key_valid());
+
+        // An unbounded group sees all values of the key.
+        cursor.rewind_vals();
+        let mut group = CursorGroup::new(&mut cursor, ());
+        let mut vals = Vec::new();
+        while group.key_valid() {
+            vals.push(*group.key());
+            group.step_key();
+        }
+        assert_eq!(vals, vec![20, 21, 22, 23, 24]);
+    }
+}
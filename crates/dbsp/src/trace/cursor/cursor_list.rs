@@ -6,7 +6,7 @@ use crate::{
 };
 use std::marker::PhantomData;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Forward,
     Backward,
@@ -15,11 +15,19 @@ enum Direction {
 /// Provides a cursor interface over a list of cursors.
 ///
 /// The `CursorList` tracks the indices of cursors with the minimum key, and the
-/// the indices of cursors with the minimum key and minimum value. It performs
-/// no clever management of these sets otherwise.
+/// the indices of cursors with the minimum key and minimum value.  The
+/// remaining cursors with valid keys are kept in a binary heap ordered by
+/// their current keys, so that moving to the next key in [`Cursor::step_key`]
+/// or [`Cursor::step_key_reverse`] takes `O(log n)` comparisons per merged
+/// cursor rather than a scan over all `n` cursors.
 #[derive(Debug)]
 pub struct CursorList<K, V, T, R, C: Cursor<K, V, T, R>> {
     cursors: Vec<C>,
+    // Indices of cursors with valid keys, excluding `current_key`, organized
+    // as a binary heap with the smallest (or, in `Direction::Backward` order,
+    // the largest) key at the top.
+    heap: Vec<usize>,
+    key_order: Direction,
     current_key: Vec<usize>,
     current_val: Vec<usize>,
     direction: Direction,
@@ -34,7 +42,9 @@ where
     /// Creates a new cursor list from pre-existing cursors.
     pub fn new(cursors: Vec<C>) -> Self {
         let mut result = Self {
+            heap: Vec::with_capacity(cursors.len()),
             cursors,
+            key_order: Direction::Forward,
             current_key: Vec::new(),
             current_val: Vec::new(),
             direction: Direction::Forward,
@@ -47,57 +57,127 @@ where
 
     // Initialize current_key with the indices of cursors with the minimum key.
     //
-    // This method scans the current keys of each cursor, and tracks the indices
-    // of cursors whose key equals the minimum valid key seen so far. As it goes,
-    // if it observes an improved key it clears the current list, updates the
-    // minimum key, and continues.
+    // This method rebuilds the heap from all cursors with valid keys in
+    // ascending key order and pops the cursors with the minimum key from it.
     //
     // Once finished, it invokes `minimize_vals()` to ensure the value cursor is
     // in a consistent state as well.
     fn minimize_keys(&mut self) {
         debug_assert!(self.direction == Direction::Forward);
 
-        self.current_key.clear();
+        self.rebuild_heap(Direction::Forward);
+    }
 
-        // Determine the index of the cursor with minimum key.
-        let mut min_key_opt: Option<&K> = None;
-        for (index, cursor) in self.cursors.iter().enumerate() {
-            let key = cursor.get_key();
-            if key.is_some() {
-                if min_key_opt.is_none() || key.lt(&min_key_opt) {
-                    min_key_opt = key;
-                    self.current_key.clear();
-                }
-                if key.eq(&min_key_opt) {
-                    self.current_key.push(index);
-                }
+    fn maximize_keys(&mut self) {
+        self.rebuild_heap(Direction::Backward);
+    }
+
+    fn rebuild_heap(&mut self, order: Direction) {
+        self.key_order = order;
+
+        self.heap.clear();
+        self.heap
+            .extend((0..self.cursors.len()).filter(|&index| self.cursors[index].key_valid()));
+        for pos in (0..self.heap.len() / 2).rev() {
+            self.sift_down(pos);
+        }
+
+        self.pop_keys();
+    }
+
+    // Return cursors in `current_key` that remain valid after stepping them
+    // in the direction of `key_order` to the heap and pop the next key.
+    fn advance_keys(&mut self) {
+        for i in 0..self.current_key.len() {
+            let index = self.current_key[i];
+            if self.cursors[index].key_valid() {
+                self.heap.push(index);
+                self.sift_up(self.heap.len() - 1);
             }
         }
 
-        self.minimize_vals();
+        self.pop_keys();
     }
 
-    fn maximize_keys(&mut self) {
+    // Move the cursors with the key at the top of the heap to `current_key`.
+    fn pop_keys(&mut self) {
         self.current_key.clear();
 
-        // Determine the index of the cursor with minimum key.
-        let mut max_key_opt: Option<&K> = None;
-        for (index, cursor) in self.cursors.iter().enumerate() {
-            let key = cursor.get_key();
-            if key.is_some() {
-                if max_key_opt.is_none() || key.gt(&max_key_opt) {
-                    max_key_opt = key;
-                    self.current_key.clear();
-                }
-                if key.eq(&max_key_opt) {
-                    self.current_key.push(index);
+        if let Some(first) = self.pop() {
+            self.current_key.push(first);
+            while let Some(&index) = self.heap.first() {
+                if self.cursors[index].key() != self.cursors[first].key() {
+                    break;
                 }
+                self.pop();
+                self.current_key.push(index);
             }
+
+            // Visit cursors in list order, as `fold_times` does without a heap.
+            self.current_key.sort_unstable();
         }
 
         self.minimize_vals();
     }
 
+    fn pop(&mut self) -> Option<usize> {
+        if self.heap.is_empty() {
+            return None;
+        }
+
+        let top = self.heap.swap_remove(0);
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+
+        Some(top)
+    }
+
+    // Returns `true` if the key of cursor `heap[i]` precedes the key of cursor
+    // `heap[j]` in `key_order`.
+    fn precedes(&self, i: usize, j: usize) -> bool {
+        let key1 = self.cursors[self.heap[i]].key();
+        let key2 = self.cursors[self.heap[j]].key();
+
+        match self.key_order {
+            Direction::Forward => key1 < key2,
+            Direction::Backward => key1 > key2,
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if !self.precedes(pos, parent) {
+                break;
+            }
+
+            self.heap.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let left = 2 * pos + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+
+            let child = if left + 1 < self.heap.len() && self.precedes(left + 1, left) {
+                left + 1
+            } else {
+                left
+            };
+            if !self.precedes(child, pos) {
+                break;
+            }
+
+            self.heap.swap(pos, child);
+            pos = child;
+        }
+    }
+
     // Initialize current_val with the indices of minimum key cursors with the
     // minimum value.
     //
@@ -214,7 +294,11 @@ where
             self.cursors[index].step_key();
         }
         self.direction = Direction::Forward;
-        self.minimize_keys();
+        if self.key_order == Direction::Forward {
+            self.advance_keys();
+        } else {
+            self.minimize_keys();
+        }
     }
 
     fn step_key_reverse(&mut self) {
@@ -222,7 +306,11 @@ where
             self.cursors[index].step_key_reverse();
         }
         self.direction = Direction::Forward;
-        self.maximize_keys();
+        if self.key_order == Direction::Backward {
+            self.advance_keys();
+        } else {
+            self.maximize_keys();
+        }
     }
 
     fn seek_key(&mut self, key: &K) {
@@ -335,3 +423,93 @@ where
         self.maximize_vals();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::CursorList;
+    use crate::{
+        trace::{cursor::Cursor, Batch, BatchReader},
+        OrdIndexedZSet,
+    };
+    use std::collections::BTreeSet;
+
+    // Collect non-zero `(key, val, weight)` tuples from `cursor`.
+    fn contents<C>(cursor: &mut C) -> Vec<(u64, u64, i64)>
+    where
+        C: Cursor<u64, u64, (), i64>,
+    {
+        let mut result = Vec::new();
+        cursor.rewind_keys();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let weight = cursor.weight();
+                if weight != 0 {
+                    result.push((*cursor.key(), *cursor.val(), weight));
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+        result
+    }
+
+    #[test]
+    fn merge_many_cursors() {
+        let tuples: Vec<Vec<((u64, u64), i64)>> = (0..10u64)
+            .map(|i| {
+                (0..50u64)
+                    .map(|j| {
+                        (
+                            ((i * 7 + j * 13) % 40, j % 3),
+                            if j % 4 == 0 { -1 } else { 1 },
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        let batches: Vec<OrdIndexedZSet<u64, u64, i64>> = tuples
+            .iter()
+            .map(|tuples| OrdIndexedZSet::from_tuples((), tuples.clone()))
+            .collect();
+        let expected = OrdIndexedZSet::<u64, u64, i64>::from_tuples((), tuples.concat());
+
+        let mut cursor = CursorList::new(batches.iter().map(|batch| batch.cursor()).collect());
+        assert_eq!(contents(&mut cursor), contents(&mut expected.cursor()));
+
+        // Reverse traversal visits every key in the merged cursors once,
+        // including keys whose weights cancel out.
+        let expected_keys: Vec<u64> = tuples
+            .iter()
+            .flatten()
+            .map(|((key, _), _)| *key)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+
+        let mut keys = Vec::new();
+        cursor.fast_forward_keys();
+        while cursor.key_valid() {
+            keys.push(*cursor.key());
+            cursor.step_key_reverse();
+        }
+        assert_eq!(keys, expected_keys);
+
+        // Seeking lands on the first key greater than or equal to the target.
+        cursor.rewind_keys();
+        cursor.seek_key(&20);
+        assert_eq!(cursor.key(), &20);
+        cursor.step_key();
+        assert_eq!(cursor.key(), &21);
+        cursor.seek_key_reverse(&10);
+        assert_eq!(cursor.key(), &10);
+        cursor.step_key_reverse();
+        assert_eq!(cursor.key(), &9);
+
+        // `fold_times` visits the weights of all merged cursors.
+        cursor.rewind_keys();
+        cursor.rewind_vals();
+        let sum = cursor.fold_times(0, |sum, _, w| sum + w);
+        assert_eq!(sum, cursor.weight());
+    }
+}
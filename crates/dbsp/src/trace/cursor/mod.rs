@@ -11,10 +11,14 @@
 //! on multiple levels (key and val), but also because it supports efficient
 //! seeking (via the `seek_key` and `seek_val` methods).
 
+pub mod cursor_either;
+pub mod cursor_filter;
 pub mod cursor_group;
 pub mod cursor_list;
 
-pub use cursor_group::CursorGroup;
+pub use cursor_either::CursorEither;
+pub use cursor_filter::CursorFilter;
+pub use cursor_group::{BoundedCursorGroup, CursorGroup};
 pub use cursor_list::CursorList;
 
 /// A cursor for navigating ordered `(key, val, time, diff)` tuples.
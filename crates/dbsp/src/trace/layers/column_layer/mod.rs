@@ -27,7 +27,7 @@ use std::{
 };
 
 /// A layer of unordered values
-#[derive(Debug, Clone, Eq, PartialEq, SizeOf)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, SizeOf)]
 pub struct ColumnLayer<K, R> {
     // Invariant: keys.len == diffs.len
     pub(super) keys: Vec<K>,
@@ -53,6 +53,11 @@ impl<K, R> ColumnLayer<K, R> {
         }
     }
 
+    /// Returns the number of keys the layer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.keys.capacity()
+    }
+
     /// Exposes a `ColumnLayer` as its component parts
     pub fn as_parts(&self) -> (&[K], &[R], usize) {
         (&self.keys, &self.diffs, self.lower_bound)
@@ -355,7 +360,11 @@ where
     R: Eq + HasZero + AddAssign + AddAssignByRef + Clone,
 {
     type Item = (K, R);
-    type Cursor<'s> = ColumnLayerCursor<'s, K, R> where K: 's, R: 's;
+    type Cursor<'s>
+        = ColumnLayerCursor<'s, K, R>
+    where
+        K: 's,
+        R: 's;
     type MergeBuilder = ColumnLayerBuilder<K, R>;
     type TupleBuilder = ColumnLayerBuilder<K, R>;
 
@@ -0,0 +1,579 @@
+//! Implementation using ordered keys and a hash index.
+//!
+//! [`HashedLeaf`] stores sorted `(key, diff)` tuples alongside an
+//! open-addressing hash table that maps each key to its position, so that
+//! seeking to a key that occurs in the layer takes expected constant time
+//! instead of a binary search.  Seeking to an absent key falls back to an
+//! exponential search, so cursors follow the usual [`Cursor`] contract:
+//! [`Cursor::seek`] moves to the first key greater than or equal to the
+//! target and never moves backwards.
+//!
+//! Since the index is shared by the entire layer, keys must be unique across
+//! the whole layer rather than within each sub-range.  Hence the layer is
+//! meant to be used as the top-level layer of a trie, e.g., to store a Z-set.
+
+use crate::{
+    algebra::MonoidValue,
+    default_hash,
+    trace::{
+        consolidation::consolidate,
+        layers::{advance, retreat, Builder, Cursor, MergeBuilder, Trie, TupleBuilder},
+    },
+};
+use size_of::SizeOf;
+use std::{
+    cmp::{min, Ordering},
+    hash::Hash,
+};
+
+/// Marks an unoccupied slot in a [`HashIndex`].
+const EMPTY: usize = usize::MAX;
+
+/// An open-addressing hash table with linear probing that maps keys to
+/// their positions in a vector of tuples.
+///
+/// The index does not store keys itself, but looks them up in the vector.
+/// At most half of its slots are occupied, so probe sequences are short and
+/// always terminate.
+#[derive(Debug, Clone, Default, SizeOf)]
+struct HashIndex {
+    slots: Vec<usize>,
+}
+
+impl HashIndex {
+    const fn new() -> Self {
+        Self { slots: Vec::new() }
+    }
+
+    /// The number of slots needed to index `len` tuples.
+    fn slots_for(len: usize) -> usize {
+        if len == 0 {
+            0
+        } else {
+            (2 * len).next_power_of_two()
+        }
+    }
+
+    /// Index distinct keys in `vals`.
+    fn build<K, R>(vals: &[(K, R)]) -> Self
+    where
+        K: Hash + Eq,
+    {
+        let mut index = Self {
+            slots: vec![EMPTY; Self::slots_for(vals.len())],
+        };
+
+        for (pos, (key, _)) in vals.iter().enumerate() {
+            match index.find(vals, key) {
+                Ok(_) => panic!("duplicate key in a hashed layer"),
+                Err(slot) => index.slots[slot] = pos,
+            }
+        }
+
+        index
+    }
+
+    /// Returns `Ok(pos)` if `key` occurs in `vals` at position `pos` or
+    /// `Err(slot)` with the slot where it should be inserted otherwise.
+    ///
+    /// The index must contain at least one slot.
+    fn find<K, R>(&self, vals: &[(K, R)], key: &K) -> Result<usize, usize>
+    where
+        K: Hash + Eq,
+    {
+        debug_assert!(self.slots.len().is_power_of_two());
+
+        let mask = self.slots.len() - 1;
+        let mut slot = default_hash(key) as usize & mask;
+        loop {
+            match self.slots[slot] {
+                EMPTY => return Err(slot),
+                pos if &vals[pos].0 == key => return Ok(pos),
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+    }
+
+    /// Returns the position of `key` in `vals`, if any.
+    fn lookup<K, R>(&self, vals: &[(K, R)], key: &K) -> Option<usize>
+    where
+        K: Hash + Eq,
+    {
+        if self.slots.is_empty() {
+            None
+        } else {
+            self.find(vals, key).ok()
+        }
+    }
+}
+
+/// A layer of ordered values with a hash index.
+#[derive(Debug, Clone, SizeOf)]
+pub struct HashedLeaf<K, R> {
+    /// Values ordered by key.
+    vals: Vec<(K, R)>,
+    index: HashIndex,
+    lower_bound: usize,
+}
+
+impl<K, R> HashedLeaf<K, R> {
+    /// Create an empty `HashedLeaf`.
+    pub const fn empty() -> Self {
+        Self {
+            vals: Vec::new(),
+            index: HashIndex::new(),
+            lower_bound: 0,
+        }
+    }
+
+    /// Values in the layer ordered by key.
+    pub fn vals(&self) -> &[(K, R)] {
+        &self.vals
+    }
+}
+
+impl<K, R> HashedLeaf<K, R>
+where
+    K: Hash + Eq,
+{
+    /// Returns the position of `key` in the layer, or `None` if the layer
+    /// doesn't contain `key` or `key` is below the lower bound of the layer.
+    pub fn position_of(&self, key: &K) -> Option<usize> {
+        self.index
+            .lookup(&self.vals, key)
+            .filter(|&pos| pos >= self.lower_bound)
+    }
+}
+
+impl<K, R> Default for HashedLeaf<K, R> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<K, R> PartialEq for HashedLeaf<K, R>
+where
+    K: PartialEq,
+    R: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.vals[self.lower_bound..] == other.vals[other.lower_bound..]
+    }
+}
+
+impl<K, R> Eq for HashedLeaf<K, R>
+where
+    K: Eq,
+    R: Eq,
+{
+}
+
+impl<K, R> Trie for HashedLeaf<K, R>
+where
+    K: Hash + Ord + Clone,
+    R: MonoidValue,
+{
+    type Item = (K, R);
+    type Cursor<'s> = HashedLeafCursor<'s, K, R> where K: 's, R: 's;
+    type MergeBuilder = HashedLeafBuilder<K, R>;
+    type TupleBuilder = HashedLeafBuilder<K, R>;
+
+    fn keys(&self) -> usize {
+        self.vals.len() - self.lower_bound
+    }
+
+    fn tuples(&self) -> usize {
+        <HashedLeaf<K, R> as Trie>::keys(self)
+    }
+
+    fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor<'_> {
+        HashedLeafCursor {
+            storage: self,
+            bounds: (lower, upper),
+            pos: lower as isize,
+        }
+    }
+
+    fn lower_bound(&self) -> usize {
+        self.lower_bound
+    }
+
+    fn truncate_below(&mut self, lower_bound: usize) {
+        if lower_bound > self.lower_bound {
+            self.lower_bound = min(lower_bound, self.vals.len());
+        }
+    }
+}
+
+/// A builder for hashed leaves.
+///
+/// Tuples can be pushed in any order: [`Builder::done`] sorts them, adds up
+/// the weights of equal keys and removes keys whose weights add up to zero
+/// before indexing the layer.  Merging layers produces consolidated tuples
+/// in key order directly.
+#[derive(Debug, SizeOf)]
+pub struct HashedLeafBuilder<K, R> {
+    vals: Vec<(K, R)>,
+}
+
+impl<K, R> HashedLeafBuilder<K, R>
+where
+    K: Hash + Ord + Clone,
+    R: MonoidValue,
+{
+    fn with_key_capacity(capacity: usize) -> Self {
+        Self {
+            vals: Vec::with_capacity(capacity),
+        }
+    }
+}
+
+impl<K, R> Builder for HashedLeafBuilder<K, R>
+where
+    K: Hash + Ord + Clone,
+    R: MonoidValue,
+{
+    type Trie = HashedLeaf<K, R>;
+
+    fn boundary(&mut self) -> usize {
+        self.vals.len()
+    }
+
+    fn done(mut self) -> Self::Trie {
+        consolidate(&mut self.vals);
+        let index = HashIndex::build(&self.vals);
+
+        HashedLeaf {
+            vals: self.vals,
+            index,
+            lower_bound: 0,
+        }
+    }
+}
+
+impl<K, R> MergeBuilder for HashedLeafBuilder<K, R>
+where
+    K: Hash + Ord + Clone,
+    R: MonoidValue,
+{
+    fn with_capacity(other1: &Self::Trie, other2: &Self::Trie) -> Self {
+        Self::with_key_capacity(
+            <HashedLeaf<K, R> as Trie>::keys(other1) + <HashedLeaf<K, R> as Trie>::keys(other2),
+        )
+    }
+
+    fn with_key_capacity(capacity: usize) -> Self {
+        Self::with_key_capacity(capacity)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.vals.reserve(additional);
+    }
+
+    fn keys(&self) -> usize {
+        self.vals.len()
+    }
+
+    fn copy_range(&mut self, other: &Self::Trie, lower: usize, upper: usize) {
+        self.vals.extend_from_slice(&other.vals[lower..upper]);
+    }
+
+    fn push_merge<'a>(
+        &'a mut self,
+        cursor1: <Self::Trie as Trie>::Cursor<'a>,
+        cursor2: <Self::Trie as Trie>::Cursor<'a>,
+    ) -> usize {
+        let (mut lower1, upper1) = (cursor1.position(), cursor1.bounds.1);
+        let (mut lower2, upper2) = (cursor2.position(), cursor2.bounds.1);
+        let (vals1, vals2) = (&cursor1.storage.vals, &cursor2.storage.vals);
+
+        while lower1 < upper1 && lower2 < upper2 {
+            match vals1[lower1].0.cmp(&vals2[lower2].0) {
+                Ordering::Less => {
+                    let step = 1 + advance(&vals1[lower1 + 1..upper1], |(key, _)| {
+                        key < &vals2[lower2].0
+                    });
+                    self.copy_range(cursor1.storage, lower1, lower1 + step);
+                    lower1 += step;
+                }
+                Ordering::Equal => {
+                    let mut diff = vals1[lower1].1.clone();
+                    diff.add_assign_by_ref(&vals2[lower2].1);
+                    if !diff.is_zero() {
+                        self.vals.push((vals1[lower1].0.clone(), diff));
+                    }
+                    lower1 += 1;
+                    lower2 += 1;
+                }
+                Ordering::Greater => {
+                    let step = 1 + advance(&vals2[lower2 + 1..upper2], |(key, _)| {
+                        key < &vals1[lower1].0
+                    });
+                    self.copy_range(cursor2.storage, lower2, lower2 + step);
+                    lower2 += step;
+                }
+            }
+        }
+
+        if lower1 < upper1 {
+            self.copy_range(cursor1.storage, lower1, upper1);
+        }
+        if lower2 < upper2 {
+            self.copy_range(cursor2.storage, lower2, upper2);
+        }
+
+        self.vals.len()
+    }
+}
+
+impl<K, R> TupleBuilder for HashedLeafBuilder<K, R>
+where
+    K: Hash + Ord + Clone,
+    R: MonoidValue,
+{
+    type Item = (K, R);
+
+    fn new() -> Self {
+        Self { vals: Vec::new() }
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_key_capacity(capacity)
+    }
+
+    fn reserve_tuples(&mut self, additional: usize) {
+        self.vals.reserve(additional);
+    }
+
+    fn push_tuple(&mut self, tuple: (K, R)) {
+        self.vals.push(tuple);
+    }
+
+    fn tuples(&self) -> usize {
+        self.vals.len()
+    }
+}
+
+/// A cursor for walking through a hashed leaf.
+///
+/// [`Cursor::seek`] and [`Cursor::seek_reverse`] look the target key up in
+/// the hash index first and only fall back to an exponential search over
+/// the remaining keys if it does not occur in the layer.
+#[derive(Clone, Debug)]
+pub struct HashedLeafCursor<'s, K, R> {
+    pos: isize,
+    storage: &'s HashedLeaf<K, R>,
+    bounds: (usize, usize),
+}
+
+impl<'s, K, R> HashedLeafCursor<'s, K, R>
+where
+    K: Hash + Eq,
+{
+    /// Returns the position of `key` if it is within the bounds of the
+    /// cursor.
+    fn find(&self, key: &K) -> Option<isize> {
+        self.storage
+            .position_of(key)
+            .filter(|&pos| pos >= self.bounds.0 && pos < self.bounds.1)
+            .map(|pos| pos as isize)
+    }
+}
+
+impl<'s, K, R> Cursor<'s> for HashedLeafCursor<'s, K, R>
+where
+    K: Hash + Ord,
+{
+    type Key = K;
+
+    type Item<'k> = &'k (K, R)
+    where
+        Self: 'k;
+
+    type ValueStorage = ();
+
+    fn keys(&self) -> usize {
+        self.bounds.1 - self.bounds.0
+    }
+
+    fn item(&self) -> Self::Item<'s> {
+        &self.storage.vals[self.pos as usize]
+    }
+
+    fn values(&self) {}
+
+    fn step(&mut self) {
+        self.pos += 1;
+
+        if self.pos >= self.bounds.1 as isize {
+            self.pos = self.bounds.1 as isize;
+        }
+    }
+
+    fn seek(&mut self, key: &Self::Key) {
+        if self.valid() {
+            match self.find(key) {
+                // Never move backwards: if `key` is behind the cursor, the
+                // current key is already greater than it.
+                Some(pos) => self.pos = self.pos.max(pos),
+                None => {
+                    self.pos += advance(
+                        &self.storage.vals[self.pos as usize..self.bounds.1],
+                        |(k, _)| k < key,
+                    ) as isize
+                }
+            }
+        }
+    }
+
+    fn valid(&self) -> bool {
+        self.pos >= self.bounds.0 as isize && self.pos < self.bounds.1 as isize
+    }
+
+    fn rewind(&mut self) {
+        self.pos = self.bounds.0 as isize;
+    }
+
+    fn position(&self) -> usize {
+        self.pos as usize
+    }
+
+    fn reposition(&mut self, lower: usize, upper: usize) {
+        self.pos = lower as isize;
+        self.bounds = (lower, upper);
+    }
+
+    fn step_reverse(&mut self) {
+        self.pos -= 1;
+
+        if self.pos < self.bounds.0 as isize {
+            self.pos = self.bounds.0 as isize - 1;
+        }
+    }
+
+    fn seek_reverse(&mut self, key: &Self::Key) {
+        if self.valid() {
+            match self.find(key) {
+                Some(pos) => self.pos = self.pos.min(pos),
+                None => {
+                    self.pos -= retreat(
+                        &self.storage.vals[self.bounds.0..=self.pos as usize],
+                        |(k, _)| k > key,
+                    ) as isize
+                }
+            }
+        }
+    }
+
+    fn fast_forward(&mut self) {
+        self.pos = self.bounds.1 as isize - 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HashedLeaf, HashedLeafBuilder};
+    use crate::trace::layers::{Builder, Cursor, Trie, TupleBuilder};
+
+    fn build(tuples: &[(u64, i64)]) -> HashedLeaf<u64, i64> {
+        let mut builder = HashedLeafBuilder::new();
+        builder.extend_tuples(tuples.iter().cloned());
+        builder.done()
+    }
+
+    #[test]
+    fn seek() {
+        let leaf = build(
+            &(0..1000)
+                .rev()
+                .map(|key| (key * 3, key as i64 + 1))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(leaf.keys(), 1000);
+
+        let mut cursor = leaf.cursor();
+        for key in 0..1000 {
+            // Present keys are found by exact lookup.
+            cursor.seek(&(key * 3));
+            assert!(cursor.valid());
+            assert_eq!(cursor.item(), &(key * 3, key as i64 + 1));
+
+            // Absent keys move the cursor to the next larger key.
+            cursor.seek(&(key * 3 + 1));
+            if key < 999 {
+                assert_eq!(cursor.item(), &(key * 3 + 3, key as i64 + 2));
+            } else {
+                assert!(!cursor.valid());
+            }
+        }
+
+        // Seeking never moves the cursor backwards.
+        let mut cursor = leaf.cursor();
+        cursor.seek(&30);
+        cursor.seek(&3);
+        assert_eq!(cursor.item(), &(30, 11));
+        cursor.seek(&4);
+        assert_eq!(cursor.item(), &(30, 11));
+
+        // Keys outside of the cursor's bounds are not found.
+        let mut cursor = leaf.cursor_from(10, 20);
+        cursor.seek(&30);
+        assert_eq!(cursor.item(), &(30, 11));
+        cursor.seek(&0);
+        assert_eq!(cursor.item(), &(30, 11));
+        cursor.seek(&60);
+        assert!(!cursor.valid());
+
+        cursor.fast_forward();
+        cursor.seek_reverse(&60);
+        assert_eq!(cursor.item(), &(57, 20));
+        cursor.seek_reverse(&40);
+        assert_eq!(cursor.item(), &(39, 14));
+        cursor.seek_reverse(&42);
+        assert_eq!(cursor.item(), &(39, 14));
+        cursor.seek_reverse(&0);
+        assert!(!cursor.valid());
+
+        assert!(HashedLeaf::<u64, i64>::empty().position_of(&0).is_none());
+    }
+
+    #[test]
+    fn accumulate_and_merge() {
+        let leaf1 = build(&[(1, 1), (2, 2), (1, 1), (3, 3), (3, -3)]);
+        assert_eq!(leaf1.vals(), &[(1, 2), (2, 2)]);
+        assert_eq!(leaf1.position_of(&3), None);
+
+        let leaf2 = build(&[(2, -2), (4, 4), (1, 1), (0, 5)]);
+        let merged = leaf1.merge(&leaf2);
+        assert_eq!(merged.vals(), &[(0, 5), (1, 3), (4, 4)]);
+        assert_eq!(merged.position_of(&4), Some(2));
+
+        let mut cursor = merged.cursor();
+        cursor.seek(&4);
+        assert_eq!(cursor.item(), &(4, 4));
+        cursor.seek(&2);
+        assert_eq!(cursor.item(), &(4, 4));
+
+        let canceled = merged.merge(&build(&[(0, -5), (1, -3), (4, -4)]));
+        assert!(canceled.vals().is_empty());
+        assert!(!canceled.cursor().valid());
+    }
+
+    #[test]
+    fn truncate() {
+        let mut leaf = build(&[(1, 1), (2, 1), (3, 1)]);
+        leaf.truncate_below(1);
+        assert_eq!(leaf.keys(), 2);
+        assert_eq!(leaf.position_of(&1), None);
+        assert_eq!(leaf.position_of(&2), Some(1));
+
+        let mut cursor = leaf.cursor();
+        assert_eq!(cursor.item(), &(2, 1));
+        cursor.seek(&1);
+        assert_eq!(cursor.item(), &(2, 1));
+
+        // Truncated tuples are not included in merges.
+        let merged = leaf.merge(&build(&[(1, 1)]));
+        assert_eq!(merged.vals(), &[(1, 1), (2, 1), (3, 1)]);
+    }
+}
@@ -8,11 +8,11 @@ mod advance;
 
 pub mod column_layer;
 pub mod erased;
+pub mod hashed;
 pub mod ordered;
 pub mod ordered_leaf;
 pub mod unordered;
-// pub mod hashed;
-// pub mod weighted;
+pub mod weighted;
 
 #[cfg(test)]
 mod test;
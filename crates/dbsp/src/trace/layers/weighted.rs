@@ -0,0 +1,106 @@
+//! Leaf layer with accumulated weights.
+//!
+//! A weighted leaf stores each key at most once, together with its non-zero
+//! weight.  Merging two weighted leaves adds up the weights of equal keys and
+//! discards keys whose weights add up to zero, so the result is consolidated
+//! without a separate consolidation pass.
+//!
+//! [`ColumnLayer`] already maintains exactly this invariant: its merge
+//! builder walks both inputs in key order, accumulates weights of matching
+//! keys and skips zero sums.  Rather than maintaining a second
+//! implementation, the weighted layer is an alias for it, which gives it
+//! ordered cursors with the usual [`Cursor`](`super::Cursor`) contract
+//! (monotone `seek`) and correct `truncate_below` semantics.
+
+use crate::trace::layers::column_layer::{ColumnLayer, ColumnLayerBuilder, ColumnLayerCursor};
+
+/// A layer of distinct keys with non-zero weights.
+pub type WeightedLeaf<K, R> = ColumnLayer<K, R>;
+
+/// A cursor over a [`WeightedLeaf`].
+pub type WeightedCursor<'s, K, R> = ColumnLayerCursor<'s, K, R>;
+
+/// A builder for [`WeightedLeaf`]s, used both to merge leaves and to build
+/// them from sorted, consolidated tuples.
+pub type WeightedLeafBuilder<K, R> = ColumnLayerBuilder<K, R>;
+
+#[cfg(test)]
+mod test {
+    use super::{WeightedLeaf, WeightedLeafBuilder};
+    use crate::trace::layers::{Builder, Cursor, Trie, TupleBuilder};
+
+    fn build(tuples: &[(u64, i64)]) -> WeightedLeaf<u64, i64> {
+        let mut builder = WeightedLeafBuilder::new();
+        builder.extend_tuples(tuples.iter().cloned());
+        builder.done()
+    }
+
+    #[test]
+    fn merge() {
+        let leaf1 = build(&[(1, 1), (2, 2), (3, 3)]);
+        let leaf2 = build(&[(1, -1), (2, -1), (4, 4)]);
+
+        let merged = leaf1.merge(&leaf2);
+        assert_eq!(merged.keys(), &[2, 3, 4]);
+        assert_eq!(merged.diffs(), &[1, 3, 4]);
+    }
+
+    #[test]
+    fn merge_canceling() {
+        let leaf1 = build(&[(1, 1), (2, 2), (3, -3)]);
+        let leaf2 = build(&[(1, -1), (2, -2), (3, 3)]);
+
+        let merged = leaf1.merge(&leaf2);
+        assert!(merged.is_empty());
+        assert_eq!(<WeightedLeaf<_, _> as Trie>::keys(&merged), 0);
+        assert!(!merged.cursor().valid());
+    }
+
+    #[test]
+    fn seek() {
+        let leaf = build(&[(1, 1), (3, 3), (5, 5), (7, 7)]);
+
+        let mut cursor = leaf.cursor();
+        cursor.seek(&3);
+        assert_eq!(cursor.item(), (&3, &3));
+
+        // Seeking to an absent key lands on the next larger key.
+        cursor.seek(&4);
+        assert_eq!(cursor.item(), (&5, &5));
+
+        // Seeking never moves the cursor backwards.
+        cursor.seek(&1);
+        assert_eq!(cursor.item(), (&5, &5));
+
+        cursor.seek(&8);
+        assert!(!cursor.valid());
+
+        cursor.fast_forward();
+        cursor.seek_reverse(&4);
+        assert_eq!(cursor.item(), (&3, &3));
+    }
+
+    #[test]
+    fn truncate_below() {
+        let mut leaf = build(&[(1, 1), (3, 3), (5, 5), (7, 7)]);
+
+        leaf.truncate_below(2);
+        assert_eq!(<WeightedLeaf<_, _> as Trie>::keys(&leaf), 2);
+
+        let mut cursor = leaf.cursor();
+        assert_eq!(cursor.item(), (&5, &5));
+        cursor.step();
+        assert_eq!(cursor.item(), (&7, &7));
+        cursor.step();
+        assert!(!cursor.valid());
+
+        // Truncated keys don't come back in merges.
+        let merged = leaf.merge(&build(&[(1, 1), (7, -7)]));
+        assert_eq!(merged.keys(), &[1, 5]);
+        assert_eq!(merged.diffs(), &[1, 5]);
+
+        // Truncating below the current bound is a no-op.
+        leaf.truncate_below(1);
+        assert_eq!(<WeightedLeaf<_, _> as Trie>::keys(&leaf), 2);
+    }
+}
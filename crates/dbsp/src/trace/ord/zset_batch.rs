@@ -24,7 +24,7 @@ use std::{
 };
 
 /// An immutable collection of `(key, weight)` pairs without timing information.
-#[derive(Debug, Clone, Eq, PartialEq, SizeOf)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, SizeOf)]
 pub struct OrdZSet<K, R> {
     #[doc(hidden)]
     pub layer: ColumnLayer<K, R>,
@@ -22,6 +22,7 @@ macro_rules! run_queries {
         let (source_step_tx, source_step_rx): (mpsc::SyncSender<()>, mpsc::Receiver<()>) =
             mpsc::sync_channel(1);
         let (source_exhausted_tx, source_exhausted_rx) = mpsc::sync_channel(1);
+        let input_handle = input_handle.with_capacity_hint($nexmark_config.input_batch_size);
         spawn_source_producer(
             $nexmark_config,
             input_handle,
@@ -26,6 +26,11 @@ pub struct Config {
     #[clap(long, default_value = "3", env = "NEXMARK_AUCTION_PROPORTION")]
     pub auction_proportion: usize,
 
+    /// Specify the proportion of events that will be updates to the reserve
+    /// price of existing auctions. 0 disables auction updates.
+    #[clap(long, default_value = "0", env = "NEXMARK_AUCTION_UPDATE_PROPORTION")]
+    pub auction_update_proportion: usize,
+
     /// Average idealized size of a 'new auction' event, in bytes.
     #[clap(long, default_value = "500", env = "NEXMARK_AVG_AUCTION_BYTE_SIZE")]
     pub avg_auction_byte_size: usize,
@@ -66,6 +71,12 @@ pub struct Config {
     #[clap(long, default_value = "100000000", env = "NEXMARK_MAX_EVENTS")]
     pub max_events: u64,
 
+    /// Event rate (per second) that the generator alternates with
+    /// `first_event_rate` according to `rate_shape`.  Defaults to
+    /// `first_event_rate`, i.e., a constant event rate.
+    #[clap(long, env = "NEXMARK_NEXT_EVENT_RATE")]
+    pub next_event_rate: Option<usize>,
+
     /// Maximum number of people to consider as active for placing auctions or
     /// bids.
     #[clap(long, default_value = "1000", env = "NEXMARK_NUM_ACTIVE_PEOPLE")]
@@ -91,6 +102,27 @@ pub struct Config {
     #[clap(long, default_value = "1", env = "NEXMARK_PERSON_PROPORTION")]
     pub person_proportion: usize,
 
+    /// Time in seconds it takes the event rate to go from
+    /// `first_event_rate` to `next_event_rate` and back.
+    #[clap(long, default_value = "600", env = "NEXMARK_RATE_PERIOD_SEC")]
+    pub rate_period_sec: usize,
+
+    /// Shape of the event rate over time when `first_event_rate` and
+    /// `next_event_rate` differ.
+    #[clap(long, default_value = "square", env = "NEXMARK_RATE_SHAPE", value_enum)]
+    pub rate_shape: RateShape,
+
+    /// Probability with which the generator emits a retraction of a
+    /// previously generated event instead of a new event. 0 disables
+    /// retractions.
+    #[clap(long, default_value = "0", env = "NEXMARK_RETRACTION_PROBABILITY")]
+    pub retraction_probability: f64,
+
+    /// Maximum number of recently generated events that are remembered as
+    /// candidates for retraction.
+    #[clap(long, default_value = "1000", env = "NEXMARK_RETRACTION_HISTORY_SIZE")]
+    pub retraction_history_size: usize,
+
     /// Dump DBSP profiles for all executed queries to the specified directory.
     #[clap(long, env = "NEXMARK_PROFILE_PATH")]
     pub profile_path: Option<String>,
@@ -113,11 +145,68 @@ pub struct Config {
     pub output_csv: Option<String>,
 }
 
+/// Shape of the event rate over time.
+///
+/// Based on the Java implementation at
+/// [NexmarkUtils.java](https://github.com/nexmark/nexmark/blob/v0.2.0/nexmark-flink/src/main/java/com/github/nexmark/flink/utils/NexmarkUtils.java).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum RateShape {
+    /// Alternate between the first and the next event rate, e.g., to
+    /// simulate bursts.
+    Square,
+    /// Follow a sine wave between the first and the next event rate, e.g., to
+    /// simulate a diurnal pattern.
+    Sine,
+}
+
+impl RateShape {
+    /// Number of steps that a sine wave is approximated with.
+    const SINE_STEPS: usize = 10;
+
+    /// Returns the delays between events, in microseconds, for each step of
+    /// the rate shape.
+    pub fn inter_event_delays_us(&self, first_rate: usize, next_rate: usize) -> Vec<f64> {
+        let delay_us = |rate: f64| 1_000_000.0 / rate;
+
+        if first_rate == next_rate {
+            return vec![delay_us(first_rate as f64)];
+        }
+
+        match self {
+            Self::Square => vec![delay_us(first_rate as f64), delay_us(next_rate as f64)],
+            Self::Sine => {
+                let mid = (first_rate + next_rate) as f64 / 2.0;
+                let amplitude = (first_rate as f64 - next_rate as f64) / 2.0;
+                (0..Self::SINE_STEPS)
+                    .map(|i| {
+                        let radians =
+                            2.0 * std::f64::consts::PI * i as f64 / Self::SINE_STEPS as f64;
+                        delay_us((mid + amplitude * radians.cos()).round())
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns the length of each step in seconds, such that all steps add
+    /// up to at least `rate_period_sec`.
+    pub fn step_length_sec(&self, rate_period_sec: usize) -> usize {
+        let steps = match self {
+            Self::Square => 2,
+            Self::Sine => Self::SINE_STEPS,
+        };
+        (rate_period_sec + steps - 1) / steps
+    }
+}
+
 /// Implementation of config methods based on the Java implementation at
 /// [NexmarkConfig.java](https://github.com/nexmark/nexmark/blob/master/nexmark-flink/src/main/java/com/github/nexmark/flink/NexmarkConfiguration.java).
 impl Config {
     pub fn total_proportion(&self) -> usize {
-        self.person_proportion + self.auction_proportion + self.bid_proportion
+        self.person_proportion
+            + self.auction_proportion
+            + self.bid_proportion
+            + self.auction_update_proportion
     }
 }
 
@@ -126,6 +215,7 @@ impl Default for Config {
         Config {
             __bench: true,
             auction_proportion: 3,
+            auction_update_proportion: 0,
             avg_auction_byte_size: 500,
             avg_bid_byte_size: 100,
             avg_person_byte_size: 200,
@@ -136,11 +226,16 @@ impl Default for Config {
             hot_bidders_ratio: 4,
             hot_sellers_ratio: 4,
             max_events: 100_000_000,
+            next_event_rate: None,
             num_active_people: 1000,
             num_event_generators: 2,
             num_in_flight_auctions: 100,
             out_of_order_group_size: 1,
             person_proportion: 1,
+            rate_period_sec: 600,
+            rate_shape: RateShape::Square,
+            retraction_probability: 0.0,
+            retraction_history_size: 1000,
             profile_path: None,
             query: Vec::new(),
             source_buffer_size: 10_000,
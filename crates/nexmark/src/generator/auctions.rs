@@ -3,7 +3,7 @@
 //! API based on the equivalent [Nexmark Flink PersonGenerator API](https://github.com/nexmark/nexmark/blob/v0.2.0/nexmark-flink/src/main/java/com/github/nexmark/flink/generator/model/AuctionGenerator.java).
 
 use super::{
-    super::model::Auction,
+    super::model::{Auction, AuctionUpdate},
     config::{FIRST_AUCTION_ID, FIRST_CATEGORY_ID, FIRST_PERSON_ID},
     NexmarkGenerator,
 };
@@ -80,6 +80,19 @@ impl<R: Rng> NexmarkGenerator<R> {
         })
     }
 
+    /// Generate and return an update to the reserve price of a random
+    /// existing auction.
+    pub fn next_auction_update(&mut self, event_id: u64, timestamp: u64) -> AuctionUpdate {
+        let auction = self.next_base0_auction_id(event_id) + FIRST_AUCTION_ID as u64;
+        let reserve = self.next_price();
+
+        AuctionUpdate {
+            auction,
+            reserve,
+            date_time: timestamp,
+        }
+    }
+
     /// Return the last valid auction id (ignoring FIRST_AUCTION_ID). Will be
     /// the current auction id if due to generate an auction.
     pub fn last_base0_auction_id(&self, event_id: u64) -> u64 {
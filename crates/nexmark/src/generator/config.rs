@@ -37,9 +37,20 @@ pub struct Config {
     pub first_event_number: usize,
 
     /// Delay between events, in microseconds. If the array has more than one
-    /// entry then the rate is changed every {@link #stepLengthSec}, and wraps
+    /// entry then the rate is changed every `step_length_sec`, and wraps
     /// around.
-    pub inter_event_delay_us: [f64; 1],
+    pub inter_event_delay_us: Vec<f64>,
+
+    /// Number of events generated in each step of `inter_event_delay_us`.
+    /// Empty if the rate is constant.
+    pub events_per_step: Vec<u64>,
+
+    /// Sum of `events_per_step`: the number of events in one period of the
+    /// rate shape.
+    pub events_per_epoch: u64,
+
+    /// Duration of one period of the rate shape, in microseconds.
+    pub epoch_period_us: f64,
 }
 
 /// Implementation of config methods based on the Java implementation at
@@ -59,7 +70,34 @@ impl Config {
         // event numbers 0, 3 and 6 etc., where as the Java implementation uses
         // 0, 1 and 2 locally for each generator and so adds a factor of
         // num_generators.
-        let inter_event_delay = 1_000_000.0 / (nexmark_config.first_event_rate as f64);
+        let inter_event_delay_us = nexmark_config.rate_shape.inter_event_delays_us(
+            nexmark_config.first_event_rate,
+            nexmark_config
+                .next_event_rate
+                .unwrap_or(nexmark_config.first_event_rate),
+        );
+
+        // When the rate changes over time, each delay applies for
+        // `step_length_sec` seconds.
+        let (events_per_step, epoch_period_us) = if inter_event_delay_us.len() > 1 {
+            let step_length_us = nexmark_config
+                .rate_shape
+                .step_length_sec(nexmark_config.rate_period_sec)
+                as f64
+                * 1_000_000.0;
+            let events_per_step: Vec<u64> = inter_event_delay_us
+                .iter()
+                .map(|delay_us| ((step_length_us / delay_us).round() as u64).max(1))
+                .collect();
+            let epoch_period_us = inter_event_delay_us
+                .iter()
+                .zip(events_per_step.iter())
+                .map(|(delay_us, events)| delay_us * *events as f64)
+                .sum();
+            (events_per_step, epoch_period_us)
+        } else {
+            (Vec::new(), 0.0)
+        };
 
         // Original Java implementation says:
         // "Scale maximum down to avoid overflow in getEstimatedSizeBytes."
@@ -84,7 +122,10 @@ impl Config {
             first_event_id,
             max_events,
             first_event_number,
-            inter_event_delay_us: [inter_event_delay],
+            events_per_epoch: events_per_step.iter().sum(),
+            events_per_step,
+            epoch_period_us,
+            inter_event_delay_us,
         }
     }
 
@@ -116,7 +157,30 @@ impl Config {
     // What timestamp should the event with `eventNumber` have for this
     // generator?
     pub fn timestamp_for_event(&self, event_number: u64) -> u64 {
-        self.base_time + (self.inter_event_delay_us[0] * event_number as f64) as u64 / 1000
+        if self.events_per_step.is_empty() {
+            return self.base_time
+                + (self.inter_event_delay_us[0] * event_number as f64) as u64 / 1000;
+        }
+
+        // Find the step within the current period of the rate shape, and
+        // the offset of the event within that step.
+        let epoch = event_number / self.events_per_epoch;
+        let mut n = event_number % self.events_per_epoch;
+        let mut offset_us = epoch as f64 * self.epoch_period_us;
+
+        for (delay_us, events) in self
+            .inter_event_delay_us
+            .iter()
+            .zip(self.events_per_step.iter())
+        {
+            if n < *events {
+                return self.base_time + (offset_us + delay_us * n as f64) as u64 / 1000;
+            }
+            n -= events;
+            offset_us += delay_us * *events as f64;
+        }
+
+        unreachable!("event number {event_number} is outside of its epoch")
     }
 }
 
@@ -140,7 +204,7 @@ impl Default for Config {
 
 #[cfg(test)]
 pub mod tests {
-    use super::super::super::config::Config as NexmarkConfig;
+    use super::super::super::config::{Config as NexmarkConfig, RateShape};
     use super::*;
     use rstest::rstest;
     use std::iter::zip;
@@ -277,4 +341,79 @@ pub mod tests {
             expected,
         );
     }
+
+    /// Returns the number of events with timestamps in each one-second
+    /// interval, for the first `num_events` events.
+    fn events_per_second(config: &Config, num_events: u64) -> Vec<u64> {
+        let mut counts = Vec::new();
+
+        for event_number in 0..num_events {
+            let second = (config.timestamp_for_event(event_number) / 1000) as usize;
+            if counts.len() <= second {
+                counts.resize(second + 1, 0);
+            }
+            counts[second] += 1;
+        }
+
+        counts
+    }
+
+    fn shaped_config(rate_shape: RateShape, rate_period_sec: usize) -> Config {
+        Config::new(
+            NexmarkConfig {
+                first_event_rate: 1000,
+                next_event_rate: Some(100),
+                rate_shape,
+                rate_period_sec,
+                ..NexmarkConfig::default()
+            },
+            0,
+            0,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_timestamp_for_event_square() {
+        // A square wave with a 2 second period alternates between one second
+        // at 1000 events/s and one second at 100 events/s.
+        let config = shaped_config(RateShape::Square, 2);
+        let counts = events_per_second(&config, 3 * 1100);
+
+        assert_eq!(counts, vec![1000, 100, 1000, 100, 1000, 100]);
+    }
+
+    #[test]
+    fn test_timestamp_for_event_sine() {
+        // A sine wave with a 10 second period approximated by ten 1 second
+        // steps, starting at the first event rate.
+        let config = shaped_config(RateShape::Sine, 10);
+        let expected: Vec<f64> = (0..10)
+            .map(|i| 550.0 + 450.0 * (2.0 * std::f64::consts::PI * i as f64 / 10.0).cos())
+            .collect();
+        let num_events = expected.iter().map(|rate| rate.round() as u64).sum();
+        let counts = events_per_second(&config, num_events);
+
+        assert_eq!(counts.len(), expected.len());
+        for (count, expected) in zip(counts, expected) {
+            assert!(
+                (count as f64 - expected).abs() <= expected * 0.01 + 1.0,
+                "expected about {expected} events, got {count}"
+            );
+        }
+    }
+
+    #[rstest]
+    #[case::square(RateShape::Square, 3)]
+    #[case::sine(RateShape::Sine, 7)]
+    fn test_timestamp_for_event_monotonic(#[case] rate_shape: RateShape, #[case] period: usize) {
+        let config = shaped_config(rate_shape, period);
+
+        let mut previous = 0;
+        for event_number in 0..20_000 {
+            let timestamp = config.timestamp_for_event(event_number);
+            assert!(timestamp >= previous);
+            previous = timestamp;
+        }
+    }
 }
@@ -7,8 +7,11 @@ use super::model::Event;
 use anyhow::Result;
 use arcstr::ArcStr;
 use bids::CHANNELS_NUMBER;
-use cached::SizedCache;
-use rand::Rng;
+use bincode::{Decode, Encode};
+use cached::{Cached, SizedCache};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
 
 mod auctions;
 mod bids;
@@ -35,6 +38,10 @@ pub struct NexmarkGenerator<R: Rng> {
     /// Wallclock time at which we emit the first event (ms since epoch).
     /// Set when generator created.
     wallclock_base_time: u64,
+
+    /// Recently generated events that may be retracted by
+    /// `next_event_with_weight`.
+    history: VecDeque<NextEvent>,
 }
 
 impl<R: Rng> NexmarkGenerator<R> {
@@ -68,12 +75,15 @@ impl<R: Rng> NexmarkGenerator<R> {
         let wallclock_timestamp =
             self.wallclock_base_time + event_timestamp - self.config.base_time;
 
-        let (auction_proportion, person_proportion, total_proportion) = (
+        let (auction_proportion, person_proportion, bid_proportion, total_proportion) = (
             self.config.nexmark_config.auction_proportion as u64,
             self.config.nexmark_config.person_proportion as u64,
+            self.config.nexmark_config.bid_proportion as u64,
             self.config.nexmark_config.total_proportion() as u64,
         );
 
+        // Auction updates come last in each epoch, so that they don't affect
+        // the computation of person and auction ids.
         let rem = new_event_id % total_proportion;
         let event = if rem < person_proportion {
             Event::Person(self.next_person(new_event_id, adjusted_event_timestamp))
@@ -83,8 +93,10 @@ impl<R: Rng> NexmarkGenerator<R> {
                 new_event_id,
                 adjusted_event_timestamp,
             )?)
-        } else {
+        } else if rem < person_proportion + auction_proportion + bid_proportion {
             Event::Bid(self.next_bid(new_event_id, adjusted_event_timestamp))
+        } else {
+            Event::AuctionUpdate(self.next_auction_update(new_event_id, adjusted_event_timestamp))
         };
 
         self.events_count_so_far += 1;
@@ -96,6 +108,42 @@ impl<R: Rng> NexmarkGenerator<R> {
         }))
     }
 
+    /// Returns the next event along with its weight.
+    ///
+    /// With probability `retraction_probability` (see
+    /// [`crate::config::Config`]) this returns a retraction, i.e., one of the
+    /// last `retraction_history_size` generated events, chosen at random,
+    /// with weight -1.  Each event is retracted at most once.  Otherwise,
+    /// generates a new event with weight 1.
+    pub fn next_event_with_weight(&mut self) -> Result<Option<(NextEvent, isize)>> {
+        if !self.has_next() {
+            return Ok(None);
+        }
+
+        let retraction_probability = self.config.nexmark_config.retraction_probability.min(1.0);
+        if retraction_probability > 0.0
+            && !self.history.is_empty()
+            && self.rng.gen_bool(retraction_probability)
+        {
+            let index = self.rng.gen_range(0..self.history.len());
+            let retracted = self.history.swap_remove_back(index).unwrap();
+            return Ok(Some((retracted, -1)));
+        }
+
+        let next_event = self.next_event()?;
+        if let Some(next_event) = &next_event {
+            let history_size = self.config.nexmark_config.retraction_history_size;
+            if retraction_probability > 0.0 && history_size > 0 {
+                if self.history.len() >= history_size {
+                    self.history.pop_front();
+                }
+                self.history.push_back(next_event.clone());
+            }
+        }
+
+        Ok(next_event.map(|next_event| (next_event, 1)))
+    }
+
     pub fn new(config: Config, rng: R, wallclock_base_time: u64) -> NexmarkGenerator<R> {
         NexmarkGenerator {
             config,
@@ -103,6 +151,7 @@ impl<R: Rng> NexmarkGenerator<R> {
             bid_channel_cache: SizedCache::with_size(CHANNELS_NUMBER as usize),
             events_count_so_far: 0,
             wallclock_base_time,
+            history: VecDeque::new(),
         }
     }
 
@@ -117,9 +166,90 @@ impl<R: Rng> NexmarkGenerator<R> {
     }
 }
 
+impl NexmarkGenerator<ChaCha8Rng> {
+    /// Returns the current state of the generator.
+    ///
+    /// Restoring the state with [`Self::restore`] produces a generator that
+    /// emits exactly the same sequence of events as `self` from this point
+    /// on.  The state of the random number generator is captured as its seed,
+    /// stream and word position, so the state can be encoded and decoded
+    /// with `bincode`.
+    pub fn checkpoint(&self) -> GeneratorState {
+        let bid_channels = self
+            .bid_channel_cache
+            .key_order()
+            .zip(self.bid_channel_cache.value_order())
+            .map(|(channel_number, (channel, url))| (*channel_number, channel.clone(), url.clone()))
+            .rev()
+            .collect();
+
+        GeneratorState {
+            events_count_so_far: self.events_count_so_far,
+            wallclock_base_time: self.wallclock_base_time,
+            rng_seed: self.rng.get_seed(),
+            rng_stream: self.rng.get_stream(),
+            rng_word_pos: self.rng.get_word_pos(),
+            bid_channels,
+            history: self.history.clone(),
+        }
+    }
+
+    /// Creates a generator that resumes from a state returned by
+    /// [`Self::checkpoint`].
+    ///
+    /// `config` must be the configuration of the checkpointed generator.
+    pub fn restore(config: Config, state: GeneratorState) -> NexmarkGenerator<ChaCha8Rng> {
+        let mut rng = ChaCha8Rng::from_seed(state.rng_seed);
+        rng.set_stream(state.rng_stream);
+        rng.set_word_pos(state.rng_word_pos);
+
+        let mut generator = NexmarkGenerator::new(config, rng, state.wallclock_base_time);
+        generator.events_count_so_far = state.events_count_so_far;
+        generator.history = state.history;
+
+        // Channels are stored from least to most recently used, so inserting
+        // them in order restores the eviction order of the cache.
+        for (channel_number, channel, url) in state.bid_channels {
+            generator
+                .bid_channel_cache
+                .cache_set(channel_number, (channel, url));
+        }
+
+        generator
+    }
+}
+
+/// The progress of a [`NexmarkGenerator`], as returned by
+/// [`NexmarkGenerator::checkpoint`].
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct GeneratorState {
+    /// Number of events generated so far.
+    pub events_count_so_far: u64,
+
+    /// Wallclock time at which the first event was emitted (ms since epoch).
+    pub wallclock_base_time: u64,
+
+    /// Seed of the random number generator.
+    pub rng_seed: [u8; 32],
+
+    /// Stream of the random number generator.
+    pub rng_stream: u64,
+
+    /// Position of the random number generator in its stream, in 32-bit
+    /// words.
+    pub rng_word_pos: u128,
+
+    /// Contents of the bid channel cache as `(channel number, channel, url)`
+    /// tuples, from least to most recently used.
+    pub bid_channels: Vec<(u32, ArcStr, ArcStr)>,
+
+    /// Recently generated events that may be retracted.
+    pub history: VecDeque<NextEvent>,
+}
+
 /// The next event and its various timestamps. Ordered by increasing wallclock
 /// timestamp, then (arbitrary but stable) event hash order.
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Encode, Decode)]
 pub struct NextEvent {
     /// When, in wallclock time, should this event be emitted?
     pub wallclock_timestamp: u64,
@@ -141,8 +271,13 @@ pub mod tests {
         config::Config as NexmarkConfig,
         model::{Auction, Bid, Person},
     };
-    use rand::{rngs::mock::StepRng, thread_rng};
+    use dbsp::trace::consolidation::consolidate;
+    use rand::{
+        rngs::{mock::StepRng, SmallRng},
+        thread_rng, SeedableRng,
+    };
     use rstest::rstest;
+    use std::collections::{HashMap, HashSet};
 
     pub fn make_test_generator() -> NexmarkGenerator<StepRng> {
         NexmarkGenerator::new(
@@ -347,4 +482,197 @@ pub mod tests {
             expected_events
         );
     }
+
+    fn make_retracting_generator(retraction_probability: f64) -> NexmarkGenerator<SmallRng> {
+        NexmarkGenerator::new(
+            Config {
+                nexmark_config: NexmarkConfig {
+                    num_event_generators: 1,
+                    retraction_probability,
+                    retraction_history_size: 100,
+                    ..NexmarkConfig::default()
+                },
+                ..Config::default()
+            },
+            SmallRng::seed_from_u64(42),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_next_event_with_weight_no_retractions() {
+        let mut ng = make_test_generator();
+        let expected_events = generate_expected_next_events(0, 100);
+
+        assert_eq!(
+            (0..100)
+                .map(
+                    |_| ng.next_event_with_weight().unwrap().map(|(event, weight)| {
+                        assert_eq!(weight, 1);
+                        event
+                    })
+                )
+                .collect::<Vec<Option<NextEvent>>>(),
+            expected_events
+        );
+    }
+
+    // Every retraction must cancel an event that was previously emitted and
+    // not yet retracted, bringing the net weight of the event back to zero.
+    #[test]
+    fn test_retractions_reference_emitted_events() {
+        let mut ng = make_retracting_generator(0.3);
+        let mut live: HashMap<NextEvent, usize> = HashMap::new();
+        let mut stream = Vec::new();
+
+        for _ in 0..10_000 {
+            let (event, weight) = ng.next_event_with_weight().unwrap().unwrap();
+            stream.push((event.clone(), weight));
+            match weight {
+                1 => *live.entry(event).or_default() += 1,
+                -1 => {
+                    let count = live
+                        .get_mut(&event)
+                        .expect("retraction of an event that was never emitted");
+                    assert!(*count > 0, "event retracted more than once: {event:?}");
+                    *count -= 1;
+                }
+                _ => panic!("unexpected weight {weight}"),
+            }
+        }
+        assert!(stream.iter().any(|(_, weight)| *weight < 0));
+
+        // After consolidation, retracted events are gone from the stream and
+        // only events that are still live remain, with their multiplicities.
+        consolidate(&mut stream);
+        let mut expected = live
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(event, count)| (event, count as isize))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(stream, expected);
+    }
+
+    #[rstest]
+    #[case(0.1)]
+    #[case(0.3)]
+    #[case(0.5)]
+    fn test_retraction_ratio(#[case] retraction_probability: f64) {
+        let mut ng = make_retracting_generator(retraction_probability);
+        let total = 20_000;
+
+        let retractions = (0..total)
+            .filter(|_| ng.next_event_with_weight().unwrap().unwrap().1 == -1)
+            .count();
+
+        let ratio = retractions as f64 / total as f64;
+        assert!(
+            (ratio - retraction_probability).abs() < 0.05,
+            "retraction ratio {ratio}, expected {retraction_probability}"
+        );
+    }
+
+    fn make_updating_generator(auction_update_proportion: usize) -> NexmarkGenerator<SmallRng> {
+        NexmarkGenerator::new(
+            Config {
+                nexmark_config: NexmarkConfig {
+                    num_event_generators: 1,
+                    auction_update_proportion,
+                    ..NexmarkConfig::default()
+                },
+                ..Config::default()
+            },
+            SmallRng::seed_from_u64(42),
+            0,
+        )
+    }
+
+    // Every auction update must refer to an auction that was generated
+    // earlier.
+    #[test]
+    fn test_auction_updates_reference_existing_auctions() {
+        let mut ng = make_updating_generator(5);
+        let mut auctions = HashSet::new();
+        let mut num_updates = 0;
+
+        for _ in 0..10_000 {
+            match ng.next_event().unwrap().unwrap().event {
+                Event::Auction(auction) => {
+                    auctions.insert(auction.id);
+                }
+                Event::AuctionUpdate(update) => {
+                    assert!(
+                        auctions.contains(&update.auction),
+                        "update of unknown auction: {update:?}"
+                    );
+                    num_updates += 1;
+                }
+                _ => (),
+            }
+        }
+
+        assert!(num_updates > 0);
+    }
+
+    #[test]
+    fn test_auction_update_proportion() {
+        let mut ng = make_updating_generator(5);
+        let total_proportion = ng.config.nexmark_config.total_proportion();
+        assert_eq!(total_proportion, 55);
+
+        let num_epochs = 100;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for _ in 0..num_epochs * total_proportion {
+            let kind = match ng.next_event().unwrap().unwrap().event {
+                Event::Person(_) => "person",
+                Event::Auction(_) => "auction",
+                Event::Bid(_) => "bid",
+                Event::AuctionUpdate(_) => "auction_update",
+            };
+            *counts.entry(kind).or_default() += 1;
+        }
+
+        assert_eq!(counts["person"], num_epochs);
+        assert_eq!(counts["auction"], 3 * num_epochs);
+        assert_eq!(counts["bid"], 46 * num_epochs);
+        assert_eq!(counts["auction_update"], 5 * num_epochs);
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let mut ng = NexmarkGenerator::new(
+            Config {
+                nexmark_config: NexmarkConfig {
+                    num_event_generators: 1,
+                    retraction_probability: 0.1,
+                    retraction_history_size: 100,
+                    ..NexmarkConfig::default()
+                },
+                ..Config::default()
+            },
+            ChaCha8Rng::seed_from_u64(42),
+            0,
+        );
+
+        for _ in 0..1000 {
+            ng.next_event_with_weight().unwrap();
+        }
+
+        // Round-trip the state through its serialized form.
+        let encoded = bincode::encode_to_vec(ng.checkpoint(), bincode::config::standard()).unwrap();
+        let (state, _): (GeneratorState, _) =
+            bincode::decode_from_slice(&encoded, bincode::config::standard()).unwrap();
+
+        let expected_events: Vec<_> = (0..1000)
+            .map(|_| ng.next_event_with_weight().unwrap())
+            .collect();
+
+        let mut restored = NexmarkGenerator::restore(ng.config.clone(), state);
+        let events: Vec<_> = (0..1000)
+            .map(|_| restored.next_event_with_weight().unwrap())
+            .collect();
+
+        assert_eq!(events, expected_events);
+    }
 }
@@ -351,6 +351,10 @@ pub mod tests {
                     Event::Bid(_) => (),
                     _ => panic!("expected bid, got {got:?}"),
                 },
+                Event::AuctionUpdate(_) => match got {
+                    Event::AuctionUpdate(_) => (),
+                    _ => panic!("expected auction update, got {got:?}"),
+                },
             }
         }
     }
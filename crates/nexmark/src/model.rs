@@ -63,11 +63,26 @@ pub struct Bid {
     pub extra: ArcStr,
 }
 
+/// A change to the reserve price of an existing auction.
+///
+/// This is not part of the original Nexmark model.  It is used to benchmark
+/// queries in the presence of updates to previously inserted auctions.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SizeOf, Encode, Decode)]
+pub struct AuctionUpdate {
+    /// Id of the auction being updated.
+    pub auction: u64,
+    /// New reserve price, in cents.
+    pub reserve: usize,
+    /// Instant at which the update was made.
+    pub date_time: u64,
+}
+
 /// An event in the auction system, either a (new) `Person`, a (new) `Auction`,
-/// or a `Bid`.
+/// a `Bid`, or an `AuctionUpdate` to an existing auction.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, SizeOf, Encode, Decode)]
 pub enum Event {
     Person(Person),
     Auction(Auction),
     Bid(Bid),
+    AuctionUpdate(AuctionUpdate),
 }
@@ -1,7 +1,10 @@
 //! Nexmark Queries in DBSP.
 
 use super::model::Event;
-use dbsp::{OrdZSet, RootCircuit, Stream};
+use dbsp::{
+    operator::{FilterMap, Pivot},
+    DBData, OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+};
 use std::time::SystemTime;
 
 type NexmarkStream = Stream<RootCircuit, OrdZSet<Event, isize>>;
@@ -60,3 +63,128 @@ fn process_time() -> u64 {
         .unwrap()
         .as_millis() as u64
 }
+
+/// A `(filter, project)` pair passed to [`distinct_counts`].
+pub type DistinctCount<V, D> = (Box<dyn Fn(&V) -> bool>, Box<dyn Fn(&V) -> D>);
+
+/// Computes several filtered distinct counts per group in a single pass over
+/// the input.
+///
+/// For each group in `input` and each `(filter, project)` pair in `counts`,
+/// computes the number of distinct values `project(v)` over records `v` in
+/// the group that satisfy `filter(v)`, i.e., the equivalent of SQL's
+/// `count(distinct project(v)) filter (where filter(v))`.  The output maps
+/// each group to a vector of counts in the same order as `counts`.  Groups
+/// without records that satisfy any of the filters are not in the output.
+///
+/// This is the pattern used by q15 and q16, which compute several such
+/// counts per day.  Instead of filtering the input once per count, each
+/// record is tagged with the index of every count it contributes to, so
+/// all counts share a single `distinct` and a single aggregation.
+pub fn distinct_counts<K, V, D>(
+    input: &Stream<RootCircuit, OrdIndexedZSet<K, V, isize>>,
+    counts: Vec<DistinctCount<V, D>>,
+) -> Stream<RootCircuit, OrdIndexedZSet<K, Vec<isize>, isize>>
+where
+    K: DBData,
+    V: DBData,
+    D: DBData,
+{
+    let num_counts = counts.len();
+
+    input
+        .flat_map_index(move |(group, record)| {
+            counts
+                .iter()
+                .enumerate()
+                .filter(|(_, (filter, _))| filter(record))
+                .map(|(index, (_, project))| ((group.clone(), index), project(record)))
+                .collect::<Vec<_>>()
+        })
+        .distinct()
+        .count()
+        .map_index(|((group, index), count)| (group.clone(), (*index, *count)))
+        .aggregate(Pivot::new((0..num_counts).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distinct_counts, DistinctCount};
+    use dbsp::{indexed_zset, operator::FilterMap, Runtime};
+
+    // (auction, price, bidder)
+    type Bid = (u64, u64, u64);
+
+    fn counts() -> Vec<DistinctCount<Bid, u64>> {
+        vec![
+            (
+                Box::new(|_: &Bid| true),
+                Box::new(|(_, _, bidder): &Bid| *bidder),
+            ),
+            (
+                Box::new(|(_, price, _): &Bid| *price < 10_000),
+                Box::new(|(_, _, bidder): &Bid| *bidder),
+            ),
+            (
+                Box::new(|(_, price, _): &Bid| *price >= 10_000),
+                Box::new(|(auction, _, _): &Bid| *auction),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_distinct_counts() {
+        let (mut dbsp, mut input_handle) = Runtime::init_circuit(2, move |circuit| {
+            let (bids, input_handle) = circuit.add_input_indexed_zset::<u64, Bid, isize>();
+
+            let combined = distinct_counts(&bids, counts()).integrate().gather(0);
+
+            // Compute each count separately and compare with the corresponding
+            // column of the combined output.
+            for (index, (filter, project)) in counts().into_iter().enumerate() {
+                let expected = bids
+                    .flat_map_index(move |(day, bid)| filter(bid).then(|| (*day, project(bid))))
+                    .distinct()
+                    .count()
+                    .integrate()
+                    .gather(0);
+                let actual = combined.flat_map_index(move |(day, counts)| {
+                    (counts[index] != 0).then(|| (*day, counts[index]))
+                });
+
+                expected.apply2(&actual, |expected, actual| assert_eq!(expected, actual));
+            }
+
+            combined.inspect(|combined| {
+                if Runtime::worker_index() == 0 {
+                    assert_eq!(
+                        combined,
+                        &indexed_zset! {
+                            1 => { vec![2, 1, 2] => 1 },
+                            2 => { vec![1, 0, 1] => 1 },
+                        }
+                    );
+                }
+            });
+
+            input_handle
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, ((1, 100, 1), 1)),
+            (1, ((1, 200, 1), 1)),
+            (1, ((2, 20_000, 2), 1)),
+            (1, ((3, 30_000, 1), 1)),
+            (2, ((4, 50_000, 3), 1)),
+        ]);
+        dbsp.step().unwrap();
+
+        // Retracting one of bidder 1's two low-price bids on day 1 doesn't
+        // change the output.
+        input_handle.append(&mut vec![(1, ((1, 200, 1), -1))]);
+        dbsp.step().unwrap();
+
+        dbsp.kill().unwrap();
+    }
+}
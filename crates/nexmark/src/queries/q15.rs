@@ -1,4 +1,4 @@
-use super::NexmarkStream;
+use super::{distinct_counts, DistinctCount, NexmarkStream};
 use dbsp::{
     operator::FilterMap,
     RootCircuit, OrdIndexedZSet, OrdZSet, Stream,
@@ -109,41 +109,28 @@ pub fn q15(input: NexmarkStream) -> Q15Stream {
         bids.filter(|(_day, (_auction, price, _bidder))| *price >= 10_000 && *price < 1_000_000);
     let rank3_bids = bids.filter(|(_day, (_auction, price, _bidder))| *price >= 1_000_000);
 
-    // Compute unique bidders across all bids and for each price range.
-    let distinct_bidder = bids
-        .map(|(day, (_auction, _price, bidder))| (*day, *bidder))
-        .distinct()
-        .index();
-    let rank1_distinct_bidder = rank1_bids
-        .map(|(day, (_auction, _price, bidder))| (*day, *bidder))
-        .distinct()
-        .index();
-    let rank2_distinct_bidder = rank2_bids
-        .map(|(day, (_auction, _price, bidder))| (*day, *bidder))
-        .distinct()
-        .index();
-    let rank3_distinct_bidder = rank3_bids
-        .map(|(day, (_auction, _price, bidder))| (*day, *bidder))
-        .distinct()
-        .index();
-
-    // Compute unique auctions across all bids and for each price range.
-    let distinct_auction = bids
-        .map(|(day, (auction, _price, _bidder))| (*day, *auction))
-        .distinct()
-        .index();
-    let rank1_distinct_auction = rank1_bids
-        .map(|(day, (auction, _price, _bidder))| (*day, *auction))
-        .distinct()
-        .index();
-    let rank2_distinct_auction = rank2_bids
-        .map(|(day, (auction, _price, _bidder))| (*day, *auction))
-        .distinct()
-        .index();
-    let rank3_distinct_auction = rank3_bids
-        .map(|(day, (auction, _price, _bidder))| (*day, *auction))
-        .distinct()
-        .index();
+    // Count distinct bidders and auctions per day, across all bids and for
+    // each price range, with a single `distinct` and aggregation.
+    let price_ranges: [fn(u64) -> bool; 4] = [
+        |_price| true,
+        |price| price < 10_000,
+        |price| (10_000..1_000_000).contains(&price),
+        |price| price >= 1_000_000,
+    ];
+    let mut counts: Vec<DistinctCount<(u64, u64, u64), u64>> = Vec::new();
+    for in_range in price_ranges {
+        counts.push((
+            Box::new(move |(_auction, price, _bidder): &(u64, u64, u64)| in_range(*price)),
+            Box::new(|(_auction, _price, bidder): &(u64, u64, u64)| *bidder),
+        ));
+    }
+    for in_range in price_ranges {
+        counts.push((
+            Box::new(move |(_auction, price, _bidder): &(u64, u64, u64)| in_range(*price)),
+            Box::new(|(auction, _price, _bidder): &(u64, u64, u64)| *auction),
+        ));
+    }
+    let distinct = distinct_counts(&bids.index(), counts);
 
     // Compute bids per day.
     let count_total_bids: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> = bids
@@ -159,28 +146,9 @@ pub fn q15(input: NexmarkStream) -> Q15Stream {
         .index()
         .aggregate_linear(|_, _| -> isize { 1 });
 
-    // Count unique bidders per day.
-    let count_total_bidders: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        distinct_bidder.aggregate_linear(|_, _| -> isize { 1 });
-    let count_rank1_bidders: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        rank1_distinct_bidder.aggregate_linear(|_, _| -> isize { 1 });
-    let count_rank2_bidders: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        rank2_distinct_bidder.aggregate_linear(|_, _| -> isize { 1 });
-    let count_rank3_bidders: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        rank3_distinct_bidder.aggregate_linear(|_, _| -> isize { 1 });
-
-    // Count unique auctions per day.
-    let count_total_auctions: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        distinct_auction.aggregate_linear(|_, _| -> isize { 1 });
-    let count_rank1_auctions: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        rank1_distinct_auction.aggregate_linear(|_, _| -> isize { 1 });
-    let count_rank2_auctions: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        rank2_distinct_auction.aggregate_linear(|_, _| -> isize { 1 });
-    let count_rank3_auctions: Stream<_, OrdIndexedZSet<OrdinalDate, isize, _>> =
-        rank3_distinct_auction.aggregate_linear(|_, _| -> isize { 1 });
-
-    // The following abomination simply joins all aggregates computed above into a
-    // single output stream.
+    // Join all aggregates computed above into a single output stream.  Every
+    // day with bids has a distinct bidder count, so `distinct` contains the
+    // same days as `count_total_bids`.
     count_total_bids
         .outer_join_default(&count_rank1_bids, |date, total_bids, rank1_bids| {
             (*date, (*total_bids, *rank1_bids))
@@ -200,210 +168,9 @@ pub fn q15(input: NexmarkStream) -> Q15Stream {
             },
         )
         .index()
-        .outer_join_default(
-            &count_total_bidders,
-            |date, (total_bids, rank1_bids, rank2_bids, rank3_bids), total_bidders| {
-                (
-                    *date,
-                    (
-                        *total_bids,
-                        *rank1_bids,
-                        *rank2_bids,
-                        *rank3_bids,
-                        *total_bidders,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_rank1_bidders,
-            |date,
-             (total_bids, rank1_bids, rank2_bids, rank3_bids, total_bidders),
-             rank1_bidders| {
-                (
-                    *date,
-                    (
-                        *total_bids,
-                        *rank1_bids,
-                        *rank2_bids,
-                        *rank3_bids,
-                        *total_bidders,
-                        *rank1_bidders,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_rank2_bidders,
-            |date,
-             (total_bids, rank1_bids, rank2_bids, rank3_bids, total_bidders, rank1_bidders),
-             rank2_bidders| {
-                (
-                    *date,
-                    (
-                        *total_bids,
-                        *rank1_bids,
-                        *rank2_bids,
-                        *rank3_bids,
-                        *total_bidders,
-                        *rank1_bidders,
-                        *rank2_bidders,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_rank3_bidders,
-            |date,
-             (
-                total_bids,
-                rank1_bids,
-                rank2_bids,
-                rank3_bids,
-                total_bidders,
-                rank1_bidders,
-                rank2_bidders,
-            ),
-             rank3_bidders| {
-                (
-                    *date,
-                    (
-                        *total_bids,
-                        *rank1_bids,
-                        *rank2_bids,
-                        *rank3_bids,
-                        *total_bidders,
-                        *rank1_bidders,
-                        *rank2_bidders,
-                        *rank3_bidders,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_total_auctions,
-            |date,
-             (
-                total_bids,
-                rank1_bids,
-                rank2_bids,
-                rank3_bids,
-                total_bidders,
-                rank1_bidders,
-                rank2_bidders,
-                rank3_bidders,
-            ),
-             total_auctions| {
-                (
-                    *date,
-                    (
-                        *total_bids,
-                        *rank1_bids,
-                        *rank2_bids,
-                        *rank3_bids,
-                        *total_bidders,
-                        *rank1_bidders,
-                        *rank2_bidders,
-                        *rank3_bidders,
-                        *total_auctions,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_rank1_auctions,
-            |date,
-             (
-                total_bids,
-                rank1_bids,
-                rank2_bids,
-                rank3_bids,
-                total_bidders,
-                rank1_bidders,
-                rank2_bidders,
-                rank3_bidders,
-                total_auctions,
-            ),
-             rank1_auctions| {
-                (
-                    *date,
-                    (
-                        *total_bids,
-                        *rank1_bids,
-                        *rank2_bids,
-                        *rank3_bids,
-                        *total_bidders,
-                        *rank1_bidders,
-                        *rank2_bidders,
-                        *rank3_bidders,
-                        *total_auctions,
-                        *rank1_auctions,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_rank2_auctions,
-            |date,
-             (
-                total_bids,
-                rank1_bids,
-                rank2_bids,
-                rank3_bids,
-                total_bidders,
-                rank1_bidders,
-                rank2_bidders,
-                rank3_bidders,
-                total_auctions,
-                rank1_auctions,
-            ),
-             rank2_auctions| {
-                (
-                    *date,
-                    (
-                        (
-                            *total_bids,
-                            *rank1_bids,
-                            *rank2_bids,
-                            *rank3_bids,
-                            *total_bidders,
-                            *rank1_bidders,
-                            *rank2_bidders,
-                            *rank3_bidders,
-                            *total_auctions,
-                            *rank1_auctions,
-                        ),
-                        *rank2_auctions,
-                    ),
-                )
-            },
-        )
-        .index()
-        .outer_join_default(
-            &count_rank3_auctions,
-            |date,
-             (
-                (
-                    total_bids,
-                    rank1_bids,
-                    rank2_bids,
-                    rank3_bids,
-                    total_bidders,
-                    rank1_bidders,
-                    rank2_bidders,
-                    rank3_bidders,
-                    total_auctions,
-                    rank1_auctions,
-                ),
-                rank2_auctions,
-            ),
-             rank3_auctions| Q15Output {
+        .join(
+            &distinct,
+            |date, (total_bids, rank1_bids, rank2_bids, rank3_bids), counts| Q15Output {
                 day: Date::from_ordinal_date(date.0, date.1)
                     .unwrap()
                     .format(iso8601_day_format)
@@ -412,14 +179,14 @@ pub fn q15(input: NexmarkStream) -> Q15Stream {
                 rank1_bids: *rank1_bids as usize,
                 rank2_bids: *rank2_bids as usize,
                 rank3_bids: *rank3_bids as usize,
-                total_bidders: *total_bidders as usize,
-                rank1_bidders: *rank1_bidders as usize,
-                rank2_bidders: *rank2_bidders as usize,
-                rank3_bidders: *rank3_bidders as usize,
-                total_auctions: *total_auctions as usize,
-                rank1_auctions: *rank1_auctions as usize,
-                rank2_auctions: *rank2_auctions as usize,
-                rank3_auctions: *rank3_auctions as usize,
+                total_bidders: counts[0] as usize,
+                rank1_bidders: counts[1] as usize,
+                rank2_bidders: counts[2] as usize,
+                rank3_bidders: counts[3] as usize,
+                total_auctions: counts[4] as usize,
+                rank1_auctions: counts[5] as usize,
+                rank2_auctions: counts[6] as usize,
+                rank3_auctions: counts[7] as usize,
             },
         )
 }
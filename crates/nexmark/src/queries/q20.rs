@@ -224,6 +224,39 @@ mod tests {
             (Bid { auction: 1, bidder: 40, price: 40, ..make_bid()}, Auction { id: 1, category: FILTERED_CATEGORY, ..make_auction() }) => 1,
             (Bid { auction: 2, bidder: 60, price: 60, ..make_bid()}, Auction { id: 2, category: FILTERED_CATEGORY, ..make_auction() }) => 1,
         }])]
+    #[case::bids_before_auction(
+        vec![vec![
+            // The auction for these bids hasn't been seen yet.
+            Event::Bid(Bid {
+                auction: 1,
+                bidder: 10,
+                price: 10,
+                ..make_bid()
+            }),
+            Event::Bid(Bid {
+                auction: 1,
+                bidder: 20,
+                price: 20,
+                ..make_bid()
+            }),
+        ], vec![
+            Event::Auction(Auction {
+                id: 1,
+                category: FILTERED_CATEGORY,
+                ..make_auction()
+            }),
+            Event::Bid(Bid {
+                auction: 1,
+                bidder: 30,
+                price: 30,
+                ..make_bid()
+            }),
+        ]],
+        vec![zset! {}, zset! {
+            (Bid { auction: 1, bidder: 10, price: 10, ..make_bid()}, Auction { id: 1, category: FILTERED_CATEGORY, ..make_auction() }) => 1,
+            (Bid { auction: 1, bidder: 20, price: 20, ..make_bid()}, Auction { id: 1, category: FILTERED_CATEGORY, ..make_auction() }) => 1,
+            (Bid { auction: 1, bidder: 30, price: 30, ..make_bid()}, Auction { id: 1, category: FILTERED_CATEGORY, ..make_auction() }) => 1,
+        }])]
     fn test_q20(
         #[case] input_event_batches: Vec<Vec<Event>>,
         #[case] expected_zsets: Vec<OrdZSet<(Bid, Auction), isize>>,